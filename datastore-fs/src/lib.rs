@@ -0,0 +1,534 @@
+//! A file-backed document store for [`datastore`].
+//!
+//! [`FileStore`] keeps one directory per [`DataDescriptor::ident`] below its root directory and
+//! stores every item as a single JSON file in that directory. No database is required, which
+//! makes it a good fit for small tools and command line applications.
+//!
+//! ```
+//! use datastore::{Store, StoreData, StoreExt};
+//! use datastore_fs::FileStore;
+//!
+//! #[derive(StoreData)]
+//! struct Person {
+//!     id: i64,
+//!     name: String,
+//! }
+//!
+//! # async fn run() -> Result<(), datastore_fs::Error> {
+//! let store = FileStore::connect("file://./data").await?;
+//! store.create(store.descriptor::<Person>()).await?;
+//!
+//! let person = Person {
+//!     id: 1,
+//!     name: String::from("Robb"),
+//! };
+//! store.insert(store.descriptor::<Person>(), person).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Durability
+//!
+//! Items are first written to a hidden temporary file, which is synced to disk and then renamed
+//! to its final name. The directory is synced after every rename and removal. A crash therefore
+//! never leaves a partially written item behind, only a temporary file which is ignored when
+//! reading.
+//!
+//! # Queries
+//!
+//! [`DataQuery`]s are evaluated by reading every item of the type and comparing the fields
+//! written by the query for equality. The cost of every operation except [`Store::insert`] grows
+//! with the number of stored items.
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use datastore::json::{self, JsonReader};
+use datastore::value::{self, Record};
+use datastore::{
+    Capabilities, ConnectOptions, DataDescriptor, DataQuery, Read, Reader, Store, StoreData,
+    TypeWriter, Write, Writer,
+};
+
+/// The file extension of stored items.
+const EXTENSION: &str = "json";
+
+/// A [`Store`] keeping every item in its own file.
+#[derive(Debug)]
+pub struct FileStore {
+    root: PathBuf,
+    counter: AtomicU32,
+}
+
+impl FileStore {
+    /// Opens the `FileStore` in the directory `root`, creating it if it does not exist.
+    pub fn open<P>(root: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let root = root.as_ref().to_owned();
+        fs::create_dir_all(&root)?;
+
+        Ok(Self {
+            root,
+            counter: AtomicU32::new(0),
+        })
+    }
+
+    /// Returns the root directory of the `FileStore`.
+    #[inline]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the directory storing all items with the identifier `ident`.
+    fn dir(&self, ident: &str) -> Result<PathBuf, Error> {
+        // The identifier must resolve to exactly one directory directly below the root.
+        if ident.is_empty() || ident == "." || ident == ".." || ident.contains(['/', '\\', '\0']) {
+            return Err(Error::InvalidIdent {
+                ident: ident.to_owned(),
+            });
+        }
+
+        Ok(self.root.join(ident))
+    }
+
+    /// Returns a new unique file name. Names usually sort in insertion order, but the wall clock
+    /// can go backwards.
+    fn file_name(&self) -> String {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|dur| dur.as_nanos())
+            .unwrap_or_default();
+        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+
+        format!(
+            "{:032x}-{:08x}-{:08x}.{}",
+            nanos,
+            std::process::id(),
+            counter,
+            EXTENSION
+        )
+    }
+
+    /// Reads all items from the directory `dir` matching `query`, returning at most `limit`
+    /// items together with their paths.
+    fn read_items<T, D>(
+        &self,
+        dir: &Path,
+        descriptor: &D,
+        query: &Record,
+        limit: Option<usize>,
+    ) -> Result<Vec<(PathBuf, T)>, Error>
+    where
+        T: StoreData<Self>,
+        D: DataDescriptor<T, Self>,
+    {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            // Skip temporary files left behind by an interrupted insert.
+            let is_item = path.extension().is_some_and(|ext| ext == EXTENSION)
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| !name.starts_with('.'));
+
+            if is_item {
+                paths.push(path);
+            }
+        }
+
+        paths.sort();
+
+        let mut items = Vec::new();
+        for path in paths {
+            if limit.is_some_and(|limit| items.len() >= limit) {
+                break;
+            }
+
+            let buf = match fs::read_to_string(&path) {
+                Ok(buf) => buf,
+                // The item was deleted concurrently.
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            let item = descriptor.read(&mut JsonReader::new(&buf)?)?;
+
+            if matches(&item, query)? {
+                items.push((path, item));
+            }
+        }
+
+        Ok(items)
+    }
+}
+
+/// Registers [`FileStore`] for the `file` scheme, allowing [`datastore::connect`] to connect to
+/// `file://` URIs.
+pub fn register() {
+    datastore::registry::register::<FileStore>("file");
+}
+
+#[async_trait]
+impl Store for FileStore {
+    type DataStore = Self;
+    type Error = Error;
+
+    /// Opens the `FileStore` in the directory `uri`, optionally prefixed with `file://`.
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        Self::open(uri.strip_prefix("file://").unwrap_or(uri))
+    }
+
+    /// Opens the `FileStore` in the directory made of the host and path of the `options`.
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        Self::open(options.host_path())
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let dir = self.dir(descriptor.ident())?;
+        fs::create_dir_all(&dir)?;
+        sync_dir(&self.root)?;
+
+        Ok(())
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        let dir = self.dir(descriptor.ident())?;
+        let items = self.read_items(&dir, &descriptor, &record, None)?;
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        for (path, _) in items {
+            match fs::remove_file(&path) {
+                Ok(()) => (),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        sync_dir(&dir)
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        let dir = self.dir(descriptor.ident())?;
+        let items = self.read_items(&dir, &descriptor, &record, None)?;
+        Ok(items.into_iter().map(|(_, item)| item).collect())
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let dir = self.dir(descriptor.ident())?;
+        let items = self.read_items(&dir, &descriptor, &Record::new(), None)?;
+        Ok(items.into_iter().map(|(_, item)| item).collect())
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        let dir = self.dir(descriptor.ident())?;
+        let items = self.read_items(&dir, &descriptor, &record, Some(1))?;
+        Ok(items.into_iter().next().map(|(_, item)| item))
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let buf = json::to_string(&data)?;
+
+        let dir = self.dir(descriptor.ident())?;
+        let name = self.file_name();
+        let tmp = dir.join(format!(".{}.tmp", name));
+
+        let res = (|| {
+            let mut file = File::options().write(true).create_new(true).open(&tmp)?;
+            file.write_all(buf.as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp, dir.join(&name))
+        })();
+
+        if let Err(err) = res {
+            let _ = fs::remove_file(&tmp);
+            return Err(err.into());
+        }
+
+        sync_dir(&dir)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::WRITE
+    }
+}
+
+/// Returns `true` if all fields of `query` are equal to the fields of `item`.
+fn matches<T>(item: &T, query: &Record) -> Result<bool, Error>
+where
+    T: StoreData<FileStore>,
+{
+    if query.is_empty() {
+        return Ok(true);
+    }
+
+    let item = Record::from_data(item)?;
+    Ok(query
+        .iter()
+        .all(|(key, value)| item.get(key) == Some(value)))
+}
+
+/// Flushes the entries of the directory `path` to disk.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> Result<(), Error> {
+    File::open(path)?.sync_all()?;
+    Ok(())
+}
+
+/// Directories cannot be opened as files on this platform. Renames are durable without syncing
+/// the directory.
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+/// An error returned by [`FileStore`].
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O error.
+    Io(io::Error),
+    /// An item could not be encoded or decoded.
+    Json(json::Error),
+    /// A query or item could not be captured.
+    Value(value::Error),
+    /// The identifier `ident` is not a valid directory name.
+    InvalidIdent { ident: String },
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => Display::fmt(err, f),
+            Self::Json(err) => Display::fmt(err, f),
+            Self::Value(err) => Display::fmt(err, f),
+            Self::InvalidIdent { ident } => write!(f, "invalid identifier {:?}", ident),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Value(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl datastore::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<json::Error> for Error {
+    fn from(err: json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<value::Error> for Error {
+    fn from(err: value::Error) -> Self {
+        Self::Value(err)
+    }
+}
+
+macro_rules! impl_types {
+    ($($ty:ty => $write:ident, $read:ident;)*) => {
+        $(
+            impl Write<FileStore> for $ty {
+                #[inline]
+                fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: Writer<FileStore>,
+                {
+                    writer.$write(*self)
+                }
+
+                #[inline]
+                fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: TypeWriter<FileStore>,
+                {
+                    writer.$write()
+                }
+            }
+
+            impl Read<FileStore> for $ty {
+                #[inline]
+                fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+                where
+                    R: Reader<FileStore>,
+                {
+                    reader.$read()
+                }
+            }
+        )*
+    };
+}
+
+impl_types! {
+    bool => write_bool, read_bool;
+    i8 => write_i8, read_i8;
+    i16 => write_i16, read_i16;
+    i32 => write_i32, read_i32;
+    i64 => write_i64, read_i64;
+    u8 => write_u8, read_u8;
+    u16 => write_u16, read_u16;
+    u32 => write_u32, read_u32;
+    u64 => write_u64, read_u64;
+    f32 => write_f32, read_f32;
+    f64 => write_f64, read_f64;
+}
+
+impl Write<FileStore> for str {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<FileStore>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<FileStore>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Write<FileStore> for String {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<FileStore>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<FileStore>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Read<FileStore> for String {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<FileStore>,
+    {
+        reader.read_string()
+    }
+}
+
+impl Write<FileStore> for [u8] {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<FileStore>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<FileStore>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Write<FileStore> for Vec<u8> {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<FileStore>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<FileStore>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Read<FileStore> for Vec<u8> {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<FileStore>,
+    {
+        reader.read_byte_buf()
+    }
+}
@@ -0,0 +1,225 @@
+use std::fs;
+use std::path::PathBuf;
+
+use datastore::value::{Record, RecordDescriptor, RecordQuery, Type};
+use datastore::{Capabilities, ConnectOptions, Store, StoreData, StoreExt};
+use datastore_fs::{Error, FileStore};
+
+#[derive(Clone, Debug, PartialEq, StoreData)]
+struct Person {
+    id: i64,
+    name: String,
+    age: u8,
+    active: bool,
+}
+
+fn people() -> Vec<Person> {
+    vec![
+        Person {
+            id: 1,
+            name: "Alice".to_owned(),
+            age: 30,
+            active: true,
+        },
+        Person {
+            id: 2,
+            name: "Bob".to_owned(),
+            age: 30,
+            active: false,
+        },
+        Person {
+            id: 3,
+            name: "Carol".to_owned(),
+            age: 42,
+            active: true,
+        },
+    ]
+}
+
+/// A temporary directory that is removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("datastore-fs-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+async fn store(dir: &TempDir) -> FileStore {
+    let store = FileStore::open(&dir.0).unwrap();
+    store.create(store.descriptor::<Person>()).await.unwrap();
+
+    for person in people() {
+        store
+            .insert(store.descriptor::<Person>(), person)
+            .await
+            .unwrap();
+    }
+
+    store
+}
+
+#[tokio::test]
+async fn test_fs_get_all() {
+    let dir = TempDir::new("get_all");
+    let store = store(&dir).await;
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people());
+
+    let files = fs::read_dir(dir.0.join("Person")).unwrap().count();
+    assert_eq!(files, 3);
+}
+
+#[tokio::test]
+async fn test_fs_capabilities() {
+    let dir = TempDir::new("capabilities");
+    let store = store(&dir).await;
+
+    // The order of items depends on the wall clock, so it is not guaranteed.
+    assert_eq!(store.capabilities(), Capabilities::WRITE);
+}
+
+#[tokio::test]
+async fn test_fs_get() {
+    let dir = TempDir::new("get");
+    let store = store(&dir).await;
+
+    let output = store
+        .get(store.descriptor::<Person>(), PersonQuery::default().age(30))
+        .await
+        .unwrap();
+    assert_eq!(output, people()[..2]);
+
+    let output = store
+        .get(
+            store.descriptor::<Person>(),
+            PersonQuery::default().age(30).active(true),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output, people()[..1]);
+
+    let output = store
+        .get_one(
+            store.descriptor::<Person>(),
+            PersonQuery::default().name("Carol".to_owned()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output, Some(people().remove(2)));
+
+    let output = store
+        .get_one(store.descriptor::<Person>(), PersonQuery::default().id(4))
+        .await
+        .unwrap();
+    assert_eq!(output, None);
+}
+
+#[tokio::test]
+async fn test_fs_delete() {
+    let dir = TempDir::new("delete");
+    let store = store(&dir).await;
+
+    store
+        .delete(
+            store.descriptor::<Person>(),
+            PersonQuery::default().active(true),
+        )
+        .await
+        .unwrap();
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people()[1..2]);
+
+    let files = fs::read_dir(dir.0.join("Person")).unwrap().count();
+    assert_eq!(files, 1);
+}
+
+#[tokio::test]
+async fn test_fs_reopen() {
+    let dir = TempDir::new("reopen");
+    drop(store(&dir).await);
+
+    let store = FileStore::connect(&format!("file://{}", dir.0.display()))
+        .await
+        .unwrap();
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people());
+}
+
+#[tokio::test]
+async fn test_fs_ignore_temporary_files() {
+    let dir = TempDir::new("temporary");
+    let store = store(&dir).await;
+
+    // A write interrupted before the rename.
+    fs::write(dir.0.join("Person").join(".partial.json.tmp"), "{\"id\":").unwrap();
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people());
+}
+
+#[tokio::test]
+async fn test_fs_invalid_ident() {
+    let dir = TempDir::new("ident");
+    let store = FileStore::open(&dir.0).unwrap();
+
+    for ident in ["", "..", "a/b"] {
+        let err = store
+            .create(RecordDescriptor::new(ident))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidIdent { .. }));
+    }
+}
+
+#[tokio::test]
+async fn test_fs_record() {
+    let dir = TempDir::new("record");
+    let store = FileStore::open(&dir.0).unwrap();
+
+    let descriptor = RecordDescriptor::new("record")
+        .field("id", Type::I64)
+        .field("name", Type::String);
+    store.create(descriptor.clone()).await.unwrap();
+
+    let record: Record = [("id", 1i64.into()), ("name", "Alice".into())]
+        .into_iter()
+        .collect();
+    store
+        .insert(descriptor.clone(), record.clone())
+        .await
+        .unwrap();
+
+    let output = store
+        .get_one(descriptor, RecordQuery::new().field("name", "Alice"))
+        .await
+        .unwrap();
+    assert_eq!(output, Some(record));
+}
+
+#[tokio::test]
+async fn test_fs_connect_with() {
+    let dir = TempDir::new("connect with spaces");
+    let path = dir.0.to_str().unwrap();
+
+    // The path is decoded before opening the store.
+    let options = ConnectOptions::new("file").with_path(path);
+    assert!(options.to_string().contains("%20"));
+    let store = FileStore::connect_with(&options).await.unwrap();
+    store.create(store.descriptor::<Person>()).await.unwrap();
+    assert!(dir.0.exists());
+
+    let options = ConnectOptions::parse(&format!("file://{}", path.replace(' ', "%20"))).unwrap();
+    FileStore::connect_with(&options).await.unwrap();
+}
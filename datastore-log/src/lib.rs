@@ -0,0 +1,826 @@
+//! A log-structured store for [`datastore`].
+//!
+//! [`LogStore`] appends every [`Store::insert`] and [`Store::delete`] as an entry to a segment
+//! file in its directory. Items are encoded using the [`binary`] format. An in-memory index maps
+//! every live item to its position in the log, so reads never replay the log. The index is
+//! rebuilt by replaying all segments when the store is opened.
+//!
+//! ```
+//! use datastore::{Store, StoreData, StoreExt};
+//! use datastore_log::LogStore;
+//!
+//! #[derive(StoreData)]
+//! struct Event {
+//!     id: u64,
+//!     kind: String,
+//! }
+//!
+//! # async fn run() -> Result<(), datastore_log::Error> {
+//! let store = LogStore::connect("log://./events").await?;
+//!
+//! let event = Event {
+//!     id: 1,
+//!     kind: String::from("login"),
+//! };
+//! store.insert(store.descriptor::<Event>(), event).await?;
+//!
+//! // Reclaim the space of deleted items.
+//! store.compact()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Durability
+//!
+//! Every entry is synced to disk before the operation returns. Entries carry a CRC-32 checksum.
+//! When opening the store, an incomplete entry at the end of the newest segment, left behind by
+//! an interrupted write, is truncated. Any other invalid entry results in
+//! [`Error::Corrupted`].
+//!
+//! # Compaction
+//!
+//! Deleted items keep occupying space in the log until [`LogStore::compact`] is called. It
+//! rewrites all live items into a new segment and removes all older segments.
+//!
+//! [`binary`]: datastore::binary
+mod segment;
+
+use std::collections::{BTreeMap, HashMap};
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Read as _, Seek, SeekFrom, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use datastore::binary::{self, BinaryReader};
+use datastore::value::{self, Record};
+use datastore::{
+    Capabilities, ConnectOptions, DataDescriptor, DataQuery, Read, Reader, Store, StoreData,
+    TypeWriter, Write, Writer,
+};
+
+use self::segment::{Entry, Frame};
+
+/// The file extension of segment files.
+const EXTENSION: &str = "log";
+
+/// The default size after which a new segment is started.
+const DEFAULT_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A log-structured [`Store`].
+#[derive(Debug)]
+pub struct LogStore {
+    dir: PathBuf,
+    segment_size: u64,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    /// All segments in log order. The last segment is the active segment.
+    segments: BTreeMap<u64, Segment>,
+    /// The position of all live items by their identifier and id.
+    index: HashMap<String, BTreeMap<u64, Location>>,
+    next_id: u64,
+}
+
+#[derive(Debug)]
+struct Segment {
+    file: File,
+    len: u64,
+}
+
+/// The position of the encoded item in a segment.
+#[derive(Copy, Clone, Debug)]
+struct Location {
+    segment: u64,
+    offset: u64,
+    len: usize,
+}
+
+impl LogStore {
+    /// Opens the `LogStore` in the directory `dir`, creating it if it does not exist.
+    ///
+    /// All segments in the directory are replayed to rebuild the index.
+    pub fn open<P>(dir: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref().to_owned();
+        fs::create_dir_all(&dir)?;
+
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+
+            match path.extension().and_then(|ext| ext.to_str()) {
+                // Remove segments of an interrupted compaction.
+                Some("tmp") => fs::remove_file(&path)?,
+                Some(EXTENSION) => {
+                    if let Some(id) = path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .and_then(|stem| u64::from_str_radix(stem, 16).ok())
+                    {
+                        ids.push(id);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        ids.sort_unstable();
+
+        let mut inner = Inner {
+            segments: BTreeMap::new(),
+            index: HashMap::new(),
+            next_id: 0,
+        };
+
+        for (index, id) in ids.iter().enumerate() {
+            let is_last = index == ids.len() - 1;
+            inner.replay(&dir, *id, is_last)?;
+        }
+
+        if inner.segments.is_empty() {
+            inner.create_segment(&dir, 0)?;
+        }
+
+        Ok(Self {
+            dir,
+            segment_size: DEFAULT_SEGMENT_SIZE,
+            inner: Mutex::new(inner),
+        })
+    }
+
+    /// Sets the size in bytes after which a new segment is started. The default is 64 MiB.
+    #[inline]
+    pub fn with_segment_size(mut self, segment_size: u64) -> Self {
+        self.segment_size = segment_size;
+        self
+    }
+
+    /// Returns the directory of the `LogStore`.
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the number of segments.
+    pub fn segments(&self) -> usize {
+        self.inner().segments.len()
+    }
+
+    /// Rewrites all live items into a new segment and removes all older segments.
+    ///
+    /// The new segment is written to a temporary file and only renamed once it has been synced.
+    /// If compaction is interrupted the log remains valid: leftover temporary files are removed
+    /// and replaying the remaining older segments before the new one yields the same items.
+    pub fn compact(&self) -> Result<(), Error> {
+        let mut inner = self.inner();
+        let inner = &mut *inner;
+
+        let id = inner.active_id() + 1;
+
+        let mut buf = Vec::new();
+        let mut index = HashMap::with_capacity(inner.index.len());
+        for (ident, items) in &inner.index {
+            let mut locations = BTreeMap::new();
+            for (item, location) in items {
+                let data = inner.read(location)?;
+
+                let offset = buf.len();
+                let data_offset = Entry::Insert {
+                    id: *item,
+                    ident,
+                    data: &data,
+                }
+                .encode(&mut buf);
+
+                locations.insert(
+                    *item,
+                    Location {
+                        segment: id,
+                        offset: (offset + data_offset) as u64,
+                        len: data.len(),
+                    },
+                );
+            }
+
+            index.insert(ident.clone(), locations);
+        }
+
+        let tmp = self.dir.join(format!("{:016x}.tmp", id));
+        let file = (|| -> Result<File, Error> {
+            let mut file = File::options()
+                .read(true)
+                .append(true)
+                .create_new(true)
+                .open(&tmp)?;
+            file.write_all(&buf)?;
+            file.sync_all()?;
+
+            // The open file stays valid after the rename.
+            fs::rename(&tmp, segment_path(&self.dir, id))?;
+            Ok(file)
+        })();
+
+        let file = match file {
+            Ok(file) => file,
+            Err(err) => {
+                // A leftover temporary file would make the next compaction fail.
+                let _ = fs::remove_file(&tmp);
+                return Err(err);
+            }
+        };
+
+        // The new segment is in place, so it becomes the active segment even if the steps
+        // below fail. Older segments stay in the log until they have been removed.
+        inner.segments.insert(
+            id,
+            Segment {
+                file,
+                len: buf.len() as u64,
+            },
+        );
+        inner.index = index;
+
+        sync_dir(&self.dir)?;
+
+        // Older segments are removed oldest first. An interruption leaves a suffix of the old
+        // log in place, which still replays correctly.
+        let old: Vec<u64> = inner.segments.range(..id).map(|(id, _)| *id).collect();
+        for id in old {
+            inner.segments.remove(&id);
+            fs::remove_file(segment_path(&self.dir, id))?;
+        }
+
+        sync_dir(&self.dir)
+    }
+
+    fn inner(&self) -> MutexGuard<'_, Inner> {
+        // A panic while holding the lock cannot leave the index in an inconsistent state since
+        // it is only updated after an entry has been written.
+        match self.inner.lock() {
+            Ok(inner) => inner,
+            Err(err) => err.into_inner(),
+        }
+    }
+
+    /// Appends the `entry` to the active segment, starting a new segment if the active segment
+    /// is full. Returns the location of the item data for insert entries.
+    fn append(&self, inner: &mut Inner, entry: &Entry<'_>) -> Result<Location, Error> {
+        let active = inner.active_id();
+        let len = inner.segments[&active].len;
+        if len > 0 && len >= self.segment_size {
+            inner.create_segment(&self.dir, active + 1)?;
+        }
+
+        let mut buf = Vec::new();
+        let data_offset = entry.encode(&mut buf);
+
+        let id = inner.active_id();
+        let segment = inner.segments.get_mut(&id).unwrap();
+
+        let offset = segment.len;
+        if let Err(err) = segment
+            .file
+            .write_all(&buf)
+            .and_then(|_| segment.file.sync_data())
+        {
+            // Remove a partially written entry so that later entries stay readable.
+            let _ = segment.file.set_len(offset);
+            return Err(err.into());
+        }
+        segment.len += buf.len() as u64;
+
+        Ok(Location {
+            segment: id,
+            offset: offset + data_offset as u64,
+            len: buf.len() - data_offset,
+        })
+    }
+
+    /// Returns all items described by `descriptor` matching the `query` together with their
+    /// ids, returning at most `limit` items.
+    fn read_items<T, D>(
+        &self,
+        inner: &mut Inner,
+        descriptor: &D,
+        query: &Record,
+        limit: Option<usize>,
+    ) -> Result<Vec<(u64, T)>, Error>
+    where
+        T: StoreData<Self>,
+        D: DataDescriptor<T, Self>,
+    {
+        let locations: Vec<(u64, Location)> = match inner.index.get(descriptor.ident()) {
+            Some(items) => items.iter().map(|(id, loc)| (*id, *loc)).collect(),
+            None => return Ok(Vec::new()),
+        };
+
+        let mut items = Vec::new();
+        for (id, location) in locations {
+            if limit.is_some_and(|limit| items.len() >= limit) {
+                break;
+            }
+
+            let data = inner.read(&location)?;
+            let item = descriptor.read(&mut BinaryReader::new(&data)?)?;
+
+            if matches(&item, query)? {
+                items.push((id, item));
+            }
+        }
+
+        Ok(items)
+    }
+}
+
+impl Inner {
+    /// Returns the id of the active segment.
+    fn active_id(&self) -> u64 {
+        *self.segments.keys().next_back().unwrap()
+    }
+
+    fn create_segment(&mut self, dir: &Path, id: u64) -> Result<(), Error> {
+        let file = File::options()
+            .read(true)
+            .append(true)
+            .create_new(true)
+            .open(segment_path(dir, id))?;
+        sync_dir(dir)?;
+
+        self.segments.insert(id, Segment { file, len: 0 });
+        Ok(())
+    }
+
+    /// Replays the segment `id` into the index. A torn entry at the end of the last segment is
+    /// truncated.
+    fn replay(&mut self, dir: &Path, id: u64, is_last: bool) -> Result<(), Error> {
+        let mut file = File::options()
+            .read(true)
+            .append(true)
+            .open(segment_path(dir, id))?;
+
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+
+        let mut offset = 0;
+        while offset < buf.len() {
+            let (payload, len) = match segment::read_frame(&buf[offset..]) {
+                Frame::Complete { payload, len } => (payload, len),
+                Frame::Torn if is_last => {
+                    file.set_len(offset as u64)?;
+                    file.sync_all()?;
+                    break;
+                }
+                Frame::Torn | Frame::Corrupted => {
+                    return Err(Error::Corrupted {
+                        segment: id,
+                        offset: offset as u64,
+                    })
+                }
+            };
+
+            let entry = Entry::decode(payload).ok_or(Error::Corrupted {
+                segment: id,
+                offset: offset as u64,
+            })?;
+
+            match entry {
+                Entry::Insert {
+                    id: item,
+                    ident,
+                    data,
+                } => {
+                    let data_offset = data.as_ptr() as usize - buf.as_ptr() as usize;
+
+                    self.index.entry(ident.to_owned()).or_default().insert(
+                        item,
+                        Location {
+                            segment: id,
+                            offset: data_offset as u64,
+                            len: data.len(),
+                        },
+                    );
+
+                    self.next_id = self.next_id.max(item + 1);
+                }
+                Entry::Delete { ident, ids } => {
+                    if let Some(items) = self.index.get_mut(ident) {
+                        for item in ids {
+                            items.remove(&item);
+                        }
+                    }
+                }
+            }
+
+            offset += len;
+        }
+
+        let len = offset as u64;
+        self.segments.insert(id, Segment { file, len });
+        Ok(())
+    }
+
+    /// Reads the encoded item at `location`.
+    fn read(&self, location: &Location) -> Result<Vec<u8>, Error> {
+        let mut file = &self.segments[&location.segment].file;
+
+        let mut buf = vec![0; location.len];
+        file.seek(SeekFrom::Start(location.offset))?;
+        file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+/// Registers [`LogStore`] for the `log` scheme, allowing [`datastore::connect`] to connect to
+/// `log://` URIs.
+pub fn register() {
+    datastore::registry::register::<LogStore>("log");
+}
+
+#[async_trait]
+impl Store for LogStore {
+    type DataStore = Self;
+    type Error = Error;
+
+    /// Opens the `LogStore` in the directory `uri`, optionally prefixed with `log://`.
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        Self::open(uri.strip_prefix("log://").unwrap_or(uri))
+    }
+
+    /// Opens the `LogStore` in the directory made of the host and path of the `options`.
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        Self::open(options.host_path())
+    }
+
+    /// The `LogStore` does not require any initialization.
+    async fn create<T, D>(&self, _descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        Ok(())
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        let mut inner = self.inner();
+        let ids: Vec<u64> = self
+            .read_items(&mut inner, &descriptor, &record, None)?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let ident = descriptor.ident();
+        self.append(
+            &mut inner,
+            &Entry::Delete {
+                ident,
+                ids: ids.clone(),
+            },
+        )?;
+
+        if let Some(items) = inner.index.get_mut(ident) {
+            for id in ids {
+                items.remove(&id);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        let items = self.read_items(&mut self.inner(), &descriptor, &record, None)?;
+        Ok(items.into_iter().map(|(_, item)| item).collect())
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let items = self.read_items(&mut self.inner(), &descriptor, &Record::new(), None)?;
+        Ok(items.into_iter().map(|(_, item)| item).collect())
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        let items = self.read_items(&mut self.inner(), &descriptor, &record, Some(1))?;
+        Ok(items.into_iter().next().map(|(_, item)| item))
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let ident = descriptor.ident();
+        if ident.len() > usize::from(u16::MAX) {
+            return Err(Error::InvalidIdent {
+                ident: ident.to_owned(),
+            });
+        }
+
+        let data = binary::to_vec(&data)?;
+
+        let mut inner = self.inner();
+        let id = inner.next_id;
+        let location = self.append(
+            &mut inner,
+            &Entry::Insert {
+                id,
+                ident,
+                data: &data,
+            },
+        )?;
+
+        inner.next_id += 1;
+        inner
+            .index
+            .entry(ident.to_owned())
+            .or_default()
+            .insert(id, location);
+
+        Ok(())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::WRITE | Capabilities::ORDERING
+    }
+}
+
+fn segment_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{:016x}.{}", id, EXTENSION))
+}
+
+/// Returns `true` if all fields of `query` are equal to the fields of `item`.
+fn matches<T>(item: &T, query: &Record) -> Result<bool, Error>
+where
+    T: StoreData<LogStore>,
+{
+    if query.is_empty() {
+        return Ok(true);
+    }
+
+    let item = Record::from_data(item)?;
+    Ok(query
+        .iter()
+        .all(|(key, value)| item.get(key) == Some(value)))
+}
+
+/// Flushes the entries of the directory `path` to disk.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> Result<(), Error> {
+    File::open(path)?.sync_all()?;
+    Ok(())
+}
+
+/// Directories cannot be opened as files on this platform. Renames are durable without syncing
+/// the directory.
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+/// An error returned by [`LogStore`].
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O error.
+    Io(io::Error),
+    /// An item could not be encoded or decoded.
+    Binary(binary::Error),
+    /// A query or item could not be captured.
+    Value(value::Error),
+    /// The entry at `offset` in the segment `segment` is invalid.
+    Corrupted { segment: u64, offset: u64 },
+    /// The identifier `ident` is longer than 65535 bytes.
+    InvalidIdent { ident: String },
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => Display::fmt(err, f),
+            Self::Binary(err) => Display::fmt(err, f),
+            Self::Value(err) => Display::fmt(err, f),
+            Self::Corrupted { segment, offset } => write!(
+                f,
+                "corrupted entry at offset {} in segment {:016x}",
+                offset, segment
+            ),
+            Self::InvalidIdent { ident } => write!(f, "invalid identifier {:?}", ident),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Binary(err) => Some(err),
+            Self::Value(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl datastore::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<binary::Error> for Error {
+    fn from(err: binary::Error) -> Self {
+        Self::Binary(err)
+    }
+}
+
+impl From<value::Error> for Error {
+    fn from(err: value::Error) -> Self {
+        Self::Value(err)
+    }
+}
+
+macro_rules! impl_types {
+    ($($ty:ty => $write:ident, $read:ident;)*) => {
+        $(
+            impl Write<LogStore> for $ty {
+                #[inline]
+                fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: Writer<LogStore>,
+                {
+                    writer.$write(*self)
+                }
+
+                #[inline]
+                fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: TypeWriter<LogStore>,
+                {
+                    writer.$write()
+                }
+            }
+
+            impl Read<LogStore> for $ty {
+                #[inline]
+                fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+                where
+                    R: Reader<LogStore>,
+                {
+                    reader.$read()
+                }
+            }
+        )*
+    };
+}
+
+impl_types! {
+    bool => write_bool, read_bool;
+    i8 => write_i8, read_i8;
+    i16 => write_i16, read_i16;
+    i32 => write_i32, read_i32;
+    i64 => write_i64, read_i64;
+    u8 => write_u8, read_u8;
+    u16 => write_u16, read_u16;
+    u32 => write_u32, read_u32;
+    u64 => write_u64, read_u64;
+    f32 => write_f32, read_f32;
+    f64 => write_f64, read_f64;
+}
+
+impl Write<LogStore> for str {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<LogStore>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<LogStore>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Write<LogStore> for String {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<LogStore>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<LogStore>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Read<LogStore> for String {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<LogStore>,
+    {
+        reader.read_string()
+    }
+}
+
+impl Write<LogStore> for [u8] {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<LogStore>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<LogStore>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Write<LogStore> for Vec<u8> {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<LogStore>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<LogStore>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Read<LogStore> for Vec<u8> {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<LogStore>,
+    {
+        reader.read_byte_buf()
+    }
+}
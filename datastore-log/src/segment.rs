@@ -0,0 +1,197 @@
+//! The on-disk format of log entries.
+//!
+//! Every entry is framed by a header containing the length of the payload and its CRC-32:
+//!
+//! | Bytes    | Content                                        |
+//! | -------- | ---------------------------------------------- |
+//! | 4        | The length of the payload, little endian.      |
+//! | 4        | The CRC-32 (IEEE) of the payload, little endian. |
+//! | variable | The payload.                                   |
+//!
+//! The payload starts with an opcode. An insert entry (`1`) contains the id of the item, the
+//! identifier of its type and the item encoded using [`BinaryWriter`]. A delete entry (`2`)
+//! contains the identifier and the ids of all removed items.
+//!
+//! [`BinaryWriter`]: datastore::binary::BinaryWriter
+use std::convert::TryInto;
+
+/// The length of the frame header.
+pub(crate) const HEADER_LEN: usize = 8;
+
+const OP_INSERT: u8 = 1;
+const OP_DELETE: u8 = 2;
+
+/// A decoded log entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Entry<'a> {
+    Insert {
+        id: u64,
+        ident: &'a str,
+        data: &'a [u8],
+    },
+    Delete {
+        ident: &'a str,
+        ids: Vec<u64>,
+    },
+}
+
+impl<'a> Entry<'a> {
+    /// Appends the framed entry to `buf`. Returns the offset of the item data relative to the
+    /// start of the frame for insert entries.
+    ///
+    /// The identifier must be at most [`u16::MAX`] bytes long.
+    pub(crate) fn encode(&self, buf: &mut Vec<u8>) -> usize {
+        let start = buf.len();
+        buf.extend_from_slice(&[0; HEADER_LEN]);
+
+        let mut data_offset = 0;
+        match self {
+            Self::Insert { id, ident, data } => {
+                buf.push(OP_INSERT);
+                buf.extend_from_slice(&id.to_le_bytes());
+                encode_ident(ident, buf);
+
+                data_offset = buf.len() - start;
+                buf.extend_from_slice(data);
+            }
+            Self::Delete { ident, ids } => {
+                buf.push(OP_DELETE);
+                encode_ident(ident, buf);
+
+                for id in ids {
+                    buf.extend_from_slice(&id.to_le_bytes());
+                }
+            }
+        }
+
+        let payload = &buf[start + HEADER_LEN..];
+        let len = payload.len() as u32;
+        let crc = crc32(payload);
+
+        buf[start..start + 4].copy_from_slice(&len.to_le_bytes());
+        buf[start + 4..start + 8].copy_from_slice(&crc.to_le_bytes());
+
+        data_offset
+    }
+
+    /// Decodes the entry from the `payload` of a frame. Returns `None` if the payload is
+    /// malformed.
+    pub(crate) fn decode(payload: &'a [u8]) -> Option<Self> {
+        let (op, rest) = payload.split_first()?;
+
+        match *op {
+            OP_INSERT => {
+                let (id, rest) = split_u64(rest)?;
+                let (ident, data) = decode_ident(rest)?;
+
+                Some(Self::Insert { id, ident, data })
+            }
+            OP_DELETE => {
+                let (ident, mut rest) = decode_ident(rest)?;
+
+                if rest.len() % 8 != 0 {
+                    return None;
+                }
+
+                let mut ids = Vec::with_capacity(rest.len() / 8);
+                while !rest.is_empty() {
+                    let (id, tail) = split_u64(rest)?;
+                    ids.push(id);
+                    rest = tail;
+                }
+
+                Some(Self::Delete { ident, ids })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The result of reading a frame from a segment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Frame<'a> {
+    /// A complete frame with a valid checksum. `len` is the length of the whole frame.
+    Complete { payload: &'a [u8], len: usize },
+    /// The frame was cut off by the end of the segment. This happens when a write was
+    /// interrupted.
+    Torn,
+    /// The frame is complete but its checksum does not match.
+    Corrupted,
+}
+
+/// Reads the frame at the start of `buf`. `buf` must not be empty.
+pub(crate) fn read_frame(buf: &[u8]) -> Frame<'_> {
+    if buf.len() < HEADER_LEN {
+        return Frame::Torn;
+    }
+
+    let len = u32::from_le_bytes(buf[0..4].try_into().unwrap()) as usize;
+    let crc = u32::from_le_bytes(buf[4..8].try_into().unwrap());
+
+    let payload = match buf[HEADER_LEN..].get(..len) {
+        Some(payload) => payload,
+        None => return Frame::Torn,
+    };
+
+    if crc32(payload) != crc {
+        // A frame reaching exactly to the end of the segment may have been partially written.
+        if HEADER_LEN + len == buf.len() {
+            return Frame::Torn;
+        }
+
+        return Frame::Corrupted;
+    }
+
+    Frame::Complete {
+        payload,
+        len: HEADER_LEN + len,
+    }
+}
+
+fn encode_ident(ident: &str, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&(ident.len() as u16).to_le_bytes());
+    buf.extend_from_slice(ident.as_bytes());
+}
+
+fn decode_ident(buf: &[u8]) -> Option<(&str, &[u8])> {
+    if buf.len() < 2 {
+        return None;
+    }
+
+    let (len, rest) = buf.split_at(2);
+    let len = u16::from_le_bytes(len.try_into().unwrap()) as usize;
+
+    if rest.len() < len {
+        return None;
+    }
+
+    let (ident, rest) = rest.split_at(len);
+    Some((std::str::from_utf8(ident).ok()?, rest))
+}
+
+fn split_u64(buf: &[u8]) -> Option<(u64, &[u8])> {
+    if buf.len() < 8 {
+        return None;
+    }
+
+    let (v, rest) = buf.split_at(8);
+    Some((u64::from_le_bytes(v.try_into().unwrap()), rest))
+}
+
+/// Computes the CRC-32 (IEEE 802.3) checksum of `buf`.
+fn crc32(buf: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for b in buf {
+        crc ^= u32::from(*b);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
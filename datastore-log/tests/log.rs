@@ -0,0 +1,291 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use datastore::{ConnectOptions, Store, StoreData, StoreExt};
+use datastore_log::{Error, LogStore};
+
+#[derive(Clone, Debug, PartialEq, StoreData)]
+struct Event {
+    id: u64,
+    kind: String,
+    payload: Vec<u8>,
+}
+
+fn events() -> Vec<Event> {
+    vec![
+        Event {
+            id: 1,
+            kind: "login".to_owned(),
+            payload: vec![1],
+        },
+        Event {
+            id: 2,
+            kind: "logout".to_owned(),
+            payload: vec![2, 2],
+        },
+        Event {
+            id: 3,
+            kind: "login".to_owned(),
+            payload: vec![3, 3, 3],
+        },
+    ]
+}
+
+/// A temporary directory that is removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("datastore-log-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+async fn store(dir: &TempDir) -> LogStore {
+    let store = LogStore::open(&dir.0).unwrap();
+    store.create(store.descriptor::<Event>()).await.unwrap();
+
+    for event in events() {
+        store
+            .insert(store.descriptor::<Event>(), event)
+            .await
+            .unwrap();
+    }
+
+    store
+}
+
+/// Returns the paths of all segments in `dir` in log order.
+fn segments(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[tokio::test]
+async fn test_log_get() {
+    let dir = TempDir::new("get");
+    let store = store(&dir).await;
+
+    let output: Vec<Event> = store.get_all(store.descriptor::<Event>()).await.unwrap();
+    assert_eq!(output, events());
+
+    let output = store
+        .get(
+            store.descriptor::<Event>(),
+            EventQuery::default().kind("login".to_owned()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output, [events().remove(0), events().remove(2)]);
+
+    let output = store
+        .get_one(store.descriptor::<Event>(), EventQuery::default().id(2))
+        .await
+        .unwrap();
+    assert_eq!(output, Some(events().remove(1)));
+}
+
+#[tokio::test]
+async fn test_log_delete_replay() {
+    let dir = TempDir::new("replay");
+    let store = store(&dir).await;
+
+    store
+        .delete(
+            store.descriptor::<Event>(),
+            EventQuery::default().kind("login".to_owned()),
+        )
+        .await
+        .unwrap();
+    drop(store);
+
+    let store = LogStore::connect(&format!("log://{}", dir.0.display()))
+        .await
+        .unwrap();
+    let output: Vec<Event> = store.get_all(store.descriptor::<Event>()).await.unwrap();
+    assert_eq!(output, events()[1..2]);
+
+    // Ids continue after the replayed entries.
+    store
+        .insert(store.descriptor::<Event>(), events().remove(0))
+        .await
+        .unwrap();
+    let output: Vec<Event> = store.get_all(store.descriptor::<Event>()).await.unwrap();
+    assert_eq!(output, [events().remove(1), events().remove(0)]);
+}
+
+#[tokio::test]
+async fn test_log_torn_record() {
+    let dir = TempDir::new("torn");
+    drop(store(&dir).await);
+
+    let segment = segments(&dir.0).pop().unwrap();
+    let len = fs::metadata(&segment).unwrap().len();
+
+    // Simulate a crash in the middle of writing the final entry.
+    let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+    file.write_all(&[64, 0, 0, 0, 1, 2, 3, 4, 1, 0]).unwrap();
+    drop(file);
+
+    let store = LogStore::open(&dir.0).unwrap();
+    assert_eq!(fs::metadata(&segment).unwrap().len(), len);
+
+    let output: Vec<Event> = store.get_all(store.descriptor::<Event>()).await.unwrap();
+    assert_eq!(output, events());
+
+    store
+        .insert(store.descriptor::<Event>(), events().remove(0))
+        .await
+        .unwrap();
+    drop(store);
+
+    let store = LogStore::open(&dir.0).unwrap();
+    let output: Vec<Event> = store.get_all(store.descriptor::<Event>()).await.unwrap();
+    assert_eq!(output.len(), 4);
+}
+
+#[tokio::test]
+async fn test_log_corrupted_record() {
+    let dir = TempDir::new("corrupted");
+    drop(store(&dir).await);
+
+    let segment = segments(&dir.0).pop().unwrap();
+    let mut buf = fs::read(&segment).unwrap();
+    buf[12] ^= 0xff;
+    fs::write(&segment, buf).unwrap();
+
+    let err = LogStore::open(&dir.0).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Corrupted {
+            segment: 0,
+            offset: 0
+        }
+    ));
+}
+
+#[tokio::test]
+async fn test_log_segments() {
+    let dir = TempDir::new("segments");
+    let store = LogStore::open(&dir.0).unwrap().with_segment_size(1);
+
+    for event in events() {
+        store
+            .insert(store.descriptor::<Event>(), event)
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(store.segments(), 3);
+    assert_eq!(segments(&dir.0).len(), 3);
+    drop(store);
+
+    let store = LogStore::open(&dir.0).unwrap();
+    let output: Vec<Event> = store.get_all(store.descriptor::<Event>()).await.unwrap();
+    assert_eq!(output, events());
+}
+
+#[tokio::test]
+async fn test_log_compact() {
+    let dir = TempDir::new("compact");
+    let store = store(&dir).await.with_segment_size(1);
+
+    store
+        .delete(store.descriptor::<Event>(), EventQuery::default().id(1))
+        .await
+        .unwrap();
+
+    let size = |dir: &Path| -> u64 {
+        segments(dir)
+            .iter()
+            .map(|path| fs::metadata(path).unwrap().len())
+            .sum()
+    };
+
+    let before = size(&dir.0);
+    store.compact().unwrap();
+
+    assert_eq!(store.segments(), 1);
+    assert_eq!(segments(&dir.0).len(), 1);
+    assert!(size(&dir.0) < before);
+
+    let output: Vec<Event> = store.get_all(store.descriptor::<Event>()).await.unwrap();
+    assert_eq!(output, events()[1..]);
+
+    store
+        .insert(store.descriptor::<Event>(), events().remove(0))
+        .await
+        .unwrap();
+    drop(store);
+
+    let store = LogStore::open(&dir.0).unwrap();
+    let output: Vec<Event> = store.get_all(store.descriptor::<Event>()).await.unwrap();
+    assert_eq!(
+        output,
+        [events().remove(1), events().remove(2), events().remove(0)]
+    );
+}
+
+#[tokio::test]
+async fn test_log_interrupted_compaction() {
+    let dir = TempDir::new("interrupted");
+    drop(store(&dir).await);
+
+    // A compaction that crashed before renaming its segment.
+    fs::write(dir.0.join("0000000000000001.tmp"), [1, 2, 3]).unwrap();
+
+    let store = LogStore::open(&dir.0).unwrap();
+    assert_eq!(segments(&dir.0).len(), 1);
+
+    let output: Vec<Event> = store.get_all(store.descriptor::<Event>()).await.unwrap();
+    assert_eq!(output, events());
+}
+
+#[tokio::test]
+async fn test_log_failed_compaction() {
+    let dir = TempDir::new("failed");
+    let store = store(&dir).await;
+
+    // The new segment cannot be renamed over a directory.
+    let blocker = dir.0.join("0000000000000001.log");
+    fs::create_dir(&blocker).unwrap();
+    assert!(matches!(store.compact(), Err(Error::Io(_))));
+    assert!(!dir.0.join("0000000000000001.tmp").exists());
+    assert_eq!(store.segments(), 1);
+
+    fs::remove_dir(&blocker).unwrap();
+    store.compact().unwrap();
+    assert_eq!(segments(&dir.0), [blocker]);
+
+    let output: Vec<Event> = store.get_all(store.descriptor::<Event>()).await.unwrap();
+    assert_eq!(output, events());
+}
+
+#[tokio::test]
+async fn test_log_connect_with() {
+    let dir = TempDir::new("connect with spaces");
+    let path = dir.0.to_str().unwrap();
+
+    // The path is decoded before opening the store.
+    let options = ConnectOptions::new("log").with_path(path);
+    assert!(options.to_string().contains("%20"));
+    let store = LogStore::connect_with(&options).await.unwrap();
+    store.create(store.descriptor::<Event>()).await.unwrap();
+    assert!(dir.0.exists());
+
+    let options = ConnectOptions::parse(&format!("log://{}", path.replace(' ', "%20"))).unwrap();
+    LogStore::connect_with(&options).await.unwrap();
+}
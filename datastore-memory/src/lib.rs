@@ -0,0 +1,379 @@
+//! An in-memory store for [`datastore`].
+//!
+//! [`MemoryStore`] keeps all items as [`Record`]s in memory. Nothing is persisted, which makes it
+//! a good fit for tests and prototypes.
+//!
+//! ```
+//! use datastore::{Store, StoreData, StoreExt};
+//! use datastore_memory::MemoryStore;
+//!
+//! #[derive(StoreData)]
+//! struct Person {
+//!     id: i64,
+//!     name: String,
+//! }
+//!
+//! # async fn run() -> Result<(), datastore_memory::Error> {
+//! let store = MemoryStore::new();
+//! store.create(store.descriptor::<Person>()).await?;
+//!
+//! let person = Person {
+//!     id: 1,
+//!     name: String::from("Robb"),
+//! };
+//! store.insert(store.descriptor::<Person>(), person).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Queries
+//!
+//! [`DataQuery`]s are evaluated by comparing the fields written by the query for equality with
+//! every stored item of the type.
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use datastore::value::{self, Record};
+use datastore::{
+    Capabilities, DataDescriptor, DataQuery, Read, Reader, Store, StoreData, TypeWriter, Write,
+    Writer,
+};
+
+/// A [`Store`] keeping all items in memory.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    /// All items by their identifier in insertion order.
+    tables: Mutex<HashMap<String, Vec<Record>>>,
+}
+
+impl MemoryStore {
+    /// Creates a new, empty `MemoryStore`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn tables(&self) -> MutexGuard<'_, HashMap<String, Vec<Record>>> {
+        // Tables are only updated by single operations that cannot panic halfway.
+        self.tables.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Reads all items with the identifier of `descriptor` matching `query`, returning at most
+    /// `limit` items.
+    fn read_items<T, D>(
+        &self,
+        descriptor: &D,
+        query: &Record,
+        limit: Option<usize>,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: StoreData<Self>,
+        D: DataDescriptor<T, Self>,
+    {
+        // Records are cloned so that items are decoded without holding the lock.
+        let records: Vec<Record> = match self.tables().get(descriptor.ident()) {
+            Some(records) => records
+                .iter()
+                .filter(|record| matches(record, query))
+                .take(limit.unwrap_or(usize::MAX))
+                .cloned()
+                .collect(),
+            None => return Ok(Vec::new()),
+        };
+
+        records
+            .into_iter()
+            .map(|mut record| Ok(descriptor.read(&mut record)?))
+            .collect()
+    }
+}
+
+/// Registers [`MemoryStore`] for the `memory` scheme, allowing [`datastore::connect`] to connect
+/// to `memory://` URIs.
+pub fn register() {
+    datastore::registry::register::<MemoryStore>("memory");
+}
+
+#[async_trait]
+impl Store for MemoryStore {
+    type DataStore = Self;
+    type Error = Error;
+
+    /// Creates a new, empty `MemoryStore`. The `uri` is ignored, every connection creates a new
+    /// store.
+    async fn connect(_uri: &str) -> Result<Self, Self::Error> {
+        Ok(Self::new())
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        self.tables()
+            .entry(descriptor.ident().to_owned())
+            .or_default();
+
+        Ok(())
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        if let Some(records) = self.tables().get_mut(descriptor.ident()) {
+            records.retain(|item| !matches(item, &record));
+        }
+
+        Ok(())
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        self.read_items(&descriptor, &record, None)
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        self.read_items(&descriptor, &Record::new(), None)
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        let items = self.read_items(&descriptor, &record, Some(1))?;
+        Ok(items.into_iter().next())
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let record = Record::from_data(&data)?;
+
+        self.tables()
+            .entry(descriptor.ident().to_owned())
+            .or_default()
+            .push(record);
+
+        Ok(())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::WRITE | Capabilities::ORDERING
+    }
+}
+
+/// Returns `true` if all fields of `query` are equal to the fields of `item`.
+fn matches(item: &Record, query: &Record) -> bool {
+    query
+        .iter()
+        .all(|(key, value)| item.get(key) == Some(value))
+}
+
+/// An error returned by [`MemoryStore`].
+#[derive(Debug)]
+pub enum Error {
+    /// A query or item could not be captured.
+    Value(value::Error),
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Value(err) => Display::fmt(err, f),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Value(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl datastore::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+}
+
+impl From<value::Error> for Error {
+    fn from(err: value::Error) -> Self {
+        Self::Value(err)
+    }
+}
+
+macro_rules! impl_types {
+    ($($ty:ty => $write:ident, $read:ident;)*) => {
+        $(
+            impl Write<MemoryStore> for $ty {
+                #[inline]
+                fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: Writer<MemoryStore>,
+                {
+                    writer.$write(*self)
+                }
+
+                #[inline]
+                fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: TypeWriter<MemoryStore>,
+                {
+                    writer.$write()
+                }
+            }
+
+            impl Read<MemoryStore> for $ty {
+                #[inline]
+                fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+                where
+                    R: Reader<MemoryStore>,
+                {
+                    reader.$read()
+                }
+            }
+        )*
+    };
+}
+
+impl_types! {
+    bool => write_bool, read_bool;
+    i8 => write_i8, read_i8;
+    i16 => write_i16, read_i16;
+    i32 => write_i32, read_i32;
+    i64 => write_i64, read_i64;
+    u8 => write_u8, read_u8;
+    u16 => write_u16, read_u16;
+    u32 => write_u32, read_u32;
+    u64 => write_u64, read_u64;
+    f32 => write_f32, read_f32;
+    f64 => write_f64, read_f64;
+}
+
+impl Write<MemoryStore> for str {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<MemoryStore>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<MemoryStore>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Write<MemoryStore> for String {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<MemoryStore>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<MemoryStore>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Read<MemoryStore> for String {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<MemoryStore>,
+    {
+        reader.read_string()
+    }
+}
+
+impl Write<MemoryStore> for [u8] {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<MemoryStore>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<MemoryStore>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Write<MemoryStore> for Vec<u8> {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<MemoryStore>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<MemoryStore>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Read<MemoryStore> for Vec<u8> {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<MemoryStore>,
+    {
+        reader.read_byte_buf()
+    }
+}
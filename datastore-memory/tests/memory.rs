@@ -0,0 +1,119 @@
+use datastore::{Capabilities, Store, StoreData, StoreExt};
+use datastore_memory::MemoryStore;
+
+#[derive(Clone, Debug, PartialEq, StoreData)]
+struct Person {
+    id: i64,
+    name: String,
+    active: bool,
+}
+
+fn people() -> Vec<Person> {
+    vec![
+        Person {
+            id: 1,
+            name: "Alice".to_owned(),
+            active: true,
+        },
+        Person {
+            id: 2,
+            name: "Bob".to_owned(),
+            active: false,
+        },
+        Person {
+            id: 3,
+            name: "Carol".to_owned(),
+            active: true,
+        },
+    ]
+}
+
+async fn store() -> MemoryStore {
+    let store = MemoryStore::new();
+    store.create(store.descriptor::<Person>()).await.unwrap();
+
+    for person in people() {
+        store
+            .insert(store.descriptor::<Person>(), person)
+            .await
+            .unwrap();
+    }
+
+    store
+}
+
+#[tokio::test]
+async fn test_memory_get_all() {
+    let store = store().await;
+
+    // Items are returned in insertion order.
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people());
+    assert!(store.capabilities().contains(Capabilities::ORDERING));
+}
+
+#[tokio::test]
+async fn test_memory_get() {
+    let store = store().await;
+
+    let output = store
+        .get(
+            store.descriptor::<Person>(),
+            PersonQuery::default().active(true),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output, [people().remove(0), people().remove(2)]);
+
+    let output = store
+        .get_one(
+            store.descriptor::<Person>(),
+            PersonQuery::default().name("Bob".to_owned()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output, Some(people().remove(1)));
+
+    let output = store
+        .get_one(store.descriptor::<Person>(), PersonQuery::default().id(4))
+        .await
+        .unwrap();
+    assert_eq!(output, None);
+}
+
+#[tokio::test]
+async fn test_memory_delete() {
+    let store = store().await;
+
+    store
+        .delete(
+            store.descriptor::<Person>(),
+            PersonQuery::default().active(true),
+        )
+        .await
+        .unwrap();
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people()[1..2]);
+}
+
+#[tokio::test]
+async fn test_memory_registry() {
+    datastore_memory::register();
+
+    let store = datastore::connect("memory://").await.unwrap();
+    for person in people() {
+        store
+            .insert(store.descriptor::<Person>(), person)
+            .await
+            .unwrap();
+    }
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people());
+
+    // Every connection creates a new store.
+    let store = datastore::connect("memory://").await.unwrap();
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert!(output.is_empty());
+}
@@ -0,0 +1,170 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::resp::{self, Frame};
+use crate::{Error, Options, Protocol};
+
+/// A command made of its name and arguments.
+#[derive(Clone, Debug)]
+pub(crate) struct Command(Vec<Frame>);
+
+impl Command {
+    pub(crate) fn new(name: &str) -> Self {
+        Self(vec![Frame::Bulk(name.as_bytes().to_vec())])
+    }
+
+    pub(crate) fn arg<T>(mut self, arg: T) -> Self
+    where
+        T: AsRef<[u8]>,
+    {
+        self.0.push(Frame::Bulk(arg.as_ref().to_vec()));
+        self
+    }
+
+    pub(crate) fn args<I, T>(self, args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        args.into_iter().fold(self, Self::arg)
+    }
+}
+
+impl From<Command> for Frame {
+    fn from(command: Command) -> Self {
+        Self::Array(command.0)
+    }
+}
+
+/// A connection to a Redis server.
+#[derive(Debug)]
+pub(crate) struct Connection {
+    stream: TcpStream,
+    buf: Vec<u8>,
+    protocol: Protocol,
+    /// Whether a request was started but did not finish. A broken connection may hold unread
+    /// replies or an open transaction and must not be reused.
+    pub(crate) broken: bool,
+}
+
+impl Connection {
+    /// Connects to the server and performs the handshake.
+    ///
+    /// RESP3 is negotiated using `HELLO 3` unless RESP2 is requested explicitly. Servers that do
+    /// not support `HELLO` are spoken to using RESP2.
+    pub(crate) async fn connect(options: &Options) -> Result<Self, Error> {
+        let stream = TcpStream::connect((options.host.as_str(), options.port)).await?;
+
+        let mut conn = Self {
+            stream,
+            buf: Vec::new(),
+            protocol: Protocol::Resp2,
+            broken: false,
+        };
+
+        let mut authenticated = false;
+        if options.protocol != Some(Protocol::Resp2) {
+            let mut args = vec!["HELLO", "3"];
+            if let Some(password) = &options.password {
+                args.extend(["AUTH", options.username.as_deref().unwrap_or("default")]);
+                args.push(password);
+            }
+
+            match conn.query(Frame::command(args)).await {
+                Ok(_) => {
+                    conn.protocol = Protocol::Resp3;
+                    authenticated = true;
+                }
+                Err(Error::Server(err)) if options.protocol.is_none() && !is_auth_error(&err) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        if !authenticated {
+            if let Some(password) = &options.password {
+                let mut args = vec!["AUTH"];
+                if let Some(username) = &options.username {
+                    args.push(username);
+                }
+                args.push(password);
+
+                conn.query(Frame::command(args)).await?;
+            }
+        }
+
+        if options.database != 0 {
+            let database = options.database.to_string();
+            conn.query(Frame::command(["SELECT", &database])).await?;
+        }
+
+        Ok(conn)
+    }
+
+    /// Returns the negotiated protocol version.
+    #[inline]
+    pub(crate) fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    /// Sends a single command and returns its reply. Error replies are returned as
+    /// [`Error::Server`].
+    pub(crate) async fn query(&mut self, command: Frame) -> Result<Frame, Error> {
+        let mut replies = self.pipeline(vec![command]).await?;
+        replies.pop().unwrap()
+    }
+
+    /// Sends all `commands` at once and returns their replies in order.
+    pub(crate) async fn pipeline(
+        &mut self,
+        commands: Vec<Frame>,
+    ) -> Result<Vec<Result<Frame, Error>>, Error> {
+        let mut buf = Vec::new();
+        for command in &commands {
+            resp::encode(command, &mut buf);
+        }
+        self.stream.write_all(&buf).await?;
+
+        let mut replies = Vec::with_capacity(commands.len());
+        for _ in 0..commands.len() {
+            let reply = match self.read().await? {
+                Frame::Error(err) => Err(Error::Server(err)),
+                frame => Ok(frame),
+            };
+
+            replies.push(reply);
+        }
+
+        Ok(replies)
+    }
+
+    /// Reads the next reply, skipping push messages.
+    async fn read(&mut self) -> Result<Frame, Error> {
+        loop {
+            if let Some((frame, len)) = resp::decode(&self.buf)? {
+                self.buf.drain(..len);
+
+                if let Frame::Push(_) = frame {
+                    continue;
+                }
+
+                return Ok(frame);
+            }
+
+            let mut chunk = [0; 4096];
+            let len = self.stream.read(&mut chunk).await?;
+            if len == 0 {
+                return Err(Error::Io(std::io::ErrorKind::UnexpectedEof.into()));
+            }
+
+            self.buf.extend_from_slice(&chunk[..len]);
+        }
+    }
+}
+
+/// Returns `true` if the error reply `err` of `HELLO` was caused by missing or invalid
+/// credentials.
+fn is_auth_error(err: &str) -> bool {
+    ["NOAUTH", "WRONGPASS", "NOPERM"]
+        .iter()
+        .any(|prefix| err.starts_with(prefix))
+}
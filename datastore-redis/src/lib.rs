@@ -0,0 +1,1068 @@
+//! A Redis driver for [`datastore`].
+//!
+//! [`RedisStore`] stores every item as a hash. The fields of the item become the fields of the
+//! hash, the key of the hash is made of the [`ident`] of the type and the values of its primary
+//! key. Inserting an item with the same primary key as an existing item replaces it. Items of
+//! types without a primary key are assigned an increasing id instead.
+//!
+//! Every type keeps a set of the keys of all its items. Additionally every field marked as an
+//! index or as part of the primary key has an index set for every value, containing the keys of
+//! all items with that value. [`Store::get`] intersects the index sets of the indexed fields in
+//! the query and only compares the remaining fields of the candidates. Queries without indexed
+//! fields scan all items of the type.
+//!
+//! ```
+//! use datastore::{Store, StoreData, StoreExt};
+//! use datastore_redis::RedisStore;
+//!
+//! #[derive(StoreData)]
+//! struct Session {
+//!     #[datastore(primary_key)]
+//!     id: String,
+//!     #[datastore(index)]
+//!     user: u64,
+//! }
+//!
+//! # async fn run() -> Result<(), datastore_redis::Error> {
+//! let store = RedisStore::connect("redis://127.0.0.1:6379/0").await?;
+//!
+//! let session = Session {
+//!     id: String::from("d2f1"),
+//!     user: 1,
+//! };
+//! store.insert(store.descriptor::<Session>(), session).await?;
+//!
+//! let sessions = store
+//!     .get(store.descriptor::<Session>(), SessionQuery::default().user(1))
+//!     .await?;
+//! assert_eq!(sessions.len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Keys
+//!
+//! For a type with the ident `Session` the following keys are used:
+//!
+//! | Key                             | Type    | Content                                      |
+//! | ------------------------------- | ------- | -------------------------------------------- |
+//! | `Session:item:{id}`             | hash    | The fields of the item with the key `{id}`.  |
+//! | `Session:items`                 | set     | The keys of all items.                       |
+//! | `Session:index:{field}:{value}` | set     | The keys of all items with `{field} = {value}`. |
+//! | `Session:next_id`               | integer | The last id assigned to an item.             |
+//!
+//! The values of a composite primary key are joined using `:`. Colons and backslashes within the
+//! values are escaped using a backslash.
+//!
+//! # Protocol
+//!
+//! [`RedisStore`] speaks both RESP2 and RESP3. RESP3 is negotiated using `HELLO 3` when
+//! connecting, falling back to RESP2 for servers that do not support it.
+//!
+//! # Limitations
+//!
+//! Every field must be a single value. Nested fields are rejected. Values are stored in their
+//! textual representation, booleans as `1` and `0`, byte arrays as is.
+//!
+//! [`ident`]: DataDescriptor::ident
+mod conn;
+pub mod resp;
+
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::str::{self, FromStr};
+
+use async_trait::async_trait;
+use datastore::sql::{self, Column, ColumnType, Table};
+use datastore::value::{self, Record, Value};
+use datastore::{
+    Capabilities, ConnectOptions, DataDescriptor, DataQuery, Read, Reader, Store, StoreData,
+    TypeWriter, Write, Writer,
+};
+use tokio::sync::{Mutex, MutexGuard};
+
+use self::conn::{Command, Connection};
+use self::resp::{DecodeError, Frame};
+
+/// The default port of a Redis server.
+const DEFAULT_PORT: u16 = 6379;
+
+/// The maximum number of attempts of a transaction that is aborted because a watched key was
+/// modified concurrently.
+const MAX_ATTEMPTS: usize = 16;
+
+/// A [`Store`] backed by a Redis server.
+///
+/// All operations are sent over a single connection that is shared behind a [`Mutex`]. If an
+/// operation fails or is cancelled part way, the connection may still hold unread replies or an
+/// open transaction and is replaced by a new connection before the next operation.
+#[derive(Debug)]
+pub struct RedisStore {
+    conn: Mutex<Connection>,
+    options: Options,
+    protocol: Protocol,
+}
+
+impl RedisStore {
+    /// Returns the version of the protocol spoken with the server.
+    #[inline]
+    pub fn protocol(&self) -> Protocol {
+        self.protocol
+    }
+}
+
+/// Registers [`RedisStore`] for the `redis` scheme, allowing [`datastore::connect`] to connect to
+/// `redis://` URIs.
+pub fn register() {
+    datastore::registry::register::<RedisStore>("redis");
+}
+
+#[async_trait]
+impl Store for RedisStore {
+    type DataStore = Self;
+    type Error = Error;
+
+    /// Connects to the Redis server at `uri`.
+    ///
+    /// `uri` has the form `redis://[[username]:password@]host[:port][/database][?protocol=2|3]`.
+    /// The port defaults to `6379` and the database to `0`. The protocol is negotiated if it is
+    /// not given.
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        Self::open(&Options::parse(uri)?).await
+    }
+
+    /// Connects to the Redis server using the `options`. See [`connect`] for the accepted
+    /// components.
+    ///
+    /// [`connect`]: Self::connect
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        Self::open(&Options::from_options(options)?).await
+    }
+
+    /// Validates the `descriptor`. Redis requires no schema, so nothing is created.
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        Table::new(&descriptor)?;
+        Ok(())
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let table = Table::new(&descriptor)?;
+        let query = fields(&query)?;
+
+        let mut conn = self.request().await?;
+        for _ in 0..MAX_ATTEMPTS {
+            let keys = candidates(&mut conn, &table, &query).await?;
+            if !keys.is_empty() {
+                conn.query(Command::new("WATCH").args(&keys).into()).await?;
+            }
+
+            let items = fetch(&mut conn, &query, keys).await?;
+            if items.is_empty() {
+                conn.query(Command::new("UNWATCH").into()).await?;
+                conn.finish();
+                return Ok(());
+            }
+
+            let mut commands = vec![Command::new("MULTI").into()];
+            for (key, hash) in &items {
+                commands.push(Command::new("DEL").arg(key).into());
+                commands.push(Command::new("SREM").arg(items_key(&table)).arg(key).into());
+
+                for column in indexed(&table) {
+                    if let Some(value) = hash.get(column.name()) {
+                        commands.push(
+                            Command::new("SREM")
+                                .arg(index_key(&table, column, value))
+                                .arg(key)
+                                .into(),
+                        );
+                    }
+                }
+            }
+            commands.push(Command::new("EXEC").into());
+
+            if exec(conn.pipeline(commands).await?)? {
+                conn.finish();
+                return Ok(());
+            }
+        }
+
+        Err(Error::Conflict)
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let query = fields(&query)?;
+        self.select(descriptor, &query).await
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        self.select(descriptor, &[]).await
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let query = fields(&query)?;
+
+        let items = self.select(descriptor, &query).await?;
+        Ok(items.into_iter().next())
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let table = Table::new(&descriptor)?;
+        let record = Record::from_data(&data)?;
+
+        let mut fields = Vec::with_capacity(record.len());
+        for (key, value) in record.iter() {
+            fields.push((key.to_owned(), to_redis(key, value)?));
+        }
+
+        let mut conn = self.request().await?;
+
+        let key = match primary_key(&table, &fields) {
+            Some(id) => item_key(&table, &id),
+            None => {
+                let reply = conn
+                    .query(Command::new("INCR").arg(next_id_key(&table)).into())
+                    .await?;
+
+                match reply {
+                    Frame::Integer(id) => item_key(&table, id.to_string().as_bytes()),
+                    _ => return Err(Error::UnexpectedReply),
+                }
+            }
+        };
+
+        for _ in 0..MAX_ATTEMPTS {
+            conn.query(Command::new("WATCH").arg(&key).into()).await?;
+            let old = hash(conn.query(Command::new("HGETALL").arg(&key).into()).await?)?;
+
+            let mut commands = vec![Command::new("MULTI").into()];
+            for column in indexed(&table) {
+                if let Some(value) = old.get(column.name()) {
+                    commands.push(
+                        Command::new("SREM")
+                            .arg(index_key(&table, column, value))
+                            .arg(&key)
+                            .into(),
+                    );
+                }
+            }
+
+            commands.push(Command::new("DEL").arg(&key).into());
+            if !fields.is_empty() {
+                let mut hset = Command::new("HSET").arg(&key);
+                for (field, value) in &fields {
+                    hset = hset.arg(field).arg(value);
+                }
+                commands.push(hset.into());
+            }
+
+            commands.push(Command::new("SADD").arg(items_key(&table)).arg(&key).into());
+
+            for column in indexed(&table) {
+                if let Some((_, value)) = fields.iter().find(|(key, _)| *key == column.name()) {
+                    commands.push(
+                        Command::new("SADD")
+                            .arg(index_key(&table, column, value))
+                            .arg(&key)
+                            .into(),
+                    );
+                }
+            }
+            commands.push(Command::new("EXEC").into());
+
+            if exec(conn.pipeline(commands).await?)? {
+                conn.finish();
+                return Ok(());
+            }
+        }
+
+        Err(Error::Conflict)
+    }
+
+    /// Sends a `PING` to the server.
+    async fn ping(&self) -> Result<(), Self::Error> {
+        let mut conn = self.request().await?;
+        match conn.query(Command::new("PING").into()).await? {
+            Frame::Simple(_) => {
+                conn.finish();
+                Ok(())
+            }
+            _ => Err(Error::UnexpectedReply),
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::WRITE | Capabilities::TRANSACTIONS | Capabilities::UPSERT
+    }
+}
+
+impl RedisStore {
+    /// Connects to the server using the `options`.
+    async fn open(options: &Options) -> Result<Self, Error> {
+        let conn = Connection::connect(options).await?;
+
+        Ok(Self {
+            protocol: conn.protocol(),
+            conn: Mutex::new(conn),
+            options: options.clone(),
+        })
+    }
+
+    /// Locks the connection for a new request, reconnecting first if the previous request did
+    /// not finish.
+    ///
+    /// The connection is marked as broken until [`Request::finish`] is called, since a request
+    /// that returns early or is dropped may leave unread replies or an open `WATCH` or `MULTI`
+    /// behind.
+    async fn request(&self) -> Result<Request<'_>, Error> {
+        let mut conn = self.conn.lock().await;
+        if conn.broken {
+            *conn = Connection::connect(&self.options).await?;
+        }
+
+        conn.broken = true;
+        Ok(Request { conn })
+    }
+
+    /// Returns all items described by `descriptor` matching the `query`.
+    async fn select<T, D>(
+        &self,
+        descriptor: D,
+        query: &[(String, Vec<u8>)],
+    ) -> Result<Vec<T>, Error>
+    where
+        T: StoreData<Self>,
+        D: DataDescriptor<T, Self> + Send,
+    {
+        let table = Table::new(&descriptor)?;
+
+        let items = {
+            let mut conn = self.request().await?;
+            let keys = candidates(&mut conn, &table, query).await?;
+            let items = fetch(&mut conn, query, keys).await?;
+            conn.finish();
+            items
+        };
+
+        let mut output = Vec::with_capacity(items.len());
+        for (_, hash) in items {
+            let mut record = Record::with_capacity(table.columns().len());
+            for column in table.columns() {
+                if let Some(value) = hash.get(column.name()) {
+                    record.insert(column.name().to_owned(), from_redis(column, value)?);
+                }
+            }
+
+            output.push(descriptor.read(&mut record)?);
+        }
+
+        Ok(output)
+    }
+}
+
+/// The connection locked for a single request.
+struct Request<'a> {
+    conn: MutexGuard<'a, Connection>,
+}
+
+impl<'a> Request<'a> {
+    /// Marks the request as finished, leaving the connection in a clean state.
+    fn finish(mut self) {
+        self.conn.broken = false;
+    }
+}
+
+impl<'a> Deref for Request<'a> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.conn
+    }
+}
+
+impl<'a> DerefMut for Request<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.conn
+    }
+}
+
+/// The version of the Redis serialization protocol.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    Resp2,
+    Resp3,
+}
+
+/// The options to connect to a server, parsed from a URI.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Options {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) username: Option<String>,
+    pub(crate) password: Option<String>,
+    pub(crate) database: u32,
+    /// The protocol to use, or `None` to negotiate it.
+    pub(crate) protocol: Option<Protocol>,
+}
+
+/// Omits the password, since the options are kept by [`RedisStore`] for reconnecting.
+impl fmt::Debug for Options {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Options")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("database", &self.database)
+            .field("protocol", &self.protocol)
+            .finish()
+    }
+}
+
+impl Options {
+    fn parse(uri: &str) -> Result<Self, Error> {
+        // The scheme is optional.
+        let options = match uri.contains("://") {
+            true => ConnectOptions::parse(uri),
+            false => ConnectOptions::parse(&format!("redis://{}", uri)),
+        };
+
+        match options {
+            Ok(options) => Self::from_options(&options),
+            Err(_) => Err(Error::InvalidUri(redact(uri))),
+        }
+    }
+
+    fn from_options(options: &ConnectOptions) -> Result<Self, Error> {
+        // A userinfo without a colon is the password of the default user.
+        let (username, password) = match (options.username(), options.password()) {
+            (Some(username), None) => (None, Some(username.to_owned())),
+            (username, password) => (username.map(str::to_owned), password.map(str::to_owned)),
+        };
+
+        let invalid = || {
+            let options = match username.is_none() && password.is_some() {
+                true => options.redacted().with_username("***"),
+                false => options.redacted(),
+            };
+
+            Error::InvalidUri(options.to_string())
+        };
+
+        let database = match options.path() {
+            "" | "/" => 0,
+            path => path
+                .strip_prefix('/')
+                .unwrap_or(path)
+                .parse()
+                .map_err(|_| invalid())?,
+        };
+
+        let mut protocol = None;
+        for (key, value) in options.params() {
+            match (key, value) {
+                ("protocol", "2" | "resp2") => protocol = Some(Protocol::Resp2),
+                ("protocol", "3" | "resp3") => protocol = Some(Protocol::Resp3),
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(Self {
+            host: match options.host() {
+                Some("") | None => "127.0.0.1".to_owned(),
+                Some(host) => host.to_owned(),
+            },
+            port: options.port().unwrap_or(DEFAULT_PORT),
+            username,
+            password,
+            database,
+            protocol,
+        })
+    }
+}
+
+/// Replaces the password in the userinfo of the unparsed `uri` by `***`. A userinfo without a
+/// colon is the password of the default user and replaced completely.
+fn redact(uri: &str) -> String {
+    let start = uri.find("://").map(|index| index + 3).unwrap_or_default();
+    let end = uri[start..]
+        .find(['/', '?', '#'])
+        .map_or(uri.len(), |index| start + index);
+
+    let userinfo = match uri[start..end].rfind('@') {
+        Some(index) => &uri[start..start + index],
+        None => return uri.to_owned(),
+    };
+
+    let password = userinfo.find(':').map_or(0, |index| index + 1);
+    format!(
+        "{}***{}",
+        &uri[..start + password],
+        &uri[start + userinfo.len()..]
+    )
+}
+
+/// Returns the keys of all items that can match the `query`.
+///
+/// If the query contains the complete primary key, only the key of that item is returned.
+/// Otherwise the index sets of all indexed fields in the query are intersected. If no field is
+/// indexed, the keys of all items are returned.
+async fn candidates(
+    conn: &mut Connection,
+    table: &Table,
+    query: &[(String, Vec<u8>)],
+) -> Result<Vec<Vec<u8>>, Error> {
+    if let Some(id) = primary_key(table, query) {
+        return Ok(vec![item_key(table, &id)]);
+    }
+
+    let sets: Vec<Vec<u8>> = indexed(table)
+        .filter_map(|column| {
+            query
+                .iter()
+                .find(|(key, _)| *key == column.name())
+                .map(|(_, value)| index_key(table, column, value))
+        })
+        .collect();
+
+    let command = match sets.is_empty() {
+        true => Command::new("SMEMBERS").arg(items_key(table)),
+        false => Command::new("SINTER").args(&sets),
+    };
+
+    let mut keys = members(conn.query(command.into()).await?)?;
+    keys.sort_unstable();
+    Ok(keys)
+}
+
+/// Fetches the hashes at `keys` and returns all that match the `query`. Keys that no longer
+/// exist are skipped.
+async fn fetch(
+    conn: &mut Connection,
+    query: &[(String, Vec<u8>)],
+    keys: Vec<Vec<u8>>,
+) -> Result<Vec<(Vec<u8>, HashMap<String, Vec<u8>>)>, Error> {
+    let commands = keys
+        .iter()
+        .map(|key| Command::new("HGETALL").arg(key).into())
+        .collect();
+    let replies = conn.pipeline(commands).await?;
+
+    let mut items = Vec::new();
+    for (key, reply) in keys.into_iter().zip(replies) {
+        let hash = hash(reply?)?;
+
+        let matches = query
+            .iter()
+            .all(|(field, value)| hash.get(field) == Some(value));
+
+        if !hash.is_empty() && matches {
+            items.push((key, hash));
+        }
+    }
+
+    Ok(items)
+}
+
+/// Checks the replies of a transaction. Returns `false` if the transaction was aborted because a
+/// watched key was modified.
+fn exec(replies: Vec<Result<Frame, Error>>) -> Result<bool, Error> {
+    let mut replies = replies.into_iter();
+    let last = replies.next_back();
+
+    for reply in replies {
+        reply?;
+    }
+
+    match last.ok_or(Error::UnexpectedReply)?? {
+        Frame::Null => Ok(false),
+        Frame::Array(results) => {
+            for result in results {
+                if let Frame::Error(err) = result {
+                    return Err(Error::Server(err));
+                }
+            }
+
+            Ok(true)
+        }
+        _ => Err(Error::UnexpectedReply),
+    }
+}
+
+/// Converts the reply of `HGETALL` into a map. RESP3 servers reply with a map, RESP2 servers
+/// with a flat array of fields and values.
+fn hash(reply: Frame) -> Result<HashMap<String, Vec<u8>>, Error> {
+    let entries = match reply {
+        Frame::Map(entries) => entries,
+        Frame::Array(frames) => {
+            if frames.len() % 2 != 0 {
+                return Err(Error::UnexpectedReply);
+            }
+
+            let mut frames = frames.into_iter();
+            let mut entries = Vec::with_capacity(frames.len() / 2);
+            while let (Some(key), Some(value)) = (frames.next(), frames.next()) {
+                entries.push((key, value));
+            }
+            entries
+        }
+        Frame::Null => Vec::new(),
+        _ => return Err(Error::UnexpectedReply),
+    };
+
+    let mut hash = HashMap::with_capacity(entries.len());
+    for (key, value) in entries {
+        let key = String::from_utf8(bytes(key)?).map_err(|_| Error::UnexpectedReply)?;
+        hash.insert(key, bytes(value)?);
+    }
+
+    Ok(hash)
+}
+
+/// Converts the reply of `SMEMBERS` or `SINTER` into a list of members.
+fn members(reply: Frame) -> Result<Vec<Vec<u8>>, Error> {
+    match reply {
+        Frame::Set(frames) | Frame::Array(frames) => frames.into_iter().map(bytes).collect(),
+        Frame::Null => Ok(Vec::new()),
+        _ => Err(Error::UnexpectedReply),
+    }
+}
+
+fn bytes(reply: Frame) -> Result<Vec<u8>, Error> {
+    match reply {
+        Frame::Bulk(bytes) => Ok(bytes),
+        Frame::Simple(s) => Ok(s.into_bytes()),
+        _ => Err(Error::UnexpectedReply),
+    }
+}
+
+/// Captures the fields written by `query` and converts their values.
+fn fields<T, Q>(query: &Q) -> Result<Vec<(String, Vec<u8>)>, Error>
+where
+    T: StoreData<RedisStore>,
+    Q: DataQuery<T, RedisStore>,
+{
+    let mut record = Record::new();
+    query.write(&mut record)?;
+
+    let mut fields = Vec::with_capacity(record.len());
+    for (key, value) in record.iter() {
+        fields.push((key.to_owned(), to_redis(key, value)?));
+    }
+
+    Ok(fields)
+}
+
+/// Returns the columns that have index sets.
+fn indexed(table: &Table) -> impl Iterator<Item = &Column> + '_ {
+    table.columns().iter().filter(|column| {
+        let attrs = column.attributes();
+        attrs.is_index() || attrs.is_primary_key()
+    })
+}
+
+/// Returns the id of an item made of the values of the primary key in `fields`. Returns `None`
+/// if the table has no primary key or `fields` does not contain all of its columns.
+fn primary_key(table: &Table, fields: &[(String, Vec<u8>)]) -> Option<Vec<u8>> {
+    let mut id = Vec::new();
+    let mut columns = 0;
+
+    for column in table.primary_key() {
+        let (_, value) = fields.iter().find(|(key, _)| *key == column.name())?;
+
+        if columns != 0 {
+            id.push(b':');
+        }
+
+        for byte in value {
+            if matches!(byte, b':' | b'\\') {
+                id.push(b'\\');
+            }
+            id.push(*byte);
+        }
+
+        columns += 1;
+    }
+
+    match columns {
+        0 => None,
+        _ => Some(id),
+    }
+}
+
+fn item_key(table: &Table, id: &[u8]) -> Vec<u8> {
+    let mut key = format!("{}:item:", table.name()).into_bytes();
+    key.extend_from_slice(id);
+    key
+}
+
+fn items_key(table: &Table) -> String {
+    format!("{}:items", table.name())
+}
+
+fn index_key(table: &Table, column: &Column, value: &[u8]) -> Vec<u8> {
+    let mut key = format!("{}:index:{}:", table.name(), column.name()).into_bytes();
+    key.extend_from_slice(value);
+    key
+}
+
+fn next_id_key(table: &Table) -> String {
+    format!("{}:next_id", table.name())
+}
+
+/// Converts the [`Value`] of the field `key` into its stored representation.
+fn to_redis(key: &str, value: &Value) -> Result<Vec<u8>, Error> {
+    let value = match value {
+        Value::Bool(v) => vec![if *v { b'1' } else { b'0' }],
+        Value::I8(v) => v.to_string().into_bytes(),
+        Value::I16(v) => v.to_string().into_bytes(),
+        Value::I32(v) => v.to_string().into_bytes(),
+        Value::I64(v) => v.to_string().into_bytes(),
+        Value::U8(v) => v.to_string().into_bytes(),
+        Value::U16(v) => v.to_string().into_bytes(),
+        Value::U32(v) => v.to_string().into_bytes(),
+        Value::U64(v) => v.to_string().into_bytes(),
+        Value::F32(v) => v.to_string().into_bytes(),
+        Value::F64(v) => v.to_string().into_bytes(),
+        Value::Bytes(v) => v.clone(),
+        Value::String(v) => v.clone().into_bytes(),
+        Value::Record(_) => {
+            return Err(Error::NestedField {
+                key: key.to_owned(),
+            })
+        }
+    };
+
+    Ok(value)
+}
+
+/// Converts the stored representation of a value in `column` back into a [`Value`].
+fn from_redis(column: &Column, value: &[u8]) -> Result<Value, Error> {
+    fn parse<T>(column: &Column, value: &[u8]) -> Result<T, Error>
+    where
+        T: FromStr,
+    {
+        str::from_utf8(value)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or(Error::InvalidValue {
+                key: column.name().to_owned(),
+            })
+    }
+
+    let value = match column.ty() {
+        ColumnType::Bool => match value {
+            b"1" => Value::Bool(true),
+            b"0" => Value::Bool(false),
+            _ => {
+                return Err(Error::InvalidValue {
+                    key: column.name().to_owned(),
+                })
+            }
+        },
+        ColumnType::I8 => Value::I8(parse(column, value)?),
+        ColumnType::I16 => Value::I16(parse(column, value)?),
+        ColumnType::I32 => Value::I32(parse(column, value)?),
+        ColumnType::I64 => Value::I64(parse(column, value)?),
+        ColumnType::U8 => Value::U8(parse(column, value)?),
+        ColumnType::U16 => Value::U16(parse(column, value)?),
+        ColumnType::U32 => Value::U32(parse(column, value)?),
+        ColumnType::U64 => Value::U64(parse(column, value)?),
+        ColumnType::F32 => Value::F32(parse(column, value)?),
+        ColumnType::F64 => Value::F64(parse(column, value)?),
+        ColumnType::Bytes => Value::Bytes(value.to_vec()),
+        ColumnType::Str => match String::from_utf8(value.to_vec()) {
+            Ok(s) => Value::String(s),
+            Err(_) => {
+                return Err(Error::InvalidValue {
+                    key: column.name().to_owned(),
+                })
+            }
+        },
+    };
+
+    Ok(value)
+}
+
+/// An error returned by [`RedisStore`].
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O error on the connection.
+    Io(io::Error),
+    /// The server sent invalid RESP.
+    Decode(DecodeError),
+    /// The server replied with an error.
+    Server(String),
+    /// The server sent a reply of an unexpected type.
+    UnexpectedReply,
+    /// The connection URI is invalid.
+    InvalidUri(String),
+    /// The layout could not be created from the descriptor.
+    Sql(sql::Error),
+    /// A value could not be written or read.
+    Value(value::Error),
+    /// The field `key` contains nested fields.
+    NestedField { key: String },
+    /// The stored value of the field `key` is invalid for its type.
+    InvalidValue { key: String },
+    /// A transaction was aborted repeatedly because of concurrent modifications.
+    Conflict,
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => Display::fmt(err, f),
+            Self::Decode(err) => Display::fmt(err, f),
+            Self::Server(msg) => write!(f, "server error: {}", msg),
+            Self::UnexpectedReply => write!(f, "unexpected reply"),
+            Self::InvalidUri(uri) => write!(f, "invalid uri {:?}", uri),
+            Self::Sql(err) => Display::fmt(err, f),
+            Self::Value(err) => Display::fmt(err, f),
+            Self::NestedField { key } => write!(f, "nested field {:?} is not supported", key),
+            Self::InvalidValue { key } => write!(f, "invalid value of field {:?}", key),
+            Self::Conflict => write!(f, "transaction aborted by concurrent modifications"),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Decode(err) => Some(err),
+            Self::Sql(err) => Some(err),
+            Self::Value(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl datastore::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Io(err) => matches!(
+                err.kind(),
+                io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::UnexpectedEof
+            ),
+            // Replies sent while the server is loading its dataset, running a blocking script
+            // or failing over.
+            Self::Server(msg) => {
+                let prefix = msg.split(' ').next().unwrap_or_default();
+                ["LOADING", "BUSY", "TRYAGAIN", "MASTERDOWN", "CLUSTERDOWN"].contains(&prefix)
+            }
+            Self::Conflict => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(err: DecodeError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+impl From<sql::Error> for Error {
+    fn from(err: sql::Error) -> Self {
+        Self::Sql(err)
+    }
+}
+
+impl From<value::Error> for Error {
+    fn from(err: value::Error) -> Self {
+        Self::Value(err)
+    }
+}
+
+macro_rules! impl_types {
+    ($($ty:ty => $write:ident, $read:ident, $tywrite:ident;)*) => {
+        $(
+            impl Write<RedisStore> for $ty {
+                #[inline]
+                fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: Writer<RedisStore>,
+                {
+                    writer.$write(*self)
+                }
+
+                #[inline]
+                fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: TypeWriter<RedisStore>,
+                {
+                    writer.$tywrite()
+                }
+            }
+
+            impl Read<RedisStore> for $ty {
+                #[inline]
+                fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+                where
+                    R: Reader<RedisStore>,
+                {
+                    reader.$read()
+                }
+            }
+        )*
+    };
+}
+
+impl_types! {
+    bool => write_bool, read_bool, write_bool;
+    i8 => write_i8, read_i8, write_i8;
+    i16 => write_i16, read_i16, write_i16;
+    i32 => write_i32, read_i32, write_i32;
+    i64 => write_i64, read_i64, write_i64;
+    u8 => write_u8, read_u8, write_u8;
+    u16 => write_u16, read_u16, write_u16;
+    u32 => write_u32, read_u32, write_u32;
+    u64 => write_u64, read_u64, write_u64;
+    f32 => write_f32, read_f32, write_f32;
+    f64 => write_f64, read_f64, write_f64;
+}
+
+impl Write<RedisStore> for str {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<RedisStore>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<RedisStore>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Write<RedisStore> for String {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<RedisStore>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<RedisStore>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Read<RedisStore> for String {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<RedisStore>,
+    {
+        reader.read_string()
+    }
+}
+
+impl Write<RedisStore> for [u8] {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<RedisStore>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<RedisStore>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Write<RedisStore> for Vec<u8> {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<RedisStore>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<RedisStore>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Read<RedisStore> for Vec<u8> {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<RedisStore>,
+    {
+        reader.read_byte_buf()
+    }
+}
@@ -0,0 +1,321 @@
+//! The Redis serialization protocol (RESP).
+//!
+//! [`decode`] parses both RESP2 and RESP3 frames, [`encode`] writes a [`Frame`] in its RESP3 form.
+//! Commands are sent as an [`Frame::Array`] of [`Frame::Bulk`] strings in both protocol versions.
+use std::fmt::{self, Display, Formatter};
+use std::str;
+
+/// The maximum nesting depth of aggregate frames.
+const MAX_DEPTH: usize = 32;
+
+/// A single RESP frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Frame {
+    /// A simple string (`+`).
+    Simple(String),
+    /// A simple error (`-`) or blob error (`!`).
+    Error(String),
+    /// An integer (`:`).
+    Integer(i64),
+    /// A bulk string (`$`) or verbatim string (`=`).
+    Bulk(Vec<u8>),
+    /// An array (`*`).
+    Array(Vec<Frame>),
+    /// A null bulk string or array in RESP2, a null (`_`) in RESP3.
+    Null,
+    /// A double (`,`).
+    Double(f64),
+    /// A boolean (`#`).
+    Boolean(bool),
+    /// A big number (`(`).
+    BigNumber(String),
+    /// A map (`%`).
+    Map(Vec<(Frame, Frame)>),
+    /// A set (`~`).
+    Set(Vec<Frame>),
+    /// An out-of-band push message (`>`).
+    Push(Vec<Frame>),
+}
+
+impl Frame {
+    /// Creates a new command frame from its `args`.
+    pub fn command<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        Self::Array(
+            args.into_iter()
+                .map(|arg| Self::Bulk(arg.as_ref().to_vec()))
+                .collect(),
+        )
+    }
+}
+
+/// Appends the encoded `frame` to `buf`.
+pub fn encode(frame: &Frame, buf: &mut Vec<u8>) {
+    match frame {
+        Frame::Simple(s) => line(buf, b'+', s),
+        Frame::Error(s) => line(buf, b'-', s),
+        Frame::Integer(v) => line(buf, b':', &v.to_string()),
+        Frame::Bulk(v) => {
+            line(buf, b'$', &v.len().to_string());
+            buf.extend_from_slice(v);
+            buf.extend_from_slice(b"\r\n");
+        }
+        Frame::Array(frames) => aggregate(buf, b'*', frames),
+        Frame::Null => buf.extend_from_slice(b"_\r\n"),
+        Frame::Double(v) => {
+            let s = if v.is_nan() {
+                "nan".to_owned()
+            } else if v.is_infinite() {
+                if *v > 0.0 { "inf" } else { "-inf" }.to_owned()
+            } else {
+                v.to_string()
+            };
+
+            line(buf, b',', &s)
+        }
+        Frame::Boolean(v) => line(buf, b'#', if *v { "t" } else { "f" }),
+        Frame::BigNumber(s) => line(buf, b'(', s),
+        Frame::Map(entries) => {
+            line(buf, b'%', &entries.len().to_string());
+            for (key, value) in entries {
+                encode(key, buf);
+                encode(value, buf);
+            }
+        }
+        Frame::Set(frames) => aggregate(buf, b'~', frames),
+        Frame::Push(frames) => aggregate(buf, b'>', frames),
+    }
+}
+
+fn line(buf: &mut Vec<u8>, prefix: u8, s: &str) {
+    buf.push(prefix);
+    buf.extend_from_slice(s.as_bytes());
+    buf.extend_from_slice(b"\r\n");
+}
+
+fn aggregate(buf: &mut Vec<u8>, prefix: u8, frames: &[Frame]) {
+    line(buf, prefix, &frames.len().to_string());
+    for frame in frames {
+        encode(frame, buf);
+    }
+}
+
+/// Decodes a single frame from the start of `buf`. Returns the frame and the number of bytes
+/// consumed, or `None` if `buf` does not contain a complete frame yet.
+///
+/// Attributes (`|`) are skipped and the frame following them is returned.
+pub fn decode(buf: &[u8]) -> Result<Option<(Frame, usize)>, DecodeError> {
+    let mut decoder = Decoder { buf, pos: 0 };
+
+    match decoder.frame(0) {
+        Ok(frame) => Ok(Some((frame, decoder.pos))),
+        Err(Incomplete::Eof) => Ok(None),
+        Err(Incomplete::Invalid(err)) => Err(err),
+    }
+}
+
+/// An error returned when the input is not valid RESP.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    /// The byte offset of the error in the input.
+    pub offset: usize,
+    pub expected: &'static str,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid RESP at offset {}: expected {}",
+            self.offset, self.expected
+        )
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+enum Incomplete {
+    Eof,
+    Invalid(DecodeError),
+}
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn invalid<T>(&self, expected: &'static str) -> Result<T, Incomplete> {
+        Err(Incomplete::Invalid(DecodeError {
+            offset: self.pos,
+            expected,
+        }))
+    }
+
+    /// Reads a line terminated by `\r\n`, not including the terminator.
+    fn line(&mut self) -> Result<&'a [u8], Incomplete> {
+        let rest = &self.buf[self.pos..];
+        match rest.windows(2).position(|w| w == b"\r\n") {
+            Some(end) => {
+                self.pos += end + 2;
+                Ok(&rest[..end])
+            }
+            None => Err(Incomplete::Eof),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Incomplete> {
+        let line = self.line()?;
+        match str::from_utf8(line) {
+            Ok(s) => Ok(s.to_owned()),
+            Err(_) => self.invalid("a UTF-8 string"),
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, Incomplete> {
+        let line = self.line()?;
+        match str::from_utf8(line).ok().and_then(|s| s.parse().ok()) {
+            Some(v) => Ok(v),
+            None => self.invalid("an integer"),
+        }
+    }
+
+    /// Reads a length. Returns `None` for the RESP2 null length `-1`.
+    fn len(&mut self) -> Result<Option<usize>, Incomplete> {
+        match self.integer()? {
+            -1 => Ok(None),
+            len if len >= 0 => Ok(Some(len as usize)),
+            _ => self.invalid("a length"),
+        }
+    }
+
+    fn blob(&mut self, len: usize) -> Result<&'a [u8], Incomplete> {
+        let rest = &self.buf[self.pos..];
+        if rest.len() < len + 2 {
+            return Err(Incomplete::Eof);
+        }
+
+        if &rest[len..len + 2] != b"\r\n" {
+            self.pos += len;
+            return self.invalid("\\r\\n");
+        }
+
+        self.pos += len + 2;
+        Ok(&rest[..len])
+    }
+
+    fn frames(&mut self, len: usize, depth: usize) -> Result<Vec<Frame>, Incomplete> {
+        // The length is untrusted, do not preallocate based on it.
+        let mut frames = Vec::new();
+        for _ in 0..len {
+            frames.push(self.frame(depth + 1)?);
+        }
+        Ok(frames)
+    }
+
+    fn frame(&mut self, depth: usize) -> Result<Frame, Incomplete> {
+        if depth > MAX_DEPTH {
+            return self.invalid("a shallower frame");
+        }
+
+        // Attributes precede the frame they describe and are skipped. They are read in a loop,
+        // since any number of attributes may precede a frame.
+        let prefix = loop {
+            let prefix = match self.buf.get(self.pos) {
+                Some(prefix) => *prefix,
+                None => return Err(Incomplete::Eof),
+            };
+            self.pos += 1;
+
+            if prefix != b'|' {
+                break prefix;
+            }
+
+            match self.len()? {
+                Some(len) => {
+                    for _ in 0..len.saturating_mul(2) {
+                        self.frame(depth + 1)?;
+                    }
+                }
+                None => return self.invalid("a length"),
+            }
+        };
+
+        match prefix {
+            b'+' => self.string().map(Frame::Simple),
+            b'-' => self.string().map(Frame::Error),
+            b':' => self.integer().map(Frame::Integer),
+            b'$' => match self.len()? {
+                Some(len) => self.blob(len).map(|v| Frame::Bulk(v.to_vec())),
+                None => Ok(Frame::Null),
+            },
+            b'*' => match self.len()? {
+                Some(len) => self.frames(len, depth).map(Frame::Array),
+                None => Ok(Frame::Null),
+            },
+            b'_' => {
+                self.line()?;
+                Ok(Frame::Null)
+            }
+            b',' => {
+                let line = self.line()?;
+                let v = match line {
+                    b"inf" => Some(f64::INFINITY),
+                    b"-inf" => Some(f64::NEG_INFINITY),
+                    b"nan" => Some(f64::NAN),
+                    _ => str::from_utf8(line).ok().and_then(|s| s.parse().ok()),
+                };
+
+                match v {
+                    Some(v) => Ok(Frame::Double(v)),
+                    None => self.invalid("a double"),
+                }
+            }
+            b'#' => match self.line()? {
+                b"t" => Ok(Frame::Boolean(true)),
+                b"f" => Ok(Frame::Boolean(false)),
+                _ => self.invalid("a boolean"),
+            },
+            b'(' => self.string().map(Frame::BigNumber),
+            b'!' => match self.len()? {
+                Some(len) => {
+                    let v = self.blob(len)?;
+                    Ok(Frame::Error(String::from_utf8_lossy(v).into_owned()))
+                }
+                None => self.invalid("a length"),
+            },
+            b'=' => match self.len()? {
+                // Verbatim strings are prefixed with a three character format and a colon.
+                Some(len) if len >= 4 => self.blob(len).map(|v| Frame::Bulk(v[4..].to_vec())),
+                _ => self.invalid("a verbatim string"),
+            },
+            b'%' => match self.len()? {
+                Some(len) => {
+                    let mut entries = Vec::new();
+                    for _ in 0..len {
+                        let key = self.frame(depth + 1)?;
+                        let value = self.frame(depth + 1)?;
+                        entries.push((key, value));
+                    }
+                    Ok(Frame::Map(entries))
+                }
+                None => self.invalid("a length"),
+            },
+            b'~' => match self.len()? {
+                Some(len) => self.frames(len, depth).map(Frame::Set),
+                None => self.invalid("a length"),
+            },
+            b'>' => match self.len()? {
+                Some(len) => self.frames(len, depth).map(Frame::Push),
+                None => self.invalid("a length"),
+            },
+            _ => {
+                self.pos -= 1;
+                self.invalid("a frame type")
+            }
+        }
+    }
+}
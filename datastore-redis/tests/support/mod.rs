@@ -0,0 +1,487 @@
+//! An in-process stand-in for a Redis server.
+//!
+//! [`FakeRedis`] implements the subset of commands used by `RedisStore` on an in-memory keyspace
+//! shared by all connections. It speaks RESP3 after `HELLO 3` and RESP2 otherwise.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use datastore_redis::resp::{self, Frame};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Reject `HELLO` like a server predating RESP3.
+    pub resp2_only: bool,
+    /// Require `AUTH` using this password.
+    pub password: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct FakeRedis {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeRedis {
+    pub async fn start() -> Self {
+        Self::with_config(Config::default()).await
+    }
+
+    pub async fn with_config(config: Config) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, shared.clone(), config.clone()));
+            }
+        });
+
+        Self { addr, state }
+    }
+
+    /// Returns the URI of the server.
+    pub fn uri(&self) -> String {
+        format!("redis://{}", self.addr)
+    }
+
+    /// Returns all keys in the keyspace.
+    pub fn keys(&self) -> Vec<String> {
+        self.state()
+            .keys
+            .keys()
+            .map(|key| String::from_utf8_lossy(key).into_owned())
+            .collect()
+    }
+
+    /// Returns the members of the set at `key`.
+    pub fn members(&self, key: &str) -> Vec<String> {
+        match self.state().keys.get(key.as_bytes()) {
+            Some(Entry::Set(set)) => set
+                .iter()
+                .map(|member| String::from_utf8_lossy(member).into_owned())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the number of connections accepted by the server.
+    pub fn connections(&self) -> usize {
+        self.state().connections
+    }
+
+    /// Sets the field `field` of the hash at `key` to `value`, bypassing the store.
+    pub fn set_field(&self, key: &str, field: &str, value: &str) {
+        let mut state = self.state();
+        state.touch(key.as_bytes());
+
+        if let Some(Entry::Hash(hash)) = state.keys.get_mut(key.as_bytes()) {
+            hash.insert(field.as_bytes().to_vec(), value.as_bytes().to_vec());
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    keys: BTreeMap<Vec<u8>, Entry>,
+    /// The number of modifications of every key, used by `WATCH`.
+    versions: HashMap<Vec<u8>, u64>,
+    connections: usize,
+}
+
+impl State {
+    fn touch(&mut self, key: &[u8]) {
+        *self.versions.entry(key.to_vec()).or_default() += 1;
+    }
+
+    fn version(&self, key: &[u8]) -> u64 {
+        self.versions.get(key).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+enum Entry {
+    String(Vec<u8>),
+    Hash(BTreeMap<Vec<u8>, Vec<u8>>),
+    Set(BTreeSet<Vec<u8>>),
+}
+
+/// The state of a single client connection.
+#[derive(Debug, Default)]
+struct Session {
+    resp3: bool,
+    authenticated: bool,
+    /// The commands queued after `MULTI`.
+    queue: Option<Vec<Vec<Vec<u8>>>>,
+    /// The keys passed to `WATCH` and their versions at that time.
+    watched: Vec<(Vec<u8>, u64)>,
+}
+
+async fn serve(mut stream: TcpStream, state: Arc<Mutex<State>>, config: Config) {
+    state.lock().unwrap().connections += 1;
+
+    let mut session = Session {
+        authenticated: config.password.is_none(),
+        ..Default::default()
+    };
+
+    let mut buf = Vec::new();
+    loop {
+        match resp::decode(&buf) {
+            Ok(Some((frame, len))) => {
+                buf.drain(..len);
+
+                let reply = match args(frame) {
+                    Some(args) => {
+                        let mut state = state.lock().unwrap();
+                        session.handle(&mut state, &config, args)
+                    }
+                    None => error("ERR Protocol error"),
+                };
+
+                let mut out = Vec::new();
+                write(&reply, session.resp3, &mut out);
+                if stream.write_all(&out).await.is_err() {
+                    return;
+                }
+            }
+            Ok(None) => {
+                let mut chunk = [0; 4096];
+                match stream.read(&mut chunk).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(len) => buf.extend_from_slice(&chunk[..len]),
+                }
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+fn args(frame: Frame) -> Option<Vec<Vec<u8>>> {
+    match frame {
+        Frame::Array(frames) if !frames.is_empty() => frames
+            .into_iter()
+            .map(|frame| match frame {
+                Frame::Bulk(arg) => Some(arg),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Writes `frame` in RESP3 or RESP2, converting RESP3 only types for RESP2 clients.
+fn write(frame: &Frame, resp3: bool, out: &mut Vec<u8>) {
+    if resp3 {
+        resp::encode(frame, out);
+        return;
+    }
+
+    match frame {
+        Frame::Null => out.extend_from_slice(b"*-1\r\n"),
+        Frame::Map(entries) => {
+            out.extend_from_slice(format!("*{}\r\n", entries.len() * 2).as_bytes());
+            for (key, value) in entries {
+                write(key, resp3, out);
+                write(value, resp3, out);
+            }
+        }
+        Frame::Array(frames) | Frame::Set(frames) => {
+            out.extend_from_slice(format!("*{}\r\n", frames.len()).as_bytes());
+            for frame in frames {
+                write(frame, resp3, out);
+            }
+        }
+        frame => resp::encode(frame, out),
+    }
+}
+
+fn ok() -> Frame {
+    Frame::Simple("OK".to_owned())
+}
+
+fn error(msg: &str) -> Frame {
+    Frame::Error(msg.to_owned())
+}
+
+fn wrong_type() -> Frame {
+    error("WRONGTYPE Operation against a key holding the wrong kind of value")
+}
+
+fn wrong_args() -> Frame {
+    error("ERR wrong number of arguments")
+}
+
+impl Session {
+    fn handle(&mut self, state: &mut State, config: &Config, args: Vec<Vec<u8>>) -> Frame {
+        let name = String::from_utf8_lossy(&args[0]).to_uppercase();
+
+        if let Some(queue) = &mut self.queue {
+            match name.as_str() {
+                "EXEC" | "DISCARD" | "MULTI" | "WATCH" => {}
+                _ => {
+                    queue.push(args);
+                    return Frame::Simple("QUEUED".to_owned());
+                }
+            }
+        }
+
+        match name.as_str() {
+            "HELLO" => return self.hello(config, &args[1..]),
+            "AUTH" => return self.auth(config, &args[1..]),
+            _ if !self.authenticated => return error("NOAUTH Authentication required."),
+            _ => {}
+        }
+
+        match name.as_str() {
+            "PING" => Frame::Simple("PONG".to_owned()),
+            "SELECT" => ok(),
+            "MULTI" if self.queue.is_some() => error("ERR MULTI calls can not be nested"),
+            "MULTI" => {
+                self.queue = Some(Vec::new());
+                ok()
+            }
+            "DISCARD" => {
+                self.queue = None;
+                self.watched.clear();
+                ok()
+            }
+            "WATCH" if self.queue.is_some() => error("ERR WATCH inside MULTI is not allowed"),
+            "WATCH" => {
+                for key in &args[1..] {
+                    self.watched.push((key.clone(), state.version(key)));
+                }
+                ok()
+            }
+            "UNWATCH" => {
+                self.watched.clear();
+                ok()
+            }
+            "EXEC" => {
+                let queue = match self.queue.take() {
+                    Some(queue) => queue,
+                    None => return error("ERR EXEC without MULTI"),
+                };
+
+                let aborted = self
+                    .watched
+                    .drain(..)
+                    .any(|(key, version)| state.version(&key) != version);
+                if aborted {
+                    return Frame::Null;
+                }
+
+                Frame::Array(queue.into_iter().map(|args| command(state, args)).collect())
+            }
+            _ => command(state, args),
+        }
+    }
+
+    fn hello(&mut self, config: &Config, args: &[Vec<u8>]) -> Frame {
+        if config.resp2_only {
+            return error("ERR unknown command 'HELLO'");
+        }
+
+        let resp3 = match args.first().map(|v| v.as_slice()) {
+            None => self.resp3,
+            Some(b"2") => false,
+            Some(b"3") => true,
+            Some(_) => return error("NOPROTO unsupported protocol version"),
+        };
+
+        match &args[1.min(args.len())..] {
+            [] => {}
+            [auth, username, password] if auth.eq_ignore_ascii_case(b"AUTH") => {
+                let reply = self.auth(config, &[username.clone(), password.clone()]);
+                if let Frame::Error(_) = reply {
+                    return reply;
+                }
+            }
+            _ => return wrong_args(),
+        }
+
+        if !self.authenticated {
+            return error("NOAUTH HELLO must be called with the client already authenticated");
+        }
+
+        self.resp3 = resp3;
+        Frame::Map(vec![
+            (bulk("server"), bulk("redis")),
+            (bulk("version"), bulk("7.0.0")),
+            (bulk("proto"), Frame::Integer(if resp3 { 3 } else { 2 })),
+        ])
+    }
+
+    fn auth(&mut self, config: &Config, args: &[Vec<u8>]) -> Frame {
+        let password = match args {
+            [password] | [_, password] => password,
+            _ => return wrong_args(),
+        };
+
+        match &config.password {
+            Some(expected) if expected.as_bytes() == password.as_slice() => {
+                self.authenticated = true;
+                ok()
+            }
+            Some(_) => error("WRONGPASS invalid username-password pair"),
+            None => error("ERR AUTH called without any password configured"),
+        }
+    }
+}
+
+fn bulk(s: &str) -> Frame {
+    Frame::Bulk(s.as_bytes().to_vec())
+}
+
+/// Executes a data command.
+fn command(state: &mut State, args: Vec<Vec<u8>>) -> Frame {
+    let name = String::from_utf8_lossy(&args[0]).to_uppercase();
+    let args = &args[1..];
+
+    match name.as_str() {
+        "DEL" => {
+            let mut count = 0;
+            for key in args {
+                if state.keys.remove(key).is_some() {
+                    state.touch(key);
+                    count += 1;
+                }
+            }
+            Frame::Integer(count)
+        }
+        "INCR" => {
+            let [key] = args else { return wrong_args() };
+
+            let value = match state.keys.get(key) {
+                None => 0,
+                Some(Entry::String(v)) => match std::str::from_utf8(v)
+                    .ok()
+                    .and_then(|v| v.parse::<i64>().ok())
+                {
+                    Some(v) => v,
+                    None => return error("ERR value is not an integer or out of range"),
+                },
+                Some(_) => return wrong_type(),
+            } + 1;
+
+            state
+                .keys
+                .insert(key.clone(), Entry::String(value.to_string().into_bytes()));
+            state.touch(key);
+            Frame::Integer(value)
+        }
+        "HSET" => {
+            let [key, pairs @ ..] = args else {
+                return wrong_args();
+            };
+            if pairs.is_empty() || pairs.len() % 2 != 0 {
+                return wrong_args();
+            }
+
+            let hash = match state
+                .keys
+                .entry(key.clone())
+                .or_insert_with(|| Entry::Hash(BTreeMap::new()))
+            {
+                Entry::Hash(hash) => hash,
+                _ => return wrong_type(),
+            };
+
+            let mut count = 0;
+            for pair in pairs.chunks(2) {
+                if hash.insert(pair[0].clone(), pair[1].clone()).is_none() {
+                    count += 1;
+                }
+            }
+
+            state.touch(key);
+            Frame::Integer(count)
+        }
+        "HGETALL" => {
+            let [key] = args else { return wrong_args() };
+
+            match state.keys.get(key) {
+                None => Frame::Map(Vec::new()),
+                Some(Entry::Hash(hash)) => Frame::Map(
+                    hash.iter()
+                        .map(|(field, value)| {
+                            (Frame::Bulk(field.clone()), Frame::Bulk(value.clone()))
+                        })
+                        .collect(),
+                ),
+                Some(_) => wrong_type(),
+            }
+        }
+        "SADD" => {
+            let [key, members @ ..] = args else {
+                return wrong_args();
+            };
+
+            let set = match state
+                .keys
+                .entry(key.clone())
+                .or_insert_with(|| Entry::Set(BTreeSet::new()))
+            {
+                Entry::Set(set) => set,
+                _ => return wrong_type(),
+            };
+
+            let count = members
+                .iter()
+                .filter(|member| set.insert(member.to_vec()))
+                .count();
+
+            state.touch(key);
+            Frame::Integer(count as i64)
+        }
+        "SREM" => {
+            let [key, members @ ..] = args else {
+                return wrong_args();
+            };
+
+            let set = match state.keys.get_mut(key) {
+                None => return Frame::Integer(0),
+                Some(Entry::Set(set)) => set,
+                Some(_) => return wrong_type(),
+            };
+
+            let count = members.iter().filter(|member| set.remove(*member)).count();
+            if set.is_empty() {
+                state.keys.remove(key);
+            }
+
+            state.touch(key);
+            Frame::Integer(count as i64)
+        }
+        "SMEMBERS" | "SINTER" => {
+            if args.is_empty() {
+                return wrong_args();
+            }
+
+            let mut result: Option<BTreeSet<Vec<u8>>> = None;
+            for key in args {
+                let set = match state.keys.get(key) {
+                    None => BTreeSet::new(),
+                    Some(Entry::Set(set)) => set.clone(),
+                    Some(_) => return wrong_type(),
+                };
+
+                result = Some(match result {
+                    None => set,
+                    Some(result) => result.intersection(&set).cloned().collect(),
+                });
+            }
+
+            Frame::Set(result.unwrap().into_iter().map(Frame::Bulk).collect())
+        }
+        _ => error(&format!("ERR unknown command '{}'", name)),
+    }
+}
@@ -0,0 +1,569 @@
+//! A SQLite driver for [`datastore`].
+//!
+//! [`SqliteStore`] stores every [`StoreData`] type in its own table. The table is created by
+//! [`Store::create`] from the [`DataDescriptor`] of the type using `CREATE TABLE IF NOT EXISTS`.
+//! [`DataQuery`]s are translated into parameterized `WHERE` clauses, the fields written by the
+//! query are compared for equality and combined using `AND`.
+//!
+//! ```
+//! use datastore::{Store, StoreData, StoreExt};
+//! use datastore_sqlite::SqliteStore;
+//!
+//! #[derive(StoreData)]
+//! struct Person {
+//!     #[datastore(primary_key)]
+//!     id: i64,
+//!     name: String,
+//! }
+//!
+//! # async fn run() -> Result<(), datastore_sqlite::Error> {
+//! let store = SqliteStore::connect(":memory:").await?;
+//! store.create(store.descriptor::<Person>()).await?;
+//!
+//! let person = Person {
+//!     id: 1,
+//!     name: String::from("Robb"),
+//! };
+//! store.insert(store.descriptor::<Person>(), person).await?;
+//!
+//! let people: Vec<Person> = store.get_all(store.descriptor::<Person>()).await?;
+//! assert_eq!(people.len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Limitations
+//!
+//! Every field must be a single column. Nested fields are rejected when creating the table.
+//! `u64` values larger than [`i64::MAX`] cannot be stored in a SQLite `INTEGER` column and are
+//! rejected when writing. SQLite `NULL` values are treated as missing fields.
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use datastore::sql::{self, ColumnType, Dialect, Table};
+use datastore::value::{self, Record, Value};
+use datastore::{
+    Capabilities, ConnectOptions, DataDescriptor, DataQuery, Read, Reader, Store, StoreData,
+    TypeWriter, Write, Writer,
+};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params_from_iter, Connection, ErrorCode};
+
+/// A [`Store`] backed by a SQLite database.
+///
+/// Operations are executed synchronously on a single connection that is shared behind a
+/// [`Mutex`].
+#[derive(Debug)]
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Opens the SQLite database at `path`, creating it if it does not exist.
+    pub fn open<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::from_connection(Connection::open(path)?))
+    }
+
+    /// Opens a new, empty in-memory SQLite database.
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Ok(Self::from_connection(Connection::open_in_memory()?))
+    }
+
+    /// Creates a new `SqliteStore` from an existing [`Connection`].
+    pub fn from_connection(conn: Connection) -> Self {
+        Self {
+            conn: Mutex::new(conn),
+        }
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        // A panic while holding the lock cannot leave the connection in an invalid state.
+        match self.conn.lock() {
+            Ok(conn) => conn,
+            Err(err) => err.into_inner(),
+        }
+    }
+}
+
+/// Registers [`SqliteStore`] for the `sqlite` scheme, allowing [`datastore::connect`] to connect to
+/// `sqlite://` URIs.
+pub fn register() {
+    datastore::registry::register::<SqliteStore>("sqlite");
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    type DataStore = Self;
+    type Error = Error;
+
+    /// Connects to the SQLite database at `uri`.
+    ///
+    /// `uri` is either a file path or `:memory:` for an in-memory database, optionally prefixed
+    /// with `sqlite://`.
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        let path = uri.strip_prefix("sqlite://").unwrap_or(uri);
+
+        match path {
+            ":memory:" => Self::open_in_memory(),
+            path => Self::open(path),
+        }
+    }
+
+    /// Connects to the SQLite database at the host and path of the `options`. See [`connect`]
+    /// for the accepted values.
+    ///
+    /// [`connect`]: Self::connect
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        // The trailing colon of `:memory:` is parsed as an empty port.
+        match options.host_path().as_str() {
+            ":memory" | ":memory:" => Self::open_in_memory(),
+            path => Self::open(path),
+        }
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let stmts = sql::create_table(&descriptor, Dialect::Sqlite)?;
+
+        let conn = self.conn();
+        for stmt in stmts {
+            conn.execute(&stmt, [])?;
+        }
+
+        Ok(())
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        let (filter, params) = where_clause(&record)?;
+        let stmt = format!(
+            "DELETE FROM {}{}",
+            Dialect::Sqlite.quote_ident(descriptor.ident()),
+            filter
+        );
+
+        self.conn().execute(&stmt, params_from_iter(params))?;
+        Ok(())
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        self.select(&descriptor, &record, None)
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        self.select(&descriptor, &Record::new(), None)
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        query.write(&mut record)?;
+
+        let items = self.select(&descriptor, &record, Some(1))?;
+        Ok(items.into_iter().next())
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let record = Record::from_data(&data)?;
+
+        let mut columns = Vec::with_capacity(record.len());
+        let mut placeholders = Vec::with_capacity(record.len());
+        let mut params = Vec::with_capacity(record.len());
+        for (index, (key, value)) in record.iter().enumerate() {
+            columns.push(Dialect::Sqlite.quote_ident(key));
+            placeholders.push(format!("?{}", index + 1));
+            params.push(to_sql(key, value)?);
+        }
+
+        let stmt = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            Dialect::Sqlite.quote_ident(descriptor.ident()),
+            columns.join(", "),
+            placeholders.join(", ")
+        );
+
+        self.conn().execute(&stmt, params_from_iter(params))?;
+        Ok(())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::WRITE | Capabilities::TRANSACTIONS
+    }
+}
+
+impl SqliteStore {
+    /// Returns all items described by `descriptor` matching the `query`, returning at most
+    /// `limit` items.
+    fn select<T, D>(
+        &self,
+        descriptor: &D,
+        query: &Record,
+        limit: Option<usize>,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: StoreData<Self>,
+        D: DataDescriptor<T, Self>,
+    {
+        let table = Table::new(descriptor)?;
+
+        let columns: Vec<String> = table
+            .columns()
+            .iter()
+            .map(|column| Dialect::Sqlite.quote_ident(column.name()))
+            .collect();
+
+        let (filter, params) = where_clause(query)?;
+        let mut stmt = format!(
+            "SELECT {} FROM {}{}",
+            columns.join(", "),
+            Dialect::Sqlite.quote_ident(table.name()),
+            filter
+        );
+
+        if let Some(limit) = limit {
+            stmt.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&stmt)?;
+        let mut rows = stmt.query(params_from_iter(params))?;
+
+        let mut items = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut record = Record::with_capacity(table.columns().len());
+            for (index, column) in table.columns().iter().enumerate() {
+                if let Some(value) = from_sql(column.ty(), row.get_ref(index)?) {
+                    record.insert(column.name().to_owned(), value);
+                }
+            }
+
+            items.push(descriptor.read(&mut record)?);
+        }
+
+        Ok(items)
+    }
+}
+
+/// Builds the `WHERE` clause comparing all fields of `query` for equality. Returns an empty
+/// clause if `query` contains no fields.
+fn where_clause(query: &Record) -> Result<(String, Vec<SqlValue>), Error> {
+    let mut clause = String::new();
+    let mut params = Vec::with_capacity(query.len());
+
+    for (index, (key, value)) in query.iter().enumerate() {
+        clause.push_str(if index == 0 { " WHERE " } else { " AND " });
+        clause.push_str(&format!(
+            "{} = ?{}",
+            Dialect::Sqlite.quote_ident(key),
+            index + 1
+        ));
+
+        params.push(to_sql(key, value)?);
+    }
+
+    Ok((clause, params))
+}
+
+/// Converts the [`Value`] of the field `key` into a SQLite value.
+fn to_sql(key: &str, value: &Value) -> Result<SqlValue, Error> {
+    let value = match value {
+        Value::Bool(v) => SqlValue::Integer(i64::from(*v)),
+        Value::I8(v) => SqlValue::Integer(i64::from(*v)),
+        Value::I16(v) => SqlValue::Integer(i64::from(*v)),
+        Value::I32(v) => SqlValue::Integer(i64::from(*v)),
+        Value::I64(v) => SqlValue::Integer(*v),
+        Value::U8(v) => SqlValue::Integer(i64::from(*v)),
+        Value::U16(v) => SqlValue::Integer(i64::from(*v)),
+        Value::U32(v) => SqlValue::Integer(i64::from(*v)),
+        Value::U64(v) => match i64::try_from(*v) {
+            Ok(v) => SqlValue::Integer(v),
+            Err(_) => {
+                return Err(Error::OutOfRange {
+                    key: key.to_owned(),
+                })
+            }
+        },
+        Value::F32(v) => SqlValue::Real(f64::from(*v)),
+        Value::F64(v) => SqlValue::Real(*v),
+        Value::Bytes(v) => SqlValue::Blob(v.clone()),
+        Value::String(v) => SqlValue::Text(v.clone()),
+        Value::Record(_) => {
+            return Err(Error::NestedField {
+                key: key.to_owned(),
+            })
+        }
+    };
+
+    Ok(value)
+}
+
+/// Converts a SQLite value of a column with the type `ty` into a [`Value`]. Returns `None` for
+/// `NULL` values.
+fn from_sql(ty: ColumnType, value: ValueRef<'_>) -> Option<Value> {
+    let value = match value {
+        ValueRef::Null => return None,
+        ValueRef::Integer(v) => match ty {
+            ColumnType::Bool => Value::Bool(v != 0),
+            _ => Value::I64(v),
+        },
+        ValueRef::Real(v) => Value::F64(v),
+        ValueRef::Text(v) => Value::String(String::from_utf8_lossy(v).into_owned()),
+        ValueRef::Blob(v) => Value::Bytes(v.to_vec()),
+    };
+
+    Some(value)
+}
+
+/// An error returned by [`SqliteStore`].
+#[derive(Debug)]
+pub enum Error {
+    /// An error returned by SQLite.
+    Sqlite(rusqlite::Error),
+    /// The table could not be created from the descriptor.
+    Sql(sql::Error),
+    /// A value could not be written or read.
+    Value(value::Error),
+    /// A `u64` value of the field `key` does not fit into a SQLite `INTEGER`.
+    OutOfRange { key: String },
+    /// The field `key` contains nested fields.
+    NestedField { key: String },
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sqlite(err) => Display::fmt(err, f),
+            Self::Sql(err) => Display::fmt(err, f),
+            Self::Value(err) => Display::fmt(err, f),
+            Self::OutOfRange { key } => write!(f, "value of field {:?} is out of range", key),
+            Self::NestedField { key } => write!(f, "nested field {:?} is not supported", key),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Sqlite(err) => Some(err),
+            Self::Sql(err) => Some(err),
+            Self::Value(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl datastore::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Sqlite(err) => matches!(
+                err.sqlite_error_code(),
+                Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+            ),
+            _ => false,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Sqlite(err)
+    }
+}
+
+impl From<sql::Error> for Error {
+    fn from(err: sql::Error) -> Self {
+        Self::Sql(err)
+    }
+}
+
+impl From<value::Error> for Error {
+    fn from(err: value::Error) -> Self {
+        Self::Value(err)
+    }
+}
+
+macro_rules! impl_types {
+    ($($ty:ty => $write:ident, $read:ident, $tywrite:ident;)*) => {
+        $(
+            impl Write<SqliteStore> for $ty {
+                #[inline]
+                fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: Writer<SqliteStore>,
+                {
+                    writer.$write(*self)
+                }
+
+                #[inline]
+                fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: TypeWriter<SqliteStore>,
+                {
+                    writer.$tywrite()
+                }
+            }
+
+            impl Read<SqliteStore> for $ty {
+                #[inline]
+                fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+                where
+                    R: Reader<SqliteStore>,
+                {
+                    reader.$read()
+                }
+            }
+        )*
+    };
+}
+
+impl_types! {
+    bool => write_bool, read_bool, write_bool;
+    i8 => write_i8, read_i8, write_i8;
+    i16 => write_i16, read_i16, write_i16;
+    i32 => write_i32, read_i32, write_i32;
+    i64 => write_i64, read_i64, write_i64;
+    u8 => write_u8, read_u8, write_u8;
+    u16 => write_u16, read_u16, write_u16;
+    u32 => write_u32, read_u32, write_u32;
+    u64 => write_u64, read_u64, write_u64;
+    f32 => write_f32, read_f32, write_f32;
+    f64 => write_f64, read_f64, write_f64;
+}
+
+impl Write<SqliteStore> for str {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<SqliteStore>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<SqliteStore>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Write<SqliteStore> for String {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<SqliteStore>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<SqliteStore>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Read<SqliteStore> for String {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<SqliteStore>,
+    {
+        reader.read_string()
+    }
+}
+
+impl Write<SqliteStore> for [u8] {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<SqliteStore>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<SqliteStore>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Write<SqliteStore> for Vec<u8> {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<SqliteStore>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<SqliteStore>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Read<SqliteStore> for Vec<u8> {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<SqliteStore>,
+    {
+        reader.read_byte_buf()
+    }
+}
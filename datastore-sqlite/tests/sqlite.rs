@@ -0,0 +1,364 @@
+use datastore::namespace::Namespaced;
+use datastore::value::{Record, RecordDescriptor, RecordQuery, Type};
+use datastore::{ConnectOptions, FieldAttributes, Store, StoreData, StoreExt};
+use datastore_sqlite::{Error, SqliteStore};
+
+#[derive(Clone, Debug, PartialEq, StoreData)]
+struct Person {
+    #[datastore(primary_key)]
+    id: i64,
+    #[datastore(index)]
+    name: String,
+    age: u8,
+    active: bool,
+}
+
+fn people() -> Vec<Person> {
+    vec![
+        Person {
+            id: 1,
+            name: "Alice".to_owned(),
+            age: 30,
+            active: true,
+        },
+        Person {
+            id: 2,
+            name: "Bob".to_owned(),
+            age: 30,
+            active: false,
+        },
+        Person {
+            id: 3,
+            name: "Carol".to_owned(),
+            age: 42,
+            active: true,
+        },
+    ]
+}
+
+async fn store() -> SqliteStore {
+    let store = SqliteStore::connect(":memory:").await.unwrap();
+    store.create(store.descriptor::<Person>()).await.unwrap();
+
+    for person in people() {
+        store
+            .insert(store.descriptor::<Person>(), person)
+            .await
+            .unwrap();
+    }
+
+    store
+}
+
+#[tokio::test]
+async fn test_sqlite_get_all() {
+    let store = store().await;
+
+    let mut output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    output.sort_by_key(|person| person.id);
+    assert_eq!(output, people());
+}
+
+#[tokio::test]
+async fn test_sqlite_create_twice() {
+    let store = store().await;
+    store.create(store.descriptor::<Person>()).await.unwrap();
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output.len(), 3);
+}
+
+#[tokio::test]
+async fn test_sqlite_get() {
+    let store = store().await;
+
+    let mut output = store
+        .get(store.descriptor::<Person>(), PersonQuery::default().age(30))
+        .await
+        .unwrap();
+    output.sort_by_key(|person| person.id);
+    assert_eq!(output, people()[..2]);
+
+    let output = store
+        .get(
+            store.descriptor::<Person>(),
+            PersonQuery::default().age(30).active(true),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output, people()[..1]);
+
+    let output = store
+        .get(
+            store.descriptor::<Person>(),
+            PersonQuery::default().name("Robert'); DROP TABLE person;--".to_owned()),
+        )
+        .await
+        .unwrap();
+    assert!(output.is_empty());
+}
+
+#[tokio::test]
+async fn test_sqlite_get_one() {
+    let store = store().await;
+
+    let output = store
+        .get_one(
+            store.descriptor::<Person>(),
+            PersonQuery::default().name("Carol".to_owned()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output, Some(people().remove(2)));
+
+    let output = store
+        .get_one(store.descriptor::<Person>(), PersonQuery::default().id(4))
+        .await
+        .unwrap();
+    assert_eq!(output, None);
+}
+
+#[tokio::test]
+async fn test_sqlite_delete() {
+    let store = store().await;
+
+    store
+        .delete(
+            store.descriptor::<Person>(),
+            PersonQuery::default().active(true),
+        )
+        .await
+        .unwrap();
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people()[1..2]);
+
+    store
+        .delete(store.descriptor::<Person>(), PersonQuery::default())
+        .await
+        .unwrap();
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert!(output.is_empty());
+}
+
+#[tokio::test]
+async fn test_sqlite_insert_duplicate_key() {
+    let store = store().await;
+
+    let err = store
+        .insert(store.descriptor::<Person>(), people().remove(0))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Sqlite(_)));
+}
+
+#[tokio::test]
+async fn test_sqlite_types() {
+    #[derive(Clone, Debug, PartialEq, StoreData)]
+    struct Everything {
+        i8: i8,
+        i16: i16,
+        i32: i32,
+        i64: i64,
+        u8: u8,
+        u16: u16,
+        u32: u32,
+        u64: u64,
+        f32: f32,
+        f64: f64,
+        bytes: Vec<u8>,
+        string: String,
+    }
+
+    let data = Everything {
+        i8: i8::MIN,
+        i16: i16::MIN,
+        i32: i32::MIN,
+        i64: i64::MIN,
+        u8: u8::MAX,
+        u16: u16::MAX,
+        u32: u32::MAX,
+        u64: i64::MAX as u64,
+        f32: 0.5,
+        f64: -1e300,
+        bytes: vec![0, 1, 255],
+        string: "\u{1f600}".to_owned(),
+    };
+
+    let store = SqliteStore::open_in_memory().unwrap();
+    store
+        .create(store.descriptor::<Everything>())
+        .await
+        .unwrap();
+    store
+        .insert(store.descriptor::<Everything>(), data.clone())
+        .await
+        .unwrap();
+
+    let mut output: Vec<Everything> = store
+        .get_all(store.descriptor::<Everything>())
+        .await
+        .unwrap();
+    assert_eq!(output, [data]);
+
+    let mut data = output.remove(0);
+    data.u64 = u64::MAX;
+    let err = store
+        .insert(store.descriptor::<Everything>(), data)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::OutOfRange { key } if key == "u64"));
+}
+
+#[tokio::test]
+async fn test_sqlite_record() {
+    let descriptor = RecordDescriptor::new("record")
+        .field("id", Type::I64)
+        .field("name", Type::String);
+
+    let store = SqliteStore::open_in_memory().unwrap();
+    store.create(descriptor.clone()).await.unwrap();
+
+    let record: Record = [("id", 1i64.into()), ("name", "Alice".into())]
+        .into_iter()
+        .collect();
+    store
+        .insert(descriptor.clone(), record.clone())
+        .await
+        .unwrap();
+
+    let output = store
+        .get_one(descriptor, RecordQuery::new().field("name", "Alice"))
+        .await
+        .unwrap();
+    assert_eq!(output, Some(record));
+}
+
+#[tokio::test]
+async fn test_sqlite_record_null() {
+    let descriptor = RecordDescriptor::new("record")
+        .field("id", Type::I64)
+        .field_with("name", Type::String, FieldAttributes::new().nullable(true));
+
+    let store = SqliteStore::open_in_memory().unwrap();
+    store.create(descriptor.clone()).await.unwrap();
+
+    let record: Record = [("id", 1i64.into())].into_iter().collect();
+    store
+        .insert(descriptor.clone(), record.clone())
+        .await
+        .unwrap();
+
+    // The NULL column is left out of the record.
+    let output = store.get_all(descriptor).await.unwrap();
+    assert_eq!(output, [record]);
+}
+
+#[tokio::test]
+async fn test_sqlite_file() {
+    let path = std::env::temp_dir().join(format!("datastore-sqlite-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    {
+        let store = SqliteStore::connect(&format!("sqlite://{}", path.display()))
+            .await
+            .unwrap();
+        store.create(store.descriptor::<Person>()).await.unwrap();
+        store
+            .insert(store.descriptor::<Person>(), people().remove(0))
+            .await
+            .unwrap();
+    }
+
+    let store = SqliteStore::open(&path).unwrap();
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people()[..1]);
+
+    drop(store);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_sqlite_registry() {
+    datastore_sqlite::register();
+
+    let store = datastore::connect("sqlite://:memory:").await.unwrap();
+    store.create(store.descriptor::<Person>()).await.unwrap();
+
+    for person in people() {
+        store
+            .insert(store.descriptor::<Person>(), person)
+            .await
+            .unwrap();
+    }
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people());
+
+    let err = match datastore::connect("sqlite:///nonexistent/dir/db.sqlite").await {
+        Ok(_) => panic!("connected to a nonexistent directory"),
+        Err(err) => err,
+    };
+    match err {
+        datastore::registry::Error::Connect { scheme, error } => {
+            assert_eq!(scheme, "sqlite");
+            assert!(error.is::<Error>());
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[tokio::test]
+async fn test_sqlite_namespace_field() {
+    let store = SqliteStore::connect(":memory:").await.unwrap();
+
+    let acme = Namespaced::field(store, "tenant", "acme");
+    acme.create(acme.descriptor::<Person>()).await.unwrap();
+    for person in people() {
+        acme.insert(acme.descriptor::<Person>(), person)
+            .await
+            .unwrap();
+    }
+
+    // The tenant is part of the primary key, so tenants can use the same ids.
+    let globex = Namespaced::field(acme.into_inner(), "tenant", "globex");
+    let dave = Person {
+        id: 1,
+        name: "Dave".to_owned(),
+        age: 25,
+        active: true,
+    };
+    globex
+        .insert(globex.descriptor::<Person>(), dave.clone())
+        .await
+        .unwrap();
+
+    let output: Vec<Person> = globex.get_all(globex.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, [dave]);
+
+    let acme = Namespaced::field(globex.into_inner(), "tenant", "acme");
+    let mut output: Vec<Person> = acme.get_all(acme.descriptor::<Person>()).await.unwrap();
+    output.sort_by_key(|person| person.id);
+    assert_eq!(output, people());
+}
+
+#[tokio::test]
+async fn test_sqlite_connect_with() {
+    let options = ConnectOptions::parse("sqlite://:memory:").unwrap();
+    let store = SqliteStore::connect_with(&options).await.unwrap();
+    store.create(store.descriptor::<Person>()).await.unwrap();
+
+    // Paths are decoded before opening the database.
+    let dir = std::env::temp_dir().join(format!("datastore sqlite {}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("people.db");
+
+    let options = ConnectOptions::new("sqlite").with_path(path.to_str().unwrap());
+    assert!(options.to_string().contains("%20"));
+    let store = SqliteStore::connect_with(&options).await.unwrap();
+    store.create(store.descriptor::<Person>()).await.unwrap();
+    assert!(path.exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
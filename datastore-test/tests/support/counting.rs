@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use async_trait::async_trait;
+use datastore::value::{Record, RecordDescriptor};
+use datastore::{DataDescriptor, DataQuery, Error, Store, StoreData};
+use datastore_memory::MemoryStore;
+
+/// The state shared by all connections of a [`CountingStore`] to the same uri.
+#[derive(Debug, Default)]
+pub struct Backend {
+    pub store: MemoryStore,
+    /// The number of connections ever opened.
+    pub connects: AtomicUsize,
+    /// The number of connections that are currently open.
+    pub open: AtomicUsize,
+    /// The number of calls to `ping`.
+    pub pings: AtomicUsize,
+    /// The number of operations, excluding `connect` and `ping`.
+    pub calls: AtomicUsize,
+    /// Makes `connect` fail.
+    pub refuse: AtomicBool,
+    /// Makes `ping` fail on all connections opened before.
+    pub broken: AtomicUsize,
+    /// The number of following operations failing with a transient error.
+    pub failures: AtomicUsize,
+    /// The number of following operations that never complete.
+    pub stalls: AtomicUsize,
+    /// The number of following calls to `ping` that never complete.
+    pub ping_stalls: AtomicUsize,
+}
+
+impl Backend {
+    pub fn connects(&self) -> usize {
+        self.connects.load(Ordering::SeqCst)
+    }
+
+    pub fn open(&self) -> usize {
+        self.open.load(Ordering::SeqCst)
+    }
+
+    pub fn pings(&self) -> usize {
+        self.pings.load(Ordering::SeqCst)
+    }
+
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    pub fn refuse(&self, refuse: bool) {
+        self.refuse.store(refuse, Ordering::SeqCst);
+    }
+
+    /// Returns the number of items stored under `ident`.
+    pub async fn count(&self, ident: &str) -> usize {
+        let items: Vec<Record> = self
+            .store
+            .get_all(RecordDescriptor::new(ident))
+            .await
+            .unwrap();
+        items.len()
+    }
+
+    /// Breaks all connections that are currently open.
+    pub fn break_connections(&self) {
+        self.broken.store(self.connects(), Ordering::SeqCst);
+    }
+
+    /// Makes the next `n` operations fail with a transient error before reaching the store.
+    pub fn fail(&self, n: usize) {
+        self.failures.store(n, Ordering::SeqCst);
+    }
+
+    /// Makes the next `n` operations never complete.
+    pub fn stall(&self, n: usize) {
+        self.stalls.store(n, Ordering::SeqCst);
+    }
+
+    /// Makes the next `n` calls to `ping` never complete.
+    pub fn stall_pings(&self, n: usize) {
+        self.ping_stalls.store(n, Ordering::SeqCst);
+    }
+}
+
+/// Decrements `counter` if it is not `0`, returning whether it was decremented.
+fn take(counter: &AtomicUsize) -> bool {
+    counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok()
+}
+
+/// Returns the [`Backend`] of all [`CountingStore`]s connected to `uri`.
+pub fn backend(uri: &str) -> Arc<Backend> {
+    static BACKENDS: OnceLock<Mutex<HashMap<String, Arc<Backend>>>> = OnceLock::new();
+
+    BACKENDS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .entry(uri.to_owned())
+        .or_default()
+        .clone()
+}
+
+/// A fake connection to a [`MemoryStore`] shared by all connections to the same uri. The
+/// [`Backend`] counts connections and operations.
+#[derive(Debug)]
+pub struct CountingStore {
+    /// The number of the connection, starting at 1.
+    pub id: usize,
+    backend: Arc<Backend>,
+}
+
+impl CountingStore {
+    pub fn backend(&self) -> &Backend {
+        &self.backend
+    }
+
+    async fn call(&self) -> Result<&MemoryStore, CountingError> {
+        self.backend.calls.fetch_add(1, Ordering::SeqCst);
+
+        if take(&self.backend.stalls) {
+            future::pending::<()>().await;
+        }
+
+        match take(&self.backend.failures) {
+            true => Err(CountingError { transient: true }),
+            false => Ok(&self.backend.store),
+        }
+    }
+}
+
+impl Drop for CountingStore {
+    fn drop(&mut self) {
+        self.backend.open.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Store for CountingStore {
+    type DataStore = MemoryStore;
+    type Error = CountingError;
+
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        let backend = backend(uri);
+        if backend.refuse.load(Ordering::SeqCst) {
+            return Err(CountingError { transient: true });
+        }
+
+        let id = backend.connects.fetch_add(1, Ordering::SeqCst) + 1;
+        backend.open.fetch_add(1, Ordering::SeqCst);
+
+        Ok(Self { id, backend })
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        Ok(self.call().await?.create(descriptor).await?)
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        Ok(self.call().await?.delete(descriptor, query).await?)
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        Ok(self.call().await?.get(descriptor, query).await?)
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        Ok(self.call().await?.get_all(descriptor).await?)
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        Ok(self.call().await?.get_one(descriptor, query).await?)
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        Ok(self.call().await?.insert(descriptor, data).await?)
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.backend.pings.fetch_add(1, Ordering::SeqCst);
+
+        if take(&self.backend.ping_stalls) {
+            future::pending::<()>().await;
+        }
+
+        match self.id <= self.backend.broken.load(Ordering::SeqCst) {
+            true => Err(CountingError::default()),
+            false => Ok(()),
+        }
+    }
+}
+
+/// The error returned by a [`CountingStore`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CountingError {
+    pub transient: bool,
+}
+
+impl Display for CountingError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.transient {
+            true => f.write_str("transient error"),
+            false => f.write_str("error"),
+        }
+    }
+}
+
+impl error::Error for CountingError {}
+
+impl Error for CountingError {
+    fn custom<T>(_msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::default()
+    }
+
+    fn is_transient(&self) -> bool {
+        self.transient
+    }
+}
+
+impl From<datastore_memory::Error> for CountingError {
+    fn from(_: datastore_memory::Error) -> Self {
+        Self::default()
+    }
+}
@@ -1,12 +1,22 @@
+#![allow(dead_code)]
+
+pub mod counting;
+pub mod person;
 mod types;
 
 use std::collections::HashMap;
-use std::convert::Infallible;
 use std::error;
 use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
 
 use async_trait::async_trait;
-use datastore::{DataDescriptor, DataQuery, Error, Store, StoreData, TypeWriter, Write};
+use datastore::{
+    DataDescriptor, DataQuery, Error, FieldAttributes, ReadSeed, Reader, Store, StoreData,
+    TypeWriter, Write, WriteTypeSeed, Writer,
+};
 
 #[macro_export]
 macro_rules! __descriptor {
@@ -152,7 +162,7 @@ impl __TypeWriter {
 }
 
 impl TypeWriter<__Store> for __TypeWriter {
-    type Error = Infallible;
+    type Error = __Error;
 
     fn write_bool(&mut self) -> Result<(), Self::Error> {
         self.typ = Type::Bool;
@@ -219,11 +229,16 @@ impl TypeWriter<__Store> for __TypeWriter {
         Ok(())
     }
 
-    fn write_field<T>(&mut self, key: &'static str) -> Result<(), Self::Error>
+    fn write_field_seed<T>(
+        &mut self,
+        key: &str,
+        seed: T,
+        _attrs: FieldAttributes,
+    ) -> Result<(), Self::Error>
     where
-        T: ?Sized + Write<__Store>,
+        T: WriteTypeSeed<__Store>,
     {
-        T::write_type(self)?;
+        seed.write_type(self)?;
         self.values.insert(key.to_owned(), self.typ);
         Ok(())
     }
@@ -245,3 +260,180 @@ pub enum Type {
     Bytes,
     Str,
 }
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Bytes(Vec<u8>),
+    Str(String),
+}
+
+/// A [`Writer`] capturing all written fields.
+#[derive(Debug, Default)]
+pub struct __Writer {
+    value: Option<Value>,
+    pub values: HashMap<String, Value>,
+}
+
+impl __Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn write_value(&mut self, value: Value) -> Result<(), __Error> {
+        self.value = Some(value);
+        Ok(())
+    }
+}
+
+impl Writer<__Store> for __Writer {
+    type Error = __Error;
+
+    fn write_bool(&mut self, v: bool) -> Result<(), Self::Error> {
+        self.write_value(Value::Bool(v))
+    }
+
+    fn write_i8(&mut self, v: i8) -> Result<(), Self::Error> {
+        self.write_value(Value::I8(v))
+    }
+
+    fn write_i16(&mut self, v: i16) -> Result<(), Self::Error> {
+        self.write_value(Value::I16(v))
+    }
+
+    fn write_i32(&mut self, v: i32) -> Result<(), Self::Error> {
+        self.write_value(Value::I32(v))
+    }
+
+    fn write_i64(&mut self, v: i64) -> Result<(), Self::Error> {
+        self.write_value(Value::I64(v))
+    }
+
+    fn write_u8(&mut self, v: u8) -> Result<(), Self::Error> {
+        self.write_value(Value::U8(v))
+    }
+
+    fn write_u16(&mut self, v: u16) -> Result<(), Self::Error> {
+        self.write_value(Value::U16(v))
+    }
+
+    fn write_u32(&mut self, v: u32) -> Result<(), Self::Error> {
+        self.write_value(Value::U32(v))
+    }
+
+    fn write_u64(&mut self, v: u64) -> Result<(), Self::Error> {
+        self.write_value(Value::U64(v))
+    }
+
+    fn write_f32(&mut self, v: f32) -> Result<(), Self::Error> {
+        self.write_value(Value::F32(v))
+    }
+
+    fn write_f64(&mut self, v: f64) -> Result<(), Self::Error> {
+        self.write_value(Value::F64(v))
+    }
+
+    fn write_bytes(&mut self, v: &[u8]) -> Result<(), Self::Error> {
+        self.write_value(Value::Bytes(v.to_vec()))
+    }
+
+    fn write_str(&mut self, v: &str) -> Result<(), Self::Error> {
+        self.write_value(Value::Str(v.to_owned()))
+    }
+
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<__Store>,
+    {
+        value.write(self)?;
+
+        if let Some(value) = self.value.take() {
+            self.values.insert(key.to_owned(), value);
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`Reader`] reading from previously captured fields.
+#[derive(Debug, Default)]
+pub struct __Reader {
+    value: Option<Value>,
+    pub values: HashMap<String, Value>,
+}
+
+impl __Reader {
+    pub fn new(values: HashMap<String, Value>) -> Self {
+        Self {
+            value: None,
+            values,
+        }
+    }
+}
+
+macro_rules! read_value {
+    ($($read:ident => $variant:ident, $ty:ty;)*) => {
+        $(
+            fn $read(&mut self) -> Result<$ty, Self::Error> {
+                match self.value.take() {
+                    Some(Value::$variant(v)) => Ok(v),
+                    _ => Err(__Error),
+                }
+            }
+        )*
+    };
+}
+
+impl Reader<__Store> for __Reader {
+    type Error = __Error;
+
+    read_value! {
+        read_bool => Bool, bool;
+        read_i8 => I8, i8;
+        read_i16 => I16, i16;
+        read_i32 => I32, i32;
+        read_i64 => I64, i64;
+        read_u8 => U8, u8;
+        read_u16 => U16, u16;
+        read_u32 => U32, u32;
+        read_u64 => U64, u64;
+        read_f32 => F32, f32;
+        read_f64 => F64, f64;
+        read_byte_buf => Bytes, Vec<u8>;
+        read_string => Str, String;
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<__Store>,
+    {
+        self.value = self.values.remove(key);
+        seed.read(self)
+    }
+}
+
+/// A waker that does nothing.
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Polls the `future` once.
+pub fn poll<F>(future: &mut Pin<Box<F>>) -> Poll<F::Output>
+where
+    F: Future + ?Sized,
+{
+    let waker = Waker::from(Arc::new(NoopWaker));
+    future.as_mut().poll(&mut Context::from_waker(&waker))
+}
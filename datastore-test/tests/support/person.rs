@@ -0,0 +1,22 @@
+use datastore::{Store, StoreData};
+
+use super::counting::CountingStore;
+
+#[derive(Clone, Debug, PartialEq, StoreData)]
+pub struct Person {
+    #[datastore(primary_key)]
+    pub id: i64,
+    pub name: String,
+}
+
+pub fn person(id: i64, name: &str) -> Person {
+    Person {
+        id,
+        name: name.to_owned(),
+    }
+}
+
+/// Connects to the [`CountingStore`] backend at `uri`.
+pub async fn connect(uri: &str) -> CountingStore {
+    CountingStore::connect(uri).await.unwrap()
+}
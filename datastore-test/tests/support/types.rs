@@ -101,6 +101,36 @@ impl Read<__Store> for f64 {
     }
 }
 
+impl Read<__Store> for String {
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<__Store>,
+    {
+        reader.read_string()
+    }
+}
+
+impl Read<__Store> for Vec<u8> {
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<__Store>,
+    {
+        reader.read_byte_buf()
+    }
+}
+
+impl<T> Read<__Store> for Option<T>
+where
+    T: Read<__Store>,
+{
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<__Store>,
+    {
+        T::read(reader).map(Some)
+    }
+}
+
 impl Write<__Store> for bool {
     fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
     where
@@ -276,3 +306,90 @@ impl Write<__Store> for f64 {
         writer.write_f64()
     }
 }
+
+impl Write<__Store> for str {
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<__Store>,
+    {
+        writer.write_str(self)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<__Store>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Write<__Store> for String {
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<__Store>,
+    {
+        writer.write_str(self)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<__Store>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Write<__Store> for [u8] {
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<__Store>,
+    {
+        writer.write_bytes(self)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<__Store>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Write<__Store> for Vec<u8> {
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<__Store>,
+    {
+        writer.write_bytes(self)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<__Store>,
+    {
+        writer.write_bytes()
+    }
+}
+
+/// `None` values are not written at all.
+impl<T> Write<__Store> for Option<T>
+where
+    T: Write<__Store>,
+{
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<__Store>,
+    {
+        match self {
+            Some(value) => value.write(writer),
+            None => Ok(()),
+        }
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<__Store>,
+    {
+        T::write_type(writer)
+    }
+}
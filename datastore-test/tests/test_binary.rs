@@ -0,0 +1,209 @@
+mod support;
+
+use datastore::binary::{self, BinaryReader, Error};
+use datastore::{Read, Reader, StoreData, TypeWriter, Write, Writer};
+
+use self::support::__Store;
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Sample {
+    a: bool,
+    n: i32,
+    s: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Write<__Store> for Point {
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<__Store>,
+    {
+        writer.write_field("x", &self.x)?;
+        writer.write_field("y", &self.y)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<__Store>,
+    {
+        writer.write_field::<i32>("x")?;
+        writer.write_field::<i32>("y")
+    }
+}
+
+impl Read<__Store> for Point {
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<__Store>,
+    {
+        Ok(Self {
+            x: reader.read_field("x")?,
+            y: reader.read_field("y")?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Everything {
+    bool: bool,
+    i8: i8,
+    i16: i16,
+    i32: i32,
+    i64: i64,
+    u8: u8,
+    u16: u16,
+    u32: u32,
+    u64: u64,
+    f32: f32,
+    f64: f64,
+    bytes: Vec<u8>,
+    string: String,
+    origin: Point,
+}
+
+fn sample() -> Sample {
+    Sample {
+        a: true,
+        n: -2,
+        s: "hi".to_owned(),
+    }
+}
+
+// Version 1, message length 20
+//   "a" (tag 0xe40c292c, varint): 1
+//   "n" (tag 0xeb0c3431, varint): zigzag(-2) = 3
+//   "s" (tag 0xf60c4582, len): "hi"
+const SAMPLE: &[u8] = &[
+    0x01, 0x14, 0xe0, 0x92, 0x85, 0x83, 0x72, 0x01, 0x88, 0xc3, 0x86, 0xc3, 0x75, 0x03, 0x92, 0xd8,
+    0x88, 0x83, 0x7b, 0x02, 0x68, 0x69,
+];
+
+#[test]
+fn test_binary_write() {
+    let buf = binary::to_vec::<_, __Store>(&sample()).unwrap();
+    assert_eq!(buf, SAMPLE);
+}
+
+#[test]
+fn test_binary_write_empty() {
+    #[derive(StoreData)]
+    struct Empty {}
+
+    let buf = binary::to_vec::<_, __Store>(&Empty {}).unwrap();
+    assert_eq!(buf, [0x01, 0x00]);
+}
+
+#[test]
+fn test_binary_read() {
+    let sample: Sample = binary::from_slice::<_, __Store>(SAMPLE).unwrap();
+    assert_eq!(sample, self::sample());
+}
+
+#[test]
+fn test_binary_roundtrip() {
+    let data = Everything {
+        bool: false,
+        i8: i8::MIN,
+        i16: -300,
+        i32: i32::MAX,
+        i64: i64::MIN,
+        u8: u8::MAX,
+        u16: 300,
+        u32: u32::MAX,
+        u64: u64::MAX,
+        f32: 1.5,
+        f64: -0.25,
+        bytes: vec![0, 1, 255],
+        string: "Jo \u{1f600}".to_owned(),
+        origin: Point { x: -1, y: 2 },
+    };
+
+    let buf = binary::to_vec::<_, __Store>(&data).unwrap();
+    let output: Everything = binary::from_slice::<_, __Store>(&buf).unwrap();
+
+    assert_eq!(output, data);
+}
+
+#[test]
+fn test_binary_read_width() {
+    #[derive(Debug, PartialEq, StoreData)]
+    struct Wide {
+        a: bool,
+        n: i64,
+        s: String,
+    }
+
+    #[derive(Debug, PartialEq, StoreData)]
+    struct Narrow {
+        n: i8,
+    }
+
+    let wide: Wide = binary::from_slice::<_, __Store>(SAMPLE).unwrap();
+    assert_eq!(wide.n, -2);
+
+    let buf = binary::to_vec::<_, __Store>(&Wide {
+        a: true,
+        n: 128,
+        s: String::new(),
+    })
+    .unwrap();
+    assert_eq!(
+        binary::from_slice::<Narrow, __Store>(&buf).unwrap_err(),
+        Error::InvalidValue { expected: "an i8" }
+    );
+}
+
+#[test]
+fn test_binary_read_invalid() {
+    let mut buf = SAMPLE.to_vec();
+    buf[0] = 2;
+    assert_eq!(
+        BinaryReader::new(&buf).unwrap_err(),
+        Error::UnsupportedVersion(2)
+    );
+
+    let mut buf = SAMPLE.to_vec();
+    buf.push(0);
+    assert_eq!(BinaryReader::new(&buf).unwrap_err(), Error::TrailingBytes);
+
+    assert_eq!(
+        BinaryReader::new(&SAMPLE[..SAMPLE.len() - 1]).unwrap_err(),
+        Error::UnexpectedEof
+    );
+
+    assert_eq!(BinaryReader::new(&[]).unwrap_err(), Error::UnexpectedEof);
+}
+
+#[test]
+fn test_binary_read_missing_field() {
+    #[derive(Debug, StoreData)]
+    struct Other {
+        a: bool,
+        b: bool,
+    }
+
+    assert_eq!(
+        binary::from_slice::<Other, __Store>(SAMPLE).unwrap_err(),
+        Error::MissingField {
+            key: "b".to_owned()
+        }
+    );
+}
+
+#[test]
+fn test_binary_read_invalid_type() {
+    #[derive(Debug, StoreData)]
+    struct Other {
+        s: u8,
+    }
+
+    assert_eq!(
+        binary::from_slice::<Other, __Store>(SAMPLE).unwrap_err(),
+        Error::InvalidType { expected: "an u8" }
+    );
+}
@@ -0,0 +1,242 @@
+mod support;
+
+use std::time::Duration;
+
+use datastore::cache::Cached;
+use datastore::{ConnectOptions, Store, StoreData, StoreExt};
+
+use self::support::counting::{backend, CountingStore};
+use self::support::person::{connect, person, Person, PersonQuery};
+use self::support::poll;
+
+#[derive(Clone, Debug, PartialEq, StoreData)]
+struct Pet {
+    name: String,
+    owner: i64,
+}
+
+/// Connects to a new backend at `uri` containing two people and a pet.
+async fn store(uri: &str) -> Cached<CountingStore> {
+    let store = Cached::new(connect(uri).await);
+
+    store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+    store
+        .insert(store.descriptor::<Person>(), person(2, "Bob"))
+        .await
+        .unwrap();
+    store
+        .insert(
+            store.descriptor::<Pet>(),
+            Pet {
+                name: "Rex".to_owned(),
+                owner: 1,
+            },
+        )
+        .await
+        .unwrap();
+
+    store
+}
+
+#[tokio::test]
+async fn test_cache_read_through() {
+    let store = store("counting://cache-read-through").await;
+    let backend = backend("counting://cache-read-through");
+    let calls = backend.calls();
+
+    for _ in 0..3 {
+        let output = store
+            .get(store.descriptor::<Person>(), PersonQuery::default().id(1))
+            .await
+            .unwrap();
+        assert_eq!(output, [person(1, "Alice")]);
+
+        let output = store
+            .get_one(
+                store.descriptor::<Person>(),
+                PersonQuery::default().name("Bob".to_owned()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output, Some(person(2, "Bob")));
+
+        let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+        assert_eq!(output.len(), 2);
+    }
+
+    assert_eq!(backend.calls(), calls + 3);
+    assert_eq!(store.len(), 3);
+
+    // Different query values and operations are cached separately.
+    let output = store
+        .get(store.descriptor::<Person>(), PersonQuery::default().id(2))
+        .await
+        .unwrap();
+    assert_eq!(output, [person(2, "Bob")]);
+
+    let output = store
+        .get_one(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    assert_eq!(output, Some(person(1, "Alice")));
+
+    // Empty results are cached too.
+    for _ in 0..2 {
+        let output = store
+            .get_one(store.descriptor::<Person>(), PersonQuery::default().id(3))
+            .await
+            .unwrap();
+        assert_eq!(output, None);
+    }
+
+    assert_eq!(backend.calls(), calls + 6);
+}
+
+#[tokio::test]
+async fn test_cache_invalidate() {
+    let store = store("counting://cache-invalidate").await;
+    let backend = backend("counting://cache-invalidate");
+
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    let _: Vec<Pet> = store.get_all(store.descriptor::<Pet>()).await.unwrap();
+    assert_eq!(store.len(), 2);
+
+    store
+        .insert(store.descriptor::<Person>(), person(3, "Carol"))
+        .await
+        .unwrap();
+    assert_eq!(store.len(), 1);
+
+    let calls = backend.calls();
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output.len(), 3);
+
+    // Results of other types stay cached.
+    let _: Vec<Pet> = store.get_all(store.descriptor::<Pet>()).await.unwrap();
+    assert_eq!(backend.calls(), calls + 1);
+
+    store
+        .delete(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output.len(), 2);
+    assert_eq!(backend.calls(), calls + 3);
+
+    store.clear();
+    assert!(store.is_empty());
+}
+
+#[tokio::test]
+async fn test_cache_invalidate_cancelled() {
+    let store = store("counting://cache-invalidate-cancelled").await;
+    let backend = backend("counting://cache-invalidate-cancelled");
+
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(store.len(), 1);
+
+    // Results are invalidated as soon as the write starts.
+    backend.stall(1);
+    let mut future = Box::pin(store.insert(store.descriptor::<Person>(), person(3, "Carol")));
+    assert!(poll(&mut future).is_pending());
+    assert!(store.is_empty());
+
+    // Results read while the write is in progress are invalidated when it is cancelled.
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(store.len(), 1);
+    drop(future);
+    assert!(store.is_empty());
+
+    // The same applies to deletes.
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    backend.stall(1);
+    let mut future =
+        Box::pin(store.delete(store.descriptor::<Person>(), PersonQuery::default().id(1)));
+    assert!(poll(&mut future).is_pending());
+    assert!(store.is_empty());
+
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    drop(future);
+    assert!(store.is_empty());
+}
+
+#[tokio::test]
+async fn test_cache_lru() {
+    let store = store("counting://cache-lru").await.with_capacity(2);
+    let backend = backend("counting://cache-lru");
+
+    let get = |id| {
+        let store = &store;
+        async move {
+            store
+                .get_one(store.descriptor::<Person>(), PersonQuery::default().id(id))
+                .await
+                .unwrap()
+        }
+    };
+
+    get(1).await;
+    get(2).await;
+    // Using 1 makes 2 the least recently used result.
+    get(1).await;
+    get(3).await;
+    assert_eq!(store.len(), 2);
+
+    let calls = backend.calls();
+    get(1).await;
+    get(3).await;
+    assert_eq!(backend.calls(), calls);
+
+    get(2).await;
+    assert_eq!(backend.calls(), calls + 1);
+    assert_eq!(store.len(), 2);
+}
+
+#[tokio::test]
+async fn test_cache_ttl() {
+    let store = store("counting://cache-ttl")
+        .await
+        .with_ttl(Some(Duration::from_secs(60)))
+        .with_type_ttl("Pet", Some(Duration::ZERO))
+        .with_type_ttl("Person", Some(Duration::from_millis(20)));
+    let backend = backend("counting://cache-ttl");
+    let calls = backend.calls();
+
+    // A TTL of zero disables caching.
+    let _: Vec<Pet> = store.get_all(store.descriptor::<Pet>()).await.unwrap();
+    let _: Vec<Pet> = store.get_all(store.descriptor::<Pet>()).await.unwrap();
+    assert_eq!(backend.calls(), calls + 2);
+    assert!(store.is_empty());
+
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(backend.calls(), calls + 3);
+
+    std::thread::sleep(Duration::from_millis(30));
+
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(backend.calls(), calls + 4);
+}
+
+#[tokio::test]
+async fn test_cache_connect_with() {
+    let options =
+        ConnectOptions::parse("counting://cache-connect?cache_capacity=0&cache_ttl=1m").unwrap();
+    let store = Cached::<CountingStore>::connect_with(&options)
+        .await
+        .unwrap();
+    assert_eq!(store.get_ref().backend().connects(), 1);
+    assert_eq!(backend("counting://cache-connect").connects(), 1);
+
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert!(store.is_empty());
+
+    let options = ConnectOptions::parse("counting://cache-connect?cache_ttl=soon").unwrap();
+    assert!(Cached::<CountingStore>::connect_with(&options)
+        .await
+        .is_err());
+}
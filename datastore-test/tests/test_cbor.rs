@@ -0,0 +1,230 @@
+mod support;
+
+use datastore::cbor::{self, CborReader, Error};
+use datastore::{Read, Reader, StoreData, TypeWriter, Write, Writer};
+
+use self::support::__Store;
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Sample {
+    a: bool,
+    n: i32,
+    s: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Write<__Store> for Point {
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<__Store>,
+    {
+        writer.write_field("x", &self.x)?;
+        writer.write_field("y", &self.y)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<__Store>,
+    {
+        writer.write_field::<i32>("x")?;
+        writer.write_field::<i32>("y")
+    }
+}
+
+impl Read<__Store> for Point {
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<__Store>,
+    {
+        Ok(Self {
+            x: reader.read_field("x")?,
+            y: reader.read_field("y")?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Everything {
+    bool: bool,
+    i8: i8,
+    i16: i16,
+    i32: i32,
+    i64: i64,
+    u8: u8,
+    u16: u16,
+    u32: u32,
+    u64: u64,
+    f32: f32,
+    f64: f64,
+    bytes: Vec<u8>,
+    string: String,
+    origin: Point,
+}
+
+fn sample() -> Sample {
+    Sample {
+        a: true,
+        n: -2,
+        s: "hi".to_owned(),
+    }
+}
+
+fn everything() -> Everything {
+    Everything {
+        bool: false,
+        i8: -100,
+        i16: -300,
+        i32: 100_000,
+        i64: i64::MIN,
+        u8: 200,
+        u16: 300,
+        u32: 70_000,
+        u64: u64::MAX,
+        f32: 1.5,
+        f64: -0.25,
+        bytes: vec![0, 1, 255],
+        string: "hi".to_owned(),
+        origin: Point { x: -1, y: 2 },
+    }
+}
+
+// {"a": true, "n": -2, "s": "hi"}
+const SAMPLE: &[u8] = &[
+    0xa3, 0x61, 0x61, 0xf5, 0x61, 0x6e, 0x21, 0x61, 0x73, 0x62, 0x68, 0x69,
+];
+
+const EVERYTHING: &[u8] = &[
+    0xae, // map(14)
+    0x64, 0x62, 0x6f, 0x6f, 0x6c, 0xf4, // "bool": false
+    0x62, 0x69, 0x38, 0x38, 0x63, // "i8": -100
+    0x63, 0x69, 0x31, 0x36, 0x39, 0x01, 0x2b, // "i16": -300
+    0x63, 0x69, 0x33, 0x32, 0x1a, 0x00, 0x01, 0x86, 0xa0, // "i32": 100000
+    0x63, 0x69, 0x36, 0x34, 0x3b, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, // "i64": min
+    0x62, 0x75, 0x38, 0x18, 0xc8, // "u8": 200
+    0x63, 0x75, 0x31, 0x36, 0x19, 0x01, 0x2c, // "u16": 300
+    0x63, 0x75, 0x33, 0x32, 0x1a, 0x00, 0x01, 0x11, 0x70, // "u32": 70000
+    0x63, 0x75, 0x36, 0x34, 0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, // "u64": max
+    0x63, 0x66, 0x33, 0x32, 0xfa, 0x3f, 0xc0, 0x00, 0x00, // "f32": 1.5
+    0x63, 0x66, 0x36, 0x34, 0xfb, 0xbf, 0xd0, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, // "f64": -0.25
+    0x65, 0x62, 0x79, 0x74, 0x65, 0x73, 0x43, 0x00, 0x01, 0xff, // "bytes": h'0001ff'
+    0x66, 0x73, 0x74, 0x72, 0x69, 0x6e, 0x67, 0x62, 0x68, 0x69, // "string": "hi"
+    0x66, 0x6f, 0x72, 0x69, 0x67, 0x69, 0x6e, // "origin":
+    0xa2, 0x61, 0x78, 0x20, 0x61, 0x79, 0x02, // {"x": -1, "y": 2}
+];
+
+#[test]
+fn test_cbor_write() {
+    assert_eq!(cbor::to_vec::<_, __Store>(&sample()).unwrap(), SAMPLE);
+    assert_eq!(
+        cbor::to_vec::<_, __Store>(&everything()).unwrap(),
+        EVERYTHING
+    );
+}
+
+#[test]
+fn test_cbor_write_empty() {
+    #[derive(StoreData)]
+    struct Empty {}
+
+    assert_eq!(cbor::to_vec::<_, __Store>(&Empty {}).unwrap(), [0xa0]);
+}
+
+#[test]
+fn test_cbor_read() {
+    let sample: Sample = cbor::from_slice::<_, __Store>(SAMPLE).unwrap();
+    assert_eq!(sample, self::sample());
+
+    let everything: Everything = cbor::from_slice::<_, __Store>(EVERYTHING).unwrap();
+    assert_eq!(everything, self::everything());
+}
+
+#[test]
+fn test_cbor_read_indefinite() {
+    // 55799({_ "s": (_ "h", "i"), "x": [_ 1, null], "n": -2, "a": true})
+    let buf = [
+        0xd9, 0xd9, 0xf7, 0xbf, 0x61, 0x73, 0x7f, 0x61, 0x68, 0x61, 0x69, 0xff, 0x61, 0x78, 0x9f,
+        0x01, 0xf6, 0xff, 0x61, 0x6e, 0x21, 0x61, 0x61, 0xf5, 0xff,
+    ];
+
+    let sample: Sample = cbor::from_slice::<_, __Store>(&buf).unwrap();
+    assert_eq!(sample, self::sample());
+}
+
+#[test]
+fn test_cbor_read_half_float() {
+    #[derive(Debug, PartialEq, StoreData)]
+    struct Float {
+        f: f32,
+    }
+
+    // {"f": 1.5} with a half precision float
+    let buf = [0xa1, 0x61, 0x66, 0xf9, 0x3e, 0x00];
+
+    let float: Float = cbor::from_slice::<_, __Store>(&buf).unwrap();
+    assert_eq!(float, Float { f: 1.5 });
+}
+
+#[test]
+fn test_cbor_read_invalid() {
+    assert_eq!(
+        CborReader::new(&SAMPLE[..SAMPLE.len() - 1]).unwrap_err(),
+        Error::UnexpectedEof
+    );
+    assert_eq!(
+        CborReader::new(&[0xa0, 0x00]).unwrap_err(),
+        Error::TrailingBytes
+    );
+    assert_eq!(
+        CborReader::new(&[0xa1, 0x61, 0x61, 0x1c]).unwrap_err(),
+        Error::Malformed { offset: 3 }
+    );
+    assert_eq!(
+        CborReader::new(&[0x7f, 0x41, 0x00, 0xff]).unwrap_err(),
+        Error::Malformed { offset: 1 }
+    );
+    assert_eq!(
+        CborReader::new(&[0x9f; 1000]).unwrap_err(),
+        Error::DepthLimitExceeded
+    );
+}
+
+#[test]
+fn test_cbor_read_mismatch() {
+    #[derive(Debug, StoreData)]
+    struct Missing {
+        b: bool,
+    }
+
+    #[derive(Debug, StoreData)]
+    struct InvalidType {
+        s: u8,
+    }
+
+    #[derive(Debug, StoreData)]
+    struct InvalidValue {
+        n: u8,
+    }
+
+    assert_eq!(
+        cbor::from_slice::<Missing, __Store>(SAMPLE).unwrap_err(),
+        Error::MissingField {
+            key: "b".to_owned()
+        }
+    );
+    assert_eq!(
+        cbor::from_slice::<InvalidType, __Store>(SAMPLE).unwrap_err(),
+        Error::InvalidType { expected: "an u8" }
+    );
+    assert_eq!(
+        cbor::from_slice::<InvalidValue, __Store>(SAMPLE).unwrap_err(),
+        Error::InvalidValue { expected: "an u8" }
+    );
+}
@@ -0,0 +1,256 @@
+mod support;
+
+use datastore::csv::{self, BulkError, CsvReader, CsvWriter, Error};
+use datastore::{Read, Reader, Store, StoreData, StoreExt, TypeWriter, Write, Writer};
+use datastore_memory::MemoryStore;
+
+use self::support::__Store;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl<S> Write<S> for Point
+where
+    S: Store,
+    i32: Write<S>,
+{
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        writer.write_field("x", &self.x)?;
+        writer.write_field("y", &self.y)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        writer.write_field::<i32>("x")?;
+        writer.write_field::<i32>("y")
+    }
+}
+
+impl<S> Read<S> for Point
+where
+    S: Store,
+    i32: Read<S>,
+{
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<S>,
+    {
+        Ok(Self {
+            x: reader.read_field("x")?,
+            y: reader.read_field("y")?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, StoreData)]
+struct Person {
+    id: i64,
+    name: String,
+    score: f64,
+    active: bool,
+    avatar: Vec<u8>,
+    home: Point,
+}
+
+fn people() -> Vec<Person> {
+    vec![
+        Person {
+            id: 1,
+            name: "Alice".to_owned(),
+            score: 1.5,
+            active: true,
+            avatar: vec![0, 1, 255],
+            home: Point { x: 1, y: -2 },
+        },
+        Person {
+            id: 2,
+            name: "Bob \"B\", Jr.\nSecond line".to_owned(),
+            score: -0.25,
+            active: false,
+            avatar: vec![],
+            home: Point { x: 0, y: 0 },
+        },
+    ]
+}
+
+const PEOPLE: &str = concat!(
+    "id,name,score,active,avatar,home.x,home.y\r\n",
+    "1,Alice,1.5,true,AAH/,1,-2\r\n",
+    "2,\"Bob \"\"B\"\", Jr.\nSecond line\",-0.25,false,,0,0\r\n",
+);
+
+fn read_all(input: &str) -> Result<Vec<Person>, Error> {
+    let mut reader = CsvReader::new(input.as_bytes())?;
+
+    let mut output = Vec::new();
+    while let Some(person) = reader.read_row::<_, __Store, _>(&PersonDescriptor)? {
+        output.push(person);
+    }
+
+    Ok(output)
+}
+
+#[test]
+fn test_csv_write() {
+    let mut writer = CsvWriter::new::<Person, __Store, _>(Vec::new(), &PersonDescriptor).unwrap();
+    assert_eq!(
+        writer.header(),
+        ["id", "name", "score", "active", "avatar", "home.x", "home.y"]
+    );
+
+    for person in people() {
+        writer.write_row::<_, __Store>(&person).unwrap();
+    }
+
+    let output = String::from_utf8(writer.into_inner()).unwrap();
+    assert_eq!(output, PEOPLE);
+}
+
+#[test]
+fn test_csv_read() {
+    assert_eq!(read_all(PEOPLE).unwrap(), people());
+}
+
+#[test]
+fn test_csv_read_reordered() {
+    let input = concat!(
+        "\u{feff}home.y,notes,avatar,id,active,name,home.x,score\n",
+        "-2,ignored,AAH/,1,true,Alice,1, 1.5\n",
+        "\n",
+        "0,,,2,false,\"Bob \"\"B\"\", Jr.\nSecond line\",0,-25e-2\n",
+    );
+
+    assert_eq!(read_all(input).unwrap(), people());
+}
+
+#[test]
+fn test_csv_single_empty_column() {
+    #[derive(Clone, Debug, PartialEq, StoreData)]
+    struct Note {
+        text: String,
+    }
+
+    let notes = [
+        Note {
+            text: String::new(),
+        },
+        Note {
+            text: "hello".to_owned(),
+        },
+    ];
+
+    let mut writer = CsvWriter::new::<Note, __Store, _>(Vec::new(), &NoteDescriptor).unwrap();
+    for note in &notes {
+        writer.write_row::<_, __Store>(note).unwrap();
+    }
+
+    // The empty value is not written as an empty line, which would be skipped.
+    let output = String::from_utf8(writer.into_inner()).unwrap();
+    assert_eq!(output, "text\r\n\"\"\r\nhello\r\n");
+
+    let mut reader = CsvReader::new(output.as_bytes()).unwrap();
+    let mut output = Vec::new();
+    while let Some(note) = reader.read_row::<_, __Store, _>(&NoteDescriptor).unwrap() {
+        output.push(note);
+    }
+    assert_eq!(output, notes);
+}
+
+#[test]
+fn test_csv_read_invalid() {
+    assert!(matches!(read_all(""), Err(Error::MissingHeader)));
+
+    assert!(matches!(
+        read_all("id,id\n"),
+        Err(Error::DuplicateColumn { key }) if key == "id"
+    ));
+
+    assert!(matches!(
+        read_all("id,name\n1,\"Alice\n"),
+        Err(Error::Syntax {
+            line: 2,
+            expected: "a closing quote"
+        })
+    ));
+
+    assert!(matches!(
+        read_all("id,name\n1,\"Alice\"x\n"),
+        Err(Error::Syntax {
+            line: 2,
+            expected: "a delimiter after a closing quote"
+        })
+    ));
+
+    assert!(matches!(
+        read_all("id,name\n1\n"),
+        Err(Error::RowLength {
+            line: 2,
+            expected: 2,
+            found: 1
+        })
+    ));
+
+    assert!(matches!(
+        read_all("id,name\n1,Alice\n"),
+        Err(Error::MissingField { key }) if key == "score"
+    ));
+
+    assert!(matches!(
+        read_all(&PEOPLE.replace("1.5", "high")),
+        Err(Error::InvalidValue { expected: "a f64" })
+    ));
+
+    assert!(matches!(
+        read_all(&PEOPLE.replace("AAH/", "AAH")),
+        Err(Error::InvalidBase64)
+    ));
+}
+
+#[tokio::test]
+async fn test_csv_load_export() {
+    let store = MemoryStore::new();
+
+    let count = csv::load(&store, store.descriptor::<Person>(), PEOPLE.as_bytes())
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people());
+
+    let mut buf = Vec::new();
+    let count = csv::export(&store, store.descriptor::<Person>(), &mut buf)
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(String::from_utf8(buf).unwrap(), PEOPLE);
+}
+
+#[tokio::test]
+async fn test_csv_load_error_line() {
+    let store = MemoryStore::new();
+
+    let input = PEOPLE.replace("-0.25", "low");
+    let err = csv::load(&store, store.descriptor::<Person>(), input.as_bytes())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        BulkError::Csv {
+            line: 3,
+            error: Error::InvalidValue { .. }
+        }
+    ));
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people()[..1]);
+}
@@ -66,3 +66,24 @@ fn test_storedata_name() {
 
     assert_eq!(name!(SomeData2), "name");
 }
+
+#[test]
+fn test_storedata_field_attributes() {
+    #[derive(StoreData)]
+    struct SomeData {
+        #[datastore(primary_key)]
+        id: u64,
+        #[datastore(index)]
+        name: String,
+        #[datastore(primary_key, index)]
+        key: u32,
+        value: Option<i32>,
+    }
+
+    fields!(SomeData, {
+        "id" => U64,
+        "name" => Str,
+        "key" => U32,
+        "value" => I32,
+    });
+}
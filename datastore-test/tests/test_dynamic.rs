@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use datastore::dynamic::DynStore;
+use datastore::value::{Record, RecordDescriptor, RecordQuery, Type, Value};
+use datastore::{FieldAttributes, Store, StoreData, StoreExt};
+use datastore_memory::MemoryStore;
+
+#[derive(Clone, Debug, PartialEq, StoreData)]
+struct Person {
+    #[datastore(primary_key)]
+    id: i64,
+    name: String,
+    active: bool,
+}
+
+fn people() -> Vec<Person> {
+    vec![
+        Person {
+            id: 1,
+            name: "Alice".to_owned(),
+            active: true,
+        },
+        Person {
+            id: 2,
+            name: "Bob".to_owned(),
+            active: false,
+        },
+    ]
+}
+
+fn store() -> Arc<dyn DynStore> {
+    Arc::new(MemoryStore::new())
+}
+
+#[test]
+fn test_dynamic_record_descriptor() {
+    let descriptor =
+        RecordDescriptor::from_descriptor::<_, Arc<dyn DynStore>, _>(&PersonDescriptor).unwrap();
+
+    assert_eq!(
+        descriptor,
+        RecordDescriptor::new("Person")
+            .field_with("id", Type::I64, FieldAttributes::new().primary_key(true))
+            .field("name", Type::String)
+            .field("active", Type::Bool)
+    );
+}
+
+#[tokio::test]
+async fn test_dynamic_typed() {
+    let store = store();
+    store.create(store.descriptor::<Person>()).await.unwrap();
+
+    for person in people() {
+        store
+            .insert(store.descriptor::<Person>(), person)
+            .await
+            .unwrap();
+    }
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, people());
+
+    let output = store
+        .get_one(
+            store.descriptor::<Person>(),
+            PersonQuery::default().name("Bob".to_owned()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output, Some(people().remove(1)));
+
+    store
+        .delete(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+
+    let output = store
+        .get(
+            store.descriptor::<Person>(),
+            PersonQuery::default().active(true),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output, []);
+}
+
+#[tokio::test]
+async fn test_dynamic_records() {
+    let store = store();
+    let descriptor = RecordDescriptor::new("Person")
+        .field("id", Type::I64)
+        .field("name", Type::String);
+
+    let record: Record = [
+        ("id", Value::I64(1)),
+        ("name", Value::String("Alice".to_owned())),
+    ]
+    .into_iter()
+    .collect();
+    store
+        .insert_record(&descriptor, record.clone())
+        .await
+        .unwrap();
+
+    let output = store
+        .get_records(&descriptor, &RecordQuery::new().field("id", 1i64))
+        .await
+        .unwrap();
+    assert_eq!(output, [record]);
+
+    // Typed items can be read as records.
+    store
+        .insert(store.descriptor::<Person>(), people().remove(1))
+        .await
+        .unwrap();
+
+    let output = store.get_all_records(&descriptor).await.unwrap();
+    assert_eq!(output.len(), 2);
+    assert_eq!(
+        output[1].get("name"),
+        Some(&Value::String("Bob".to_owned()))
+    );
+    assert_eq!(output[1].get("active"), None);
+}
+
+#[tokio::test]
+async fn test_dynamic_runtime_selection() {
+    let stores: Vec<Arc<dyn DynStore>> = vec![store(), store()];
+
+    for (index, store) in stores.iter().enumerate() {
+        for person in people().into_iter().take(index + 1) {
+            store
+                .insert(store.descriptor::<Person>(), person)
+                .await
+                .unwrap();
+        }
+    }
+
+    for (index, store) in stores.iter().enumerate() {
+        let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+        assert_eq!(output.len(), index + 1);
+    }
+}
+
+#[tokio::test]
+async fn test_dynamic_error() {
+    let store = store();
+
+    // The stored item lacks the `active` field of `Person`.
+    let descriptor = RecordDescriptor::new("Person")
+        .field("id", Type::I64)
+        .field("name", Type::String);
+    let record: Record = [
+        ("id", Value::I64(1)),
+        ("name", Value::String("Alice".to_owned())),
+    ]
+    .into_iter()
+    .collect();
+    store.insert_record(&descriptor, record).await.unwrap();
+
+    let err = store
+        .get_all::<Person, _>(store.descriptor::<Person>())
+        .await
+        .unwrap_err();
+    assert!(err.is::<datastore_memory::Error>());
+}
@@ -0,0 +1,197 @@
+mod support;
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use datastore::instrument::Instrumented;
+use datastore::{Store, StoreExt};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+use self::support::counting::CountingStore;
+use self::support::person::{person, Person, PersonQuery};
+
+#[derive(Debug, Default)]
+struct Span {
+    name: &'static str,
+    fields: HashMap<&'static str, String>,
+}
+
+#[derive(Debug)]
+struct RecordedEvent {
+    parent: Option<usize>,
+    fields: HashMap<&'static str, String>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    spans: Vec<Span>,
+    events: Vec<RecordedEvent>,
+    stack: Vec<usize>,
+}
+
+/// A [`Subscriber`] recording all spans and events.
+#[derive(Clone, Debug, Default)]
+struct Recorder(Arc<Mutex<State>>);
+
+impl Recorder {
+    /// Returns the fields of all spans with `name`.
+    fn spans(&self, name: &str) -> Vec<HashMap<&'static str, String>> {
+        let state = self.0.lock().unwrap();
+        state
+            .spans
+            .iter()
+            .filter(|span| span.name == name)
+            .map(|span| span.fields.clone())
+            .collect()
+    }
+}
+
+struct Visitor<'a>(&'a mut HashMap<&'static str, String>);
+
+impl<'a> Visit for Visitor<'a> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.insert(field.name(), format!("{:?}", value));
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let mut span = Span {
+            name: attrs.metadata().name(),
+            ..Default::default()
+        };
+        attrs.record(&mut Visitor(&mut span.fields));
+
+        let mut state = self.0.lock().unwrap();
+        state.spans.push(span);
+        Id::from_u64(state.spans.len() as u64)
+    }
+
+    fn record(&self, id: &Id, values: &Record<'_>) {
+        let mut state = self.0.lock().unwrap();
+        let span = &mut state.spans[id.into_u64() as usize - 1];
+        values.record(&mut Visitor(&mut span.fields));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut state = self.0.lock().unwrap();
+
+        let mut fields = HashMap::new();
+        event.record(&mut Visitor(&mut fields));
+
+        let parent = state.stack.last().copied();
+        state.events.push(RecordedEvent { parent, fields });
+    }
+
+    fn enter(&self, id: &Id) {
+        self.0
+            .lock()
+            .unwrap()
+            .stack
+            .push(id.into_u64() as usize - 1);
+    }
+
+    fn exit(&self, _: &Id) {
+        self.0.lock().unwrap().stack.pop();
+    }
+}
+
+#[tokio::test]
+async fn test_instrument_spans() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+
+    let store = Instrumented::<CountingStore>::connect("counting://instrument-spans")
+        .await
+        .unwrap();
+    assert_eq!(recorder.spans("datastore.connect").len(), 1);
+
+    for (id, name) in [(1, "Alice"), (2, "Bob")] {
+        store
+            .insert(store.descriptor::<Person>(), person(id, name))
+            .await
+            .unwrap();
+    }
+
+    let spans = recorder.spans("datastore.insert");
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[0]["ident"], "Person");
+    assert_eq!(spans[0]["rows"], "1");
+    assert!(spans[0].contains_key("duration_us"));
+
+    store
+        .get(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    let spans = recorder.spans("datastore.get");
+    assert_eq!(spans[0]["ident"], "Person");
+    assert_eq!(spans[0]["query"], "id=1");
+    assert_eq!(spans[0]["rows"], "1");
+
+    store
+        .get_one(
+            store.descriptor::<Person>(),
+            PersonQuery::default().name("Carol".to_owned()),
+        )
+        .await
+        .unwrap();
+    let spans = recorder.spans("datastore.get_one");
+    assert_eq!(spans[0]["query"], "name=\"Carol\"");
+    assert_eq!(spans[0]["rows"], "0");
+
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    let spans = recorder.spans("datastore.get_all");
+    assert_eq!(spans[0]["rows"], "2");
+    assert!(!spans[0].contains_key("query"));
+
+    store
+        .delete(store.descriptor::<Person>(), PersonQuery::default().id(2))
+        .await
+        .unwrap();
+    let spans = recorder.spans("datastore.delete");
+    assert_eq!(spans[0]["query"], "id=2");
+    assert!(!spans[0].contains_key("error"));
+
+    assert!(recorder.0.lock().unwrap().events.is_empty());
+}
+
+#[tokio::test]
+async fn test_instrument_error() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+
+    let store = Instrumented::new(
+        CountingStore::connect("counting://instrument-error")
+            .await
+            .unwrap(),
+    );
+    store.get_ref().backend().break_connections();
+
+    assert!(store.ping().await.is_err());
+
+    let spans = recorder.spans("datastore.ping");
+    assert_eq!(spans.len(), 1);
+    assert!(spans[0].contains_key("error"));
+    assert!(spans[0].contains_key("duration_us"));
+
+    let state = recorder.0.lock().unwrap();
+    assert_eq!(state.events.len(), 1);
+    assert_eq!(
+        state.spans[state.events[0].parent.unwrap()].name,
+        "datastore.ping"
+    );
+    assert_eq!(state.events[0].fields["message"], "store operation failed");
+}
@@ -0,0 +1,273 @@
+mod support;
+
+use datastore::json::{self, Error, JsonReader};
+use datastore::{Read, Reader, StoreData, TypeWriter, Write, Writer};
+
+use self::support::__Store;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Write<__Store> for Point {
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<__Store>,
+    {
+        writer.write_field("x", &self.x)?;
+        writer.write_field("y", &self.y)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<__Store>,
+    {
+        writer.write_field::<i32>("x")?;
+        writer.write_field::<i32>("y")
+    }
+}
+
+impl Read<__Store> for Point {
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<__Store>,
+    {
+        Ok(Self {
+            x: reader.read_field("x")?,
+            y: reader.read_field("y")?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Person {
+    id: i64,
+    name: String,
+    age: u8,
+    score: f64,
+    active: bool,
+    avatar: Vec<u8>,
+}
+
+fn person() -> Person {
+    Person {
+        id: -5,
+        name: "Jo \"J\"\n".to_owned(),
+        age: 42,
+        score: 1.5,
+        active: true,
+        avatar: vec![0, 1, 255],
+    }
+}
+
+const PERSON: &str =
+    r#"{"id":-5,"name":"Jo \"J\"\n","age":42,"score":1.5,"active":true,"avatar":"AAH/"}"#;
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Everything {
+    i8: i8,
+    i16: i16,
+    i32: i32,
+    i64: i64,
+    u8: u8,
+    u16: u16,
+    u32: u32,
+    u64: u64,
+    f32: f32,
+    f64: f64,
+    bytes: Vec<u8>,
+    string: String,
+    origin: Point,
+}
+
+#[test]
+fn test_json_write() {
+    let json = json::to_string::<_, __Store>(&person()).unwrap();
+    assert_eq!(json, PERSON);
+}
+
+#[test]
+fn test_json_write_empty() {
+    #[derive(StoreData)]
+    struct Empty {}
+
+    let json = json::to_string::<_, __Store>(&Empty {}).unwrap();
+    assert_eq!(json, "{}");
+}
+
+#[test]
+fn test_json_write_non_finite() {
+    let mut person = person();
+    person.score = f64::NAN;
+
+    assert_eq!(
+        json::to_string::<_, __Store>(&person).unwrap_err(),
+        Error::NonFiniteFloat
+    );
+}
+
+#[test]
+fn test_json_read() {
+    let person: Person = json::from_str::<_, __Store>(PERSON).unwrap();
+    assert_eq!(person, self::person());
+}
+
+#[test]
+fn test_json_read_reordered() {
+    let input = r#"
+    {
+        "avatar": "AAH/",
+        "active": true,
+        "unknown": [1, {"a": null}, "b"],
+        "score": 15e-1,
+        "age": 42,
+        "name": "Jo \"J\"\u000a",
+        "id": -5
+    }"#;
+
+    let person: Person = json::from_str::<_, __Store>(input).unwrap();
+    assert_eq!(person, self::person());
+}
+
+#[test]
+fn test_json_roundtrip() {
+    let data = Everything {
+        i8: i8::MIN,
+        i16: -300,
+        i32: i32::MAX,
+        i64: i64::MIN,
+        u8: u8::MAX,
+        u16: 300,
+        u32: u32::MAX,
+        u64: u64::MAX,
+        f32: 0.1,
+        f64: -1e300,
+        bytes: vec![1, 2, 3, 4],
+        string: "\u{0}\t\u{1f600}".to_owned(),
+        origin: Point { x: -1, y: 2 },
+    };
+
+    let json = json::to_string::<_, __Store>(&data).unwrap();
+    assert!(json.contains(r#""u64":18446744073709551615"#));
+    assert!(json.contains(r#""origin":{"x":-1,"y":2}"#));
+
+    let output: Everything = json::from_str::<_, __Store>(&json).unwrap();
+    assert_eq!(output, data);
+}
+
+#[test]
+fn test_json_base64() {
+    #[derive(Debug, PartialEq, StoreData)]
+    struct Blob {
+        data: Vec<u8>,
+    }
+
+    for (data, encoded) in [
+        (&b""[..], r#"{"data":""}"#),
+        (b"f", r#"{"data":"Zg=="}"#),
+        (b"fo", r#"{"data":"Zm8="}"#),
+        (b"foo", r#"{"data":"Zm9v"}"#),
+        (b"foob", r#"{"data":"Zm9vYg=="}"#),
+    ] {
+        let blob = Blob {
+            data: data.to_vec(),
+        };
+
+        assert_eq!(json::to_string::<_, __Store>(&blob).unwrap(), encoded);
+        assert_eq!(json::from_str::<Blob, __Store>(encoded).unwrap(), blob);
+    }
+
+    for invalid in [
+        r#"{"data":"Zg="}"#,
+        r#"{"data":"Z==="}"#,
+        r#"{"data":"Zg==Zg=="}"#,
+    ] {
+        assert_eq!(
+            json::from_str::<Blob, __Store>(invalid).unwrap_err(),
+            Error::InvalidBase64
+        );
+    }
+}
+
+#[test]
+fn test_json_type_sketch() {
+    let sketch = json::type_sketch::<Everything, __Store, _>(&EverythingDescriptor).unwrap();
+
+    assert_eq!(
+        sketch,
+        concat!(
+            r#"{"i8":"i8","i16":"i16","i32":"i32","i64":"i64","u8":"u8","u16":"u16","#,
+            r#""u32":"u32","u64":"u64","f32":"f32","f64":"f64","bytes":"bytes","#,
+            r#""string":"string","origin":{"x":"i32","y":"i32"}}"#,
+        )
+    );
+}
+
+#[test]
+fn test_json_read_invalid() {
+    assert_eq!(
+        json::from_str::<Person, __Store>(r#"{"id":1,}"#).unwrap_err(),
+        Error::Syntax {
+            offset: 8,
+            expected: "a string key"
+        }
+    );
+
+    assert_eq!(
+        JsonReader::new("{} {}").unwrap_err(),
+        Error::Syntax {
+            offset: 3,
+            expected: "end of input"
+        }
+    );
+
+    assert_eq!(
+        JsonReader::new("01").unwrap_err(),
+        Error::Syntax {
+            offset: 1,
+            expected: "end of input"
+        }
+    );
+
+    assert_eq!(
+        JsonReader::new(r#""\ud83d""#).unwrap_err(),
+        Error::Syntax {
+            offset: 7,
+            expected: "\\u"
+        }
+    );
+
+    assert!(JsonReader::new(&"[".repeat(1000)).is_err());
+}
+
+#[test]
+fn test_json_read_missing_field() {
+    assert_eq!(
+        json::from_str::<Person, __Store>(r#"{"id":1}"#).unwrap_err(),
+        Error::MissingField {
+            key: "name".to_owned()
+        }
+    );
+}
+
+#[test]
+fn test_json_read_invalid_type() {
+    assert_eq!(
+        json::from_str::<Person, __Store>(r#"{"id":"1"}"#).unwrap_err(),
+        Error::InvalidType { expected: "an i64" }
+    );
+
+    assert_eq!(
+        json::from_str::<Person, __Store>(r#"{"id":1.5}"#).unwrap_err(),
+        Error::InvalidValue { expected: "an i64" }
+    );
+
+    assert_eq!(
+        json::from_str::<Person, __Store>("[]").unwrap_err(),
+        Error::InvalidType {
+            expected: "an object"
+        }
+    );
+}
@@ -0,0 +1,201 @@
+mod support;
+
+use datastore::json_schema::{self, Error};
+use datastore::{StoreData, TypeWriter, Write};
+
+use self::support::__Store;
+
+#[test]
+fn test_json_schema() {
+    #[derive(StoreData)]
+    #[datastore(name = "people")]
+    struct Person {
+        #[datastore(primary_key)]
+        id: u64,
+        name: String,
+        email: Option<String>,
+        age: u8,
+        score: i16,
+        active: bool,
+        rating: f64,
+        avatar: Vec<u8>,
+    }
+
+    let schema = json_schema::to_string::<Person, __Store, _>(&PersonDescriptor).unwrap();
+
+    assert_eq!(
+        schema,
+        r#"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "people",
+  "type": "object",
+  "properties": {
+    "id": {
+      "type": "integer",
+      "minimum": 0,
+      "maximum": 18446744073709551615
+    },
+    "name": {
+      "type": "string"
+    },
+    "email": {
+      "type": ["string", "null"]
+    },
+    "age": {
+      "type": "integer",
+      "minimum": 0,
+      "maximum": 255
+    },
+    "score": {
+      "type": "integer",
+      "minimum": -32768,
+      "maximum": 32767
+    },
+    "active": {
+      "type": "boolean"
+    },
+    "rating": {
+      "type": "number"
+    },
+    "avatar": {
+      "type": "string",
+      "format": "byte"
+    }
+  },
+  "required": ["id", "name", "age", "score", "active", "rating", "avatar"],
+  "additionalProperties": false
+}"#
+    );
+}
+
+#[test]
+fn test_json_schema_empty() {
+    #[derive(StoreData)]
+    struct Empty {}
+
+    let schema = json_schema::to_string::<Empty, __Store, _>(&EmptyDescriptor).unwrap();
+
+    assert_eq!(
+        schema,
+        r#"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "Empty",
+  "type": "object",
+  "properties": {},
+  "required": [],
+  "additionalProperties": false
+}"#
+    );
+}
+
+#[derive(Clone)]
+struct Point;
+
+impl Write<__Store> for Point {
+    fn write<W>(&self, _writer: &mut W) -> Result<(), W::Error>
+    where
+        W: datastore::Writer<__Store>,
+    {
+        Ok(())
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<__Store>,
+    {
+        writer.write_field::<i32>("x")?;
+        writer.write_field::<i32>("y")
+    }
+}
+
+impl datastore::Read<__Store> for Point {
+    fn read<R>(_reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: datastore::Reader<__Store>,
+    {
+        Ok(Self)
+    }
+}
+
+#[test]
+fn test_json_schema_nested() {
+    #[derive(StoreData)]
+    struct Shape {
+        origin: Point,
+    }
+
+    let schema = json_schema::to_string::<Shape, __Store, _>(&ShapeDescriptor).unwrap();
+
+    assert_eq!(
+        schema,
+        r#"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "Shape",
+  "type": "object",
+  "properties": {
+    "origin": {
+      "type": "object",
+      "properties": {
+        "x": {
+          "type": "integer",
+          "minimum": -2147483648,
+          "maximum": 2147483647
+        },
+        "y": {
+          "type": "integer",
+          "minimum": -2147483648,
+          "maximum": 2147483647
+        }
+      },
+      "required": ["x", "y"],
+      "additionalProperties": false
+    }
+  },
+  "required": ["origin"],
+  "additionalProperties": false
+}"#
+    );
+}
+
+#[test]
+fn test_json_schema_missing_type() {
+    #[derive(Clone)]
+    struct Nothing;
+
+    impl Write<__Store> for Nothing {
+        fn write<W>(&self, _writer: &mut W) -> Result<(), W::Error>
+        where
+            W: datastore::Writer<__Store>,
+        {
+            Ok(())
+        }
+
+        fn write_type<W>(_writer: &mut W) -> Result<(), W::Error>
+        where
+            W: TypeWriter<__Store>,
+        {
+            Ok(())
+        }
+    }
+
+    impl datastore::Read<__Store> for Nothing {
+        fn read<R>(_reader: &mut R) -> Result<Self, R::Error>
+        where
+            R: datastore::Reader<__Store>,
+        {
+            Ok(Self)
+        }
+    }
+
+    #[derive(StoreData)]
+    struct Data {
+        nothing: Nothing,
+    }
+
+    assert_eq!(
+        json_schema::to_string::<Data, __Store, _>(&DataDescriptor),
+        Err(Error::MissingType {
+            key: "nothing".to_owned()
+        })
+    );
+}
@@ -0,0 +1,154 @@
+mod support;
+
+use std::time::Duration;
+
+use datastore::metrics::{Metered, Operation};
+use datastore::value::{Record, RecordDescriptor};
+use datastore::{Store, StoreData, StoreExt};
+
+use self::support::counting::CountingStore;
+use self::support::person::{person, Person, PersonQuery};
+
+#[derive(Clone, Debug, PartialEq, StoreData)]
+struct Pet {
+    name: String,
+}
+
+async fn store(uri: &str) -> Metered<CountingStore> {
+    let store = Metered::<CountingStore>::connect(uri).await.unwrap();
+
+    for (id, name) in [(1, "Alice"), (2, "Bob")] {
+        store
+            .insert(store.descriptor::<Person>(), person(id, name))
+            .await
+            .unwrap();
+    }
+
+    store
+}
+
+#[tokio::test]
+async fn test_metrics_counters() {
+    let store = store("counting://metrics-counters").await;
+
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    store
+        .get(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    store
+        .get_one(store.descriptor::<Person>(), PersonQuery::default().id(3))
+        .await
+        .unwrap();
+    let _: Vec<Pet> = store.get_all(store.descriptor::<Pet>()).await.unwrap();
+
+    store.get_ref().backend().break_connections();
+    assert!(store.ping().await.is_err());
+
+    let snapshot = store.snapshot();
+
+    let insert = snapshot.get(Operation::Insert, "Person").unwrap();
+    assert_eq!(insert.calls(), 2);
+    assert_eq!(insert.errors(), 0);
+    assert_eq!(insert.rows(), 0);
+
+    let get_all = snapshot.get(Operation::GetAll, "Person").unwrap();
+    assert_eq!(get_all.calls(), 1);
+    assert_eq!(get_all.rows(), 2);
+
+    assert_eq!(snapshot.get(Operation::Get, "Person").unwrap().rows(), 1);
+    assert_eq!(snapshot.get(Operation::GetOne, "Person").unwrap().rows(), 0);
+    assert_eq!(snapshot.get(Operation::GetAll, "Pet").unwrap().rows(), 0);
+
+    let ping = snapshot.get(Operation::Ping, "").unwrap();
+    assert_eq!(ping.calls(), 1);
+    assert_eq!(ping.errors(), 1);
+
+    assert!(snapshot.get(Operation::Delete, "Person").is_none());
+    assert_eq!(snapshot.iter().count(), 6);
+
+    // The snapshot is ordered by ident and operation.
+    let keys: Vec<_> = snapshot
+        .iter()
+        .map(|metrics| (metrics.ident(), metrics.operation()))
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            ("", Operation::Ping),
+            ("Person", Operation::Get),
+            ("Person", Operation::GetAll),
+            ("Person", Operation::GetOne),
+            ("Person", Operation::Insert),
+            ("Pet", Operation::GetAll),
+        ]
+    );
+
+    store.reset();
+    assert_eq!(store.snapshot().iter().count(), 0);
+}
+
+#[tokio::test]
+async fn test_metrics_histogram() {
+    let store = store("counting://metrics-histogram")
+        .await
+        .with_buckets([Duration::from_secs(3600), Duration::ZERO]);
+
+    for _ in 0..3 {
+        let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    }
+
+    let snapshot = store.snapshot();
+    let latency = snapshot.get(Operation::GetAll, "Person").unwrap().latency();
+    assert_eq!(latency.count(), 3);
+    assert!(latency.sum() < Duration::from_secs(3600));
+
+    let bounds: Vec<_> = latency.buckets().iter().map(|(bound, _)| *bound).collect();
+    assert_eq!(bounds, [Duration::ZERO, Duration::from_secs(3600)]);
+    assert_eq!(latency.buckets()[1].1, 3);
+}
+
+#[tokio::test]
+async fn test_metrics_prometheus() {
+    let store = Metered::new(
+        CountingStore::connect("counting://metrics-prometheus")
+            .await
+            .unwrap(),
+    )
+    .with_buckets([Duration::from_millis(100), Duration::from_secs(3600)]);
+
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    let _: Vec<Record> = store
+        .get_all(RecordDescriptor::new("a\"b\\c"))
+        .await
+        .unwrap();
+
+    let output = store.snapshot().to_prometheus();
+    let lines: Vec<_> = output.lines().collect();
+
+    for line in [
+        "# TYPE datastore_calls_total counter",
+        "datastore_calls_total{operation=\"get_all\",ident=\"Person\"} 1",
+        "datastore_calls_total{operation=\"get_all\",ident=\"a\\\"b\\\\c\"} 1",
+        "datastore_errors_total{operation=\"get_all\",ident=\"Person\"} 0",
+        "datastore_rows_total{operation=\"get_all\",ident=\"Person\"} 0",
+        "# TYPE datastore_call_duration_seconds histogram",
+        "datastore_call_duration_seconds_bucket{operation=\"get_all\",ident=\"Person\",le=\"3600\"} 1",
+        "datastore_call_duration_seconds_bucket{operation=\"get_all\",ident=\"Person\",le=\"+Inf\"} 1",
+        "datastore_call_duration_seconds_count{operation=\"get_all\",ident=\"Person\"} 1",
+    ] {
+        assert!(lines.contains(&line), "missing {:?} in\n{}", line, output);
+    }
+
+    assert!(lines.iter().any(|line| line
+        .starts_with("datastore_call_duration_seconds_bucket{operation=\"get_all\",ident=\"Person\",le=\"0.1\"} ")));
+    assert!(lines.iter().any(|line| line.starts_with(
+        "datastore_call_duration_seconds_sum{operation=\"get_all\",ident=\"Person\"} "
+    )));
+
+    // Every sample line has a name, labels and a value.
+    for line in lines.iter().filter(|line| !line.starts_with('#')) {
+        let (_, value) = line.rsplit_once(' ').unwrap();
+        value.parse::<f64>().unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+mod support;
+
+use datastore::msgpack::{self, Error, MsgPackReader};
+use datastore::{Read, Reader, StoreData, TypeWriter, Write, Writer};
+
+use self::support::__Store;
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Sample {
+    a: bool,
+    n: i32,
+    s: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Write<__Store> for Point {
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<__Store>,
+    {
+        writer.write_field("x", &self.x)?;
+        writer.write_field("y", &self.y)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<__Store>,
+    {
+        writer.write_field::<i32>("x")?;
+        writer.write_field::<i32>("y")
+    }
+}
+
+impl Read<__Store> for Point {
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<__Store>,
+    {
+        Ok(Self {
+            x: reader.read_field("x")?,
+            y: reader.read_field("y")?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Everything {
+    bool: bool,
+    i8: i8,
+    i16: i16,
+    i32: i32,
+    i64: i64,
+    u8: u8,
+    u16: u16,
+    u32: u32,
+    u64: u64,
+    f32: f32,
+    f64: f64,
+    bytes: Vec<u8>,
+    string: String,
+    origin: Point,
+}
+
+fn sample() -> Sample {
+    Sample {
+        a: true,
+        n: -2,
+        s: "hi".to_owned(),
+    }
+}
+
+fn everything() -> Everything {
+    Everything {
+        bool: false,
+        i8: -100,
+        i16: -300,
+        i32: 100_000,
+        i64: i64::MIN,
+        u8: 200,
+        u16: 300,
+        u32: 70_000,
+        u64: u64::MAX,
+        f32: 1.5,
+        f64: -0.25,
+        bytes: vec![0, 1, 255],
+        string: "hi".to_owned(),
+        origin: Point { x: -1, y: 2 },
+    }
+}
+
+// {"a": true, "n": -2, "s": "hi"}
+const SAMPLE: &[u8] = &[
+    0x83, 0xa1, 0x61, 0xc3, 0xa1, 0x6e, 0xfe, 0xa1, 0x73, 0xa2, 0x68, 0x69,
+];
+
+const EVERYTHING: &[u8] = &[
+    0x8e, // fixmap(14)
+    0xa4, 0x62, 0x6f, 0x6f, 0x6c, 0xc2, // "bool": false
+    0xa2, 0x69, 0x38, 0xd0, 0x9c, // "i8": int 8 -100
+    0xa3, 0x69, 0x31, 0x36, 0xd1, 0xfe, 0xd4, // "i16": int 16 -300
+    0xa3, 0x69, 0x33, 0x32, 0xce, 0x00, 0x01, 0x86, 0xa0, // "i32": uint 32 100000
+    0xa3, 0x69, 0x36, 0x34, 0xd3, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, // "i64": min
+    0xa2, 0x75, 0x38, 0xcc, 0xc8, // "u8": uint 8 200
+    0xa3, 0x75, 0x31, 0x36, 0xcd, 0x01, 0x2c, // "u16": uint 16 300
+    0xa3, 0x75, 0x33, 0x32, 0xce, 0x00, 0x01, 0x11, 0x70, // "u32": uint 32 70000
+    0xa3, 0x75, 0x36, 0x34, 0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, // "u64": max
+    0xa3, 0x66, 0x33, 0x32, 0xca, 0x3f, 0xc0, 0x00, 0x00, // "f32": 1.5
+    0xa3, 0x66, 0x36, 0x34, 0xcb, 0xbf, 0xd0, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, // "f64": -0.25
+    0xa5, 0x62, 0x79, 0x74, 0x65, 0x73, 0xc4, 0x03, 0x00, 0x01, 0xff, // "bytes": bin 8
+    0xa6, 0x73, 0x74, 0x72, 0x69, 0x6e, 0x67, 0xa2, 0x68, 0x69, // "string": "hi"
+    0xa6, 0x6f, 0x72, 0x69, 0x67, 0x69, 0x6e, // "origin":
+    0x82, 0xa1, 0x78, 0xff, 0xa1, 0x79, 0x02, // {"x": -1, "y": 2}
+];
+
+#[test]
+fn test_msgpack_write() {
+    assert_eq!(msgpack::to_vec::<_, __Store>(&sample()).unwrap(), SAMPLE);
+    assert_eq!(
+        msgpack::to_vec::<_, __Store>(&everything()).unwrap(),
+        EVERYTHING
+    );
+}
+
+#[test]
+fn test_msgpack_write_empty() {
+    #[derive(StoreData)]
+    struct Empty {}
+
+    assert_eq!(msgpack::to_vec::<_, __Store>(&Empty {}).unwrap(), [0x80]);
+}
+
+#[test]
+fn test_msgpack_write_str8() {
+    let mut sample = sample();
+    sample.s = "x".repeat(32);
+
+    let buf = msgpack::to_vec::<_, __Store>(&sample).unwrap();
+    assert_eq!(&buf[9..11], [0xd9, 0x20]);
+    assert_eq!(buf.len(), 11 + 32);
+}
+
+#[test]
+fn test_msgpack_read() {
+    let sample: Sample = msgpack::from_slice::<_, __Store>(SAMPLE).unwrap();
+    assert_eq!(sample, self::sample());
+
+    let everything: Everything = msgpack::from_slice::<_, __Store>(EVERYTHING).unwrap();
+    assert_eq!(everything, self::everything());
+}
+
+#[test]
+fn test_msgpack_read_formats() {
+    // map 16 {str 8 "s": "hi", "x": [fixext 1, nil], "n": -2, "a": true}
+    let buf = [
+        0xde, 0x00, 0x04, 0xd9, 0x01, 0x73, 0xa2, 0x68, 0x69, 0xa1, 0x78, 0x92, 0xd4, 0x01, 0x02,
+        0xc0, 0xa1, 0x6e, 0xfe, 0xa1, 0x61, 0xc3,
+    ];
+
+    let sample: Sample = msgpack::from_slice::<_, __Store>(&buf).unwrap();
+    assert_eq!(sample, self::sample());
+}
+
+#[test]
+fn test_msgpack_read_invalid() {
+    assert_eq!(
+        MsgPackReader::new(&SAMPLE[..SAMPLE.len() - 1]).unwrap_err(),
+        Error::UnexpectedEof
+    );
+    assert_eq!(
+        MsgPackReader::new(&[0x80, 0x00]).unwrap_err(),
+        Error::TrailingBytes
+    );
+    assert_eq!(
+        MsgPackReader::new(&[0x81, 0xa1, 0x61, 0xc1]).unwrap_err(),
+        Error::Malformed { offset: 3 }
+    );
+    assert_eq!(
+        MsgPackReader::new(&[0x91; 1000]).unwrap_err(),
+        Error::DepthLimitExceeded
+    );
+}
+
+#[test]
+fn test_msgpack_read_mismatch() {
+    #[derive(Debug, StoreData)]
+    struct Missing {
+        b: bool,
+    }
+
+    #[derive(Debug, StoreData)]
+    struct InvalidType {
+        s: u8,
+    }
+
+    #[derive(Debug, StoreData)]
+    struct InvalidValue {
+        n: u8,
+    }
+
+    assert_eq!(
+        msgpack::from_slice::<Missing, __Store>(SAMPLE).unwrap_err(),
+        Error::MissingField {
+            key: "b".to_owned()
+        }
+    );
+    assert_eq!(
+        msgpack::from_slice::<InvalidType, __Store>(SAMPLE).unwrap_err(),
+        Error::InvalidType { expected: "an u8" }
+    );
+    assert_eq!(
+        msgpack::from_slice::<InvalidValue, __Store>(SAMPLE).unwrap_err(),
+        Error::InvalidValue { expected: "an u8" }
+    );
+}
@@ -0,0 +1,125 @@
+mod support;
+
+use datastore::namespace::Namespaced;
+use datastore::value::{RecordDescriptor, Type, Value};
+use datastore::{ConnectOptions, Store, StoreExt};
+
+use self::support::counting::{backend, CountingStore};
+use self::support::person::{connect, person, Person, PersonQuery};
+
+#[tokio::test]
+async fn test_namespace_prefix() {
+    let acme = Namespaced::prefix(connect("counting://namespace-prefix").await, "acme_");
+    let globex = Namespaced::prefix(connect("counting://namespace-prefix").await, "globex_");
+
+    acme.insert(acme.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+    globex
+        .insert(globex.descriptor::<Person>(), person(1, "Bob"))
+        .await
+        .unwrap();
+
+    let output: Vec<Person> = acme.get_all(acme.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, [person(1, "Alice")]);
+
+    let output = globex
+        .get_one(globex.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    assert_eq!(output, Some(person(1, "Bob")));
+
+    let backend = backend("counting://namespace-prefix");
+    assert_eq!(backend.count("acme_Person").await, 1);
+    assert_eq!(backend.count("globex_Person").await, 1);
+    assert_eq!(backend.count("Person").await, 0);
+}
+
+#[tokio::test]
+async fn test_namespace_field() {
+    let acme = Namespaced::field(
+        connect("counting://namespace-field").await,
+        "tenant",
+        "acme",
+    );
+    let globex = Namespaced::field(
+        connect("counting://namespace-field").await,
+        "tenant",
+        "globex",
+    );
+
+    acme.insert(acme.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+    acme.insert(acme.descriptor::<Person>(), person(2, "Bob"))
+        .await
+        .unwrap();
+    globex
+        .insert(globex.descriptor::<Person>(), person(1, "Carol"))
+        .await
+        .unwrap();
+
+    let output: Vec<Person> = acme.get_all(acme.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, [person(1, "Alice"), person(2, "Bob")]);
+
+    let output = globex
+        .get(globex.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    assert_eq!(output, [person(1, "Carol")]);
+
+    let output = globex
+        .get_one(globex.descriptor::<Person>(), PersonQuery::default().id(2))
+        .await
+        .unwrap();
+    assert_eq!(output, None);
+
+    // Deletes only affect the items of the tenant.
+    globex
+        .delete(globex.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    let output: Vec<Person> = acme.get_all(acme.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output.len(), 2);
+
+    // All tenants share the same ident and store the tenant with every item.
+    let inner = connect("counting://namespace-field").await;
+    let records = inner
+        .get_all(RecordDescriptor::new("Person").field("tenant", Type::String))
+        .await
+        .unwrap();
+    assert_eq!(records.len(), 2);
+    for record in records {
+        assert_eq!(
+            record.get("tenant"),
+            Some(&Value::String("acme".to_owned()))
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_namespace_connect_with() {
+    let options =
+        ConnectOptions::parse("counting://namespace-connect?namespace_prefix=acme_").unwrap();
+    let store = Namespaced::<CountingStore>::connect_with(&options)
+        .await
+        .unwrap();
+    assert_eq!(backend("counting://namespace-connect").connects(), 1);
+
+    store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+    assert_eq!(
+        backend("counting://namespace-connect")
+            .count("acme_Person")
+            .await,
+        1
+    );
+
+    assert!(
+        Namespaced::<CountingStore>::connect("counting://namespace-connect")
+            .await
+            .is_err()
+    );
+}
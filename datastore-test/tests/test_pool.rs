@@ -0,0 +1,252 @@
+mod support;
+
+use std::task::Poll;
+use std::time::Duration;
+
+use datastore::pool::Pool;
+use datastore::{ConnectOptions, Store, StoreExt};
+
+use self::support::counting::{backend, CountingStore};
+use self::support::person::{Person, PersonQuery};
+use self::support::poll;
+
+async fn pool(uri: &str, max_size: usize) -> Pool<CountingStore> {
+    Pool::builder()
+        .max_size(max_size)
+        .connect(uri)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_pool_store() {
+    let pool = pool("counting://store", 4).await;
+
+    pool.create(pool.descriptor::<Person>()).await.unwrap();
+    pool.insert(
+        pool.descriptor::<Person>(),
+        Person {
+            id: 1,
+            name: "Alice".to_owned(),
+        },
+    )
+    .await
+    .unwrap();
+
+    let output = pool
+        .get_one(pool.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    assert_eq!(output.unwrap().name, "Alice");
+
+    // Sequential operations reuse the same connection.
+    let backend = backend("counting://store");
+    assert_eq!(backend.connects(), 1);
+    assert_eq!(backend.calls(), 3);
+    assert_eq!(pool.size(), 1);
+    assert_eq!(pool.idle(), 1);
+}
+
+#[tokio::test]
+async fn test_pool_min_size() {
+    let pool = Pool::<CountingStore>::builder()
+        .min_size(3)
+        .connect("counting://min-size")
+        .await
+        .unwrap();
+    assert_eq!(backend("counting://min-size").open(), 3);
+    assert_eq!(pool.size(), 3);
+    assert_eq!(pool.idle(), 3);
+
+    // `min_size` is limited by `max_size`.
+    let pool = Pool::<CountingStore>::builder()
+        .min_size(5)
+        .max_size(2)
+        .connect("counting://min-size-max")
+        .await
+        .unwrap();
+    assert_eq!(pool.size(), 2);
+}
+
+#[tokio::test]
+async fn test_pool_max_size() {
+    let pool = pool("counting://max-size", 2).await;
+
+    let first = pool.acquire().await.unwrap();
+    let second = pool.acquire().await.unwrap();
+    assert_ne!(first.id, second.id);
+
+    let mut third = Box::pin(pool.acquire());
+    assert!(poll(&mut third).is_pending());
+
+    drop(first);
+    let third = match poll(&mut third) {
+        Poll::Ready(conn) => conn.unwrap(),
+        Poll::Pending => panic!("connection was not released"),
+    };
+    assert_eq!(third.id, 1);
+
+    drop(second);
+    drop(third);
+    assert_eq!(backend("counting://max-size").connects(), 2);
+    assert_eq!(pool.idle(), 2);
+}
+
+#[tokio::test]
+async fn test_pool_fair() {
+    let pool = pool("counting://fair", 1).await;
+    let conn = pool.acquire().await.unwrap();
+
+    let mut waiters: Vec<_> = (0..3).map(|_| Box::pin(pool.acquire())).collect();
+    for waiter in &mut waiters {
+        assert!(poll(waiter).is_pending());
+    }
+
+    drop(conn);
+
+    // The connection is handed to the first waiter, not to a new task or to later waiters.
+    let mut late = Box::pin(pool.acquire());
+    assert!(poll(&mut late).is_pending());
+    assert!(poll(&mut waiters[2]).is_pending());
+    assert!(poll(&mut waiters[1]).is_pending());
+
+    let conn = match poll(&mut waiters[0]) {
+        Poll::Ready(conn) => conn.unwrap(),
+        Poll::Pending => panic!("first waiter did not acquire connection"),
+    };
+    drop(conn);
+
+    // A cancelled waiter passes the connection on to the next waiter.
+    drop(waiters.remove(0));
+    drop(waiters.remove(0));
+    assert!(poll(&mut waiters[0]).is_ready());
+}
+
+#[tokio::test]
+async fn test_pool_idle_timeout() {
+    let pool = Pool::<CountingStore>::builder()
+        .min_size(1)
+        .idle_timeout(Some(Duration::ZERO))
+        .connect("counting://idle-timeout")
+        .await
+        .unwrap();
+
+    let first = pool.acquire().await.unwrap();
+    let second = pool.acquire().await.unwrap();
+    drop(first);
+    drop(second);
+    assert_eq!(pool.size(), 2);
+
+    // Acquiring closes expired connections, but keeps `min_size` connections.
+    let conn = pool.acquire().await.unwrap();
+    assert_eq!(conn.id, 2);
+    assert_eq!(pool.size(), 1);
+    assert_eq!(backend("counting://idle-timeout").open(), 1);
+}
+
+#[tokio::test]
+async fn test_pool_health_check() {
+    let pool = pool("counting://health-check", 2).await;
+    let health = backend("counting://health-check");
+
+    drop(pool.acquire().await.unwrap());
+    health.break_connections();
+
+    let conn = pool.acquire().await.unwrap();
+    assert_eq!(conn.id, 2);
+    assert_eq!(health.pings(), 1);
+    assert_eq!(health.open(), 1);
+    drop(conn);
+
+    // Connections whose health check is cancelled are closed.
+    health.stall_pings(1);
+    let mut future = Box::pin(pool.acquire());
+    assert!(poll(&mut future).is_pending());
+    drop(future);
+    assert_eq!(pool.size(), 0);
+    assert_eq!(health.open(), 0);
+
+    let conn = pool.acquire().await.unwrap();
+    assert_eq!(conn.id, 3);
+    drop(conn);
+
+    let pool = Pool::<CountingStore>::builder()
+        .health_check(false)
+        .connect("counting://no-health-check")
+        .await
+        .unwrap();
+    drop(pool.acquire().await.unwrap());
+    drop(pool.acquire().await.unwrap());
+    assert_eq!(backend("counting://no-health-check").pings(), 0);
+}
+
+#[tokio::test]
+async fn test_pool_discard() {
+    let pool = pool("counting://discard", 2).await;
+    let backend = backend("counting://discard");
+
+    pool.create(pool.descriptor::<Person>()).await.unwrap();
+    assert_eq!(pool.idle(), 1);
+
+    // Connections of failed operations are closed.
+    backend.fail(1);
+    assert!(pool
+        .get_all::<Person, _>(pool.descriptor::<Person>())
+        .await
+        .is_err());
+    assert_eq!(pool.size(), 0);
+    assert_eq!(backend.open(), 0);
+
+    // Connections of cancelled operations are closed.
+    backend.stall(1);
+    let mut future = Box::pin(pool.get_all::<Person, _>(pool.descriptor::<Person>()));
+    assert!(poll(&mut future).is_pending());
+    drop(future);
+    assert_eq!(pool.size(), 0);
+    assert_eq!(backend.open(), 0);
+
+    pool.get_all::<Person, _>(pool.descriptor::<Person>())
+        .await
+        .unwrap();
+    assert_eq!(pool.idle(), 1);
+}
+
+#[tokio::test]
+async fn test_pool_connect_error() {
+    let backend = backend("counting://connect-error");
+    backend.refuse(true);
+
+    let res = Pool::<CountingStore>::builder()
+        .min_size(1)
+        .connect("counting://connect-error")
+        .await;
+    assert!(res.is_err());
+
+    // A failed connection does not use up the pool.
+    let pool = pool("counting://connect-error", 1).await;
+    assert!(pool.acquire().await.is_err());
+    assert_eq!(pool.size(), 0);
+
+    backend.refuse(false);
+    pool.acquire().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pool_connect_with() {
+    let options = ConnectOptions::parse(
+        "counting://with?pool_min_size=2&pool_max_size=4&pool_idle_timeout=5m&pool_health_check=false&name=x",
+    )
+    .unwrap();
+    let pool = Pool::<CountingStore>::connect_with(&options).await.unwrap();
+
+    // The pool parameters are not passed on to the store.
+    let backend = backend("counting://with?name=x");
+    assert_eq!(backend.connects(), 2);
+    assert_eq!(pool.size(), 2);
+
+    drop(pool.acquire().await.unwrap());
+    assert_eq!(backend.pings(), 0);
+
+    let options = ConnectOptions::parse("counting://with?pool_max_size=0").unwrap();
+    assert!(Pool::<CountingStore>::connect_with(&options).await.is_err());
+}
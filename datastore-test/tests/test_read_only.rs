@@ -0,0 +1,119 @@
+mod support;
+
+use std::sync::Arc;
+
+use datastore::cache::Cached;
+use datastore::dynamic::DynStore;
+use datastore::pool::Pool;
+use datastore::read_only::ReadOnly;
+use datastore::{Capabilities, Error, Store, StoreExt};
+
+use self::support::counting::{backend, CountingStore};
+use self::support::person::{person, Person, PersonQuery};
+
+#[tokio::test]
+async fn test_read_only() {
+    let inner = CountingStore::connect("counting://read-only")
+        .await
+        .unwrap();
+    inner
+        .insert(inner.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+
+    let store = ReadOnly::new(inner);
+    let backend = backend("counting://read-only");
+    let calls = backend.calls();
+
+    let err = store
+        .insert(store.descriptor::<Person>(), person(2, "Bob"))
+        .await
+        .unwrap_err();
+    assert!(err.is_permission_denied());
+    assert!(!err.is_transient());
+    assert_eq!(
+        err.to_string(),
+        "permission denied: insert on a read-only store"
+    );
+
+    let err = store
+        .delete(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap_err();
+    assert!(err.is_permission_denied());
+
+    let err = store
+        .create(store.descriptor::<Person>())
+        .await
+        .unwrap_err();
+    assert!(err.is_permission_denied());
+
+    // Rejected writes never reach the inner store.
+    assert_eq!(backend.calls(), calls);
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output.len(), 1);
+    assert_eq!(backend.calls(), calls + 1);
+
+    // Errors of the inner store are passed through.
+    backend.fail(1);
+    let err = store
+        .get_one(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap_err();
+    assert!(!err.is_permission_denied());
+    assert!(err.is_transient());
+}
+
+#[tokio::test]
+async fn test_read_only_capabilities() {
+    let store = CountingStore::connect("counting://read-only-capabilities")
+        .await
+        .unwrap();
+    assert_eq!(store.capabilities(), Capabilities::WRITE);
+
+    let store = ReadOnly::new(Cached::new(store));
+    assert!(store.capabilities().is_empty());
+
+    let store: Arc<dyn DynStore> = Arc::new(store);
+    assert!(store.capabilities().is_empty());
+
+    let pool = Pool::<ReadOnly<CountingStore>>::builder()
+        .connect("counting://read-only-capabilities")
+        .await
+        .unwrap();
+    assert_eq!(pool.capabilities(), Capabilities::WRITE);
+
+    let conn = pool.acquire().await.unwrap();
+    drop(conn);
+    assert!(pool.capabilities().is_empty());
+}
+
+#[test]
+fn test_capabilities() {
+    let capabilities = Capabilities::WRITE | Capabilities::UPSERT;
+    assert!(capabilities.contains(Capabilities::WRITE));
+    assert!(capabilities.contains(capabilities));
+    assert!(!capabilities.contains(Capabilities::WRITE | Capabilities::ORDERING));
+    assert!(Capabilities::all().contains(capabilities));
+    assert!(Capabilities::empty().is_empty());
+
+    assert_eq!(
+        capabilities.difference(Capabilities::UPSERT),
+        Capabilities::WRITE
+    );
+    assert_eq!(
+        capabilities.intersection(Capabilities::UPSERT | Capabilities::STREAMING),
+        Capabilities::UPSERT
+    );
+
+    assert_eq!(
+        format!("{:?}", capabilities),
+        "Capabilities(WRITE | UPSERT)"
+    );
+    assert_eq!(format!("{:?}", Capabilities::empty()), "Capabilities()");
+    assert_eq!(
+        format!("{:?}", Capabilities::all()),
+        "Capabilities(WRITE | TRANSACTIONS | ORDERING | STREAMING | UPSERT)"
+    );
+}
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use datastore::dynamic::DynStore;
+use datastore::registry::{self, Error};
+use datastore::{Store, StoreData, StoreExt};
+use datastore_memory::MemoryStore;
+
+#[derive(Clone, Debug, PartialEq, StoreData)]
+struct Person {
+    id: i64,
+    name: String,
+}
+
+// The registry is global, every test registers its own scheme.
+
+#[tokio::test]
+async fn test_registry_connect() {
+    registry::register::<MemoryStore>("memory");
+
+    let store = datastore::connect("memory://").await.unwrap();
+    let person = Person {
+        id: 1,
+        name: "Alice".to_owned(),
+    };
+    store
+        .insert(store.descriptor::<Person>(), person.clone())
+        .await
+        .unwrap();
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, [person]);
+
+    // Every connection creates a new store.
+    let store = <Arc<dyn DynStore>>::connect("MEMORY://").await.unwrap();
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, []);
+}
+
+#[tokio::test]
+async fn test_registry_unknown_scheme() {
+    registry::register::<MemoryStore>("known");
+
+    let err = match datastore::connect("unknown://localhost").await {
+        Ok(_) => panic!("connected to an unknown scheme"),
+        Err(err) => err,
+    };
+    match err {
+        Error::UnknownScheme { scheme, known } => {
+            assert_eq!(scheme, "unknown");
+            assert!(known.contains(&"known".to_owned()));
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    assert!(registry::unregister("KNOWN"));
+    assert!(!registry::schemes().contains(&"known".to_owned()));
+    assert!(!registry::unregister("known"));
+}
+
+#[tokio::test]
+async fn test_registry_missing_scheme() {
+    for uri in ["localhost", "://localhost", "1db://localhost", "/tmp/db"] {
+        assert!(matches!(
+            datastore::connect(uri).await,
+            Err(Error::MissingScheme { uri: output }) if output == uri
+        ));
+    }
+}
+
+#[test]
+#[should_panic(expected = "invalid URI scheme")]
+fn test_registry_invalid_scheme() {
+    registry::register::<MemoryStore>("mem ory");
+}
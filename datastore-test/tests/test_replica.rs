@@ -0,0 +1,187 @@
+mod support;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use datastore::cache::Cached;
+use datastore::replica::Replicated;
+use datastore::{ConnectOptions, Store, StoreExt};
+
+use self::support::counting::{backend, CountingStore};
+use self::support::person::{connect, person, Person, PersonQuery};
+
+#[tokio::test]
+async fn test_replicated() {
+    let store = Replicated::new(
+        connect("counting://replicated-primary").await,
+        vec![
+            connect("counting://replicated-0").await,
+            connect("counting://replicated-1").await,
+        ],
+    );
+
+    store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+    store
+        .delete(store.descriptor::<Person>(), PersonQuery::default().id(2))
+        .await
+        .unwrap();
+
+    // Writes only reach the primary.
+    assert_eq!(backend("counting://replicated-primary").calls(), 2);
+    assert_eq!(backend("counting://replicated-0").calls(), 0);
+    assert_eq!(backend("counting://replicated-1").calls(), 0);
+
+    // Reads are distributed over the replicas, which have not seen the insert.
+    for _ in 0..4 {
+        let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+        assert!(output.is_empty());
+    }
+    let output = store
+        .get_one(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    assert_eq!(output, None);
+
+    assert_eq!(backend("counting://replicated-primary").calls(), 2);
+    assert_eq!(backend("counting://replicated-0").calls(), 3);
+    assert_eq!(backend("counting://replicated-1").calls(), 2);
+}
+
+#[tokio::test]
+async fn test_replicated_read_your_writes() {
+    let store = Replicated::new(
+        connect("counting://replicated-ryw-primary").await,
+        vec![connect("counting://replicated-ryw-0").await],
+    )
+    .with_read_your_writes(Some(Duration::from_millis(100)));
+
+    // Reads go to the replicas before the first write.
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert!(output.is_empty());
+    assert_eq!(backend("counting://replicated-ryw-0").calls(), 1);
+
+    store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+
+    // Reads are pinned to the primary after a write.
+    let output = store
+        .get_one(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    assert_eq!(output, Some(person(1, "Alice")));
+    assert_eq!(backend("counting://replicated-ryw-primary").calls(), 2);
+    assert_eq!(backend("counting://replicated-ryw-0").calls(), 1);
+
+    // Other sessions do not see the write.
+    let session = Replicated::new(
+        connect("counting://replicated-ryw-primary").await,
+        vec![connect("counting://replicated-ryw-0").await],
+    )
+    .with_read_your_writes(Some(Duration::from_millis(100)));
+    let output: Vec<Person> = session
+        .get_all(session.descriptor::<Person>())
+        .await
+        .unwrap();
+    assert!(output.is_empty());
+
+    // Reads return to the replicas after the window.
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert!(output.is_empty());
+    assert_eq!(backend("counting://replicated-ryw-0").calls(), 3);
+}
+
+#[tokio::test]
+async fn test_replicated_session() {
+    let store = Arc::new(
+        Replicated::new(
+            connect("counting://replicated-session-primary").await,
+            vec![connect("counting://replicated-session-0").await],
+        )
+        .with_read_your_writes(Some(Duration::from_secs(60))),
+    );
+
+    let alice = store.session();
+    let bob = store.session();
+    alice
+        .insert(alice.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+
+    // Only the session that wrote reads from the primary.
+    let output: Vec<Person> = alice.get_all(alice.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, [person(1, "Alice")]);
+    let output = bob
+        .get_one(bob.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    assert_eq!(output, None);
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert!(output.is_empty());
+
+    assert_eq!(backend("counting://replicated-session-primary").calls(), 2);
+    assert_eq!(backend("counting://replicated-session-0").calls(), 2);
+
+    // Sessions are stores and can be wrapped like any other store.
+    let carol = Cached::new(store.session());
+    carol
+        .insert(carol.descriptor::<Person>(), person(2, "Carol"))
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        let output: Vec<Person> = carol.get_all(carol.descriptor::<Person>()).await.unwrap();
+        assert_eq!(output, [person(1, "Alice"), person(2, "Carol")]);
+    }
+
+    assert_eq!(backend("counting://replicated-session-primary").calls(), 4);
+    assert_eq!(backend("counting://replicated-session-0").calls(), 2);
+}
+
+#[tokio::test]
+async fn test_replicated_without_replicas() {
+    let store = Replicated::new(connect("counting://replicated-single").await, Vec::new());
+    store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, [person(1, "Alice")]);
+}
+
+#[tokio::test]
+async fn test_replicated_connect_with() {
+    let options = ConnectOptions::parse("counting://replicated-connect-primary")
+        .unwrap()
+        .with_param("replica", "counting://replicated-connect-0")
+        .with_param("replica", "counting://replicated-connect-1")
+        .with_param("read_your_writes", "1s");
+    let store = Replicated::<CountingStore>::connect(&options.to_string())
+        .await
+        .unwrap();
+    assert_eq!(store.replicas().len(), 2);
+    assert_eq!(
+        backend("counting://replicated-connect-primary").connects(),
+        1
+    );
+    assert_eq!(backend("counting://replicated-connect-1").connects(), 1);
+
+    store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, [person(1, "Alice")]);
+
+    let options = ConnectOptions::parse("counting://replicated-connect-primary")
+        .unwrap()
+        .with_param("read_your_writes", "soon");
+    assert!(Replicated::<CountingStore>::connect_with(&options)
+        .await
+        .is_err());
+}
@@ -0,0 +1,178 @@
+mod support;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use datastore::dynamic::DynStore;
+use datastore::namespace::Namespaced;
+use datastore::retry::Retrying;
+use datastore::value::{RecordDescriptor, Type, Value};
+use datastore::{ConnectOptions, Error, Store, StoreExt};
+
+use self::support::counting::{backend, CountingStore};
+use self::support::person::{connect, person, Person, PersonQuery};
+
+/// Connects to a new backend at `uri` retrying without delays.
+async fn store(uri: &str) -> Retrying<CountingStore> {
+    Retrying::new(connect(uri).await).with_backoff(Duration::ZERO, Duration::ZERO)
+}
+
+#[tokio::test]
+async fn test_retry_transient() {
+    let store = store("counting://retry-transient").await;
+    let backend = backend("counting://retry-transient");
+
+    store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+
+    let calls = backend.calls();
+    backend.fail(2);
+    let output = store
+        .get(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap();
+    assert_eq!(output, [person(1, "Alice")]);
+    assert_eq!(backend.calls(), calls + 3);
+
+    // The error of the last attempt is returned.
+    let store = store.with_max_attempts(2);
+    backend.fail(2);
+    let err = store
+        .get_one(store.descriptor::<Person>(), PersonQuery::default().id(1))
+        .await
+        .unwrap_err();
+    assert!(err.is_transient());
+    assert_eq!(backend.calls(), calls + 5);
+
+    // Errors that are not transient are not retried.
+    backend.break_connections();
+    assert!(store.ping().await.is_err());
+    assert_eq!(backend.pings(), 1);
+}
+
+#[tokio::test]
+async fn test_retry_insert() {
+    let store = store("counting://retry-insert").await;
+    let backend = backend("counting://retry-insert");
+
+    let calls = backend.calls();
+    backend.fail(1);
+    assert!(store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .is_err());
+    assert_eq!(backend.calls(), calls + 1);
+
+    let store = store.with_idempotent_inserts(true);
+    backend.fail(2);
+    store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+    assert_eq!(backend.calls(), calls + 4);
+
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(output, [person(1, "Alice")]);
+}
+
+#[tokio::test]
+async fn test_retry_insert_tenanted() {
+    let store = Namespaced::field(
+        store("counting://retry-insert-tenanted")
+            .await
+            .with_idempotent_inserts(true),
+        "tenant",
+        "acme",
+    );
+    let backend = backend("counting://retry-insert-tenanted");
+
+    // The retried copy is read through the descriptor and keeps the tenant field.
+    backend.fail(1);
+    store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+
+    let inner = connect("counting://retry-insert-tenanted").await;
+    let records = inner
+        .get_all(RecordDescriptor::new("Person").field("tenant", Type::String))
+        .await
+        .unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(
+        records[0].get("tenant"),
+        Some(&Value::String("acme".to_owned()))
+    );
+}
+
+#[tokio::test]
+async fn test_retry_timeout() {
+    let store = store("counting://retry-timeout")
+        .await
+        .with_timeout(Some(Duration::from_millis(20)));
+    let backend = backend("counting://retry-timeout");
+
+    backend.stall(1);
+    let output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert!(output.is_empty());
+    assert_eq!(backend.calls(), 2);
+
+    // A timed out insert may have been applied and is not retried.
+    backend.stall(1);
+    assert!(store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .is_err());
+    assert_eq!(backend.calls(), 3);
+
+    let store = store.with_max_attempts(1);
+    backend.stall(1);
+    let res: Result<Vec<Person>, _> = store.get_all(store.descriptor::<Person>()).await;
+    assert!(res.is_err());
+    assert_eq!(backend.calls(), 4);
+}
+
+#[tokio::test]
+async fn test_retry_dynamic() {
+    let store: Arc<dyn DynStore> = Arc::new(connect("counting://retry-dynamic").await);
+    let store = Retrying::new(store)
+        .with_backoff(Duration::ZERO, Duration::ZERO)
+        .with_max_attempts(2);
+    let backend = backend("counting://retry-dynamic");
+
+    // The transient errors of the inner store are preserved by `dynamic::Error`.
+    backend.fail(1);
+    let _: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    assert_eq!(backend.calls(), 2);
+
+    backend.fail(2);
+    let res: Result<Vec<Person>, _> = store.get_all(store.descriptor::<Person>()).await;
+    assert!(res.unwrap_err().is_transient());
+    assert_eq!(backend.calls(), 4);
+}
+
+#[tokio::test]
+async fn test_retry_connect_with() {
+    let options = ConnectOptions::parse(
+        "counting://retry-connect?retry_max_attempts=2&retry_backoff=1ms&retry_timeout=1s",
+    )
+    .unwrap();
+    let store = Retrying::<CountingStore>::connect_with(&options)
+        .await
+        .unwrap();
+    assert_eq!(store.get_ref().backend().connects(), 1);
+    assert_eq!(backend("counting://retry-connect").connects(), 1);
+
+    backend("counting://retry-connect").refuse(true);
+    let err = Retrying::<CountingStore>::connect_with(&options)
+        .await
+        .unwrap_err();
+    assert!(err.is_transient());
+
+    let options = ConnectOptions::parse("counting://retry-connect?retry_max_attempts=0").unwrap();
+    assert!(Retrying::<CountingStore>::connect_with(&options)
+        .await
+        .is_err());
+}
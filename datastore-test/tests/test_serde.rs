@@ -0,0 +1,383 @@
+mod support;
+
+use std::collections::{BTreeMap, HashMap};
+
+use datastore::serde::{Serde, SerializeData};
+use datastore::sql::{self, Table};
+use datastore::value::{self as record, Record};
+use datastore::{json_schema, DataDescriptor, StoreData};
+use serde::{Deserialize, Serialize};
+
+use self::support::{__Reader, __Store, __Writer, Value};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct UserId(u64);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum Status {
+    Active,
+    Disabled,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Account {
+    id: i64,
+    #[datastore(serde)]
+    user: UserId,
+    #[datastore(serde)]
+    status: Status,
+    #[datastore(serde)]
+    initial: char,
+}
+
+fn account() -> Account {
+    Account {
+        id: 1,
+        user: UserId(7),
+        status: Status::Disabled,
+        initial: 'j',
+    }
+}
+
+fn values() -> HashMap<String, Value> {
+    let mut values = HashMap::new();
+    values.insert("id".to_owned(), Value::I64(1));
+    values.insert("user".to_owned(), Value::U64(7));
+    values.insert("status".to_owned(), Value::Str("Disabled".to_owned()));
+    values.insert("initial".to_owned(), Value::Str("j".to_owned()));
+    values
+}
+
+#[test]
+fn test_serde_descriptor() {
+    fields!(Account, {
+        "id" => I64,
+        "user" => U64,
+        "status" => Str,
+        "initial" => Str,
+    });
+}
+
+#[test]
+fn test_serde_write() {
+    let mut writer = __Writer::new();
+    StoreData::<__Store>::write(&account(), &mut writer).unwrap();
+
+    assert_eq!(writer.values, values());
+}
+
+#[test]
+fn test_serde_read() {
+    let mut reader = __Reader::new(values());
+    let account: Account = StoreData::<__Store>::read(&mut reader).unwrap();
+
+    assert_eq!(account, self::account());
+}
+
+#[test]
+fn test_serde_read_unknown_variant() {
+    let mut values = values();
+    values.insert("status".to_owned(), Value::Str("Deleted".to_owned()));
+
+    let mut reader = __Reader::new(values);
+    assert!(<Account as StoreData<__Store>>::read(&mut reader).is_err());
+}
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Shape {
+    #[datastore(serde)]
+    origin: Point,
+    #[datastore(serde)]
+    size: (u32, u32),
+    #[datastore(serde)]
+    label: Option<String>,
+}
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Polygon {
+    #[datastore(serde)]
+    points: Vec<Point>,
+    #[datastore(serde)]
+    tags: BTreeMap<String, u8>,
+    #[datastore(serde)]
+    parent: Option<Point>,
+}
+
+fn roundtrip<T>(data: &T) -> (Record, T)
+where
+    T: StoreData<__Store>,
+{
+    let record = Record::from_data::<T, __Store>(data).unwrap();
+    let output = record.to_data::<T, __Store>().unwrap();
+    (record, output)
+}
+
+#[test]
+fn test_serde_struct_roundtrip() {
+    let shape = Shape {
+        origin: Point { x: 1, y: -2 },
+        size: (3, 4),
+        label: Some("square".to_owned()),
+    };
+    let (record, output) = roundtrip(&shape);
+    assert_eq!(output, shape);
+
+    // Structs and tuples are written as nested fields.
+    let origin = match record.get("origin") {
+        Some(record::Value::Record(origin)) => origin,
+        value => panic!("expected a record, got {:?}", value),
+    };
+    assert_eq!(origin.get("x"), Some(&record::Value::I32(1)));
+    assert_eq!(origin.get("y"), Some(&record::Value::I32(-2)));
+    let size = match record.get("size") {
+        Some(record::Value::Record(size)) => size,
+        value => panic!("expected a record, got {:?}", value),
+    };
+    assert_eq!(size.get("1"), Some(&record::Value::U32(4)));
+
+    // `None` writes nothing and is read back from the missing field.
+    let shape = Shape {
+        label: None,
+        ..shape
+    };
+    let (record, output) = roundtrip(&shape);
+    assert_eq!(record.get("label"), None);
+    assert_eq!(output, shape);
+}
+
+#[test]
+fn test_serde_read_malformed_option() {
+    let shape = Shape {
+        origin: Point { x: 1, y: -2 },
+        size: (3, 4),
+        label: Some("square".to_owned()),
+    };
+    let mut record = Record::from_data::<_, __Store>(&shape).unwrap();
+
+    // Only a missing optional field is read as `None`.
+    record.insert("label", record::Value::I64(1));
+    assert!(record.to_data::<Shape, __Store>().is_err());
+
+    // A missing field inside an existing optional value is an error.
+    let polygon = Polygon {
+        points: Vec::new(),
+        tags: BTreeMap::new(),
+        parent: Some(Point { x: 5, y: 5 }),
+    };
+    let mut record = Record::from_data::<_, __Store>(&polygon).unwrap();
+    if let Some(record::Value::Record(parent)) = record.get_mut("parent") {
+        parent.remove("x");
+    }
+    assert!(record.to_data::<Polygon, __Store>().is_err());
+}
+
+#[test]
+fn test_serde_sequence_roundtrip() {
+    let mut tags = BTreeMap::new();
+    tags.insert("color".to_owned(), 3);
+    tags.insert("layer".to_owned(), 1);
+
+    let polygon = Polygon {
+        points: vec![
+            Point { x: 0, y: 0 },
+            Point { x: 1, y: 0 },
+            Point { x: 0, y: 1 },
+        ],
+        tags,
+        parent: Some(Point { x: 5, y: 5 }),
+    };
+    let (record, output) = roundtrip(&polygon);
+    assert_eq!(output, polygon);
+
+    // Sequences and maps are packed into bytes.
+    assert!(matches!(
+        record.get("points"),
+        Some(record::Value::Bytes(_))
+    ));
+    assert!(matches!(record.get("tags"), Some(record::Value::Bytes(_))));
+
+    let polygon = Polygon {
+        points: Vec::new(),
+        tags: BTreeMap::new(),
+        parent: None,
+    };
+    let (_, output) = roundtrip(&polygon);
+    assert_eq!(output, polygon);
+}
+
+#[test]
+fn test_serde_nested_sequence_roundtrip() {
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Layer {
+        name: String,
+        bounds: (Point, Point),
+        shapes: Vec<Option<(u8, Vec<Point>)>>,
+        meta: HashMap<String, serde_json::Value>,
+    }
+
+    #[derive(Debug, PartialEq, StoreData)]
+    struct Drawing {
+        #[datastore(serde)]
+        layer: Layer,
+        #[datastore(serde)]
+        history: Vec<Layer>,
+    }
+
+    let mut meta = HashMap::new();
+    meta.insert("tags".to_owned(), serde_json::json!(["a", 1, null]));
+    let layer = Layer {
+        name: "background".to_owned(),
+        bounds: (Point { x: 0, y: 0 }, Point { x: 10, y: 10 }),
+        shapes: vec![None, Some((1, vec![Point { x: 1, y: 2 }]))],
+        meta,
+    };
+
+    // Elements of sequences are read without tracing them, even for types that cannot be traced.
+    let drawing = Drawing {
+        layer: layer.clone(),
+        history: vec![layer.clone(), layer],
+    };
+    let (_, output) = roundtrip(&drawing);
+    assert_eq!(output, drawing);
+}
+
+#[test]
+fn test_serde_nested_type() {
+    // Nested values are described as nested fields.
+    let schema = json_schema::to_string::<Shape, __Store, _>(&ShapeDescriptor).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&schema).unwrap();
+    let origin = &value["properties"]["origin"];
+    assert_eq!(origin["type"], "object");
+    assert_eq!(origin["properties"]["x"]["type"], "integer");
+    assert_eq!(
+        value["properties"]["size"]["properties"]["0"]["type"],
+        "integer"
+    );
+    assert_eq!(
+        value["properties"]["label"]["type"],
+        serde_json::json!(["string", "null"])
+    );
+
+    assert!(matches!(
+        Table::new::<Shape, __Store, _>(&ShapeDescriptor),
+        Err(sql::Error::NestedField { .. })
+    ));
+
+    // Sequences and maps are described as bytes.
+    let schema = json_schema::to_string::<Polygon, __Store, _>(&PolygonDescriptor).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&schema).unwrap();
+    assert_eq!(value["properties"]["points"]["format"], "byte");
+    assert_eq!(value["properties"]["tags"]["format"], "byte");
+}
+
+#[test]
+fn test_serde_untraceable() {
+    #[derive(StoreData)]
+    struct Document {
+        #[datastore(serde)]
+        body: serde_json::Value,
+    }
+
+    // Types relying on `deserialize_any` cannot be traced.
+    assert!(matches!(
+        Table::new::<Document, __Store, _>(&DocumentDescriptor),
+        Err(sql::Error::Custom(_))
+    ));
+
+    let mut record = Record::new();
+    record.insert("body", record::Value::String("{}".to_owned()));
+    assert!(record.to_data::<Document, __Store>().is_err());
+}
+
+#[test]
+fn test_serde_from_ref() {
+    let status = Status::Active;
+    assert_eq!(Serde::from_ref(&status).0, Status::Active);
+}
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Person {
+    id: i64,
+    name: String,
+    age: u8,
+    score: f64,
+    active: bool,
+    avatar: Vec<u8>,
+}
+
+fn person() -> Person {
+    Person {
+        id: -5,
+        name: String::from("Jo \"J\""),
+        age: 42,
+        score: 1.5,
+        active: true,
+        avatar: vec![0, 1, 255],
+    }
+}
+
+#[test]
+fn test_serde_serialize_json() {
+    let json = serde_json::to_string(&SerializeData::<_, __Store>::new(&person())).unwrap();
+
+    assert_eq!(
+        json,
+        r#"{"id":-5,"name":"Jo \"J\"","age":42,"score":1.5,"active":true,"avatar":[0,1,255]}"#
+    );
+}
+
+#[test]
+fn test_serde_deserialize_json() {
+    let json =
+        r#"{"avatar":[0,1,255],"active":true,"score":1.5,"age":42,"name":"Jo \"J\"","id":-5}"#;
+
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let person: Person = datastore::serde::deserialize::<_, __Store, _>(&mut deserializer).unwrap();
+
+    assert_eq!(person, self::person());
+}
+
+#[test]
+fn test_serde_roundtrip_json() {
+    let value = serde_json::to_value(SerializeData::<_, __Store>::new(&person())).unwrap();
+    let person: Person = datastore::serde::deserialize::<_, __Store, _>(value).unwrap();
+
+    assert_eq!(person, self::person());
+}
+
+#[test]
+fn test_serde_roundtrip_json_none() {
+    let shape = Shape {
+        origin: Point { x: 1, y: -2 },
+        size: (3, 4),
+        label: None,
+    };
+
+    // `None` is serialized as null and read back as a missing field.
+    let value = serde_json::to_value(SerializeData::<_, __Store>::new(&shape)).unwrap();
+    assert_eq!(value["label"], serde_json::Value::Null);
+    let output: Shape = datastore::serde::deserialize::<_, __Store, _>(value).unwrap();
+
+    assert_eq!(output, shape);
+}
+
+#[test]
+fn test_serde_deserialize_json_invalid() {
+    // Missing field
+    let json = r#"{"id":1}"#;
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let res: Result<Person, _> = datastore::serde::deserialize::<_, __Store, _>(&mut deserializer);
+    assert!(res.is_err());
+
+    // Out of range
+    let json = r#"{"id":1,"name":"","age":256,"score":0,"active":false,"avatar":[]}"#;
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let res: Result<Person, _> = datastore::serde::deserialize::<_, __Store, _>(&mut deserializer);
+    assert!(res.is_err());
+}
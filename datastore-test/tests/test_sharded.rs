@@ -0,0 +1,190 @@
+mod support;
+
+use datastore::shard::{self, JumpHash, ModuloHash, Router, Sharded};
+use datastore::value::Value;
+use datastore::{Capabilities, ConnectOptions, Error, Store, StoreExt};
+
+use self::support::counting::{backend, CountingStore};
+use self::support::person::{connect, person, Person, PersonQuery};
+
+async fn shards(prefix: &str, n: usize) -> Vec<CountingStore> {
+    let mut shards = Vec::new();
+    for i in 0..n {
+        let uri = format!("counting://{}-{}", prefix, i);
+        shards.push(connect(&uri).await);
+    }
+    shards
+}
+
+async fn len(prefix: &str, i: usize) -> usize {
+    backend(&format!("counting://{}-{}", prefix, i))
+        .count("Person")
+        .await
+}
+
+#[tokio::test]
+async fn test_sharded() {
+    let store = Sharded::new(shards("sharded", 3).await, "id");
+
+    for id in 0..30 {
+        store
+            .insert(store.descriptor::<Person>(), person(id, "Alice"))
+            .await
+            .unwrap();
+    }
+
+    // Every item is stored on exactly one shard.
+    let mut lens = Vec::new();
+    for i in 0..3 {
+        lens.push(len("sharded", i).await);
+    }
+    assert_eq!(lens.iter().sum::<usize>(), 30);
+    assert!(lens.iter().all(|len| *len > 0));
+
+    // Items are stored on the shard selected by the router.
+    let index = ModuloHash.route(&Value::I64(7), 3);
+    let shard = &store.shards()[index];
+    let output = shard
+        .get_one(shard.descriptor::<Person>(), PersonQuery::default().id(7))
+        .await
+        .unwrap();
+    assert_eq!(output, Some(person(7, "Alice")));
+
+    let mut output: Vec<Person> = store.get_all(store.descriptor::<Person>()).await.unwrap();
+    output.sort_by_key(|person| person.id);
+    assert_eq!(
+        output,
+        (0..30).map(|id| person(id, "Alice")).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn test_sharded_query() {
+    let store = Sharded::new(shards("sharded-query", 2).await, "id");
+    for id in 0..10 {
+        store
+            .insert(store.descriptor::<Person>(), person(id, "Alice"))
+            .await
+            .unwrap();
+    }
+
+    // Queries containing the shard key only reach a single shard.
+    let calls: Vec<_> = (0..2)
+        .map(|i| backend(&format!("counting://sharded-query-{}", i)).calls())
+        .collect();
+    let output = store
+        .get_one(store.descriptor::<Person>(), PersonQuery::default().id(3))
+        .await
+        .unwrap();
+    assert_eq!(output, Some(person(3, "Alice")));
+    let after: Vec<_> = (0..2)
+        .map(|i| backend(&format!("counting://sharded-query-{}", i)).calls())
+        .collect();
+    assert_eq!(after[0] + after[1], calls[0] + calls[1] + 1);
+
+    // Other queries are sent to all shards and merged.
+    let output = store
+        .get(
+            store.descriptor::<Person>(),
+            PersonQuery::default().name("Alice".to_owned()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(output.len(), 10);
+
+    store
+        .delete(
+            store.descriptor::<Person>(),
+            PersonQuery::default().name("Alice".to_owned()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        len("sharded-query", 0).await + len("sharded-query", 1).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_sharded_router() {
+    let store = Sharded::new(shards("sharded-router", 2).await, "id")
+        .with_type_key("Person", "name")
+        .with_router(|key: &Value, _: usize| match key {
+            Value::String(name) if name.starts_with('A') => 0,
+            _ => 1,
+        });
+
+    store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap();
+    store
+        .insert(store.descriptor::<Person>(), person(2, "Bob"))
+        .await
+        .unwrap();
+    store
+        .insert(store.descriptor::<Person>(), person(3, "Anna"))
+        .await
+        .unwrap();
+    assert_eq!(len("sharded-router", 0).await, 2);
+    assert_eq!(len("sharded-router", 1).await, 1);
+
+    // A router selecting a missing shard is an error.
+    let store = Sharded::new(store.into_shards(), "id").with_router(|_: &Value, n: usize| n);
+    assert!(store
+        .insert(store.descriptor::<Person>(), person(4, "Carol"))
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_sharded_missing_key() {
+    let store = Sharded::new(shards("sharded-missing-key", 2).await, "tenant");
+    let err = store
+        .insert(store.descriptor::<Person>(), person(1, "Alice"))
+        .await
+        .unwrap_err();
+    assert!(!err.is_transient());
+    assert_eq!(backend("counting://sharded-missing-key-0").calls(), 0);
+    assert_eq!(backend("counting://sharded-missing-key-1").calls(), 0);
+    assert_eq!(store.capabilities(), Capabilities::WRITE);
+}
+
+#[tokio::test]
+async fn test_sharded_connect_with() {
+    let options = ConnectOptions::new("sharded")
+        .with_param("shard_key", "id")
+        .with_param("shard", "counting://sharded-connect-0")
+        .with_param("shard", "counting://sharded-connect-1");
+    let store = Sharded::<CountingStore>::connect(&options.to_string())
+        .await
+        .unwrap();
+    assert_eq!(store.shards().len(), 2);
+    assert_eq!(backend("counting://sharded-connect-1").connects(), 1);
+
+    let options = ConnectOptions::new("sharded").with_param("shard_key", "id");
+    assert!(Sharded::<CountingStore>::connect_with(&options)
+        .await
+        .is_err());
+}
+
+#[test]
+fn test_hash() {
+    // Integers hash equally regardless of their type.
+    assert_eq!(shard::hash(&Value::I64(42)), shard::hash(&Value::U8(42)));
+    assert_ne!(shard::hash(&Value::I64(42)), shard::hash(&Value::I64(43)));
+    assert_ne!(
+        shard::hash(&Value::String("42".to_owned())),
+        shard::hash(&Value::Bytes(b"42".to_vec()))
+    );
+
+    for id in 0..100 {
+        let key = Value::I64(id);
+        assert!(JumpHash.route(&key, 5) < 5);
+
+        // Adding a shard only moves items to the new shard.
+        let before = JumpHash.route(&key, 5);
+        let after = JumpHash.route(&key, 6);
+        assert!(after == before || after == 5);
+    }
+}
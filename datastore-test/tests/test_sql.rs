@@ -0,0 +1,156 @@
+mod support;
+
+use datastore::sql::{create_table, ColumnType, Dialect, Error, Table};
+use datastore::{StoreData, TypeWriter, Write};
+
+use self::support::__Store;
+
+#[derive(StoreData)]
+#[datastore(name = "people")]
+struct Person {
+    #[datastore(primary_key)]
+    id: i64,
+    #[datastore(index)]
+    name: String,
+    email: Option<String>,
+    age: u8,
+    avatar: Vec<u8>,
+}
+
+fn ddl(dialect: Dialect) -> Vec<String> {
+    create_table::<Person, __Store, _>(&PersonDescriptor, dialect).unwrap()
+}
+
+#[test]
+fn test_sql_table() {
+    let table = Table::new::<Person, __Store, _>(&PersonDescriptor).unwrap();
+
+    assert_eq!(table.name(), "people");
+
+    let columns: Vec<_> = table
+        .columns()
+        .iter()
+        .map(|column| (column.name(), column.ty()))
+        .collect();
+    assert_eq!(
+        columns,
+        [
+            ("id", ColumnType::I64),
+            ("name", ColumnType::Str),
+            ("email", ColumnType::Str),
+            ("age", ColumnType::U8),
+            ("avatar", ColumnType::Bytes),
+        ]
+    );
+
+    let primary_key: Vec<_> = table.primary_key().map(|column| column.name()).collect();
+    assert_eq!(primary_key, ["id"]);
+
+    assert!(table.columns()[2].attributes().is_nullable());
+    assert!(!table.columns()[1].attributes().is_nullable());
+}
+
+#[test]
+fn test_sql_sqlite() {
+    assert_eq!(
+        ddl(Dialect::Sqlite),
+        [
+            "CREATE TABLE IF NOT EXISTS \"people\" (\n    \
+            \"id\" INTEGER NOT NULL,\n    \
+            \"name\" TEXT NOT NULL,\n    \
+            \"email\" TEXT,\n    \
+            \"age\" INTEGER NOT NULL,\n    \
+            \"avatar\" BLOB NOT NULL,\n    \
+            PRIMARY KEY (\"id\")\n)",
+            "CREATE INDEX IF NOT EXISTS \"people_name_idx\" ON \"people\" (\"name\")",
+        ]
+    );
+}
+
+#[test]
+fn test_sql_postgres() {
+    assert_eq!(
+        ddl(Dialect::Postgres),
+        [
+            "CREATE TABLE IF NOT EXISTS \"people\" (\n    \
+            \"id\" BIGINT NOT NULL,\n    \
+            \"name\" TEXT NOT NULL,\n    \
+            \"email\" TEXT,\n    \
+            \"age\" SMALLINT NOT NULL,\n    \
+            \"avatar\" BYTEA NOT NULL,\n    \
+            PRIMARY KEY (\"id\")\n)",
+            "CREATE INDEX IF NOT EXISTS \"people_name_idx\" ON \"people\" (\"name\")",
+        ]
+    );
+}
+
+#[test]
+fn test_sql_mysql() {
+    assert_eq!(
+        ddl(Dialect::MySql),
+        ["CREATE TABLE IF NOT EXISTS `people` (\n    \
+            `id` BIGINT NOT NULL,\n    \
+            `name` VARCHAR(255) NOT NULL,\n    \
+            `email` TEXT,\n    \
+            `age` TINYINT UNSIGNED NOT NULL,\n    \
+            `avatar` BLOB NOT NULL,\n    \
+            PRIMARY KEY (`id`),\n    \
+            INDEX `people_name_idx` (`name`)\n)"]
+    );
+}
+
+#[test]
+fn test_sql_no_primary_key() {
+    #[derive(StoreData)]
+    struct Event {
+        ts: u64,
+    }
+
+    assert_eq!(
+        create_table::<Event, __Store, _>(&EventDescriptor, Dialect::Postgres).unwrap(),
+        ["CREATE TABLE IF NOT EXISTS \"Event\" (\n    \"ts\" NUMERIC(20) NOT NULL\n)"]
+    );
+}
+
+#[test]
+fn test_sql_nested_field() {
+    #[derive(Clone)]
+    struct Nested;
+
+    impl Write<__Store> for Nested {
+        fn write<W>(&self, _writer: &mut W) -> Result<(), W::Error>
+        where
+            W: datastore::Writer<__Store>,
+        {
+            Ok(())
+        }
+
+        fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+        where
+            W: TypeWriter<__Store>,
+        {
+            writer.write_field::<u8>("inner")
+        }
+    }
+
+    impl datastore::Read<__Store> for Nested {
+        fn read<R>(_reader: &mut R) -> Result<Self, R::Error>
+        where
+            R: datastore::Reader<__Store>,
+        {
+            Ok(Self)
+        }
+    }
+
+    #[derive(StoreData)]
+    struct Outer {
+        nested: Nested,
+    }
+
+    assert_eq!(
+        Table::new::<Outer, __Store, _>(&OuterDescriptor),
+        Err(Error::NestedField {
+            key: "inner".to_owned()
+        })
+    );
+}
@@ -0,0 +1,209 @@
+mod support;
+
+use std::collections::HashMap;
+
+use datastore::value::{Error, Record, RecordDescriptor, RecordQuery, Type, Value};
+use datastore::{DataDescriptor, DataQuery, FieldAttributes, StoreData};
+
+use self::support::{__Reader, __Store, __TypeWriter, __Writer};
+
+#[derive(Debug, PartialEq, StoreData)]
+struct Person {
+    id: i64,
+    name: String,
+    age: u8,
+    avatar: Vec<u8>,
+}
+
+fn person() -> Person {
+    Person {
+        id: 1,
+        name: "Jo".to_owned(),
+        age: 42,
+        avatar: vec![0, 1, 255],
+    }
+}
+
+fn descriptor() -> RecordDescriptor {
+    RecordDescriptor::new("people")
+        .field_with("id", Type::I64, FieldAttributes::new().primary_key(true))
+        .field("name", Type::String)
+        .field("age", Type::U8)
+}
+
+#[test]
+fn test_record_from_data() {
+    let record = Record::from_data::<_, __Store>(&person()).unwrap();
+
+    let fields: Vec<_> = record.iter().collect();
+    assert_eq!(
+        fields,
+        [
+            ("id", &Value::I64(1)),
+            ("name", &Value::String("Jo".to_owned())),
+            ("age", &Value::U8(42)),
+            ("avatar", &Value::Bytes(vec![0, 1, 255])),
+        ]
+    );
+}
+
+#[test]
+fn test_record_to_data() {
+    let record = Record::from_data::<_, __Store>(&person()).unwrap();
+    let person: Person = record.to_data::<_, __Store>().unwrap();
+
+    assert_eq!(person, self::person());
+}
+
+#[test]
+fn test_record_to_data_convert() {
+    let record: Record = vec![
+        ("id", Value::U8(1)),
+        ("name", Value::from("Jo")),
+        ("age", Value::I64(42)),
+        ("avatar", Value::from(vec![0, 1, 255])),
+    ]
+    .into_iter()
+    .collect();
+
+    let person: Person = record.to_data::<_, __Store>().unwrap();
+    assert_eq!(person, self::person());
+}
+
+#[test]
+fn test_record_to_data_invalid() {
+    let mut record = Record::from_data::<_, __Store>(&person()).unwrap();
+
+    record.insert("age", Value::I64(256));
+    assert_eq!(
+        record.to_data::<Person, __Store>().unwrap_err(),
+        Error::InvalidType { expected: "an u8" }
+    );
+
+    record.remove("age");
+    assert_eq!(
+        record.to_data::<Person, __Store>().unwrap_err(),
+        Error::MissingField {
+            key: "age".to_owned()
+        }
+    );
+}
+
+#[test]
+fn test_record_insert() {
+    let mut record = Record::new();
+    assert!(record.is_empty());
+
+    assert_eq!(record.insert("a", Value::Bool(true)), None);
+    assert_eq!(record.insert("b", Value::Bool(false)), None);
+    assert_eq!(record.insert("a", Value::U8(1)), Some(Value::Bool(true)));
+
+    assert_eq!(record.len(), 2);
+    assert_eq!(record.keys().collect::<Vec<_>>(), ["a", "b"]);
+    assert_eq!(record.get("a"), Some(&Value::U8(1)));
+}
+
+#[test]
+fn test_record_read() {
+    let mut reader = __Reader::new(HashMap::new());
+    assert!(<Record as StoreData<__Store>>::read(&mut reader).is_err());
+}
+
+#[test]
+fn test_record_descriptor_write() {
+    let descriptor = descriptor();
+    assert_eq!(
+        DataDescriptor::<Record, __Store>::ident(&descriptor),
+        "people"
+    );
+
+    let mut writer = __TypeWriter::new();
+    DataDescriptor::<Record, __Store>::write(&descriptor, &mut writer).unwrap();
+
+    let mut expected = HashMap::new();
+    expected.insert("id".to_owned(), support::Type::I64);
+    expected.insert("name".to_owned(), support::Type::Str);
+    expected.insert("age".to_owned(), support::Type::U8);
+    assert_eq!(writer.values, expected);
+
+    assert!(descriptor.fields()[0].attributes().is_primary_key());
+}
+
+#[test]
+fn test_record_descriptor_read() {
+    let mut values = HashMap::new();
+    values.insert("id".to_owned(), support::Value::I64(1));
+    values.insert("name".to_owned(), support::Value::Str("Jo".to_owned()));
+    values.insert("age".to_owned(), support::Value::U8(42));
+    values.insert("avatar".to_owned(), support::Value::Bytes(vec![0]));
+
+    let mut reader = __Reader::new(values);
+    let record = DataDescriptor::<Record, __Store>::read(&descriptor(), &mut reader).unwrap();
+
+    let expected: Record = vec![
+        ("id", Value::I64(1)),
+        ("name", Value::from("Jo")),
+        ("age", Value::U8(42)),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(record, expected);
+}
+
+#[test]
+fn test_record_descriptor_read_nullable() {
+    let descriptor = RecordDescriptor::new("people")
+        .field("id", Type::I64)
+        .field_with("name", Type::String, FieldAttributes::new().nullable(true));
+
+    // A missing nullable field is left out.
+    let mut input: Record = vec![("id", Value::I64(1))].into_iter().collect();
+    let record = DataDescriptor::<Record, __Store>::read(&descriptor, &mut input).unwrap();
+    assert_eq!(record, input);
+
+    // A nullable field with an invalid value is an error.
+    input.insert("name", Value::I64(2));
+    assert_eq!(
+        DataDescriptor::<Record, __Store>::read(&descriptor, &mut input).unwrap_err(),
+        Error::InvalidType {
+            expected: "a string"
+        }
+    );
+}
+
+#[test]
+fn test_record_query() {
+    let query = RecordQuery::new().field("name", "Jo").field("age", 42u8);
+
+    let mut writer = __Writer::new();
+    DataQuery::<Record, __Store>::write(&query, &mut writer).unwrap();
+
+    let mut expected = HashMap::new();
+    expected.insert("name".to_owned(), support::Value::Str("Jo".to_owned()));
+    expected.insert("age".to_owned(), support::Value::U8(42));
+    assert_eq!(writer.values, expected);
+}
+
+#[test]
+fn test_record_runtime_names() {
+    let names: Vec<String> = (0..2).map(|index| format!("field{}", index)).collect();
+
+    let descriptor = names
+        .iter()
+        .fold(RecordDescriptor::new("fields"), |descriptor, name| {
+            descriptor.field(name.clone(), Type::U32)
+        });
+    assert_eq!(descriptor.fields()[1].name(), "field1");
+
+    let mut input: Record = vec![
+        (names[0].clone(), Value::U32(0)),
+        (names[1].clone(), Value::U32(1)),
+    ]
+    .into_iter()
+    .collect();
+    let record = DataDescriptor::<Record, __Store>::read(&descriptor, &mut input).unwrap();
+    assert_eq!(record, input);
+
+    let query = RecordQuery::new().field(names[0].clone(), 0u32);
+    assert_eq!(query.as_record().get("field0"), Some(&Value::U32(0)));
+}
@@ -0,0 +1,756 @@
+//! A compact binary encoding for [`StoreData`] types.
+//!
+//! [`BinaryWriter`] and [`BinaryReader`] turn any [`StoreData`] type into a portable `Vec<u8>`
+//! and back. This is useful for stores that only save opaque blobs, like key-value stores, caches
+//! or message queues:
+//!
+//! ```
+//! # use datastore::{Store, StoreData};
+//! # fn roundtrip<T, S>(data: &T) -> T
+//! # where
+//! #     T: StoreData<S>,
+//! #     S: Store,
+//! # {
+//! let buf = datastore::binary::to_vec(data).unwrap();
+//! datastore::binary::from_slice(&buf).unwrap()
+//! # }
+//! ```
+//!
+//! # Wire format
+//!
+//! The encoding is stable and versioned. An encoded value consists of a header followed by a
+//! message:
+//!
+//! | Bytes    | Content                                      |
+//! | -------- | -------------------------------------------- |
+//! | 1        | The format version, currently [`VERSION`].   |
+//! | varint   | The length of the message in bytes.          |
+//! | variable | The message.                                 |
+//!
+//! A message is a sequence of fields written by [`Writer::write_field`]. Each field starts with a
+//! varint key `(tag << 3) | wire_type` followed by the value. The `tag` is the 32-bit FNV-1a hash
+//! of the UTF-8 field key. Fields are looked up by their tag, therefore the order of fields does
+//! not matter. Fields whose value writes nothing are omitted.
+//!
+//! | Wire type | Encoding                   | Used for                               |
+//! | --------- | -------------------------- | -------------------------------------- |
+//! | 0         | varint                     | `bool`, unsigned and signed integers   |
+//! | 1         | 8 bytes, little endian     | `f64`                                  |
+//! | 2         | varint length, then bytes  | bytes, strings and nested messages     |
+//! | 5         | 4 bytes, little endian     | `f32`                                  |
+//!
+//! Varints are encoded in LEB128: 7 bits per byte, least significant group first, with the most
+//! significant bit set on all bytes except the last. Signed integers are zigzag encoded before
+//! the varint encoding (`0 => 0`, `-1 => 1`, `1 => 2`, ...). Integers may be read with a
+//! different width than they were written with as long as the value fits, but the signedness must
+//! match.
+//!
+//! [`StoreData`]: crate::StoreData
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::str;
+
+use crate::{ReadSeed, Reader, Store, StoreData, Write, Writer};
+
+/// The current version of the wire format.
+pub const VERSION: u8 = 1;
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LEN: u8 = 2;
+const WIRE_FIXED32: u8 = 5;
+
+/// Serializes the [`StoreData`] `data` into a new buffer.
+pub fn to_vec<T, S>(data: &T) -> Result<Vec<u8>, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    let mut writer = BinaryWriter::new();
+    data.write(&mut writer)?;
+    Ok(writer.finish())
+}
+
+/// Deserializes a new [`StoreData`] from the buffer `buf`.
+pub fn from_slice<T, S>(buf: &[u8]) -> Result<T, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    T::read(&mut BinaryReader::new(buf)?)
+}
+
+/// Returns the tag of the field `key`.
+fn tag(key: &str) -> u32 {
+    // 32-bit FNV-1a
+    key.bytes().fold(0x811c_9dc5, |hash, b| {
+        (hash ^ u32::from(b)).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+
+    buf.push(v as u8);
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn unzigzag(v: u64) -> i64 {
+    (v >> 1) as i64 ^ -((v & 1) as i64)
+}
+
+/// A [`Writer`] encoding a message in the binary wire format.
+///
+/// See the [module documentation] for the format.
+///
+/// [module documentation]: self
+#[derive(Clone, Debug, Default)]
+pub struct BinaryWriter {
+    message: Message,
+}
+
+impl BinaryWriter {
+    /// Creates a new, empty `BinaryWriter`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Completes the message, returning the encoded buffer including the header.
+    pub fn finish(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.message.buf.len() + 6);
+        buf.push(VERSION);
+        write_varint(&mut buf, self.message.buf.len() as u64);
+        buf.extend_from_slice(&self.message.buf);
+        buf
+    }
+}
+
+impl<S> Writer<S> for BinaryWriter
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self, _v: bool) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_i8(&mut self, _v: i8) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_i16(&mut self, _v: i16) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_i32(&mut self, _v: i32) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_i64(&mut self, _v: i64) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_u8(&mut self, _v: u8) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_u16(&mut self, _v: u16) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_u32(&mut self, _v: u32) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_u64(&mut self, _v: u64) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_f32(&mut self, _v: f32) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_f64(&mut self, _v: f64) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_bytes(&mut self, _v: &[u8]) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_str(&mut self, _v: &str) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<S>,
+    {
+        self.message.write_field(key, value)
+    }
+}
+
+/// The fields of an encoded message.
+#[derive(Clone, Debug, Default)]
+struct Message {
+    buf: Vec<u8>,
+    keys: Vec<(u32, String)>,
+}
+
+impl Message {
+    fn write_field<T, S>(&mut self, key: &str, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Write<S>,
+        S: Store,
+    {
+        let tag = tag(key);
+        if let Some((_, other)) = self.keys.iter().find(|(t, _)| *t == tag) {
+            return Err(Error::DuplicateTag {
+                key: key.to_owned(),
+                other: other.clone(),
+            });
+        }
+        self.keys.push((tag, key.to_owned()));
+
+        let mut writer = FieldWriter { value: None };
+        value.write(&mut writer)?;
+
+        let (wire_type, value) = match writer.value {
+            Some(value) => (value.wire_type(), value),
+            None => return Ok(()),
+        };
+
+        write_varint(&mut self.buf, u64::from(tag) << 3 | u64::from(wire_type));
+        match value {
+            Encoded::Varint(v) => write_varint(&mut self.buf, v),
+            Encoded::Fixed32(v) => self.buf.extend_from_slice(&v.to_le_bytes()),
+            Encoded::Fixed64(v) => self.buf.extend_from_slice(&v.to_le_bytes()),
+            Encoded::Bytes(v) => {
+                write_varint(&mut self.buf, v.len() as u64);
+                self.buf.extend_from_slice(&v);
+            }
+            Encoded::Message(message) => {
+                write_varint(&mut self.buf, message.buf.len() as u64);
+                self.buf.extend_from_slice(&message.buf);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A single encoded field value.
+enum Encoded {
+    Varint(u64),
+    Fixed32(u32),
+    Fixed64(u64),
+    Bytes(Vec<u8>),
+    Message(Message),
+}
+
+impl Encoded {
+    fn wire_type(&self) -> u8 {
+        match self {
+            Self::Varint(_) => WIRE_VARINT,
+            Self::Fixed32(_) => WIRE_FIXED32,
+            Self::Fixed64(_) => WIRE_FIXED64,
+            Self::Bytes(_) | Self::Message(_) => WIRE_LEN,
+        }
+    }
+}
+
+/// The [`Writer`] encoding a single field value.
+struct FieldWriter {
+    value: Option<Encoded>,
+}
+
+impl FieldWriter {
+    fn write_value(&mut self, value: Encoded) -> Result<(), Error> {
+        match self.value {
+            Some(_) => Err(Error::MultipleValues),
+            None => {
+                self.value = Some(value);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<S> Writer<S> for FieldWriter
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self, v: bool) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Varint(u64::from(v)))
+    }
+
+    fn write_i8(&mut self, v: i8) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Varint(zigzag(i64::from(v))))
+    }
+
+    fn write_i16(&mut self, v: i16) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Varint(zigzag(i64::from(v))))
+    }
+
+    fn write_i32(&mut self, v: i32) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Varint(zigzag(i64::from(v))))
+    }
+
+    fn write_i64(&mut self, v: i64) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Varint(zigzag(v)))
+    }
+
+    fn write_u8(&mut self, v: u8) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Varint(u64::from(v)))
+    }
+
+    fn write_u16(&mut self, v: u16) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Varint(u64::from(v)))
+    }
+
+    fn write_u32(&mut self, v: u32) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Varint(u64::from(v)))
+    }
+
+    fn write_u64(&mut self, v: u64) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Varint(v))
+    }
+
+    fn write_f32(&mut self, v: f32) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Fixed32(v.to_bits()))
+    }
+
+    fn write_f64(&mut self, v: f64) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Fixed64(v.to_bits()))
+    }
+
+    fn write_bytes(&mut self, v: &[u8]) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Bytes(v.to_vec()))
+    }
+
+    fn write_str(&mut self, v: &str) -> Result<(), Self::Error> {
+        self.write_value(Encoded::Bytes(v.as_bytes().to_vec()))
+    }
+
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<S>,
+    {
+        let message = match &mut self.value {
+            Some(Encoded::Message(message)) => message,
+            Some(_) => return Err(Error::MultipleValues),
+            None => {
+                self.value = Some(Encoded::Message(Message::default()));
+                match &mut self.value {
+                    Some(Encoded::Message(message)) => message,
+                    _ => unreachable!(),
+                }
+            }
+        };
+
+        message.write_field(key, value)
+    }
+}
+
+/// A cursor over an encoded buffer.
+#[derive(Copy, Clone, Debug)]
+struct Cursor<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.buf.len() < len {
+            return Err(Error::UnexpectedEof);
+        }
+
+        let (bytes, rem) = self.buf.split_at(len);
+        self.buf = rem;
+        Ok(bytes)
+    }
+
+    fn read_varint(&mut self) -> Result<u64, Error> {
+        let mut v = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_bytes(1)?[0];
+
+            // The 10th byte may only contain the last bit.
+            if shift == 63 && byte > 1 {
+                return Err(Error::InvalidVarint);
+            }
+
+            v |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+
+        Err(Error::InvalidVarint)
+    }
+
+    fn read_len(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.read_varint()?;
+        let len = usize::try_from(len).map_err(|_| Error::UnexpectedEof)?;
+        self.read_bytes(len)
+    }
+
+    /// Reads the next field, returning its tag, wire type and value.
+    fn read_field(&mut self) -> Result<(u32, u8, &'a [u8]), Error> {
+        let key = self.read_varint()?;
+        let tag = u32::try_from(key >> 3).map_err(|_| Error::InvalidVarint)?;
+        let wire_type = (key & 0b111) as u8;
+
+        let value = match wire_type {
+            WIRE_VARINT => {
+                let start = self.buf;
+                self.read_varint()?;
+                &start[..start.len() - self.buf.len()]
+            }
+            WIRE_FIXED64 => self.read_bytes(8)?,
+            WIRE_LEN => self.read_len()?,
+            WIRE_FIXED32 => self.read_bytes(4)?,
+            _ => return Err(Error::InvalidWireType(wire_type)),
+        };
+
+        Ok((tag, wire_type, value))
+    }
+}
+
+/// A [`Reader`] decoding a message in the binary wire format.
+///
+/// See the [module documentation] for the format.
+///
+/// [module documentation]: self
+#[derive(Copy, Clone, Debug)]
+pub struct BinaryReader<'a> {
+    message: &'a [u8],
+}
+
+impl<'a> BinaryReader<'a> {
+    /// Creates a new `BinaryReader` from the encoded buffer `buf`.
+    ///
+    /// Returns an error if the header is invalid or the length of `buf` does not match the
+    /// length in the header.
+    pub fn new(buf: &'a [u8]) -> Result<Self, Error> {
+        let mut cursor = Cursor { buf };
+
+        let version = cursor.read_bytes(1)?[0];
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let message = cursor.read_len()?;
+        if !cursor.buf.is_empty() {
+            return Err(Error::TrailingBytes);
+        }
+
+        Ok(Self { message })
+    }
+
+    fn read_field_seed<T, S>(message: &'a [u8], key: &str, seed: T) -> Result<T::Value, Error>
+    where
+        T: ReadSeed<S>,
+        S: Store,
+    {
+        let tag = tag(key);
+
+        let mut cursor = Cursor { buf: message };
+        while !cursor.buf.is_empty() {
+            let (t, wire_type, value) = cursor.read_field()?;
+            if t == tag {
+                return seed.read(&mut FieldReader { wire_type, value });
+            }
+        }
+
+        Err(Error::MissingField {
+            key: key.to_owned(),
+        })
+    }
+}
+
+impl<'a, S> Reader<S> for BinaryReader<'a>
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn read_bool(&mut self) -> Result<bool, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_string(&mut self) -> Result<String, Self::Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>,
+    {
+        Self::read_field_seed(self.message, key, seed)
+    }
+}
+
+/// The [`Reader`] decoding a single field value.
+struct FieldReader<'a> {
+    wire_type: u8,
+    value: &'a [u8],
+}
+
+impl<'a> FieldReader<'a> {
+    fn expect(&self, wire_type: u8, expected: &'static str) -> Result<Cursor<'a>, Error> {
+        if self.wire_type == wire_type {
+            Ok(Cursor { buf: self.value })
+        } else {
+            Err(Error::InvalidType { expected })
+        }
+    }
+
+    fn read_unsigned<T>(&self, expected: &'static str) -> Result<T, Error>
+    where
+        T: TryFrom<u64>,
+    {
+        let v = self.expect(WIRE_VARINT, expected)?.read_varint()?;
+        T::try_from(v).map_err(|_| Error::InvalidValue { expected })
+    }
+
+    fn read_signed<T>(&self, expected: &'static str) -> Result<T, Error>
+    where
+        T: TryFrom<i64>,
+    {
+        let v = unzigzag(self.expect(WIRE_VARINT, expected)?.read_varint()?);
+        T::try_from(v).map_err(|_| Error::InvalidValue { expected })
+    }
+}
+
+impl<'a, S> Reader<S> for FieldReader<'a>
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn read_bool(&mut self) -> Result<bool, Self::Error> {
+        match self.read_unsigned::<u64>("a bool")? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::InvalidValue { expected: "a bool" }),
+        }
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Self::Error> {
+        self.read_signed("an i8")
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        self.read_signed("an i16")
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        self.read_signed("an i32")
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Self::Error> {
+        self.read_signed("an i64")
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        self.read_unsigned("an u8")
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        self.read_unsigned("an u16")
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        self.read_unsigned("an u32")
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Self::Error> {
+        self.read_unsigned("an u64")
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Self::Error> {
+        let bytes = self.expect(WIRE_FIXED32, "a f32")?.read_bytes(4)?;
+        Ok(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Self::Error> {
+        let bytes = self.expect(WIRE_FIXED64, "a f64")?.read_bytes(8)?;
+        let mut buf = [0; 8];
+        buf.copy_from_slice(bytes);
+        Ok(f64::from_le_bytes(buf))
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Self::Error> {
+        self.expect(WIRE_LEN, "a byte buffer")?;
+        Ok(self.value.to_vec())
+    }
+
+    fn read_string(&mut self) -> Result<String, Self::Error> {
+        self.expect(WIRE_LEN, "a string")?;
+        match str::from_utf8(self.value) {
+            Ok(s) => Ok(s.to_owned()),
+            Err(_) => Err(Error::InvalidUtf8),
+        }
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>,
+    {
+        self.expect(WIRE_LEN, "a message")?;
+        BinaryReader::read_field_seed(self.value, key, seed)
+    }
+}
+
+/// An error that can occur when encoding or decoding the binary wire format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The buffer was encoded with an unsupported format version.
+    UnsupportedVersion(u8),
+    /// The buffer ended unexpectedly.
+    UnexpectedEof,
+    /// The buffer contains bytes after the end of the message.
+    TrailingBytes,
+    /// A varint is longer than 10 bytes or overflows.
+    InvalidVarint,
+    /// A field has an unknown wire type.
+    InvalidWireType(u8),
+    /// A field has a different wire type than expected.
+    InvalidType { expected: &'static str },
+    /// A value is out of range for the expected type.
+    InvalidValue { expected: &'static str },
+    /// A string is not valid UTF-8.
+    InvalidUtf8,
+    /// The field `key` does not exist.
+    MissingField { key: String },
+    /// The tag of the field `key` collides with the field `other` in the same message.
+    DuplicateTag { key: String, other: String },
+    /// A value was written outside of a field.
+    UnexpectedValue,
+    /// More than a single value was written into a field.
+    MultipleValues,
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => write!(f, "unsupported version {}", version),
+            Self::UnexpectedEof => write!(f, "unexpected end of buffer"),
+            Self::TrailingBytes => write!(f, "trailing bytes after message"),
+            Self::InvalidVarint => write!(f, "invalid varint"),
+            Self::InvalidWireType(wire_type) => write!(f, "invalid wire type {}", wire_type),
+            Self::InvalidType { expected } => write!(f, "invalid type, expected {}", expected),
+            Self::InvalidValue { expected } => write!(f, "invalid value, expected {}", expected),
+            Self::InvalidUtf8 => write!(f, "invalid utf-8"),
+            Self::MissingField { key } => write!(f, "missing field {:?}", key),
+            Self::DuplicateTag { key, other } if key == other => {
+                write!(f, "duplicate field {:?}", key)
+            }
+            Self::DuplicateTag { key, other } => {
+                write!(f, "tag of field {:?} collides with field {:?}", key, other)
+            }
+            Self::UnexpectedValue => write!(f, "value written outside of a field"),
+            Self::MultipleValues => write!(f, "multiple values written into a field"),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl crate::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+
+    #[inline]
+    fn is_missing_field(&self) -> bool {
+        matches!(self, Self::MissingField { .. })
+    }
+}
@@ -0,0 +1,500 @@
+//! A read-through cache for any [`Store`].
+//!
+//! [`Cached`] wraps a store and keeps the results of [`get`], [`get_all`] and [`get_one`] in
+//! memory. Results are cached per [`ident`] and query, so repeating a query with the same values
+//! does not reach the inner store until the cached result is evicted:
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use datastore::cache::Cached;
+//! # use datastore::Store;
+//!
+//! # async fn run<SqliteStore: Store>() -> Result<(), SqliteStore::Error> {
+//! let store = SqliteStore::connect("sqlite://data.db").await?;
+//!
+//! let store = Cached::new(store)
+//!     .with_capacity(4096)
+//!     .with_ttl(Some(Duration::from_secs(60)))
+//!     .with_type_ttl("Session", Some(Duration::from_secs(5)));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! All cached results of an [`ident`] are invalidated when [`insert`] or [`delete`] is called
+//! with the same [`ident`]. Writes that do not go through the same `Cached` store are not
+//! noticed; use a TTL if other processes write to the inner store.
+//!
+//! Items are cached in the [`binary`] encoding and decoded on every hit, so cached types do not
+//! need to implement [`Clone`]. Results that cannot be encoded are not cached.
+//!
+//! [`get`]: Store::get
+//! [`get_all`]: Store::get_all
+//! [`get_one`]: Store::get_one
+//! [`insert`]: Store::insert
+//! [`delete`]: Store::delete
+//! [`ident`]: DataDescriptor::ident
+//! [`binary`]: crate::binary
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::binary::{self, BinaryReader, BinaryWriter};
+use crate::options::ConnectOptions;
+use crate::{Capabilities, DataDescriptor, DataQuery, Error, Store, StoreData};
+
+/// The query parameters read by [`Cached::connect_with`].
+const PARAMS: [&str; 2] = ["cache_capacity", "cache_ttl"];
+
+/// A [`Store`] caching the results of read operations of the inner store `S`.
+#[derive(Debug)]
+pub struct Cached<S> {
+    store: S,
+    capacity: usize,
+    ttl: Option<Duration>,
+    type_ttls: HashMap<String, Option<Duration>>,
+    state: Mutex<State>,
+}
+
+impl<S> Cached<S> {
+    /// Creates a new `Cached` store wrapping `store`. The cache holds up to 1024 results that do
+    /// not expire.
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            capacity: 1024,
+            ttl: None,
+            type_ttls: HashMap::new(),
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Sets the maximum number of cached results. The least recently used result is evicted
+    /// when the cache is full. A `capacity` of `0` disables the cache.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets the duration after which cached results expire, or `None` to keep results until they
+    /// are evicted or invalidated. This applies to all types without a TTL set by
+    /// [`with_type_ttl`].
+    ///
+    /// [`with_type_ttl`]: Self::with_type_ttl
+    pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the duration after which cached results of the type with the [`ident`] expire. A
+    /// `ttl` of zero disables caching for the type.
+    ///
+    /// [`ident`]: DataDescriptor::ident
+    pub fn with_type_ttl(mut self, ident: &str, ttl: Option<Duration>) -> Self {
+        self.type_ttls.insert(ident.to_owned(), ttl);
+        self
+    }
+
+    /// Returns a reference to the inner store.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.store
+    }
+
+    /// Consumes the `Cached` store, returning the inner store.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    /// Returns the number of cached results, including expired results that were not evicted
+    /// yet.
+    pub fn len(&self) -> usize {
+        self.lock().order.len()
+    }
+
+    /// Returns `true` if no results are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached results of the type with the [`ident`].
+    ///
+    /// [`ident`]: DataDescriptor::ident
+    pub fn invalidate(&self, ident: &str) {
+        self.lock().invalidate(ident);
+    }
+
+    /// Removes all cached results.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+
+        let idents: Vec<_> = state.tables.keys().cloned().collect();
+        for ident in idents {
+            state.invalidate(&ident);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn ttl(&self, ident: &str) -> Option<Duration> {
+        match self.type_ttls.get(ident) {
+            Some(ttl) => *ttl,
+            None => self.ttl,
+        }
+    }
+}
+
+impl<S> Cached<S>
+where
+    S: Store,
+{
+    /// Returns the cache key for `query` and the current generation of the type with `ident`,
+    /// or `None` if the query cannot be encoded.
+    fn key<T, Q>(&self, ident: &str, kind: Kind, query: &Q) -> Option<(Key, u64)>
+    where
+        T: StoreData<S::DataStore>,
+        Q: DataQuery<T, S::DataStore>,
+    {
+        let mut writer = BinaryWriter::new();
+        query.write(&mut writer).ok()?;
+
+        let key = Key {
+            ident: ident.to_owned(),
+            query: (kind, writer.finish()),
+        };
+
+        let generation = self.lock().generation(ident);
+        Some((key, generation))
+    }
+
+    /// Returns the cached items for `key` decoded using `descriptor`, or `None` if `key` is not
+    /// cached. Expired results and results that fail to decode are removed.
+    fn lookup<T, D>(&self, descriptor: &D, key: &Key) -> Option<Vec<T>>
+    where
+        T: StoreData<S::DataStore>,
+        D: DataDescriptor<T, S::DataStore>,
+    {
+        let mut state = self.lock();
+
+        let entry = state.tables.get(&key.ident)?.entries.get(&key.query)?;
+        let items = match entry.expires {
+            Some(expires) if expires <= Instant::now() => None,
+            _ => entry
+                .items
+                .iter()
+                .map(|buf| descriptor.read(&mut BinaryReader::new(buf)?))
+                .collect::<Result<Vec<_>, binary::Error>>()
+                .ok(),
+        };
+
+        match items {
+            Some(items) => {
+                state.touch(key);
+                Some(items)
+            }
+            None => {
+                state.remove(key);
+                None
+            }
+        }
+    }
+
+    /// Caches the `items` for `key` unless the type was invalidated after `generation`.
+    fn save<T>(&self, key: Key, generation: u64, items: &[T])
+    where
+        T: StoreData<S::DataStore>,
+    {
+        let ttl = self.ttl(&key.ident);
+        if self.capacity == 0 || ttl == Some(Duration::ZERO) {
+            return;
+        }
+
+        let items = match items
+            .iter()
+            .map(binary::to_vec::<T, S::DataStore>)
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(items) => items,
+            Err(_) => return,
+        };
+
+        let mut state = self.lock();
+        if state.generation(&key.ident) != generation {
+            return;
+        }
+
+        state.remove(&key);
+        while state.order.len() >= self.capacity {
+            state.evict();
+        }
+
+        state.tick += 1;
+        let tick = state.tick;
+
+        state.order.insert(tick, key.clone());
+        state.tables.entry(key.ident).or_default().entries.insert(
+            key.query,
+            Entry {
+                items,
+                expires: ttl.and_then(|ttl| Instant::now().checked_add(ttl)),
+                tick,
+            },
+        );
+    }
+}
+
+#[async_trait]
+impl<S> Store for Cached<S>
+where
+    S: Store,
+{
+    type DataStore = S::DataStore;
+    type Error = S::Error;
+
+    /// Connects to the inner store at `uri` and creates a `Cached` store with the default
+    /// configuration.
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        S::connect(uri).await.map(Self::new)
+    }
+
+    /// Connects to the inner store using `options` and creates a `Cached` store configured from
+    /// the query parameters `cache_capacity` and `cache_ttl`. All other options are passed on to
+    /// the inner store.
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        let capacity = options.get_as("cache_capacity").map_err(S::Error::custom)?;
+        let ttl = options.duration("cache_ttl").map_err(S::Error::custom)?;
+
+        let mut options = options.clone();
+        for key in PARAMS {
+            options.remove(key);
+        }
+
+        let mut store = Self::new(S::connect_with(&options).await?).with_ttl(ttl);
+        if let Some(capacity) = capacity {
+            store = store.with_capacity(capacity);
+        }
+
+        Ok(store)
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        self.store.create(descriptor).await
+    }
+
+    /// Deletes all items matching `query` from the inner store. All cached results of the type are
+    /// invalidated before and after the delete, even if it fails or is cancelled.
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let _guard = Invalidate::new(self, descriptor.ident().to_owned());
+        self.store.delete(descriptor, query).await
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let (key, generation) = match self.key(descriptor.ident(), Kind::Get, &query) {
+            Some(key) => key,
+            None => return self.store.get(descriptor, query).await,
+        };
+
+        if let Some(items) = self.lookup(&descriptor, &key) {
+            return Ok(items);
+        }
+
+        let items = self.store.get(descriptor, query).await?;
+        self.save(key, generation, &items);
+        Ok(items)
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let ident = descriptor.ident().to_owned();
+        let key = Key {
+            query: (Kind::GetAll, Vec::new()),
+            ident,
+        };
+        let generation = self.lock().generation(&key.ident);
+
+        if let Some(items) = self.lookup(&descriptor, &key) {
+            return Ok(items);
+        }
+
+        let items = self.store.get_all(descriptor).await?;
+        self.save(key, generation, &items);
+        Ok(items)
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let (key, generation) = match self.key(descriptor.ident(), Kind::GetOne, &query) {
+            Some(key) => key,
+            None => return self.store.get_one(descriptor, query).await,
+        };
+
+        if let Some(items) = self.lookup(&descriptor, &key) {
+            return Ok(items.into_iter().next());
+        }
+
+        let item = self.store.get_one(descriptor, query).await?;
+        self.save(key, generation, item.as_slice());
+        Ok(item)
+    }
+
+    /// Inserts `data` into the inner store. All cached results of the type are invalidated before
+    /// and after the insert, even if it fails or is cancelled.
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let _guard = Invalidate::new(self, descriptor.ident().to_owned());
+        self.store.insert(descriptor, data).await
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.store.ping().await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.store.capabilities()
+    }
+}
+
+/// Invalidates the cached results of a type when created and again when dropped.
+///
+/// Results read while a write is in progress may be outdated once it completes. Invalidating on
+/// drop also covers writes that fail or whose future is dropped, since they may still have been
+/// applied by the inner store.
+struct Invalidate<'a, S> {
+    cached: &'a Cached<S>,
+    ident: String,
+}
+
+impl<'a, S> Invalidate<'a, S> {
+    fn new(cached: &'a Cached<S>, ident: String) -> Self {
+        cached.invalidate(&ident);
+        Self { cached, ident }
+    }
+}
+
+impl<'a, S> Drop for Invalidate<'a, S> {
+    fn drop(&mut self) {
+        self.cached.invalidate(&self.ident);
+    }
+}
+
+/// The read operation a result was cached for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Kind {
+    Get,
+    GetAll,
+    GetOne,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Key {
+    ident: String,
+    /// The operation and the encoded query.
+    query: (Kind, Vec<u8>),
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// The encoded items.
+    items: Vec<Vec<u8>>,
+    expires: Option<Instant>,
+    /// The tick of the last use, used as the key in [`State::order`].
+    tick: u64,
+}
+
+/// The cached results of a single type.
+#[derive(Debug, Default)]
+struct Table {
+    /// Incremented whenever the type is invalidated. Results read before an invalidation are not
+    /// cached.
+    generation: u64,
+    entries: HashMap<(Kind, Vec<u8>), Entry>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    tables: HashMap<String, Table>,
+    /// All cached keys ordered from least to most recently used.
+    order: BTreeMap<u64, Key>,
+    tick: u64,
+}
+
+impl State {
+    fn generation(&self, ident: &str) -> u64 {
+        self.tables
+            .get(ident)
+            .map(|table| table.generation)
+            .unwrap_or_default()
+    }
+
+    /// Marks `key` as the most recently used key.
+    fn touch(&mut self, key: &Key) {
+        self.tick += 1;
+
+        if let Some(entry) = self
+            .tables
+            .get_mut(&key.ident)
+            .and_then(|table| table.entries.get_mut(&key.query))
+        {
+            let key = self.order.remove(&entry.tick).unwrap();
+            entry.tick = self.tick;
+            self.order.insert(self.tick, key);
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(entry) = self
+            .tables
+            .get_mut(&key.ident)
+            .and_then(|table| table.entries.remove(&key.query))
+        {
+            self.order.remove(&entry.tick);
+        }
+    }
+
+    /// Removes the least recently used key.
+    fn evict(&mut self) {
+        if let Some((_, key)) = self.order.pop_first() {
+            if let Some(table) = self.tables.get_mut(&key.ident) {
+                table.entries.remove(&key.query);
+            }
+        }
+    }
+
+    fn invalidate(&mut self, ident: &str) {
+        let table = self.tables.entry(ident.to_owned()).or_default();
+        table.generation += 1;
+
+        for (_, entry) in table.entries.drain() {
+            self.order.remove(&entry.tick);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Optional features of a [`Store`].
+//!
+//! [`Store::capabilities`] returns the set of features a store supports, so generic code can
+//! adapt to the store or fail early:
+//!
+//! ```
+//! use datastore::{Capabilities, Store};
+//!
+//! fn check<S: Store>(store: &S) -> Result<(), String> {
+//!     let missing = (Capabilities::WRITE | Capabilities::UPSERT).difference(store.capabilities());
+//!     if !missing.is_empty() {
+//!         return Err(format!("store does not support {:?}", missing));
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`Store`]: crate::Store
+//! [`Store::capabilities`]: crate::Store::capabilities
+use std::fmt::{self, Debug, Formatter};
+use std::ops::BitOr;
+
+/// A set of optional features supported by a [`Store`].
+///
+/// [`Store`]: crate::Store
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    bits: u32,
+}
+
+impl Capabilities {
+    /// The store accepts [`insert`], [`delete`] and [`create`].
+    ///
+    /// [`insert`]: crate::Store::insert
+    /// [`delete`]: crate::Store::delete
+    /// [`create`]: crate::Store::create
+    pub const WRITE: Self = Self { bits: 1 };
+
+    /// Every operation is applied atomically in a transaction. A failed operation leaves no
+    /// partial changes behind.
+    pub const TRANSACTIONS: Self = Self { bits: 1 << 1 };
+
+    /// [`get`] and [`get_all`] return items in the order they were inserted.
+    ///
+    /// [`get`]: crate::Store::get
+    /// [`get_all`]: crate::Store::get_all
+    pub const ORDERING: Self = Self { bits: 1 << 2 };
+
+    /// Items are read from the backend incrementally instead of being loaded at once.
+    pub const STREAMING: Self = Self { bits: 1 << 3 };
+
+    /// Inserting an item with the same primary key as an existing item replaces the existing
+    /// item.
+    pub const UPSERT: Self = Self { bits: 1 << 4 };
+
+    const NAMES: [(Self, &'static str); 5] = [
+        (Self::WRITE, "WRITE"),
+        (Self::TRANSACTIONS, "TRANSACTIONS"),
+        (Self::ORDERING, "ORDERING"),
+        (Self::STREAMING, "STREAMING"),
+        (Self::UPSERT, "UPSERT"),
+    ];
+
+    /// Returns an empty set of capabilities.
+    #[inline]
+    pub const fn empty() -> Self {
+        Self { bits: 0 }
+    }
+
+    /// Returns the set of all capabilities.
+    #[inline]
+    pub const fn all() -> Self {
+        Self { bits: (1 << 5) - 1 }
+    }
+
+    /// Returns `true` if the set contains no capabilities.
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns `true` if the set contains all capabilities in `other`.
+    #[inline]
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    /// Returns the capabilities contained in `self` or `other`.
+    #[inline]
+    pub const fn union(self, other: Self) -> Self {
+        Self {
+            bits: self.bits | other.bits,
+        }
+    }
+
+    /// Returns the capabilities contained in both `self` and `other`.
+    #[inline]
+    pub const fn intersection(self, other: Self) -> Self {
+        Self {
+            bits: self.bits & other.bits,
+        }
+    }
+
+    /// Returns the capabilities contained in `self` but not in `other`.
+    #[inline]
+    pub const fn difference(self, other: Self) -> Self {
+        Self {
+            bits: self.bits & !other.bits,
+        }
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Self;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl Debug for Capabilities {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Capabilities(")?;
+
+        let mut names = Self::NAMES
+            .iter()
+            .filter(|(capability, _)| self.contains(*capability))
+            .map(|(_, name)| name);
+
+        if let Some(name) = names.next() {
+            f.write_str(name)?;
+        }
+
+        for name in names {
+            write!(f, " | {}", name)?;
+        }
+
+        f.write_str(")")
+    }
+}
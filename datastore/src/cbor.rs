@@ -0,0 +1,647 @@
+//! CBOR encoding for [`StoreData`] types.
+//!
+//! This module requires the `cbor` feature.
+//!
+//! [`CborWriter`] encodes a [`StoreData`] type as a CBOR ([RFC 8949]) map keyed by field name
+//! and [`CborReader`] decodes it back:
+//!
+//! ```
+//! # use datastore::{Store, StoreData};
+//! # fn roundtrip<T, S>(data: &T) -> T
+//! # where
+//! #     T: StoreData<S>,
+//! #     S: Store,
+//! # {
+//! let buf = datastore::cbor::to_vec(data).unwrap();
+//! datastore::cbor::from_slice(&buf).unwrap()
+//! # }
+//! ```
+//!
+//! Every `write_*` call maps to the corresponding major type: `bool` to the simple values
+//! `false`/`true`, unsigned integers to major type 0, negative integers to major type 1, `f32`
+//! and `f64` to single and double precision floats, bytes to major type 2 and strings to major
+//! type 3. All lengths and integer arguments use the shortest encoding. Fields become the
+//! entries of a definite-length map (major type 5) with text string keys. Fields whose value
+//! writes nothing are omitted.
+//!
+//! The reader accepts any well-formed CBOR item, including indefinite-length items, half
+//! precision floats and tagged values (the tag is ignored). Fields are looked up by key
+//! regardless of their order.
+//!
+//! [RFC 8949]: https://www.rfc-editor.org/rfc/rfc8949
+//! [`StoreData`]: crate::StoreData
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+
+use crate::{ReadSeed, Reader, Store, StoreData, Write, Writer};
+
+/// The maximum nesting depth accepted by the reader.
+const MAX_DEPTH: usize = 128;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const MAJOR_SIMPLE: u8 = 7;
+
+/// Serializes the [`StoreData`] `data` into a new buffer.
+pub fn to_vec<T, S>(data: &T) -> Result<Vec<u8>, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    let mut writer = CborWriter::new();
+    data.write(&mut writer)?;
+    Ok(writer.finish())
+}
+
+/// Deserializes a new [`StoreData`] from the buffer `buf`.
+pub fn from_slice<T, S>(buf: &[u8]) -> Result<T, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    T::read(&mut CborReader::new(buf)?)
+}
+
+/// Writes the head of an item with the `major` type and the argument `v`.
+fn write_head(buf: &mut Vec<u8>, major: u8, v: u64) {
+    let major = major << 5;
+
+    if v < 24 {
+        buf.push(major | v as u8);
+    } else if let Ok(v) = u8::try_from(v) {
+        buf.extend_from_slice(&[major | 24, v]);
+    } else if let Ok(v) = u16::try_from(v) {
+        buf.push(major | 25);
+        buf.extend_from_slice(&v.to_be_bytes());
+    } else if let Ok(v) = u32::try_from(v) {
+        buf.push(major | 26);
+        buf.extend_from_slice(&v.to_be_bytes());
+    } else {
+        buf.push(major | 27);
+        buf.extend_from_slice(&v.to_be_bytes());
+    }
+}
+
+fn write_signed(buf: &mut Vec<u8>, v: i64) {
+    if v < 0 {
+        // -1 - v never overflows for negative v.
+        write_head(buf, MAJOR_NEGATIVE, (-1 - v) as u64);
+    } else {
+        write_head(buf, MAJOR_UNSIGNED, v as u64);
+    }
+}
+
+/// A [`Writer`] encoding CBOR.
+///
+/// See the [module documentation] for the format.
+///
+/// [module documentation]: self
+#[derive(Clone, Debug, Default)]
+pub struct CborWriter {
+    buf: Vec<u8>,
+    state: State,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+enum State {
+    #[default]
+    Empty,
+    /// A map with the given number of entries.
+    Map(u64),
+    Value,
+}
+
+impl CborWriter {
+    /// Creates a new, empty `CborWriter`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Completes the item, returning the encoded buffer. If nothing was written an empty map is
+    /// returned.
+    pub fn finish(self) -> Vec<u8> {
+        self.finish_value().unwrap_or_else(|| vec![MAJOR_MAP << 5])
+    }
+
+    /// Completes a field value. Returns `None` if nothing was written.
+    fn finish_value(self) -> Option<Vec<u8>> {
+        match self.state {
+            State::Empty => None,
+            State::Map(len) => {
+                let mut buf = Vec::with_capacity(self.buf.len() + 9);
+                write_head(&mut buf, MAJOR_MAP, len);
+                buf.extend_from_slice(&self.buf);
+                Some(buf)
+            }
+            State::Value => Some(self.buf),
+        }
+    }
+
+    fn write_value<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Vec<u8>),
+    {
+        match self.state {
+            State::Empty => {
+                f(&mut self.buf);
+                self.state = State::Value;
+                Ok(())
+            }
+            _ => Err(Error::MultipleValues),
+        }
+    }
+}
+
+impl<S> Writer<S> for CborWriter
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self, v: bool) -> Result<(), Self::Error> {
+        self.write_value(|buf| buf.push(if v { 0xf5 } else { 0xf4 }))
+    }
+
+    fn write_i8(&mut self, v: i8) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_signed(buf, i64::from(v)))
+    }
+
+    fn write_i16(&mut self, v: i16) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_signed(buf, i64::from(v)))
+    }
+
+    fn write_i32(&mut self, v: i32) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_signed(buf, i64::from(v)))
+    }
+
+    fn write_i64(&mut self, v: i64) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_signed(buf, v))
+    }
+
+    fn write_u8(&mut self, v: u8) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_head(buf, MAJOR_UNSIGNED, u64::from(v)))
+    }
+
+    fn write_u16(&mut self, v: u16) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_head(buf, MAJOR_UNSIGNED, u64::from(v)))
+    }
+
+    fn write_u32(&mut self, v: u32) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_head(buf, MAJOR_UNSIGNED, u64::from(v)))
+    }
+
+    fn write_u64(&mut self, v: u64) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_head(buf, MAJOR_UNSIGNED, v))
+    }
+
+    fn write_f32(&mut self, v: f32) -> Result<(), Self::Error> {
+        self.write_value(|buf| {
+            buf.push(MAJOR_SIMPLE << 5 | 26);
+            buf.extend_from_slice(&v.to_be_bytes());
+        })
+    }
+
+    fn write_f64(&mut self, v: f64) -> Result<(), Self::Error> {
+        self.write_value(|buf| {
+            buf.push(MAJOR_SIMPLE << 5 | 27);
+            buf.extend_from_slice(&v.to_be_bytes());
+        })
+    }
+
+    fn write_bytes(&mut self, v: &[u8]) -> Result<(), Self::Error> {
+        self.write_value(|buf| {
+            write_head(buf, MAJOR_BYTES, v.len() as u64);
+            buf.extend_from_slice(v);
+        })
+    }
+
+    fn write_str(&mut self, v: &str) -> Result<(), Self::Error> {
+        self.write_value(|buf| {
+            write_head(buf, MAJOR_TEXT, v.len() as u64);
+            buf.extend_from_slice(v.as_bytes());
+        })
+    }
+
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<S>,
+    {
+        let len = match self.state {
+            State::Empty => 0,
+            State::Map(len) => len,
+            State::Value => return Err(Error::MultipleValues),
+        };
+
+        let mut writer = CborWriter::new();
+        value.write(&mut writer)?;
+
+        if let Some(value) = writer.finish_value() {
+            write_head(&mut self.buf, MAJOR_TEXT, key.len() as u64);
+            self.buf.extend_from_slice(key.as_bytes());
+            self.buf.extend_from_slice(&value);
+            self.state = State::Map(len + 1);
+        } else {
+            self.state = State::Map(len);
+        }
+
+        Ok(())
+    }
+}
+
+/// A decoded CBOR item.
+#[derive(Clone, Debug)]
+enum Item {
+    Null,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Bytes(Vec<u8>),
+    Text(String),
+    /// An array. Arrays are never read, therefore their elements are discarded.
+    Array,
+    Map(Vec<(Item, Item)>),
+}
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn malformed(&self) -> Error {
+        Error::Malformed { offset: self.pos }
+    }
+
+    fn read_bytes(&mut self, len: u64) -> Result<&'a [u8], Error> {
+        let len = usize::try_from(len).map_err(|_| Error::UnexpectedEof)?;
+        if self.buf.len() - self.pos < len {
+            return Err(Error::UnexpectedEof);
+        }
+
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_bytes(N as u64)?);
+        Ok(array)
+    }
+
+    /// Reads the head of an item, returning the major type and the argument. The argument is
+    /// `None` for indefinite-length items.
+    fn read_head(&mut self) -> Result<(u8, u8, Option<u64>), Error> {
+        let b = self.read_array::<1>()?[0];
+        let (major, info) = (b >> 5, b & 0x1f);
+
+        let arg = match info {
+            0..=23 => Some(u64::from(info)),
+            24 => Some(u64::from(self.read_array::<1>()?[0])),
+            25 => Some(u64::from(u16::from_be_bytes(self.read_array()?))),
+            26 => Some(u64::from(u32::from_be_bytes(self.read_array()?))),
+            27 => Some(u64::from_be_bytes(self.read_array()?)),
+            31 => None,
+            _ => {
+                self.pos -= 1;
+                return Err(self.malformed());
+            }
+        };
+
+        Ok((major, info, arg))
+    }
+
+    /// Returns `true` and consumes the byte if the next byte is the "break" stop code.
+    fn read_break(&mut self) -> bool {
+        if self.buf.get(self.pos) == Some(&0xff) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn read_item(&mut self) -> Result<Item, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::DepthLimitExceeded);
+        }
+
+        self.depth += 1;
+        let item = self.read_item_inner();
+        self.depth -= 1;
+        item
+    }
+
+    fn read_item_inner(&mut self) -> Result<Item, Error> {
+        let start = self.pos;
+        let (major, info, arg) = self.read_head()?;
+
+        match (major, arg) {
+            (MAJOR_UNSIGNED, Some(v)) => Ok(Item::Int(i128::from(v))),
+            (MAJOR_NEGATIVE, Some(v)) => Ok(Item::Int(-1 - i128::from(v))),
+            (MAJOR_BYTES, _) => self.read_string(major, arg).map(Item::Bytes),
+            (MAJOR_TEXT, _) => {
+                let bytes = self.read_string(major, arg)?;
+                String::from_utf8(bytes)
+                    .map(Item::Text)
+                    .map_err(|_| Error::InvalidUtf8)
+            }
+            (MAJOR_ARRAY, Some(len)) => {
+                for _ in 0..len {
+                    self.read_item()?;
+                }
+                Ok(Item::Array)
+            }
+            (MAJOR_ARRAY, None) => {
+                while !self.read_break() {
+                    self.read_item()?;
+                }
+                Ok(Item::Array)
+            }
+            (MAJOR_MAP, Some(len)) => {
+                // Every entry takes at least 2 bytes.
+                let capacity = len.min((self.buf.len() - self.pos) as u64 / 2);
+                let mut map = Vec::with_capacity(capacity as usize);
+                for _ in 0..len {
+                    map.push((self.read_item()?, self.read_item()?));
+                }
+                Ok(Item::Map(map))
+            }
+            (MAJOR_MAP, None) => {
+                let mut map = Vec::new();
+                while !self.read_break() {
+                    map.push((self.read_item()?, self.read_item()?));
+                }
+                Ok(Item::Map(map))
+            }
+            (MAJOR_TAG, Some(_)) => self.read_item(),
+            (MAJOR_SIMPLE, _) => match info {
+                20 => Ok(Item::Bool(false)),
+                21 => Ok(Item::Bool(true)),
+                22 | 23 => Ok(Item::Null),
+                25 => Ok(Item::Float(f16_to_f64(arg.unwrap() as u16))),
+                26 => Ok(Item::Float(f64::from(f32::from_bits(arg.unwrap() as u32)))),
+                27 => Ok(Item::Float(f64::from_bits(arg.unwrap()))),
+                _ => {
+                    self.pos = start;
+                    Err(self.malformed())
+                }
+            },
+            _ => {
+                self.pos = start;
+                Err(self.malformed())
+            }
+        }
+    }
+
+    /// Reads the contents of a byte or text string with the `major` type.
+    fn read_string(&mut self, major: u8, len: Option<u64>) -> Result<Vec<u8>, Error> {
+        match len {
+            Some(len) => self.read_bytes(len).map(<[u8]>::to_vec),
+            None => {
+                // Indefinite-length strings consist of definite-length chunks of the same major
+                // type.
+                let mut bytes = Vec::new();
+                while !self.read_break() {
+                    let start = self.pos;
+                    match self.read_head()? {
+                        (m, _, Some(len)) if m == major => {
+                            bytes.extend_from_slice(self.read_bytes(len)?)
+                        }
+                        _ => {
+                            self.pos = start;
+                            return Err(self.malformed());
+                        }
+                    }
+                }
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+/// Converts an IEEE 754 half precision float to a `f64`.
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = i32::from((bits >> 10) & 0x1f);
+    let mant = f64::from(bits & 0x3ff);
+
+    sign * match exp {
+        0 => mant * 2f64.powi(-24),
+        31 if mant == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1024.0 + mant) * 2f64.powi(exp - 25),
+    }
+}
+
+/// A [`Reader`] decoding CBOR.
+///
+/// The input is decoded when the `CborReader` is created. Fields are looked up by key regardless
+/// of their order.
+#[derive(Clone, Debug)]
+pub struct CborReader {
+    item: Item,
+}
+
+impl CborReader {
+    /// Creates a new `CborReader` by decoding a single item from `buf`.
+    ///
+    /// Returns an error if `buf` is not well-formed or contains bytes after the item.
+    pub fn new(buf: &[u8]) -> Result<Self, Error> {
+        let mut decoder = Decoder {
+            buf,
+            pos: 0,
+            depth: 0,
+        };
+
+        let item = decoder.read_item()?;
+        if decoder.pos != buf.len() {
+            return Err(Error::TrailingBytes);
+        }
+
+        Ok(Self { item })
+    }
+
+    fn take(&mut self) -> Item {
+        mem::replace(&mut self.item, Item::Null)
+    }
+
+    fn read_int<T>(&mut self, expected: &'static str) -> Result<T, Error>
+    where
+        T: TryFrom<i128>,
+    {
+        match self.take() {
+            Item::Int(v) => T::try_from(v).map_err(|_| Error::InvalidValue { expected }),
+            _ => Err(Error::InvalidType { expected }),
+        }
+    }
+
+    fn read_float(&mut self, expected: &'static str) -> Result<f64, Error> {
+        match self.take() {
+            Item::Float(v) => Ok(v),
+            _ => Err(Error::InvalidType { expected }),
+        }
+    }
+}
+
+impl<S> Reader<S> for CborReader
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn read_bool(&mut self) -> Result<bool, Self::Error> {
+        match self.take() {
+            Item::Bool(v) => Ok(v),
+            _ => Err(Error::InvalidType { expected: "a bool" }),
+        }
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Self::Error> {
+        self.read_int("an i8")
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        self.read_int("an i16")
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        self.read_int("an i32")
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Self::Error> {
+        self.read_int("an i64")
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        self.read_int("an u8")
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        self.read_int("an u16")
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        self.read_int("an u32")
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Self::Error> {
+        self.read_int("an u64")
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Self::Error> {
+        self.read_float("a f32").map(|v| v as f32)
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Self::Error> {
+        self.read_float("a f64")
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Self::Error> {
+        match self.take() {
+            Item::Bytes(v) => Ok(v),
+            _ => Err(Error::InvalidType {
+                expected: "a byte string",
+            }),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, Self::Error> {
+        match self.take() {
+            Item::Text(v) => Ok(v),
+            _ => Err(Error::InvalidType {
+                expected: "a text string",
+            }),
+        }
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>,
+    {
+        let map = match &mut self.item {
+            Item::Map(map) => map,
+            _ => return Err(Error::InvalidType { expected: "a map" }),
+        };
+
+        let index = map
+            .iter()
+            .position(|(k, _)| matches!(k, Item::Text(k) if k == key))
+            .ok_or(Error::MissingField {
+                key: key.to_owned(),
+            })?;
+
+        let (_, item) = map.swap_remove(index);
+        seed.read(&mut CborReader { item })
+    }
+}
+
+/// An error that can occur when encoding or decoding CBOR.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The buffer ended unexpectedly.
+    UnexpectedEof,
+    /// The buffer contains bytes after the end of the item.
+    TrailingBytes,
+    /// The buffer is not well-formed CBOR.
+    Malformed {
+        /// The byte offset of the malformed item.
+        offset: usize,
+    },
+    /// The buffer contains too deeply nested items.
+    DepthLimitExceeded,
+    /// An item has a different type than expected.
+    InvalidType { expected: &'static str },
+    /// An item is out of range for the expected type.
+    InvalidValue { expected: &'static str },
+    /// A text string is not valid UTF-8.
+    InvalidUtf8,
+    /// The field `key` does not exist.
+    MissingField { key: String },
+    /// More than a single value was written.
+    MultipleValues,
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "unexpected end of buffer"),
+            Self::TrailingBytes => write!(f, "trailing bytes after item"),
+            Self::Malformed { offset } => write!(f, "malformed item at offset {}", offset),
+            Self::DepthLimitExceeded => write!(f, "depth limit exceeded"),
+            Self::InvalidType { expected } => write!(f, "invalid type, expected {}", expected),
+            Self::InvalidValue { expected } => write!(f, "invalid value, expected {}", expected),
+            Self::InvalidUtf8 => write!(f, "invalid utf-8"),
+            Self::MissingField { key } => write!(f, "missing field {:?}", key),
+            Self::MultipleValues => write!(f, "multiple values written"),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl crate::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+
+    #[inline]
+    fn is_missing_field(&self) -> bool {
+        matches!(self, Self::MissingField { .. })
+    }
+}
@@ -0,0 +1,882 @@
+//! CSV encoding for [`StoreData`] types.
+//!
+//! [`CsvWriter`] writes one row per [`StoreData`] item below a header derived from the fields of
+//! a [`DataDescriptor`]. [`CsvReader`] reads the rows back, looking up every field by the column
+//! with the same name in the header:
+//!
+//! ```
+//! # use datastore::{DataDescriptor, Store, StoreData};
+//! use datastore::csv::{CsvReader, CsvWriter};
+//!
+//! # fn roundtrip<T, S, D>(descriptor: &D, items: &[T]) -> Vec<T>
+//! # where
+//! #     T: StoreData<S>,
+//! #     S: Store,
+//! #     D: DataDescriptor<T, S>,
+//! # {
+//! let mut writer = CsvWriter::new(Vec::new(), descriptor).unwrap();
+//! for item in items {
+//!     writer.write_row(item).unwrap();
+//! }
+//! let buf = writer.into_inner();
+//!
+//! let mut reader = CsvReader::new(&buf[..]).unwrap();
+//! let mut output = Vec::new();
+//! while let Some(item) = reader.read_row(descriptor).unwrap() {
+//!     output.push(item);
+//! }
+//! # output
+//! # }
+//! ```
+//!
+//! # Format
+//!
+//! The format follows RFC 4180. Cells are separated by `,` and rows are terminated by `\r\n`.
+//! Cells containing `,`, `"`, `\r` or `\n` are quoted, quotes inside of quoted cells are doubled.
+//! When reading, rows may also be terminated by `\n` and empty lines are skipped.
+//!
+//! Nested fields become separate columns, named by joining the keys of all enclosing fields with
+//! a `.`. Booleans are written as `true` or `false`, numbers in their decimal representation and
+//! bytes as a base64 string using the standard alphabet with padding. Fields that write nothing
+//! are left empty. The columns of the header may appear in any order and columns that are not
+//! read are ignored.
+//!
+//! # Bulk operations
+//!
+//! [`load`] inserts all rows of a CSV file into a [`Store`] and [`export`] writes all items
+//! returned by [`Store::get_all`] as CSV.
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufRead};
+use std::mem;
+use std::str::FromStr;
+
+use crate::json::{base64_decode, base64_encode};
+use crate::{
+    DataDescriptor, FieldAttributes, ReadSeed, Reader, Store, StoreData, TypeWriter, Write,
+    WriteTypeSeed, Writer,
+};
+
+/// A [`Writer`] writing rows of CSV into an [`io::Write`].
+///
+/// See the [module documentation] for the format.
+///
+/// [module documentation]: self
+#[derive(Debug)]
+pub struct CsvWriter<W> {
+    writer: W,
+    header: Vec<String>,
+    row: Vec<Option<String>>,
+    path: String,
+}
+
+impl<W> CsvWriter<W>
+where
+    W: io::Write,
+{
+    /// Creates a new `CsvWriter` and writes the header containing all fields of the
+    /// [`DataDescriptor`] `descriptor`.
+    pub fn new<T, S, D>(writer: W, descriptor: &D) -> Result<Self, Error>
+    where
+        T: StoreData<S>,
+        S: Store,
+        D: DataDescriptor<T, S>,
+    {
+        let mut header = HeaderWriter {
+            columns: Vec::new(),
+            path: String::new(),
+        };
+        descriptor.write(&mut header)?;
+
+        let mut this = Self {
+            writer,
+            row: vec![None; header.columns.len()],
+            header: header.columns,
+            path: String::new(),
+        };
+
+        let cells = this.header.clone();
+        this.write_line(cells.iter().map(|cell| cell.as_str()))?;
+        Ok(this)
+    }
+
+    /// Returns the columns of the header.
+    #[inline]
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    /// Writes `data` as a new row.
+    pub fn write_row<T, S>(&mut self, data: &T) -> Result<(), Error>
+    where
+        T: StoreData<S>,
+        S: Store,
+    {
+        if let Err(err) = data.write(self) {
+            self.row.iter_mut().for_each(|cell| *cell = None);
+            return Err(err);
+        }
+
+        self.end_row()
+    }
+
+    /// Terminates the current row, consisting of all fields written since the last row. Fields
+    /// that were not written are left empty.
+    pub fn end_row(&mut self) -> Result<(), Error> {
+        let row = mem::replace(&mut self.row, vec![None; self.header.len()]);
+        self.write_line(row.iter().map(|cell| cell.as_deref().unwrap_or("")))
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns the underlying writer.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_line<'a, I>(&mut self, cells: I) -> Result<(), Error>
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let mut buf = String::new();
+        let mut len = 0;
+        for (index, cell) in cells.enumerate() {
+            if index != 0 {
+                buf.push(',');
+            }
+
+            if cell.contains([',', '"', '\r', '\n']) {
+                buf.push('"');
+                buf.push_str(&cell.replace('"', "\"\""));
+                buf.push('"');
+            } else {
+                buf.push_str(cell);
+            }
+
+            len += 1;
+        }
+
+        // A single empty cell is quoted, since empty lines are skipped when reading.
+        if len == 1 && buf.is_empty() {
+            buf.push_str("\"\"");
+        }
+        buf.push_str("\r\n");
+
+        self.writer.write_all(buf.as_bytes())?;
+        Ok(())
+    }
+
+    fn write_cell(&mut self, value: String) -> Result<(), Error> {
+        if self.path.is_empty() {
+            return Err(Error::UnexpectedValue);
+        }
+
+        let index = match self.header.iter().position(|column| *column == self.path) {
+            Some(index) => index,
+            None => {
+                return Err(Error::UnknownField {
+                    key: self.path.clone(),
+                })
+            }
+        };
+
+        match self.row[index] {
+            Some(_) => Err(Error::MultipleValues),
+            None => {
+                self.row[index] = Some(value);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<W, S> Writer<S> for CsvWriter<W>
+where
+    W: io::Write,
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self, v: bool) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_i8(&mut self, v: i8) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_i16(&mut self, v: i16) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_i32(&mut self, v: i32) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_i64(&mut self, v: i64) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_u8(&mut self, v: u8) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_u16(&mut self, v: u16) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_u32(&mut self, v: u32) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_u64(&mut self, v: u64) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_f32(&mut self, v: f32) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_f64(&mut self, v: f64) -> Result<(), Self::Error> {
+        self.write_cell(v.to_string())
+    }
+
+    fn write_bytes(&mut self, v: &[u8]) -> Result<(), Self::Error> {
+        let mut buf = String::new();
+        base64_encode(&mut buf, v);
+        self.write_cell(buf)
+    }
+
+    fn write_str(&mut self, v: &str) -> Result<(), Self::Error> {
+        self.write_cell(v.to_owned())
+    }
+
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<S>,
+    {
+        let len = push_key(&mut self.path, key);
+        let res = value.write(self);
+        self.path.truncate(len);
+        res
+    }
+}
+
+/// The [`TypeWriter`] collecting the columns of the header.
+struct HeaderWriter {
+    columns: Vec<String>,
+    path: String,
+}
+
+impl HeaderWriter {
+    fn write_type(&mut self) -> Result<(), Error> {
+        if self.path.is_empty() {
+            return Err(Error::UnexpectedValue);
+        }
+
+        if self.columns.contains(&self.path) {
+            return Err(Error::DuplicateColumn {
+                key: self.path.clone(),
+            });
+        }
+
+        self.columns.push(self.path.clone());
+        Ok(())
+    }
+}
+
+impl<S> TypeWriter<S> for HeaderWriter
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_i8(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_i16(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_i32(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_i64(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_u8(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_u16(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_u32(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_u64(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_f32(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_f64(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_bytes(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_str(&mut self) -> Result<(), Self::Error> {
+        self.write_type()
+    }
+
+    fn write_field_seed<T>(
+        &mut self,
+        key: &str,
+        seed: T,
+        _attrs: FieldAttributes,
+    ) -> Result<(), Self::Error>
+    where
+        T: WriteTypeSeed<S>,
+    {
+        let len = push_key(&mut self.path, key);
+        let res = seed.write_type(self);
+        self.path.truncate(len);
+        res
+    }
+}
+
+/// A [`Reader`] reading rows of CSV from an [`io::BufRead`].
+///
+/// The `CsvReader` reads the fields of the current row, which is advanced by
+/// [`next_row`](Self::next_row). See the [module documentation] for the format.
+///
+/// [module documentation]: self
+#[derive(Debug)]
+pub struct CsvReader<R> {
+    reader: R,
+    header: Vec<String>,
+    row: Vec<String>,
+    /// The number of lines read.
+    lines: usize,
+    /// The line the current row starts at.
+    line: usize,
+    path: String,
+}
+
+impl<R> CsvReader<R>
+where
+    R: BufRead,
+{
+    /// Creates a new `CsvReader`, reading the header from the first row of `reader`.
+    pub fn new(reader: R) -> Result<Self, Error> {
+        let mut this = Self {
+            reader,
+            header: Vec::new(),
+            row: Vec::new(),
+            lines: 0,
+            line: 0,
+            path: String::new(),
+        };
+
+        let mut header = this.read_record()?.ok_or(Error::MissingHeader)?;
+
+        // Spreadsheet applications commonly prefix the file with a byte order mark.
+        if let Some(column) = header.first_mut() {
+            if let Some(name) = column.strip_prefix('\u{feff}') {
+                *column = name.to_owned();
+            }
+        }
+
+        for (index, column) in header.iter().enumerate() {
+            if header[..index].contains(column) {
+                return Err(Error::DuplicateColumn {
+                    key: column.clone(),
+                });
+            }
+        }
+
+        this.header = header;
+        Ok(this)
+    }
+
+    /// Returns the columns of the header.
+    #[inline]
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    /// Returns the line number the current row starts at. The header is on line 1.
+    #[inline]
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Advances to the next row. Returns `false` if the end of the input was reached.
+    pub fn next_row(&mut self) -> Result<bool, Error> {
+        match self.read_record()? {
+            Some(row) => {
+                if row.len() != self.header.len() {
+                    return Err(Error::RowLength {
+                        line: self.line,
+                        expected: self.header.len(),
+                        found: row.len(),
+                    });
+                }
+
+                self.row = row;
+                Ok(true)
+            }
+            None => {
+                self.row.clear();
+                Ok(false)
+            }
+        }
+    }
+
+    /// Advances to the next row and reads it using the [`DataDescriptor`] `descriptor`. Returns
+    /// `None` if the end of the input was reached.
+    pub fn read_row<T, S, D>(&mut self, descriptor: &D) -> Result<Option<T>, Error>
+    where
+        T: StoreData<S>,
+        S: Store,
+        D: DataDescriptor<T, S>,
+    {
+        if !self.next_row()? {
+            return Ok(None);
+        }
+
+        descriptor.read(self).map(Some)
+    }
+
+    /// Reads the next non-empty record. Returns `None` at the end of the input.
+    fn read_record(&mut self) -> Result<Option<Vec<String>>, Error> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            self.lines += 1;
+
+            if !line.trim_end_matches(['\r', '\n']).is_empty() {
+                break;
+            }
+        }
+
+        self.line = self.lines;
+
+        let mut cells = Vec::new();
+        let mut cell = Vec::new();
+        let mut pos = 0;
+        let mut quoted = false;
+        let mut at_start = true;
+        let mut after_quote = false;
+
+        loop {
+            let bytes = line.as_bytes();
+            if pos == bytes.len() {
+                if !quoted {
+                    break;
+                }
+
+                // A quoted cell continues on the next line.
+                if self.reader.read_line(&mut line)? == 0 {
+                    return Err(Error::Syntax {
+                        line: self.line,
+                        expected: "a closing quote",
+                    });
+                }
+                self.lines += 1;
+                continue;
+            }
+
+            let b = bytes[pos];
+            pos += 1;
+
+            if quoted {
+                if b == b'"' {
+                    if bytes.get(pos) == Some(&b'"') {
+                        cell.push(b'"');
+                        pos += 1;
+                    } else {
+                        quoted = false;
+                        after_quote = true;
+                    }
+                } else {
+                    cell.push(b);
+                }
+
+                continue;
+            }
+
+            match b {
+                b',' => {
+                    cells.push(mem::take(&mut cell));
+                    at_start = true;
+                    after_quote = false;
+                }
+                b'\n' => break,
+                b'\r' if matches!(bytes.get(pos), None | Some(b'\n')) => break,
+                _ if after_quote => {
+                    return Err(Error::Syntax {
+                        line: self.lines,
+                        expected: "a delimiter after a closing quote",
+                    })
+                }
+                b'"' if at_start => {
+                    quoted = true;
+                    at_start = false;
+                }
+                _ => {
+                    cell.push(b);
+                    at_start = false;
+                }
+            }
+        }
+
+        cells.push(cell);
+
+        // All cells are split at ASCII characters of a valid string, which keeps them valid.
+        Ok(Some(
+            cells
+                .into_iter()
+                .map(|cell| String::from_utf8_lossy(&cell).into_owned())
+                .collect(),
+        ))
+    }
+
+    fn cell(&self) -> Result<&str, Error> {
+        if self.path.is_empty() {
+            return Err(Error::InvalidType {
+                expected: "a field",
+            });
+        }
+
+        match self.header.iter().position(|column| *column == self.path) {
+            Some(index) => match self.row.get(index) {
+                Some(cell) => Ok(cell),
+                None => Err(Error::MissingRow),
+            },
+            None => Err(Error::MissingField {
+                key: self.path.clone(),
+            }),
+        }
+    }
+
+    fn parse<T>(&self, expected: &'static str) -> Result<T, Error>
+    where
+        T: FromStr,
+    {
+        self.cell()?
+            .trim()
+            .parse()
+            .map_err(|_| Error::InvalidValue { expected })
+    }
+}
+
+impl<R, S> Reader<S> for CsvReader<R>
+where
+    R: BufRead,
+    S: Store,
+{
+    type Error = Error;
+
+    fn read_bool(&mut self) -> Result<bool, Self::Error> {
+        self.parse("a bool")
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Self::Error> {
+        self.parse("an i8")
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        self.parse("an i16")
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        self.parse("an i32")
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Self::Error> {
+        self.parse("an i64")
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        self.parse("an u8")
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        self.parse("an u16")
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        self.parse("an u32")
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Self::Error> {
+        self.parse("an u64")
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Self::Error> {
+        self.parse("a f32")
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Self::Error> {
+        self.parse("a f64")
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Self::Error> {
+        base64_decode(self.cell()?).ok_or(Error::InvalidBase64)
+    }
+
+    fn read_string(&mut self) -> Result<String, Self::Error> {
+        self.cell().map(|cell| cell.to_owned())
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>,
+    {
+        let len = push_key(&mut self.path, key);
+        let res = seed.read(self);
+        self.path.truncate(len);
+        res
+    }
+}
+
+/// Appends `key` to the column `path`. Returns the previous length of `path`.
+fn push_key(path: &mut String, key: &str) -> usize {
+    let len = path.len();
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(key);
+    len
+}
+
+/// Inserts all rows read from `reader` into the `store`. Returns the number of inserted items.
+///
+/// Items are inserted one at a time in the order of the rows. If an error occurs, all rows before
+/// the failing row remain inserted.
+pub async fn load<S, T, D, R>(
+    store: &S,
+    descriptor: D,
+    reader: R,
+) -> Result<usize, BulkError<S::Error>>
+where
+    S: Store,
+    T: StoreData<S::DataStore> + Send + Sync + 'static,
+    D: DataDescriptor<T, S::DataStore> + Clone + Send,
+    R: BufRead,
+{
+    let mut reader = CsvReader::new(reader).map_err(|error| BulkError::Csv { line: 1, error })?;
+
+    let mut count = 0;
+    loop {
+        let item = match reader.read_row(&descriptor) {
+            Ok(Some(item)) => item,
+            Ok(None) => return Ok(count),
+            Err(error) => {
+                return Err(BulkError::Csv {
+                    line: reader.line(),
+                    error,
+                })
+            }
+        };
+
+        store
+            .insert(descriptor.clone(), item)
+            .await
+            .map_err(BulkError::Store)?;
+        count += 1;
+    }
+}
+
+/// Writes all items returned by [`Store::get_all`] as CSV into `writer`. Returns the number of
+/// written rows, not including the header.
+pub async fn export<S, T, D, W>(
+    store: &S,
+    descriptor: D,
+    writer: W,
+) -> Result<usize, BulkError<S::Error>>
+where
+    S: Store,
+    T: StoreData<S::DataStore> + Send + Sync + 'static,
+    D: DataDescriptor<T, S::DataStore> + Send + Sync,
+    W: io::Write,
+{
+    let mut writer =
+        CsvWriter::new(writer, &descriptor).map_err(|error| BulkError::Csv { line: 1, error })?;
+
+    let items = store.get_all(descriptor).await.map_err(BulkError::Store)?;
+    for (index, item) in items.iter().enumerate() {
+        writer.write_row(item).map_err(|error| BulkError::Csv {
+            line: index + 2,
+            error,
+        })?;
+    }
+
+    writer.flush().map_err(|error| BulkError::Csv {
+        line: items.len() + 1,
+        error,
+    })?;
+
+    Ok(items.len())
+}
+
+/// An error that can occur when writing or reading CSV.
+#[derive(Debug)]
+pub enum Error {
+    /// An I/O error.
+    Io(io::Error),
+    /// The input is not valid CSV.
+    Syntax {
+        /// The line of the error in the input.
+        line: usize,
+        expected: &'static str,
+    },
+    /// The input is empty.
+    MissingHeader,
+    /// The header contains the column `key` more than once.
+    DuplicateColumn { key: String },
+    /// A row has a different number of cells than the header.
+    RowLength {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A field was read before a row was read.
+    MissingRow,
+    /// The header contains no column for the field `key`.
+    MissingField { key: String },
+    /// A field `key` was written that is not in the header.
+    UnknownField { key: String },
+    /// A value was read outside of a field.
+    InvalidType { expected: &'static str },
+    /// A cell cannot be parsed as the expected type.
+    InvalidValue { expected: &'static str },
+    /// A cell is not valid base64.
+    InvalidBase64,
+    /// A value was written outside of a field.
+    UnexpectedValue,
+    /// A field wrote more than a single value.
+    MultipleValues,
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => Display::fmt(err, f),
+            Self::Syntax { line, expected } => {
+                write!(f, "syntax error at line {}: expected {}", line, expected)
+            }
+            Self::MissingHeader => write!(f, "missing header"),
+            Self::DuplicateColumn { key } => write!(f, "duplicate column {:?}", key),
+            Self::RowLength {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row at line {} has {} cells, expected {}",
+                line, found, expected
+            ),
+            Self::MissingRow => write!(f, "no row was read"),
+            Self::MissingField { key } => write!(f, "missing column {:?}", key),
+            Self::UnknownField { key } => write!(f, "unknown column {:?}", key),
+            Self::InvalidType { expected } => write!(f, "invalid type: expected {}", expected),
+            Self::InvalidValue { expected } => write!(f, "invalid value: expected {}", expected),
+            Self::InvalidBase64 => write!(f, "invalid base64"),
+            Self::UnexpectedValue => write!(f, "value written outside of a field"),
+            Self::MultipleValues => write!(f, "field has multiple values"),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl crate::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+
+    #[inline]
+    fn is_missing_field(&self) -> bool {
+        matches!(self, Self::MissingField { .. })
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// An error returned by [`load`] and [`export`].
+#[derive(Debug)]
+pub enum BulkError<E> {
+    /// A row could not be written or read.
+    Csv {
+        /// The line of the row.
+        line: usize,
+        error: Error,
+    },
+    /// The store returned an error.
+    Store(E),
+}
+
+impl<E> Display for BulkError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Csv { line, error } => write!(f, "line {}: {}", line, error),
+            Self::Store(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E> error::Error for BulkError<E>
+where
+    E: error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Csv { error, .. } => Some(error),
+            Self::Store(err) => Some(err),
+        }
+    }
+}
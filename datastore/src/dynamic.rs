@@ -0,0 +1,545 @@
+//! Type-erased stores.
+//!
+//! [`Store`] has generic methods and can therefore not be used as a trait object. [`DynStore`] is
+//! an object-safe version of [`Store`] that operates on [`Record`]s described by a
+//! [`RecordDescriptor`]. It is implemented for every [`Store`], which allows selecting a store at
+//! runtime:
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use datastore::dynamic::DynStore;
+//! # use datastore::Store;
+//!
+//! # fn open<S>(store: S) -> Arc<dyn DynStore>
+//! # where
+//! #     S: Store + 'static,
+//! #     S::Error: Send + Sync + 'static,
+//! # {
+//! let store: Arc<dyn DynStore> = Arc::new(store);
+//! # store
+//! # }
+//! ```
+//!
+//! `Arc<dyn DynStore>` itself implements [`Store`], so [`StoreData`] types can be used with it as
+//! with any other store. Every item is converted from and into a [`Record`] using the
+//! [`RecordDescriptor`] created from its [`DataDescriptor`]:
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use datastore::dynamic::{self, DynStore};
+//! use datastore::{Store, StoreData, StoreExt};
+//!
+//! #[derive(StoreData)]
+//! struct Person {
+//!     id: i64,
+//!     name: String,
+//! }
+//!
+//! # async fn run(store: Arc<dyn DynStore>) -> Result<(), dynamic::Error> {
+//! let person = Person {
+//!     id: 1,
+//!     name: String::from("Robb"),
+//! };
+//! store.insert(store.descriptor::<Person>(), person).await?;
+//!
+//! let people: Vec<Person> = store.get_all(store.descriptor::<Person>()).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Since a [`RecordDescriptor`] only describes single values, types with nested fields cannot be
+//! used through a [`DynStore`].
+use std::error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::registry;
+use crate::sql;
+use crate::value::{self, Record, RecordDescriptor, RecordQuery};
+use crate::{
+    Capabilities, DataDescriptor, DataQuery, Read, Reader, Store, StoreData, TypeWriter, Write,
+    Writer,
+};
+
+/// An object-safe [`Store`] operating on [`Record`]s.
+///
+/// `DynStore` is implemented for every [`Store`] with an error type that is `Send + Sync`. The
+/// methods mirror the methods of [`Store`] but are named differently, so that both traits can be
+/// imported at the same time.
+#[async_trait]
+pub trait DynStore: Send + Sync {
+    /// Initializes the store for storing records described by `descriptor`. See
+    /// [`Store::create`].
+    async fn create_records(&self, descriptor: &RecordDescriptor) -> Result<(), Error>;
+
+    /// Deletes all records matching the `query`. See [`Store::delete`].
+    async fn delete_records(
+        &self,
+        descriptor: &RecordDescriptor,
+        query: &RecordQuery,
+    ) -> Result<(), Error>;
+
+    /// Returns all records matching the `query`. See [`Store::get`].
+    async fn get_records(
+        &self,
+        descriptor: &RecordDescriptor,
+        query: &RecordQuery,
+    ) -> Result<Vec<Record>, Error>;
+
+    /// Returns all records. See [`Store::get_all`].
+    async fn get_all_records(&self, descriptor: &RecordDescriptor) -> Result<Vec<Record>, Error>;
+
+    /// Returns a record matching the `query`. See [`Store::get_one`].
+    async fn get_one_record(
+        &self,
+        descriptor: &RecordDescriptor,
+        query: &RecordQuery,
+    ) -> Result<Option<Record>, Error>;
+
+    /// Inserts the record `data`. See [`Store::insert`].
+    async fn insert_record(&self, descriptor: &RecordDescriptor, data: Record)
+        -> Result<(), Error>;
+
+    /// Checks whether the store is still usable. See [`Store::ping`].
+    async fn ping_store(&self) -> Result<(), Error>;
+
+    /// Returns the optional features supported by the store. See [`Store::capabilities`].
+    fn store_capabilities(&self) -> Capabilities;
+}
+
+#[async_trait]
+impl<S> DynStore for S
+where
+    S: Store,
+    S::Error: Send + Sync + 'static,
+{
+    async fn create_records(&self, descriptor: &RecordDescriptor) -> Result<(), Error> {
+        self.create(descriptor.clone())
+            .await
+            .map_err(Error::from_store)
+    }
+
+    async fn delete_records(
+        &self,
+        descriptor: &RecordDescriptor,
+        query: &RecordQuery,
+    ) -> Result<(), Error> {
+        self.delete(descriptor.clone(), query.clone())
+            .await
+            .map_err(Error::from_store)
+    }
+
+    async fn get_records(
+        &self,
+        descriptor: &RecordDescriptor,
+        query: &RecordQuery,
+    ) -> Result<Vec<Record>, Error> {
+        self.get(descriptor.clone(), query.clone())
+            .await
+            .map_err(Error::from_store)
+    }
+
+    async fn get_all_records(&self, descriptor: &RecordDescriptor) -> Result<Vec<Record>, Error> {
+        self.get_all(descriptor.clone())
+            .await
+            .map_err(Error::from_store)
+    }
+
+    async fn get_one_record(
+        &self,
+        descriptor: &RecordDescriptor,
+        query: &RecordQuery,
+    ) -> Result<Option<Record>, Error> {
+        self.get_one(descriptor.clone(), query.clone())
+            .await
+            .map_err(Error::from_store)
+    }
+
+    async fn insert_record(
+        &self,
+        descriptor: &RecordDescriptor,
+        data: Record,
+    ) -> Result<(), Error> {
+        self.insert(descriptor.clone(), data)
+            .await
+            .map_err(Error::from_store)
+    }
+
+    async fn ping_store(&self) -> Result<(), Error> {
+        self.ping().await.map_err(Error::from_store)
+    }
+
+    fn store_capabilities(&self) -> Capabilities {
+        self.capabilities()
+    }
+}
+
+// `Arc<dyn DynStore>` is itself a `DynStore` through the blanket implementation. All methods
+// dispatch to the inner trait object explicitly to not call back into that implementation.
+#[async_trait]
+impl Store for Arc<dyn DynStore> {
+    type DataStore = Self;
+    type Error = Error;
+
+    /// Connects to `uri` using the store registered for its scheme. See [`registry::connect`].
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        registry::connect(uri).await.map_err(Error::new)
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let descriptor = RecordDescriptor::from_descriptor(&descriptor)?;
+        (**self).create_records(&descriptor).await
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let query = capture(&query)?;
+        let descriptor = RecordDescriptor::from_descriptor(&descriptor)?;
+
+        (**self).delete_records(&descriptor, &query).await
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let query = capture(&query)?;
+        let records = {
+            let descriptor = RecordDescriptor::from_descriptor(&descriptor)?;
+            (**self).get_records(&descriptor, &query).await?
+        };
+
+        read_all(&descriptor, records)
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let records = {
+            let descriptor = RecordDescriptor::from_descriptor(&descriptor)?;
+            (**self).get_all_records(&descriptor).await?
+        };
+
+        read_all(&descriptor, records)
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let query = capture(&query)?;
+        let record = {
+            let descriptor = RecordDescriptor::from_descriptor(&descriptor)?;
+            (**self).get_one_record(&descriptor, &query).await?
+        };
+
+        match record {
+            Some(mut record) => Ok(Some(descriptor.read(&mut record)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let record = Record::from_data(&data)?;
+        let descriptor = RecordDescriptor::from_descriptor(&descriptor)?;
+
+        (**self).insert_record(&descriptor, record).await
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        (**self).ping_store().await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        (**self).store_capabilities()
+    }
+}
+
+/// Captures the fields written by `query` into a [`RecordQuery`].
+fn capture<T, Q>(query: &Q) -> Result<RecordQuery, Error>
+where
+    T: StoreData<Arc<dyn DynStore>>,
+    Q: DataQuery<T, Arc<dyn DynStore>>,
+{
+    let mut record = Record::new();
+    query.write(&mut record)?;
+    Ok(RecordQuery::from(record))
+}
+
+/// Reads all `records` into items described by `descriptor`.
+fn read_all<T, D>(descriptor: &D, records: Vec<Record>) -> Result<Vec<T>, Error>
+where
+    T: StoreData<Arc<dyn DynStore>>,
+    D: DataDescriptor<T, Arc<dyn DynStore>>,
+{
+    records
+        .into_iter()
+        .map(|mut record| descriptor.read(&mut record).map_err(Error::from))
+        .collect()
+}
+
+/// An error returned by a [`DynStore`].
+///
+/// The `Error` wraps the error of the underlying [`Store`]. Its [`Display`] output,
+/// [`source`] and [`is_transient`] are those of the wrapped error.
+///
+/// [`source`]: error::Error::source
+/// [`is_transient`]: crate::Error::is_transient
+pub struct Error {
+    inner: Box<dyn error::Error + Send + Sync + 'static>,
+    transient: bool,
+}
+
+impl Error {
+    /// Creates a new `Error` wrapping `err`.
+    pub fn new<E>(err: E) -> Self
+    where
+        E: error::Error + Send + Sync + 'static,
+    {
+        Self {
+            inner: Box::new(err),
+            transient: false,
+        }
+    }
+
+    /// Creates a new `Error` wrapping the error `err` returned by a [`Store`].
+    fn from_store<E>(err: E) -> Self
+    where
+        E: crate::Error + Send + Sync + 'static,
+    {
+        Self {
+            transient: err.is_transient(),
+            ..Self::new(err)
+        }
+    }
+
+    /// Returns `true` if the wrapped error is of type `E`.
+    #[inline]
+    pub fn is<E>(&self) -> bool
+    where
+        E: error::Error + 'static,
+    {
+        self.inner.is::<E>()
+    }
+
+    /// Returns a reference to the wrapped error if it is of type `E`.
+    #[inline]
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: error::Error + 'static,
+    {
+        self.inner.downcast_ref()
+    }
+
+    /// Returns the wrapped error.
+    #[inline]
+    pub fn into_inner(self) -> Box<dyn error::Error + Send + Sync + 'static> {
+        self.inner
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.inner, f)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.inner.source()
+    }
+}
+
+impl crate::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::new(value::Error::Custom(msg.to_string()))
+    }
+
+    #[inline]
+    fn is_transient(&self) -> bool {
+        self.transient
+    }
+}
+
+impl From<value::Error> for Error {
+    fn from(err: value::Error) -> Self {
+        Self::new(err)
+    }
+}
+
+impl From<sql::Error> for Error {
+    fn from(err: sql::Error) -> Self {
+        Self::new(err)
+    }
+}
+
+macro_rules! impl_types {
+    ($($ty:ty => $write:ident, $read:ident, $tywrite:ident;)*) => {
+        $(
+            impl Write<Arc<dyn DynStore>> for $ty {
+                #[inline]
+                fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: Writer<Arc<dyn DynStore>>,
+                {
+                    writer.$write(*self)
+                }
+
+                #[inline]
+                fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+                where
+                    W: TypeWriter<Arc<dyn DynStore>>,
+                {
+                    writer.$tywrite()
+                }
+            }
+
+            impl Read<Arc<dyn DynStore>> for $ty {
+                #[inline]
+                fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+                where
+                    R: Reader<Arc<dyn DynStore>>,
+                {
+                    reader.$read()
+                }
+            }
+        )*
+    };
+}
+
+impl_types! {
+    bool => write_bool, read_bool, write_bool;
+    i8 => write_i8, read_i8, write_i8;
+    i16 => write_i16, read_i16, write_i16;
+    i32 => write_i32, read_i32, write_i32;
+    i64 => write_i64, read_i64, write_i64;
+    u8 => write_u8, read_u8, write_u8;
+    u16 => write_u16, read_u16, write_u16;
+    u32 => write_u32, read_u32, write_u32;
+    u64 => write_u64, read_u64, write_u64;
+    f32 => write_f32, read_f32, write_f32;
+    f64 => write_f64, read_f64, write_f64;
+}
+
+impl Write<Arc<dyn DynStore>> for str {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<Arc<dyn DynStore>>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<Arc<dyn DynStore>>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Write<Arc<dyn DynStore>> for String {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<Arc<dyn DynStore>>,
+    {
+        writer.write_str(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<Arc<dyn DynStore>>,
+    {
+        writer.write_str()
+    }
+}
+
+impl Read<Arc<dyn DynStore>> for String {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<Arc<dyn DynStore>>,
+    {
+        reader.read_string()
+    }
+}
+
+impl Write<Arc<dyn DynStore>> for [u8] {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<Arc<dyn DynStore>>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<Arc<dyn DynStore>>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Write<Arc<dyn DynStore>> for Vec<u8> {
+    #[inline]
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<Arc<dyn DynStore>>,
+    {
+        writer.write_bytes(self)
+    }
+
+    #[inline]
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<Arc<dyn DynStore>>,
+    {
+        writer.write_bytes()
+    }
+}
+
+impl Read<Arc<dyn DynStore>> for Vec<u8> {
+    #[inline]
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<Arc<dyn DynStore>>,
+    {
+        reader.read_byte_buf()
+    }
+}
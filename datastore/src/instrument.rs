@@ -0,0 +1,312 @@
+//! [`tracing`] instrumentation for any [`Store`].
+//!
+//! [`Instrumented`] wraps a store and runs every operation inside a `DEBUG` level span with the
+//! target `datastore`. The span is named after the operation, e.g. `datastore.get`, and records
+//! the following fields:
+//!
+//! | Field         | Content                                                         |
+//! | ------------- | --------------------------------------------------------------- |
+//! | `ident`       | The [`ident`] of the descriptor.                                |
+//! | `query`       | The fields written by the [`DataQuery`], e.g. `id=1 name="Bob"`. |
+//! | `rows`        | The number of returned or inserted items.                       |
+//! | `duration_us` | The duration of the operation in microseconds.                  |
+//! | `error`       | The error returned by the operation.                            |
+//!
+//! Failed operations additionally emit an `ERROR` level event inside the span.
+//!
+//! ```
+//! use datastore::instrument::Instrumented;
+//! # use datastore::Store;
+//!
+//! # async fn run<SqliteStore: Store>() -> Result<(), SqliteStore::Error> {
+//! let store = Instrumented::new(SqliteStore::connect("sqlite://data.db").await?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! This module is only available with the `tracing` feature.
+//!
+//! [`ident`]: DataDescriptor::ident
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use tracing::field::{self, Empty};
+use tracing::{Instrument, Span};
+
+use crate::options::ConnectOptions;
+use crate::value::{Record, Value};
+use crate::{Capabilities, DataDescriptor, DataQuery, Store, StoreData};
+
+/// A [`Store`] recording all operations of the inner store `S` as [`tracing`] spans.
+#[derive(Debug)]
+pub struct Instrumented<S> {
+    store: S,
+}
+
+impl<S> Instrumented<S> {
+    /// Creates a new `Instrumented` store wrapping `store`.
+    #[inline]
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    /// Returns a reference to the inner store.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.store
+    }
+
+    /// Consumes the `Instrumented` store, returning the inner store.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+#[async_trait]
+impl<S> Store for Instrumented<S>
+where
+    S: Store,
+{
+    type DataStore = S::DataStore;
+    type Error = S::Error;
+
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        let span = tracing::debug_span!(
+            target: "datastore",
+            "datastore.connect",
+            duration_us = Empty,
+            error = Empty,
+        );
+
+        instrument(span, S::connect(uri), |_| None)
+            .await
+            .map(Self::new)
+    }
+
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        let span = tracing::debug_span!(
+            target: "datastore",
+            "datastore.connect",
+            duration_us = Empty,
+            error = Empty,
+        );
+
+        instrument(span, S::connect_with(options), |_| None)
+            .await
+            .map(Self::new)
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let span = tracing::debug_span!(
+            target: "datastore",
+            "datastore.create",
+            ident = descriptor.ident(),
+            duration_us = Empty,
+            error = Empty,
+        );
+
+        instrument(span, self.store.create(descriptor), |_| None).await
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let span = tracing::debug_span!(
+            target: "datastore",
+            "datastore.delete",
+            ident = descriptor.ident(),
+            query = Empty,
+            duration_us = Empty,
+            error = Empty,
+        );
+        record_query(&span, &query);
+
+        instrument(span, self.store.delete(descriptor, query), |_| None).await
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let span = tracing::debug_span!(
+            target: "datastore",
+            "datastore.get",
+            ident = descriptor.ident(),
+            query = Empty,
+            rows = Empty,
+            duration_us = Empty,
+            error = Empty,
+        );
+        record_query(&span, &query);
+
+        instrument(span, self.store.get(descriptor, query), |items| {
+            Some(items.len())
+        })
+        .await
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let span = tracing::debug_span!(
+            target: "datastore",
+            "datastore.get_all",
+            ident = descriptor.ident(),
+            rows = Empty,
+            duration_us = Empty,
+            error = Empty,
+        );
+
+        instrument(span, self.store.get_all(descriptor), |items| {
+            Some(items.len())
+        })
+        .await
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let span = tracing::debug_span!(
+            target: "datastore",
+            "datastore.get_one",
+            ident = descriptor.ident(),
+            query = Empty,
+            rows = Empty,
+            duration_us = Empty,
+            error = Empty,
+        );
+        record_query(&span, &query);
+
+        instrument(span, self.store.get_one(descriptor, query), |item| {
+            Some(usize::from(item.is_some()))
+        })
+        .await
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let span = tracing::debug_span!(
+            target: "datastore",
+            "datastore.insert",
+            ident = descriptor.ident(),
+            rows = Empty,
+            duration_us = Empty,
+            error = Empty,
+        );
+
+        instrument(span, self.store.insert(descriptor, data), |_| Some(1)).await
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        let span = tracing::debug_span!(
+            target: "datastore",
+            "datastore.ping",
+            duration_us = Empty,
+            error = Empty,
+        );
+
+        instrument(span, self.store.ping(), |_| None).await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.store.capabilities()
+    }
+}
+
+/// Runs `future` inside `span` and records the duration, the number of rows returned by `rows`
+/// and the error, if any.
+async fn instrument<F, T, E>(span: Span, future: F, rows: fn(&T) -> Option<usize>) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Display,
+{
+    let start = Instant::now();
+    let res = future.instrument(span.clone()).await;
+    span.record("duration_us", start.elapsed().as_micros() as u64);
+
+    match &res {
+        Ok(value) => {
+            if let Some(rows) = rows(value) {
+                span.record("rows", rows as u64);
+            }
+        }
+        Err(err) => {
+            span.record("error", field::display(err));
+            span.in_scope(|| {
+                tracing::error!(target: "datastore", error = %err, "store operation failed");
+            });
+        }
+    }
+
+    res
+}
+
+/// Records the fields written by `query` in the `query` field of `span`.
+fn record_query<T, S, Q>(span: &Span, query: &Q)
+where
+    T: StoreData<S>,
+    S: Store,
+    Q: DataQuery<T, S>,
+{
+    if span.is_disabled() {
+        return;
+    }
+
+    let mut record = Record::new();
+    if query.write(&mut record).is_ok() {
+        span.record("query", field::display(Fields(&record)));
+    }
+}
+
+/// Formats the fields of a [`Record`] as `key=value` pairs separated by spaces.
+struct Fields<'a>(&'a Record);
+
+impl<'a> Display for Fields<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, (key, value)) in self.0.iter().enumerate() {
+            if index != 0 {
+                f.write_str(" ")?;
+            }
+
+            write!(f, "{}=", key)?;
+            match value {
+                Value::Bool(v) => write!(f, "{}", v),
+                Value::I8(v) => write!(f, "{}", v),
+                Value::I16(v) => write!(f, "{}", v),
+                Value::I32(v) => write!(f, "{}", v),
+                Value::I64(v) => write!(f, "{}", v),
+                Value::U8(v) => write!(f, "{}", v),
+                Value::U16(v) => write!(f, "{}", v),
+                Value::U32(v) => write!(f, "{}", v),
+                Value::U64(v) => write!(f, "{}", v),
+                Value::F32(v) => write!(f, "{}", v),
+                Value::F64(v) => write!(f, "{}", v),
+                Value::Bytes(v) => write!(f, "{:?}", v),
+                Value::String(v) => write!(f, "{:?}", v),
+                Value::Record(v) => write!(f, "{{{}}}", Fields(v)),
+            }?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,957 @@
+//! JSON encoding for [`StoreData`] types.
+//!
+//! Unlike the [`serde`] module this module has no dependencies. [`JsonWriter`] writes a
+//! [`StoreData`] type as a JSON object and [`JsonReader`] reads it back:
+//!
+//! ```
+//! # use datastore::{Store, StoreData};
+//! # fn roundtrip<T, S>(data: &T) -> T
+//! # where
+//! #     T: StoreData<S>,
+//! #     S: Store,
+//! # {
+//! let json = datastore::json::to_string(data).unwrap();
+//! datastore::json::from_str(&json).unwrap()
+//! # }
+//! ```
+//!
+//! Fields written using [`Writer::write_field`] become the entries of an object and are looked
+//! up by key when reading, regardless of their order. Fields whose value writes nothing are
+//! omitted. Integers are written with their full width, floats must be finite. Bytes are written
+//! as a base64 string using the standard alphabet with padding.
+//!
+//! [`JsonTypeWriter`] writes a sketch of the type described by a [`DataDescriptor`], mapping each
+//! field to the name of its type (`"bool"`, `"i8"`, ..., `"f64"`, `"bytes"` or `"string"`):
+//!
+//! ```
+//! # use datastore::{DataDescriptor, Store, StoreData};
+//! # fn print<T, S, D>(descriptor: &D)
+//! # where
+//! #     T: StoreData<S>,
+//! #     S: Store,
+//! #     D: DataDescriptor<T, S>,
+//! # {
+//! // {"id":"i64","name":"string"}
+//! println!("{}", datastore::json::type_sketch(descriptor).unwrap());
+//! # }
+//! ```
+//!
+//! [`serde`]: crate::serde
+//! [`StoreData`]: crate::StoreData
+use std::error;
+use std::fmt::{self, Display, Formatter, Write as _};
+use std::mem;
+use std::str::FromStr;
+
+use crate::{
+    DataDescriptor, FieldAttributes, ReadSeed, Reader, Store, StoreData, TypeWriter, Write,
+    WriteTypeSeed, Writer,
+};
+
+/// The maximum nesting depth accepted by the parser.
+const MAX_DEPTH: usize = 128;
+
+/// Serializes the [`StoreData`] `data` into a JSON string.
+pub fn to_string<T, S>(data: &T) -> Result<String, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    let mut buf = String::new();
+    let mut writer = JsonWriter::new(&mut buf);
+    data.write(&mut writer)?;
+    writer.finish();
+    Ok(buf)
+}
+
+/// Deserializes a new [`StoreData`] from the JSON string `s`.
+pub fn from_str<T, S>(s: &str) -> Result<T, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    T::read(&mut JsonReader::new(s)?)
+}
+
+/// Returns a JSON sketch of the type described by `descriptor`.
+pub fn type_sketch<T, S, D>(descriptor: &D) -> Result<String, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+    D: DataDescriptor<T, S>,
+{
+    let mut buf = String::new();
+    let mut writer = JsonTypeWriter::new(&mut buf);
+    descriptor.write(&mut writer)?;
+    writer.finish();
+    Ok(buf)
+}
+
+/// The output of a [`JsonWriter`] or [`JsonTypeWriter`].
+struct Output<'a> {
+    buf: &'a mut String,
+    state: State,
+}
+
+#[derive(Copy, Clone)]
+enum State {
+    Empty,
+    Object,
+    Value,
+}
+
+/// The output position before a field was started.
+struct Mark {
+    len: usize,
+    state: State,
+}
+
+impl<'a> Output<'a> {
+    fn new(buf: &'a mut String) -> Self {
+        Self {
+            buf,
+            state: State::Empty,
+        }
+    }
+
+    fn value<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut String),
+    {
+        match self.state {
+            State::Empty => {
+                f(self.buf);
+                self.state = State::Value;
+                Ok(())
+            }
+            _ => Err(Error::MultipleValues),
+        }
+    }
+
+    fn begin_field(&mut self, key: &str) -> Result<Mark, Error> {
+        let mark = Mark {
+            len: self.buf.len(),
+            state: self.state,
+        };
+
+        match self.state {
+            State::Empty => self.buf.push('{'),
+            State::Object => self.buf.push(','),
+            State::Value => return Err(Error::MultipleValues),
+        }
+
+        write_str(self.buf, key);
+        self.buf.push(':');
+        self.state = State::Object;
+        Ok(mark)
+    }
+
+    /// Completes a nested value. Returns `false` if nothing was written.
+    fn end_field(self) -> bool {
+        match self.state {
+            State::Empty => false,
+            State::Object => {
+                self.buf.push('}');
+                true
+            }
+            State::Value => true,
+        }
+    }
+
+    fn rollback(&mut self, mark: Mark) {
+        self.buf.truncate(mark.len);
+        self.state = mark.state;
+    }
+
+    fn finish(self) {
+        match self.state {
+            State::Empty => self.buf.push_str("{}"),
+            State::Object => self.buf.push('}'),
+            State::Value => (),
+        }
+    }
+}
+
+/// A [`Writer`] writing JSON into a `String`.
+///
+/// See the [module documentation] for the format.
+///
+/// [module documentation]: self
+pub struct JsonWriter<'a> {
+    output: Output<'a>,
+}
+
+impl<'a> JsonWriter<'a> {
+    /// Creates a new `JsonWriter` appending to `buf`.
+    #[inline]
+    pub fn new(buf: &'a mut String) -> Self {
+        Self {
+            output: Output::new(buf),
+        }
+    }
+
+    /// Completes the JSON value. If nothing was written an empty object is written.
+    #[inline]
+    pub fn finish(self) {
+        self.output.finish();
+    }
+
+    fn write_int<T>(&mut self, v: T) -> Result<(), Error>
+    where
+        T: Display,
+    {
+        self.output.value(|buf| {
+            let _ = write!(buf, "{}", v);
+        })
+    }
+
+    fn write_float<T>(&mut self, v: T, finite: bool) -> Result<(), Error>
+    where
+        T: fmt::Debug,
+    {
+        if !finite {
+            return Err(Error::NonFiniteFloat);
+        }
+
+        self.output.value(|buf| {
+            let _ = write!(buf, "{:?}", v);
+        })
+    }
+}
+
+impl<'a, S> Writer<S> for JsonWriter<'a>
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self, v: bool) -> Result<(), Self::Error> {
+        self.output.value(|buf| {
+            buf.push_str(if v { "true" } else { "false" });
+        })
+    }
+
+    fn write_i8(&mut self, v: i8) -> Result<(), Self::Error> {
+        self.write_int(v)
+    }
+
+    fn write_i16(&mut self, v: i16) -> Result<(), Self::Error> {
+        self.write_int(v)
+    }
+
+    fn write_i32(&mut self, v: i32) -> Result<(), Self::Error> {
+        self.write_int(v)
+    }
+
+    fn write_i64(&mut self, v: i64) -> Result<(), Self::Error> {
+        self.write_int(v)
+    }
+
+    fn write_u8(&mut self, v: u8) -> Result<(), Self::Error> {
+        self.write_int(v)
+    }
+
+    fn write_u16(&mut self, v: u16) -> Result<(), Self::Error> {
+        self.write_int(v)
+    }
+
+    fn write_u32(&mut self, v: u32) -> Result<(), Self::Error> {
+        self.write_int(v)
+    }
+
+    fn write_u64(&mut self, v: u64) -> Result<(), Self::Error> {
+        self.write_int(v)
+    }
+
+    fn write_f32(&mut self, v: f32) -> Result<(), Self::Error> {
+        self.write_float(v, v.is_finite())
+    }
+
+    fn write_f64(&mut self, v: f64) -> Result<(), Self::Error> {
+        self.write_float(v, v.is_finite())
+    }
+
+    fn write_bytes(&mut self, v: &[u8]) -> Result<(), Self::Error> {
+        self.output.value(|buf| {
+            buf.push('"');
+            base64_encode(buf, v);
+            buf.push('"');
+        })
+    }
+
+    fn write_str(&mut self, v: &str) -> Result<(), Self::Error> {
+        self.output.value(|buf| write_str(buf, v))
+    }
+
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<S>,
+    {
+        let mark = self.output.begin_field(key)?;
+
+        let mut writer = JsonWriter::new(self.output.buf);
+        value.write(&mut writer)?;
+
+        if !writer.output.end_field() {
+            self.output.rollback(mark);
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`TypeWriter`] writing a JSON sketch of a type into a `String`.
+///
+/// See the [module documentation] for the format.
+///
+/// [module documentation]: self
+pub struct JsonTypeWriter<'a> {
+    output: Output<'a>,
+}
+
+impl<'a> JsonTypeWriter<'a> {
+    /// Creates a new `JsonTypeWriter` appending to `buf`.
+    #[inline]
+    pub fn new(buf: &'a mut String) -> Self {
+        Self {
+            output: Output::new(buf),
+        }
+    }
+
+    /// Completes the sketch. If nothing was written an empty object is written.
+    #[inline]
+    pub fn finish(self) {
+        self.output.finish();
+    }
+
+    fn write_type(&mut self, name: &'static str) -> Result<(), Error> {
+        self.output.value(|buf| write_str(buf, name))
+    }
+}
+
+impl<'a, S> TypeWriter<S> for JsonTypeWriter<'a>
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self) -> Result<(), Self::Error> {
+        self.write_type("bool")
+    }
+
+    fn write_i8(&mut self) -> Result<(), Self::Error> {
+        self.write_type("i8")
+    }
+
+    fn write_i16(&mut self) -> Result<(), Self::Error> {
+        self.write_type("i16")
+    }
+
+    fn write_i32(&mut self) -> Result<(), Self::Error> {
+        self.write_type("i32")
+    }
+
+    fn write_i64(&mut self) -> Result<(), Self::Error> {
+        self.write_type("i64")
+    }
+
+    fn write_u8(&mut self) -> Result<(), Self::Error> {
+        self.write_type("u8")
+    }
+
+    fn write_u16(&mut self) -> Result<(), Self::Error> {
+        self.write_type("u16")
+    }
+
+    fn write_u32(&mut self) -> Result<(), Self::Error> {
+        self.write_type("u32")
+    }
+
+    fn write_u64(&mut self) -> Result<(), Self::Error> {
+        self.write_type("u64")
+    }
+
+    fn write_f32(&mut self) -> Result<(), Self::Error> {
+        self.write_type("f32")
+    }
+
+    fn write_f64(&mut self) -> Result<(), Self::Error> {
+        self.write_type("f64")
+    }
+
+    fn write_bytes(&mut self) -> Result<(), Self::Error> {
+        self.write_type("bytes")
+    }
+
+    fn write_str(&mut self) -> Result<(), Self::Error> {
+        self.write_type("string")
+    }
+
+    fn write_field_seed<T>(
+        &mut self,
+        key: &str,
+        seed: T,
+        _attrs: FieldAttributes,
+    ) -> Result<(), Self::Error>
+    where
+        T: WriteTypeSeed<S>,
+    {
+        let mark = self.output.begin_field(key)?;
+
+        let mut writer = JsonTypeWriter::new(self.output.buf);
+        seed.write_type(&mut writer)?;
+
+        if !writer.output.end_field() {
+            self.output.rollback(mark);
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`Reader`] reading from a JSON string.
+///
+/// The input is parsed when the `JsonReader` is created. Fields are looked up by key regardless
+/// of their order.
+#[derive(Clone, Debug)]
+pub struct JsonReader {
+    value: Json,
+}
+
+impl JsonReader {
+    /// Creates a new `JsonReader` by parsing the JSON string `s`.
+    pub fn new(s: &str) -> Result<Self, Error> {
+        let mut parser = Parser {
+            input: s,
+            pos: 0,
+            depth: 0,
+        };
+
+        parser.skip_whitespace();
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+
+        if parser.pos != s.len() {
+            return Err(parser.error("end of input"));
+        }
+
+        Ok(Self { value })
+    }
+
+    fn take(&mut self) -> Json {
+        mem::replace(&mut self.value, Json::Null)
+    }
+
+    fn read_number<T>(&mut self, expected: &'static str) -> Result<T, Error>
+    where
+        T: FromStr,
+    {
+        match self.take() {
+            Json::Number(v) => v.parse().map_err(|_| Error::InvalidValue { expected }),
+            _ => Err(Error::InvalidType { expected }),
+        }
+    }
+}
+
+impl<S> Reader<S> for JsonReader
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn read_bool(&mut self) -> Result<bool, Self::Error> {
+        match self.take() {
+            Json::Bool(v) => Ok(v),
+            _ => Err(Error::InvalidType { expected: "a bool" }),
+        }
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Self::Error> {
+        self.read_number("an i8")
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        self.read_number("an i16")
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        self.read_number("an i32")
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Self::Error> {
+        self.read_number("an i64")
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        self.read_number("an u8")
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        self.read_number("an u16")
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        self.read_number("an u32")
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Self::Error> {
+        self.read_number("an u64")
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Self::Error> {
+        self.read_number("a f32")
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Self::Error> {
+        self.read_number("a f64")
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Self::Error> {
+        match self.take() {
+            Json::String(v) => base64_decode(&v).ok_or(Error::InvalidBase64),
+            _ => Err(Error::InvalidType {
+                expected: "a base64 string",
+            }),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, Self::Error> {
+        match self.take() {
+            Json::String(v) => Ok(v),
+            _ => Err(Error::InvalidType {
+                expected: "a string",
+            }),
+        }
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>,
+    {
+        let object = match &mut self.value {
+            Json::Object(object) => object,
+            _ => {
+                return Err(Error::InvalidType {
+                    expected: "an object",
+                })
+            }
+        };
+
+        let index = object
+            .iter()
+            .position(|(k, _)| k == key)
+            .ok_or(Error::MissingField {
+                key: key.to_owned(),
+            })?;
+
+        let (_, value) = object.swap_remove(index);
+        seed.read(&mut JsonReader { value })
+    }
+}
+
+/// A parsed JSON value.
+#[derive(Clone, Debug)]
+enum Json {
+    Null,
+    Bool(bool),
+    /// A number in its original representation.
+    Number(String),
+    String(String),
+    /// An array. Arrays are never read, therefore their elements are discarded.
+    Array,
+    Object(Vec<(String, Json)>),
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, expected: &'static str) -> Error {
+        Error::Syntax {
+            offset: self.pos,
+            expected,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let b = self.peek()?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn expect(&mut self, b: u8, expected: &'static str) -> Result<(), Error> {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    fn expect_literal(&mut self, literal: &'static str) -> Result<(), Error> {
+        if self.input[self.pos..].starts_with(literal) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(literal))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn parse_value(&mut self) -> Result<Json, Error> {
+        match self.peek() {
+            Some(b'{') => self.nested(Self::parse_object),
+            Some(b'[') => self.nested(Self::parse_array),
+            Some(b'"') => self.parse_string().map(Json::String),
+            Some(b't') => self.expect_literal("true").map(|_| Json::Bool(true)),
+            Some(b'f') => self.expect_literal("false").map(|_| Json::Bool(false)),
+            Some(b'n') => self.expect_literal("null").map(|_| Json::Null),
+            Some(b'-' | b'0'..=b'9') => self.parse_number().map(Json::Number),
+            _ => Err(self.error("a value")),
+        }
+    }
+
+    fn nested<F>(&mut self, f: F) -> Result<Json, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<Json, Error>,
+    {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("less nesting"));
+        }
+
+        self.depth += 1;
+        let value = f(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_object(&mut self) -> Result<Json, Error> {
+        self.expect(b'{', "'{'")?;
+        self.skip_whitespace();
+
+        let mut object = Vec::new();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(object));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("a string key"));
+            }
+            let key = self.parse_string()?;
+
+            self.skip_whitespace();
+            self.expect(b':', "':'")?;
+            self.skip_whitespace();
+            let value = self.parse_value()?;
+            object.push((key, value));
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => (),
+                Some(b'}') => return Ok(Json::Object(object)),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("',' or '}'"));
+                }
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Json, Error> {
+        self.expect(b'[', "'['")?;
+        self.skip_whitespace();
+
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array);
+        }
+
+        loop {
+            self.skip_whitespace();
+            self.parse_value()?;
+
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => (),
+                Some(b']') => return Ok(Json::Array),
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("',' or ']'"));
+                }
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<String, Error> {
+        let start = self.pos;
+
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                self.skip_digits();
+            }
+            _ => return Err(self.error("a digit")),
+        }
+
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if self.skip_digits() == 0 {
+                return Err(self.error("a digit"));
+            }
+        }
+
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+
+            if self.skip_digits() == 0 {
+                return Err(self.error("a digit"));
+            }
+        }
+
+        Ok(self.input[start..self.pos].to_owned())
+    }
+
+    fn parse_string(&mut self) -> Result<String, Error> {
+        self.expect(b'"', "'\"'")?;
+
+        let mut s = String::new();
+        let mut start = self.pos;
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    s.push_str(&self.input[start..self.pos]);
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some(b'\\') => {
+                    s.push_str(&self.input[start..self.pos]);
+                    self.pos += 1;
+                    self.parse_escape(&mut s)?;
+                    start = self.pos;
+                }
+                Some(0x00..=0x1f) => return Err(self.error("an escaped control character")),
+                Some(_) => self.pos += 1,
+                None => return Err(self.error("'\"'")),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self, s: &mut String) -> Result<(), Error> {
+        let c = match self.next() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{08}',
+            Some(b'f') => '\u{0c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                let high = self.parse_hex()?;
+                let c = match high {
+                    0xd800..=0xdbff => {
+                        self.expect_literal("\\u")?;
+                        let low = self.parse_hex()?;
+                        if !(0xdc00..=0xdfff).contains(&low) {
+                            return Err(self.error("a low surrogate"));
+                        }
+
+                        0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+                    }
+                    _ => high,
+                };
+
+                char::from_u32(c).ok_or_else(|| self.error("a valid unicode escape"))?
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("an escape sequence"));
+            }
+        };
+
+        s.push(c);
+        Ok(())
+    }
+
+    fn parse_hex(&mut self) -> Result<u32, Error> {
+        let hex = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("4 hex digits"))?;
+
+        self.pos += 4;
+        Ok(u32::from_str_radix(hex, 16).unwrap())
+    }
+}
+
+/// Writes `s` as a quoted and escaped JSON string into `buf`.
+fn write_str(buf: &mut String, s: &str) {
+    buf.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(buf, "\\u{:04x}", c as u32);
+            }
+            c => buf.push(c),
+        }
+    }
+
+    buf.push('"');
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Appends `bytes` encoded as base64 using the standard alphabet with padding to `buf`.
+pub(crate) fn base64_encode(buf: &mut String, bytes: &[u8]) {
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = u32::from(b[0]) << 16 | u32::from(b[1]) << 8 | u32::from(b[2]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                buf.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                buf.push('=');
+            }
+        }
+    }
+}
+
+/// Decodes the base64 string `s` using the standard alphabet with padding. Returns `None` if
+/// `s` is not valid base64.
+// `usize::is_multiple_of` requires Rust 1.87.
+#[allow(clippy::manual_is_multiple_of)]
+pub(crate) fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    if s.len() % 4 != 0 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(s.len() / 4 * 3);
+    for (i, chunk) in s.chunks(4).enumerate() {
+        let last = i == s.len() / 4 - 1;
+        let padding = match chunk {
+            [_, _, b'=', b'='] if last => 2,
+            [_, _, _, b'='] if last => 1,
+            _ => 0,
+        };
+
+        let mut n = 0;
+        for &c in &chunk[..4 - padding] {
+            let v = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                b'/' => 63,
+                _ => return None,
+            };
+            n = n << 6 | u32::from(v);
+        }
+        n <<= 6 * padding as u32;
+
+        let chunk = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        bytes.extend_from_slice(&chunk[..3 - padding]);
+    }
+
+    Some(bytes)
+}
+
+/// An error that can occur when writing or reading JSON.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The input is not valid JSON.
+    Syntax {
+        /// The byte offset of the error in the input.
+        offset: usize,
+        expected: &'static str,
+    },
+    /// A value has a different type than expected.
+    InvalidType { expected: &'static str },
+    /// A value is out of range for the expected type.
+    InvalidValue { expected: &'static str },
+    /// A string is not valid base64.
+    InvalidBase64,
+    /// The field `key` does not exist.
+    MissingField { key: String },
+    /// A float is NaN or infinite.
+    NonFiniteFloat,
+    /// More than a single value was written.
+    MultipleValues,
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { offset, expected } => {
+                write!(f, "expected {} at offset {}", expected, offset)
+            }
+            Self::InvalidType { expected } => write!(f, "invalid type, expected {}", expected),
+            Self::InvalidValue { expected } => write!(f, "invalid value, expected {}", expected),
+            Self::InvalidBase64 => write!(f, "invalid base64"),
+            Self::MissingField { key } => write!(f, "missing field {:?}", key),
+            Self::NonFiniteFloat => write!(f, "float is not finite"),
+            Self::MultipleValues => write!(f, "multiple values written"),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl crate::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+
+    #[inline]
+    fn is_missing_field(&self) -> bool {
+        matches!(self, Self::MissingField { .. })
+    }
+}
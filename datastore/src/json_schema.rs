@@ -0,0 +1,371 @@
+//! JSON Schema export of [`StoreData`] types.
+//!
+//! [`to_string`] walks the [`TypeWriter`] pass of a [`DataDescriptor`] and renders a JSON Schema
+//! (draft 2020-12) document describing a single item of the data. Fields are required unless they
+//! are nullable, nested fields become nested object schemas.
+use std::error;
+use std::fmt::{self, Display, Formatter, Write as _};
+
+use crate::{DataDescriptor, FieldAttributes, Store, StoreData, TypeWriter, WriteTypeSeed};
+
+const SCHEMA: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Returns the JSON Schema document for the data described by `descriptor`.
+///
+/// The [`ident`] of the descriptor is used as the `title` of the schema.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if a field of the descriptor has no or multiple types.
+///
+/// [`ident`]: DataDescriptor::ident
+pub fn to_string<T, S, D>(descriptor: &D) -> Result<String, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+    D: DataDescriptor<T, S>,
+{
+    let mut writer = SchemaWriter::new();
+    descriptor.write(&mut writer)?;
+
+    // The root frame is never popped.
+    let root = writer.frames.pop().unwrap();
+
+    let mut buf = String::new();
+    buf.push_str("{\n");
+    write_key(&mut buf, 1, "$schema");
+    write_str(&mut buf, SCHEMA);
+    buf.push_str(",\n");
+    write_key(&mut buf, 1, "title");
+    write_str(&mut buf, descriptor.ident());
+    buf.push_str(",\n");
+    root.write_object(&mut buf, 1);
+    buf.push_str("\n}");
+    Ok(buf)
+}
+
+/// An error that can occur when creating a JSON Schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A type was written outside of a field.
+    UnexpectedType,
+    /// A field did not write any type.
+    MissingType { key: String },
+    /// A field wrote more than a single type.
+    MultipleTypes { key: String },
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedType => write!(f, "type written outside of a field"),
+            Self::MissingType { key } => write!(f, "field {:?} has no type", key),
+            Self::MultipleTypes { key } => write!(f, "field {:?} has multiple types", key),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl crate::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Boolean,
+    Integer { minimum: i128, maximum: i128 },
+    Number,
+    String,
+    Bytes,
+    Object(Box<Frame>),
+}
+
+impl Node {
+    fn integer<T>(minimum: T, maximum: T) -> Self
+    where
+        T: Into<i128>,
+    {
+        Self::Integer {
+            minimum: minimum.into(),
+            maximum: maximum.into(),
+        }
+    }
+
+    fn write(&self, buf: &mut String, indent: usize, nullable: bool) {
+        let ty = match self {
+            Self::Boolean => "boolean",
+            Self::Integer { .. } => "integer",
+            Self::Number => "number",
+            Self::String | Self::Bytes => "string",
+            Self::Object(_) => "object",
+        };
+
+        buf.push_str("{\n");
+        write_key(buf, indent + 1, "type");
+        if nullable {
+            buf.push('[');
+            write_str(buf, ty);
+            buf.push_str(", \"null\"]");
+        } else {
+            write_str(buf, ty);
+        }
+
+        match self {
+            Self::Integer { minimum, maximum } => {
+                buf.push_str(",\n");
+                write_key(buf, indent + 1, "minimum");
+                let _ = write!(buf, "{}", minimum);
+                buf.push_str(",\n");
+                write_key(buf, indent + 1, "maximum");
+                let _ = write!(buf, "{}", maximum);
+            }
+            Self::Bytes => {
+                buf.push_str(",\n");
+                write_key(buf, indent + 1, "format");
+                write_str(buf, "byte");
+            }
+            Self::Object(frame) => {
+                buf.push_str(",\n");
+                frame.write_object(buf, indent + 1);
+            }
+            _ => (),
+        }
+
+        buf.push('\n');
+        write_indent(buf, indent);
+        buf.push('}');
+    }
+}
+
+/// The fields written at one level of nesting.
+#[derive(Debug, Default)]
+struct Frame {
+    key: Option<String>,
+    ty: Option<Node>,
+    properties: Vec<(String, Node, FieldAttributes)>,
+}
+
+impl Frame {
+    /// Writes the `type`, `properties`, `required` and `additionalProperties` keywords of the
+    /// object schema.
+    fn write_object(&self, buf: &mut String, indent: usize) {
+        if self.key.is_none() {
+            write_key(buf, indent, "type");
+            write_str(buf, "object");
+            buf.push_str(",\n");
+        }
+
+        write_key(buf, indent, "properties");
+        if self.properties.is_empty() {
+            buf.push_str("{}");
+        } else {
+            buf.push_str("{\n");
+            for (index, (key, node, attrs)) in self.properties.iter().enumerate() {
+                if index != 0 {
+                    buf.push_str(",\n");
+                }
+
+                write_key(buf, indent + 1, key);
+                node.write(buf, indent + 1, attrs.is_nullable());
+            }
+            buf.push('\n');
+            write_indent(buf, indent);
+            buf.push('}');
+        }
+        buf.push_str(",\n");
+
+        write_key(buf, indent, "required");
+        buf.push('[');
+        let required = self
+            .properties
+            .iter()
+            .filter(|(_, _, attrs)| !attrs.is_nullable());
+        for (index, (key, _, _)) in required.enumerate() {
+            if index != 0 {
+                buf.push_str(", ");
+            }
+
+            write_str(buf, key);
+        }
+        buf.push_str("],\n");
+
+        write_key(buf, indent, "additionalProperties");
+        buf.push_str("false");
+    }
+}
+
+/// The [`TypeWriter`] collecting the schema nodes.
+#[derive(Debug)]
+struct SchemaWriter {
+    frames: Vec<Frame>,
+}
+
+impl SchemaWriter {
+    fn new() -> Self {
+        Self {
+            frames: vec![Frame::default()],
+        }
+    }
+
+    fn write_node(&mut self, node: Node) -> Result<(), Error> {
+        // The root frame always exists.
+        let frame = self.frames.last_mut().unwrap();
+
+        match &frame.key {
+            Some(key) => {
+                if frame.ty.is_some() || !frame.properties.is_empty() {
+                    return Err(Error::MultipleTypes {
+                        key: key.to_owned(),
+                    });
+                }
+
+                frame.ty = Some(node);
+                Ok(())
+            }
+            None => Err(Error::UnexpectedType),
+        }
+    }
+}
+
+impl<S> TypeWriter<S> for SchemaWriter
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::Boolean)
+    }
+
+    fn write_i8(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::integer(i8::MIN, i8::MAX))
+    }
+
+    fn write_i16(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::integer(i16::MIN, i16::MAX))
+    }
+
+    fn write_i32(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::integer(i32::MIN, i32::MAX))
+    }
+
+    fn write_i64(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::integer(i64::MIN, i64::MAX))
+    }
+
+    fn write_u8(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::integer(u8::MIN, u8::MAX))
+    }
+
+    fn write_u16(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::integer(u16::MIN, u16::MAX))
+    }
+
+    fn write_u32(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::integer(u32::MIN, u32::MAX))
+    }
+
+    fn write_u64(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::integer(u64::MIN, u64::MAX))
+    }
+
+    fn write_f32(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::Number)
+    }
+
+    fn write_f64(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::Number)
+    }
+
+    fn write_bytes(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::Bytes)
+    }
+
+    fn write_str(&mut self) -> Result<(), Self::Error> {
+        self.write_node(Node::String)
+    }
+
+    fn write_field_seed<T>(
+        &mut self,
+        key: &str,
+        seed: T,
+        attrs: FieldAttributes,
+    ) -> Result<(), Self::Error>
+    where
+        T: WriteTypeSeed<S>,
+    {
+        // A field inside a field that already has a type.
+        let parent = self.frames.last().unwrap();
+        if let (Some(key), Some(_)) = (&parent.key, &parent.ty) {
+            return Err(Error::MultipleTypes {
+                key: key.to_owned(),
+            });
+        }
+
+        self.frames.push(Frame {
+            key: Some(key.to_owned()),
+            ..Default::default()
+        });
+
+        seed.write_type(self)?;
+
+        let mut frame = self.frames.pop().unwrap();
+        let node = match frame.ty.take() {
+            Some(node) => node,
+            None if !frame.properties.is_empty() => Node::Object(Box::new(frame)),
+            None => {
+                return Err(Error::MissingType {
+                    key: key.to_owned(),
+                })
+            }
+        };
+
+        self.frames
+            .last_mut()
+            .unwrap()
+            .properties
+            .push((key.to_owned(), node, attrs));
+
+        Ok(())
+    }
+}
+
+fn write_indent(buf: &mut String, indent: usize) {
+    for _ in 0..indent {
+        buf.push_str("  ");
+    }
+}
+
+fn write_key(buf: &mut String, indent: usize, key: &str) {
+    write_indent(buf, indent);
+    write_str(buf, key);
+    buf.push_str(": ");
+}
+
+/// Writes `s` as an escaped JSON string.
+fn write_str(buf: &mut String, s: &str) {
+    buf.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(buf, "\\u{:04x}", c as u32);
+            }
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
@@ -37,19 +37,102 @@
 //! }
 //! ```
 //!
-use std::{error::Error as StdError, fmt::Display};
+//! ### Field attributes
+//!
+//! - `#[datastore(primary_key)]`
+//!
+//! Marks the field as (part of) the primary key of the data.
+//!
+//! - `#[datastore(index)]`
+//!
+//! Marks the field as indexed.
+//!
+//! - `#[datastore(serde)]`
+//!
+//! Writes and reads the field through its `serde` implementation using [`serde::Serde`]. Requires
+//! the `serde` feature. `None` writes nothing, so an `Option<T>` field that is missing is read as
+//! `None`. Any other error reading the field is returned.
+//!
+//! Fields with an `Option<T>` type are additionally marked as nullable. The attributes of a field
+//! are passed to [`TypeWriter::write_field_with`] as [`FieldAttributes`].
+//!
+//! ###### Examples
+//!
+//! ```
+//! # use datastore::StoreData;
+//! #[derive(StoreData)]
+//! struct Person {
+//!     #[datastore(primary_key)]
+//!     id: i64,
+//!     #[datastore(index)]
+//!     name: String,
+//! }
+//! ```
+//!
+use std::{error::Error as StdError, fmt::Display, marker::PhantomData};
 
 use async_trait::async_trait;
 
 #[cfg(feature = "derive")]
 pub use datastore_derive::StoreData;
 
+pub use capabilities::Capabilities;
+pub use options::ConnectOptions;
+pub use registry::connect;
+
+pub mod binary;
+pub mod cache;
+pub mod capabilities;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod csv;
+pub mod dynamic;
+#[cfg(feature = "tracing")]
+pub mod instrument;
+pub mod json;
+pub mod json_schema;
+pub mod metrics;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod namespace;
+pub mod options;
+pub mod pool;
+pub mod read_only;
+pub mod registry;
+pub mod replica;
+#[cfg(feature = "tokio")]
+pub mod retry;
+#[cfg(feature = "serde")]
+pub mod serde;
+pub mod shard;
+mod shared;
+pub mod sql;
+pub mod value;
+
 /// An error that can occur when reading or writing a type from a [`Store`].
 pub trait Error: StdError {
     /// Creates a new custom `Error` with the given `msg`.
     fn custom<T>(msg: T) -> Self
     where
         T: Display;
+
+    /// Returns `true` if the error is temporary and the operation may succeed when it is
+    /// retried, e.g. because the connection was reset or the backend is busy.
+    ///
+    /// The default implementation returns `false`.
+    #[inline]
+    fn is_transient(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if the error was caused by reading a field that does not exist.
+    ///
+    /// Optional fields use this to tell a field that was never written apart from a field that
+    /// cannot be read. The default implementation returns `false`.
+    #[inline]
+    fn is_missing_field(&self) -> bool {
+        false
+    }
 }
 
 /// A store for associated [`StoreData`] types.
@@ -70,6 +153,21 @@ pub trait Store: Sized + Send + Sync {
     /// ```
     async fn connect(uri: &str) -> Result<Self, Self::Error>;
 
+    /// Connects to the store using the given [`ConnectOptions`].
+    ///
+    /// The default implementation calls [`connect`] with the URI written by the `options`. Stores
+    /// should override this method to read the options directly.
+    ///
+    /// This method is defined as:
+    /// ```ignore
+    /// async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error>;
+    /// ```
+    ///
+    /// [`connect`]: Self::connect
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        Self::connect(&options.to_string()).await
+    }
+
     /// Initializes the store for storing data of the type `T`. If `create` was not called before
     /// calling [`delete`], [`get`], [`get_all`], [`get_one`] or [`insert`] on the store, the
     /// operation might fail.
@@ -175,6 +273,24 @@ pub trait Store: Sized + Send + Sync {
     where
         T: StoreData<Self::DataStore> + Send + Sync + 'static,
         D: DataDescriptor<T, Self::DataStore> + Send;
+
+    /// Checks whether the store is still usable, e.g. whether the connection to a remote server is
+    /// still alive. The default implementation always succeeds.
+    ///
+    /// This method is defined as:
+    /// ```ignore
+    /// async fn ping(&self) -> Result<(), Self::Error>;
+    /// ```
+    async fn ping(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Returns the optional features supported by the store. The default implementation only
+    /// returns [`Capabilities::WRITE`].
+    #[inline]
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::WRITE
+    }
 }
 
 /// An extension trait for [`Store`].
@@ -241,6 +357,21 @@ where
     fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
     where
         W: TypeWriter<S>;
+
+    /// Deserializes a new `T` described by this descriptor from the [`Reader`].
+    ///
+    /// Stores should read items through this method instead of [`StoreData::read`] directly.
+    /// This allows types like [`Record`] to read fields that are only known at runtime. The
+    /// default implementation calls [`StoreData::read`].
+    ///
+    /// [`Record`]: value::Record
+    #[inline]
+    fn read<R>(&self, reader: &mut R) -> Result<T, R::Error>
+    where
+        R: Reader<S>,
+    {
+        T::read(reader)
+    }
 }
 
 /// A query type for an associated [`StoreData`] type.
@@ -262,7 +393,7 @@ pub trait Writer<S>
 where
     S: Store,
 {
-    type Error;
+    type Error: Error;
 
     /// Writes a `bool` value.
     fn write_bool(&mut self, v: bool) -> Result<(), Self::Error>;
@@ -304,7 +435,7 @@ where
     fn write_str(&mut self, v: &str) -> Result<(), Self::Error>;
 
     /// Writes a field with the key `key` and the value `T`.
-    fn write_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
     where
         T: ?Sized + Write<S>;
 }
@@ -314,7 +445,7 @@ pub trait Reader<S>
 where
     S: Store,
 {
-    type Error;
+    type Error: Error;
 
     /// Reads a `bool` value from the `Reader`.
     fn read_bool(&mut self) -> Result<bool, Self::Error>;
@@ -356,9 +487,22 @@ where
     fn read_string(&mut self) -> Result<String, Self::Error>;
 
     /// Reads the field with the given `key` and the value `T` from the `Reader`.
-    fn read_field<T>(&mut self, key: &'static str) -> Result<T, Self::Error>
+    ///
+    /// The default implementation calls [`read_field_seed`] with the [`ReadSeed`] of `T`.
+    ///
+    /// [`read_field_seed`]: Self::read_field_seed
+    #[inline]
+    fn read_field<T>(&mut self, key: &str) -> Result<T, Self::Error>
     where
-        T: Sized + Read<S>;
+        T: Sized + Read<S>,
+    {
+        self.read_field_seed(key, PhantomData::<T>)
+    }
+
+    /// Reads the field with the given `key` using the [`ReadSeed`] `seed` from the `Reader`.
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>;
 }
 
 /// A writer for field types.
@@ -370,7 +514,7 @@ pub trait TypeWriter<S>
 where
     S: Store,
 {
-    type Error;
+    type Error: Error;
 
     /// Writes the `bool` type into the `TypeWriter`.
     fn write_bool(&mut self) -> Result<(), Self::Error>;
@@ -412,9 +556,109 @@ where
     fn write_str(&mut self) -> Result<(), Self::Error>;
 
     /// Writes a field with the given `key` and type `T` into the `TypeWriter`.
-    fn write_field<T>(&mut self, key: &'static str) -> Result<(), Self::Error>
+    ///
+    /// The default implementation calls [`write_field_with`] with empty [`FieldAttributes`].
+    ///
+    /// [`write_field_with`]: Self::write_field_with
+    #[inline]
+    fn write_field<T>(&mut self, key: &str) -> Result<(), Self::Error>
     where
-        T: ?Sized + Write<S>;
+        T: ?Sized + Write<S>,
+    {
+        self.write_field_with::<T>(key, FieldAttributes::new())
+    }
+
+    /// Writes a field with the given `key`, type `T` and [`FieldAttributes`] into the
+    /// `TypeWriter`.
+    ///
+    /// The default implementation calls [`write_field_seed`] with the [`WriteTypeSeed`] of `T`.
+    ///
+    /// [`write_field_seed`]: Self::write_field_seed
+    #[inline]
+    fn write_field_with<T>(&mut self, key: &str, attrs: FieldAttributes) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<S>,
+    {
+        self.write_field_seed(key, PhantomData::<T>, attrs)
+    }
+
+    /// Writes a field with the given `key`, the type written by the [`WriteTypeSeed`] `seed` and
+    /// [`FieldAttributes`] into the `TypeWriter`.
+    fn write_field_seed<T>(
+        &mut self,
+        key: &str,
+        seed: T,
+        attrs: FieldAttributes,
+    ) -> Result<(), Self::Error>
+    where
+        T: WriteTypeSeed<S>;
+}
+
+/// Additional attributes of a field written into a [`TypeWriter`].
+///
+/// ```
+/// # use datastore::FieldAttributes;
+/// let attrs = FieldAttributes::new().primary_key(true);
+///
+/// assert!(attrs.is_primary_key());
+/// assert!(!attrs.is_index());
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FieldAttributes {
+    primary_key: bool,
+    index: bool,
+    nullable: bool,
+}
+
+impl FieldAttributes {
+    /// Creates a new `FieldAttributes` with all attributes unset.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            primary_key: false,
+            index: false,
+            nullable: false,
+        }
+    }
+
+    /// Sets whether the field is (part of) the primary key.
+    #[inline]
+    pub const fn primary_key(mut self, primary_key: bool) -> Self {
+        self.primary_key = primary_key;
+        self
+    }
+
+    /// Sets whether the field is indexed.
+    #[inline]
+    pub const fn index(mut self, index: bool) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Sets whether the field can contain no value.
+    #[inline]
+    pub const fn nullable(mut self, nullable: bool) -> Self {
+        self.nullable = nullable;
+        self
+    }
+
+    /// Returns `true` if the field is (part of) the primary key.
+    #[inline]
+    pub const fn is_primary_key(&self) -> bool {
+        self.primary_key
+    }
+
+    /// Returns `true` if the field is indexed.
+    #[inline]
+    pub const fn is_index(&self) -> bool {
+        self.index
+    }
+
+    /// Returns `true` if the field can contain no value.
+    #[inline]
+    pub const fn is_nullable(&self) -> bool {
+        self.nullable
+    }
 }
 
 /// A type that can be written into a field in the [`Store`] `S`.
@@ -443,3 +687,63 @@ where
     where
         R: Reader<S>;
 }
+
+/// A stateful variant of [`Read`], reading a value whose structure is only known at runtime.
+///
+/// Every [`Read`] type `T` is read by the seed `PhantomData<T>`.
+pub trait ReadSeed<S>: Sized
+where
+    S: Store,
+{
+    /// The type of the value read by the seed.
+    type Value;
+
+    /// Reads the value from the [`Reader`].
+    fn read<R>(self, reader: &mut R) -> Result<Self::Value, R::Error>
+    where
+        R: Reader<S>;
+}
+
+impl<S, T> ReadSeed<S> for PhantomData<T>
+where
+    S: Store,
+    T: Read<S>,
+{
+    type Value = T;
+
+    #[inline]
+    fn read<R>(self, reader: &mut R) -> Result<Self::Value, R::Error>
+    where
+        R: Reader<S>,
+    {
+        T::read(reader)
+    }
+}
+
+/// A stateful variant of [`Write::write_type`], writing a type whose structure is only known at
+/// runtime.
+///
+/// The type of every [`Write`] type `T` is written by the seed `PhantomData<T>`.
+pub trait WriteTypeSeed<S>
+where
+    S: Store,
+{
+    /// Writes the type into the [`TypeWriter`].
+    fn write_type<W>(self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>;
+}
+
+impl<S, T> WriteTypeSeed<S> for PhantomData<T>
+where
+    S: Store,
+    T: ?Sized + Write<S>,
+{
+    #[inline]
+    fn write_type<W>(self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        T::write_type(writer)
+    }
+}
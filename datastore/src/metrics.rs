@@ -0,0 +1,522 @@
+//! Usage metrics for any [`Store`].
+//!
+//! [`Metered`] wraps a store and counts the calls, errors and returned rows of every operation
+//! per [`ident`]. The latency of all calls is recorded in a histogram. The current values are
+//! returned by [`Metered::snapshot`] and can be rendered in the Prometheus text exposition
+//! format:
+//!
+//! ```
+//! use datastore::metrics::{Metered, Operation};
+//! # use datastore::Store;
+//!
+//! # async fn run<SqliteStore: Store>() -> Result<(), SqliteStore::Error> {
+//! let store = Metered::new(SqliteStore::connect("sqlite://data.db").await?);
+//!
+//! // Use the store...
+//!
+//! let snapshot = store.snapshot();
+//! if let Some(metrics) = snapshot.get(Operation::Get, "Person") {
+//!     println!("{} calls, {} errors", metrics.calls(), metrics.errors());
+//! }
+//!
+//! let body = snapshot.to_prometheus();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The rendered metrics are labeled with `operation` and `ident`:
+//!
+//! | Metric                              | Type      | Content                                |
+//! | ----------------------------------- | --------- | -------------------------------------- |
+//! | `datastore_calls_total`             | counter   | The number of calls.                   |
+//! | `datastore_errors_total`            | counter   | The number of calls returning an error. |
+//! | `datastore_rows_total`              | counter   | The number of items returned by reads. |
+//! | `datastore_call_duration_seconds`   | histogram | The latency of calls.                  |
+//!
+//! [`ident`]: DataDescriptor::ident
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter, Write as _};
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::options::ConnectOptions;
+use crate::{Capabilities, DataDescriptor, DataQuery, Store, StoreData};
+
+/// The default histogram buckets in seconds. These are the default buckets of the Prometheus
+/// client libraries.
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// A [`Store`] recording metrics about all operations of the inner store `S`.
+#[derive(Debug)]
+pub struct Metered<S> {
+    store: S,
+    buckets: Vec<Duration>,
+    series: Mutex<BTreeMap<(String, Operation), Series>>,
+}
+
+impl<S> Metered<S> {
+    /// Creates a new `Metered` store wrapping `store`. Latencies are recorded in buckets from 5ms
+    /// to 10s.
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            buckets: BUCKETS
+                .iter()
+                .copied()
+                .map(Duration::from_secs_f64)
+                .collect(),
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Sets the upper bounds of the latency histogram buckets. The bounds are sorted and
+    /// deduplicated. All metrics recorded before are reset.
+    pub fn with_buckets<I>(mut self, buckets: I) -> Self
+    where
+        I: IntoIterator<Item = Duration>,
+    {
+        self.buckets = buckets.into_iter().collect();
+        self.buckets.sort();
+        self.buckets.dedup();
+
+        self.reset();
+        self
+    }
+
+    /// Returns a reference to the inner store.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.store
+    }
+
+    /// Consumes the `Metered` store, returning the inner store.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    /// Returns the current values of all metrics, ordered by ident and operation.
+    pub fn snapshot(&self) -> Snapshot {
+        let series = self.lock();
+
+        let metrics = series
+            .iter()
+            .map(|((ident, operation), series)| {
+                let mut count = 0;
+                let buckets = self
+                    .buckets
+                    .iter()
+                    .zip(&series.buckets)
+                    .map(|(bound, n)| {
+                        count += n;
+                        (*bound, count)
+                    })
+                    .collect();
+
+                Metrics {
+                    operation: *operation,
+                    ident: ident.clone(),
+                    calls: series.calls,
+                    errors: series.errors,
+                    rows: series.rows,
+                    latency: Histogram {
+                        buckets,
+                        count: series.calls,
+                        sum: series.sum,
+                    },
+                }
+            })
+            .collect();
+
+        Snapshot { metrics }
+    }
+
+    /// Resets all metrics.
+    pub fn reset(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<(String, Operation), Series>> {
+        self.series.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Runs `future` and records its latency, result and the number of rows returned by `rows`.
+    async fn measure<F, T, E>(
+        &self,
+        operation: Operation,
+        ident: &str,
+        future: F,
+        rows: fn(&T) -> u64,
+    ) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        let start = Instant::now();
+        let res = future.await;
+        let elapsed = start.elapsed();
+
+        let mut guard = self.lock();
+        let series = guard
+            .entry((ident.to_owned(), operation))
+            .or_insert_with(|| Series {
+                buckets: vec![0; self.buckets.len()],
+                ..Default::default()
+            });
+
+        series.calls += 1;
+        series.sum += elapsed;
+        match &res {
+            Ok(value) => series.rows += rows(value),
+            Err(_) => series.errors += 1,
+        }
+
+        // Values above the last bound are only counted in the implicit `+Inf` bucket.
+        if let Some(index) = self.buckets.iter().position(|bound| elapsed <= *bound) {
+            series.buckets[index] += 1;
+        }
+
+        drop(guard);
+        res
+    }
+}
+
+#[async_trait]
+impl<S> Store for Metered<S>
+where
+    S: Store,
+{
+    type DataStore = S::DataStore;
+    type Error = S::Error;
+
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        S::connect(uri).await.map(Self::new)
+    }
+
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        S::connect_with(options).await.map(Self::new)
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let ident = descriptor.ident().to_owned();
+        let future = self.store.create(descriptor);
+
+        self.measure(Operation::Create, &ident, future, |_| 0).await
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let ident = descriptor.ident().to_owned();
+        let future = self.store.delete(descriptor, query);
+
+        self.measure(Operation::Delete, &ident, future, |_| 0).await
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let ident = descriptor.ident().to_owned();
+        let future = self.store.get(descriptor, query);
+
+        self.measure(Operation::Get, &ident, future, |items| items.len() as u64)
+            .await
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let ident = descriptor.ident().to_owned();
+        let future = self.store.get_all(descriptor);
+
+        self.measure(Operation::GetAll, &ident, future, |items| {
+            items.len() as u64
+        })
+        .await
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let ident = descriptor.ident().to_owned();
+        let future = self.store.get_one(descriptor, query);
+
+        self.measure(Operation::GetOne, &ident, future, |item| {
+            u64::from(item.is_some())
+        })
+        .await
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let ident = descriptor.ident().to_owned();
+        let future = self.store.insert(descriptor, data);
+
+        self.measure(Operation::Insert, &ident, future, |_| 0).await
+    }
+
+    /// Pings the inner store. Pings are recorded with an empty ident.
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.measure(Operation::Ping, "", self.store.ping(), |_| 0)
+            .await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.store.capabilities()
+    }
+}
+
+/// An operation of a [`Store`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Operation {
+    Create,
+    Delete,
+    Get,
+    GetAll,
+    GetOne,
+    Insert,
+    Ping,
+}
+
+impl Operation {
+    /// Returns the name of the [`Store`] method of the operation, e.g. `get_all`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Delete => "delete",
+            Self::Get => "get",
+            Self::GetAll => "get_all",
+            Self::GetOne => "get_one",
+            Self::Insert => "insert",
+            Self::Ping => "ping",
+        }
+    }
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The values of all metrics of a [`Metered`] store at one point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    metrics: Vec<Metrics>,
+}
+
+impl Snapshot {
+    /// Returns the metrics of the `operation` on the type with the `ident`. Returns `None` if the
+    /// operation was never called for the type.
+    pub fn get(&self, operation: Operation, ident: &str) -> Option<&Metrics> {
+        self.metrics
+            .iter()
+            .find(|metrics| metrics.operation == operation && metrics.ident == ident)
+    }
+
+    /// Returns an iterator over the metrics of all operations and idents.
+    pub fn iter(&self) -> impl Iterator<Item = &Metrics> + '_ {
+        self.metrics.iter()
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut buf = String::new();
+
+        self.write_counter(
+            &mut buf,
+            "datastore_calls_total",
+            "The number of calls to the store.",
+            Metrics::calls,
+        );
+        self.write_counter(
+            &mut buf,
+            "datastore_errors_total",
+            "The number of calls to the store returning an error.",
+            Metrics::errors,
+        );
+        self.write_counter(
+            &mut buf,
+            "datastore_rows_total",
+            "The number of items returned by the store.",
+            Metrics::rows,
+        );
+
+        let name = "datastore_call_duration_seconds";
+        writeln!(buf, "# HELP {} The latency of calls to the store.", name).unwrap();
+        writeln!(buf, "# TYPE {} histogram", name).unwrap();
+        for metrics in &self.metrics {
+            let labels = Labels(metrics);
+            let latency = &metrics.latency;
+
+            for (bound, count) in &latency.buckets {
+                writeln!(
+                    buf,
+                    "{}_bucket{{{},le=\"{}\"}} {}",
+                    name,
+                    labels,
+                    bound.as_secs_f64(),
+                    count
+                )
+                .unwrap();
+            }
+
+            writeln!(
+                buf,
+                "{}_bucket{{{},le=\"+Inf\"}} {}",
+                name, labels, latency.count
+            )
+            .unwrap();
+            writeln!(
+                buf,
+                "{}_sum{{{}}} {}",
+                name,
+                labels,
+                latency.sum.as_secs_f64()
+            )
+            .unwrap();
+            writeln!(buf, "{}_count{{{}}} {}", name, labels, latency.count).unwrap();
+        }
+
+        buf
+    }
+
+    fn write_counter(&self, buf: &mut String, name: &str, help: &str, value: fn(&Metrics) -> u64) {
+        writeln!(buf, "# HELP {} {}", name, help).unwrap();
+        writeln!(buf, "# TYPE {} counter", name).unwrap();
+        for metrics in &self.metrics {
+            writeln!(buf, "{}{{{}}} {}", name, Labels(metrics), value(metrics)).unwrap();
+        }
+    }
+}
+
+/// The metrics of one operation on one type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metrics {
+    operation: Operation,
+    ident: String,
+    calls: u64,
+    errors: u64,
+    rows: u64,
+    latency: Histogram,
+}
+
+impl Metrics {
+    /// Returns the operation.
+    #[inline]
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// Returns the ident of the type. This is empty for [`Operation::Ping`].
+    #[inline]
+    pub fn ident(&self) -> &str {
+        &self.ident
+    }
+
+    /// Returns the number of calls, including failed calls.
+    #[inline]
+    pub fn calls(&self) -> u64 {
+        self.calls
+    }
+
+    /// Returns the number of calls that returned an error.
+    #[inline]
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Returns the number of items returned by [`Operation::Get`], [`Operation::GetAll`] and
+    /// [`Operation::GetOne`]. This is always `0` for other operations.
+    #[inline]
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// Returns the latency histogram of all calls.
+    #[inline]
+    pub fn latency(&self) -> &Histogram {
+        &self.latency
+    }
+}
+
+/// A histogram of call latencies.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    buckets: Vec<(Duration, u64)>,
+    count: u64,
+    sum: Duration,
+}
+
+impl Histogram {
+    /// Returns the upper bound of every bucket together with the number of calls that took at most
+    /// that long. The counts are cumulative.
+    #[inline]
+    pub fn buckets(&self) -> &[(Duration, u64)] {
+        &self.buckets
+    }
+
+    /// Returns the number of recorded calls.
+    #[inline]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the total duration of all recorded calls.
+    #[inline]
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    calls: u64,
+    errors: u64,
+    rows: u64,
+    /// The number of calls per bucket. These counts are not cumulative.
+    buckets: Vec<u64>,
+    sum: Duration,
+}
+
+/// Formats the labels of [`Metrics`].
+struct Labels<'a>(&'a Metrics);
+
+impl<'a> Display for Labels<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "operation=\"{}\",ident=\"", self.0.operation)?;
+
+        for c in self.0.ident.chars() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                '"' => f.write_str("\\\"")?,
+                '\n' => f.write_str("\\n")?,
+                c => f.write_char(c)?,
+            }
+        }
+
+        f.write_char('"')
+    }
+}
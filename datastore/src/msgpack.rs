@@ -0,0 +1,680 @@
+//! MessagePack encoding for [`StoreData`] types.
+//!
+//! This module requires the `msgpack` feature.
+//!
+//! [`MsgPackWriter`] encodes a [`StoreData`] type as a [MessagePack] map keyed by field name and
+//! [`MsgPackReader`] decodes it back:
+//!
+//! ```
+//! # use datastore::{Store, StoreData};
+//! # fn roundtrip<T, S>(data: &T) -> T
+//! # where
+//! #     T: StoreData<S>,
+//! #     S: Store,
+//! # {
+//! let buf = datastore::msgpack::to_vec(data).unwrap();
+//! datastore::msgpack::from_slice(&buf).unwrap()
+//! # }
+//! ```
+//!
+//! Every `write_*` call maps to the corresponding format family: `bool` to `true`/`false`,
+//! integers to the int family, `f32` to `float 32`, `f64` to `float 64`, bytes to the bin family
+//! and strings to the str family. Integers and lengths use the shortest format that can represent
+//! the value, non-negative signed integers use the unsigned formats. Fields become the entries of
+//! a map with str keys. Fields whose value writes nothing are omitted.
+//!
+//! The reader accepts any well-formed MessagePack object. Fields are looked up by key regardless
+//! of their order.
+//!
+//! [MessagePack]: https://github.com/msgpack/msgpack/blob/master/spec.md
+//! [`StoreData`]: crate::StoreData
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+
+use crate::{ReadSeed, Reader, Store, StoreData, Write, Writer};
+
+/// The maximum nesting depth accepted by the reader.
+const MAX_DEPTH: usize = 128;
+
+/// Serializes the [`StoreData`] `data` into a new buffer.
+pub fn to_vec<T, S>(data: &T) -> Result<Vec<u8>, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    let mut writer = MsgPackWriter::new();
+    data.write(&mut writer)?;
+    Ok(writer.finish())
+}
+
+/// Deserializes a new [`StoreData`] from the buffer `buf`.
+pub fn from_slice<T, S>(buf: &[u8]) -> Result<T, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    T::read(&mut MsgPackReader::new(buf)?)
+}
+
+fn write_unsigned(buf: &mut Vec<u8>, v: u64) {
+    if v < 0x80 {
+        buf.push(v as u8);
+    } else if let Ok(v) = u8::try_from(v) {
+        buf.extend_from_slice(&[0xcc, v]);
+    } else if let Ok(v) = u16::try_from(v) {
+        buf.push(0xcd);
+        buf.extend_from_slice(&v.to_be_bytes());
+    } else if let Ok(v) = u32::try_from(v) {
+        buf.push(0xce);
+        buf.extend_from_slice(&v.to_be_bytes());
+    } else {
+        buf.push(0xcf);
+        buf.extend_from_slice(&v.to_be_bytes());
+    }
+}
+
+fn write_signed(buf: &mut Vec<u8>, v: i64) {
+    if v >= 0 {
+        write_unsigned(buf, v as u64);
+    } else if v >= -32 {
+        buf.push(v as u8);
+    } else if let Ok(v) = i8::try_from(v) {
+        buf.extend_from_slice(&[0xd0, v as u8]);
+    } else if let Ok(v) = i16::try_from(v) {
+        buf.push(0xd1);
+        buf.extend_from_slice(&v.to_be_bytes());
+    } else if let Ok(v) = i32::try_from(v) {
+        buf.push(0xd2);
+        buf.extend_from_slice(&v.to_be_bytes());
+    } else {
+        buf.push(0xd3);
+        buf.extend_from_slice(&v.to_be_bytes());
+    }
+}
+
+/// Writes a length using the `fix` format if the length is below `fix_max` or one of the 8, 16
+/// or 32 bit formats `formats`. The 8 bit format is skipped if it is `None`.
+fn write_len(buf: &mut Vec<u8>, len: usize, fix: u8, fix_max: usize, formats: [Option<u8>; 3]) {
+    if len < fix_max {
+        buf.push(fix | len as u8);
+    } else if let (Some(format), Ok(len)) = (formats[0], u8::try_from(len)) {
+        buf.extend_from_slice(&[format, len]);
+    } else if let (Some(format), Ok(len)) = (formats[1], u16::try_from(len)) {
+        buf.push(format);
+        buf.extend_from_slice(&len.to_be_bytes());
+    } else if let Some(format) = formats[2] {
+        buf.push(format);
+        buf.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+fn write_str_header(buf: &mut Vec<u8>, len: usize) {
+    write_len(buf, len, 0xa0, 32, [Some(0xd9), Some(0xda), Some(0xdb)]);
+}
+
+/// A [`Writer`] encoding MessagePack.
+///
+/// See the [module documentation] for the format.
+///
+/// [module documentation]: self
+#[derive(Clone, Debug, Default)]
+pub struct MsgPackWriter {
+    buf: Vec<u8>,
+    state: State,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+enum State {
+    #[default]
+    Empty,
+    /// A map with the given number of entries.
+    Map(usize),
+    Value,
+}
+
+impl MsgPackWriter {
+    /// Creates a new, empty `MsgPackWriter`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Completes the object, returning the encoded buffer. If nothing was written an empty map is
+    /// returned.
+    pub fn finish(self) -> Vec<u8> {
+        self.finish_value().unwrap_or_else(|| vec![0x80])
+    }
+
+    /// Completes a field value. Returns `None` if nothing was written.
+    fn finish_value(self) -> Option<Vec<u8>> {
+        match self.state {
+            State::Empty => None,
+            State::Map(len) => {
+                let mut buf = Vec::with_capacity(self.buf.len() + 5);
+                write_len(&mut buf, len, 0x80, 16, [None, Some(0xde), Some(0xdf)]);
+                buf.extend_from_slice(&self.buf);
+                Some(buf)
+            }
+            State::Value => Some(self.buf),
+        }
+    }
+
+    fn write_value<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Vec<u8>),
+    {
+        match self.state {
+            State::Empty => {
+                f(&mut self.buf);
+                self.state = State::Value;
+                Ok(())
+            }
+            _ => Err(Error::MultipleValues),
+        }
+    }
+
+    fn check_len(len: usize) -> Result<(), Error> {
+        match u32::try_from(len) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(Error::LengthOverflow),
+        }
+    }
+}
+
+impl<S> Writer<S> for MsgPackWriter
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self, v: bool) -> Result<(), Self::Error> {
+        self.write_value(|buf| buf.push(if v { 0xc3 } else { 0xc2 }))
+    }
+
+    fn write_i8(&mut self, v: i8) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_signed(buf, i64::from(v)))
+    }
+
+    fn write_i16(&mut self, v: i16) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_signed(buf, i64::from(v)))
+    }
+
+    fn write_i32(&mut self, v: i32) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_signed(buf, i64::from(v)))
+    }
+
+    fn write_i64(&mut self, v: i64) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_signed(buf, v))
+    }
+
+    fn write_u8(&mut self, v: u8) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_unsigned(buf, u64::from(v)))
+    }
+
+    fn write_u16(&mut self, v: u16) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_unsigned(buf, u64::from(v)))
+    }
+
+    fn write_u32(&mut self, v: u32) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_unsigned(buf, u64::from(v)))
+    }
+
+    fn write_u64(&mut self, v: u64) -> Result<(), Self::Error> {
+        self.write_value(|buf| write_unsigned(buf, v))
+    }
+
+    fn write_f32(&mut self, v: f32) -> Result<(), Self::Error> {
+        self.write_value(|buf| {
+            buf.push(0xca);
+            buf.extend_from_slice(&v.to_be_bytes());
+        })
+    }
+
+    fn write_f64(&mut self, v: f64) -> Result<(), Self::Error> {
+        self.write_value(|buf| {
+            buf.push(0xcb);
+            buf.extend_from_slice(&v.to_be_bytes());
+        })
+    }
+
+    fn write_bytes(&mut self, v: &[u8]) -> Result<(), Self::Error> {
+        Self::check_len(v.len())?;
+        self.write_value(|buf| {
+            write_len(buf, v.len(), 0, 0, [Some(0xc4), Some(0xc5), Some(0xc6)]);
+            buf.extend_from_slice(v);
+        })
+    }
+
+    fn write_str(&mut self, v: &str) -> Result<(), Self::Error> {
+        Self::check_len(v.len())?;
+        self.write_value(|buf| {
+            write_str_header(buf, v.len());
+            buf.extend_from_slice(v.as_bytes());
+        })
+    }
+
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<S>,
+    {
+        let len = match self.state {
+            State::Empty => 0,
+            State::Map(len) => len,
+            State::Value => return Err(Error::MultipleValues),
+        };
+        Self::check_len(len + 1)?;
+
+        let mut writer = MsgPackWriter::new();
+        value.write(&mut writer)?;
+
+        if let Some(value) = writer.finish_value() {
+            write_str_header(&mut self.buf, key.len());
+            self.buf.extend_from_slice(key.as_bytes());
+            self.buf.extend_from_slice(&value);
+            self.state = State::Map(len + 1);
+        } else {
+            self.state = State::Map(len);
+        }
+
+        Ok(())
+    }
+}
+
+/// A decoded MessagePack object.
+#[derive(Clone, Debug)]
+enum Object {
+    Nil,
+    Bool(bool),
+    Int(i128),
+    F32(f32),
+    F64(f64),
+    Bin(Vec<u8>),
+    Str(String),
+    /// An array or extension. These are never read, therefore their contents are discarded.
+    Other,
+    Map(Vec<(Object, Object)>),
+}
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.buf.len() - self.pos < len {
+            return Err(Error::UnexpectedEof);
+        }
+
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Error> {
+        self.read_array().map(u16::from_be_bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        self.read_array().map(u32::from_be_bytes)
+    }
+
+    fn read_object(&mut self) -> Result<Object, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::DepthLimitExceeded);
+        }
+
+        self.depth += 1;
+        let object = self.read_object_inner();
+        self.depth -= 1;
+        object
+    }
+
+    fn read_object_inner(&mut self) -> Result<Object, Error> {
+        let b = self.read_u8()?;
+
+        match b {
+            0x00..=0x7f => Ok(Object::Int(i128::from(b))),
+            0x80..=0x8f => self.read_map(usize::from(b & 0x0f)),
+            0x90..=0x9f => self.read_other_array(usize::from(b & 0x0f)),
+            0xa0..=0xbf => self.read_str(usize::from(b & 0x1f)),
+            0xc0 => Ok(Object::Nil),
+            0xc2 => Ok(Object::Bool(false)),
+            0xc3 => Ok(Object::Bool(true)),
+            0xc4 => {
+                let len = usize::from(self.read_u8()?);
+                self.read_bin(len)
+            }
+            0xc5 => {
+                let len = usize::from(self.read_u16()?);
+                self.read_bin(len)
+            }
+            0xc6 => {
+                let len = self.read_u32()? as usize;
+                self.read_bin(len)
+            }
+            0xc7 => {
+                let len = usize::from(self.read_u8()?);
+                self.read_ext(len)
+            }
+            0xc8 => {
+                let len = usize::from(self.read_u16()?);
+                self.read_ext(len)
+            }
+            0xc9 => {
+                let len = self.read_u32()? as usize;
+                self.read_ext(len)
+            }
+            0xca => Ok(Object::F32(f32::from_be_bytes(self.read_array()?))),
+            0xcb => Ok(Object::F64(f64::from_be_bytes(self.read_array()?))),
+            0xcc => Ok(Object::Int(i128::from(self.read_u8()?))),
+            0xcd => Ok(Object::Int(i128::from(self.read_u16()?))),
+            0xce => Ok(Object::Int(i128::from(self.read_u32()?))),
+            0xcf => Ok(Object::Int(i128::from(u64::from_be_bytes(
+                self.read_array()?,
+            )))),
+            0xd0 => Ok(Object::Int(i128::from(i8::from_be_bytes(
+                self.read_array()?,
+            )))),
+            0xd1 => Ok(Object::Int(i128::from(i16::from_be_bytes(
+                self.read_array()?,
+            )))),
+            0xd2 => Ok(Object::Int(i128::from(i32::from_be_bytes(
+                self.read_array()?,
+            )))),
+            0xd3 => Ok(Object::Int(i128::from(i64::from_be_bytes(
+                self.read_array()?,
+            )))),
+            0xd4 => self.read_ext(1),
+            0xd5 => self.read_ext(2),
+            0xd6 => self.read_ext(4),
+            0xd7 => self.read_ext(8),
+            0xd8 => self.read_ext(16),
+            0xd9 => {
+                let len = usize::from(self.read_u8()?);
+                self.read_str(len)
+            }
+            0xda => {
+                let len = usize::from(self.read_u16()?);
+                self.read_str(len)
+            }
+            0xdb => {
+                let len = self.read_u32()? as usize;
+                self.read_str(len)
+            }
+            0xdc => {
+                let len = usize::from(self.read_u16()?);
+                self.read_other_array(len)
+            }
+            0xdd => {
+                let len = self.read_u32()? as usize;
+                self.read_other_array(len)
+            }
+            0xde => {
+                let len = usize::from(self.read_u16()?);
+                self.read_map(len)
+            }
+            0xdf => {
+                let len = self.read_u32()? as usize;
+                self.read_map(len)
+            }
+            0xe0..=0xff => Ok(Object::Int(i128::from(b as i8))),
+            // 0xc1 is never used.
+            _ => Err(Error::Malformed {
+                offset: self.pos - 1,
+            }),
+        }
+    }
+
+    fn read_bin(&mut self, len: usize) -> Result<Object, Error> {
+        self.read_bytes(len)
+            .map(|bytes| Object::Bin(bytes.to_vec()))
+    }
+
+    fn read_str(&mut self, len: usize) -> Result<Object, Error> {
+        let bytes = self.read_bytes(len)?;
+        match std::str::from_utf8(bytes) {
+            Ok(s) => Ok(Object::Str(s.to_owned())),
+            Err(_) => Err(Error::InvalidUtf8),
+        }
+    }
+
+    fn read_ext(&mut self, len: usize) -> Result<Object, Error> {
+        // The extension type followed by the data.
+        self.read_bytes(1 + len).map(|_| Object::Other)
+    }
+
+    fn read_other_array(&mut self, len: usize) -> Result<Object, Error> {
+        for _ in 0..len {
+            self.read_object()?;
+        }
+
+        Ok(Object::Other)
+    }
+
+    fn read_map(&mut self, len: usize) -> Result<Object, Error> {
+        // Every entry takes at least 2 bytes.
+        let mut map = Vec::with_capacity(len.min((self.buf.len() - self.pos) / 2));
+        for _ in 0..len {
+            map.push((self.read_object()?, self.read_object()?));
+        }
+
+        Ok(Object::Map(map))
+    }
+}
+
+/// A [`Reader`] decoding MessagePack.
+///
+/// The input is decoded when the `MsgPackReader` is created. Fields are looked up by key
+/// regardless of their order.
+#[derive(Clone, Debug)]
+pub struct MsgPackReader {
+    object: Object,
+}
+
+impl MsgPackReader {
+    /// Creates a new `MsgPackReader` by decoding a single object from `buf`.
+    ///
+    /// Returns an error if `buf` is not well-formed or contains bytes after the object.
+    pub fn new(buf: &[u8]) -> Result<Self, Error> {
+        let mut decoder = Decoder {
+            buf,
+            pos: 0,
+            depth: 0,
+        };
+
+        let object = decoder.read_object()?;
+        if decoder.pos != buf.len() {
+            return Err(Error::TrailingBytes);
+        }
+
+        Ok(Self { object })
+    }
+
+    fn take(&mut self) -> Object {
+        mem::replace(&mut self.object, Object::Nil)
+    }
+
+    fn read_int<T>(&mut self, expected: &'static str) -> Result<T, Error>
+    where
+        T: TryFrom<i128>,
+    {
+        match self.take() {
+            Object::Int(v) => T::try_from(v).map_err(|_| Error::InvalidValue { expected }),
+            _ => Err(Error::InvalidType { expected }),
+        }
+    }
+}
+
+impl<S> Reader<S> for MsgPackReader
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn read_bool(&mut self) -> Result<bool, Self::Error> {
+        match self.take() {
+            Object::Bool(v) => Ok(v),
+            _ => Err(Error::InvalidType { expected: "a bool" }),
+        }
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Self::Error> {
+        self.read_int("an i8")
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        self.read_int("an i16")
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        self.read_int("an i32")
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Self::Error> {
+        self.read_int("an i64")
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        self.read_int("an u8")
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        self.read_int("an u16")
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        self.read_int("an u32")
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Self::Error> {
+        self.read_int("an u64")
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Self::Error> {
+        match self.take() {
+            Object::F32(v) => Ok(v),
+            Object::F64(v) => Ok(v as f32),
+            _ => Err(Error::InvalidType { expected: "a f32" }),
+        }
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Self::Error> {
+        match self.take() {
+            Object::F32(v) => Ok(f64::from(v)),
+            Object::F64(v) => Ok(v),
+            _ => Err(Error::InvalidType { expected: "a f64" }),
+        }
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Self::Error> {
+        match self.take() {
+            Object::Bin(v) => Ok(v),
+            _ => Err(Error::InvalidType { expected: "a bin" }),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, Self::Error> {
+        match self.take() {
+            Object::Str(v) => Ok(v),
+            _ => Err(Error::InvalidType { expected: "a str" }),
+        }
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>,
+    {
+        let map = match &mut self.object {
+            Object::Map(map) => map,
+            _ => return Err(Error::InvalidType { expected: "a map" }),
+        };
+
+        let index = map
+            .iter()
+            .position(|(k, _)| matches!(k, Object::Str(k) if k == key))
+            .ok_or(Error::MissingField {
+                key: key.to_owned(),
+            })?;
+
+        let (_, object) = map.swap_remove(index);
+        seed.read(&mut MsgPackReader { object })
+    }
+}
+
+/// An error that can occur when encoding or decoding MessagePack.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The buffer ended unexpectedly.
+    UnexpectedEof,
+    /// The buffer contains bytes after the end of the object.
+    TrailingBytes,
+    /// The buffer is not well-formed MessagePack.
+    Malformed {
+        /// The byte offset of the malformed object.
+        offset: usize,
+    },
+    /// The buffer contains too deeply nested objects.
+    DepthLimitExceeded,
+    /// A string, byte buffer or map is longer than `u32::MAX`.
+    LengthOverflow,
+    /// An object has a different type than expected.
+    InvalidType { expected: &'static str },
+    /// An object is out of range for the expected type.
+    InvalidValue { expected: &'static str },
+    /// A str is not valid UTF-8.
+    InvalidUtf8,
+    /// The field `key` does not exist.
+    MissingField { key: String },
+    /// More than a single value was written.
+    MultipleValues,
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof => write!(f, "unexpected end of buffer"),
+            Self::TrailingBytes => write!(f, "trailing bytes after object"),
+            Self::Malformed { offset } => write!(f, "malformed object at offset {}", offset),
+            Self::DepthLimitExceeded => write!(f, "depth limit exceeded"),
+            Self::LengthOverflow => write!(f, "length exceeds u32::MAX"),
+            Self::InvalidType { expected } => write!(f, "invalid type, expected {}", expected),
+            Self::InvalidValue { expected } => write!(f, "invalid value, expected {}", expected),
+            Self::InvalidUtf8 => write!(f, "invalid utf-8"),
+            Self::MissingField { key } => write!(f, "missing field {:?}", key),
+            Self::MultipleValues => write!(f, "multiple values written"),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl crate::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+
+    #[inline]
+    fn is_missing_field(&self) -> bool {
+        matches!(self, Self::MissingField { .. })
+    }
+}
@@ -0,0 +1,536 @@
+//! Tenant isolation for any [`Store`].
+//!
+//! [`Namespaced`] wraps a store shared by many tenants and isolates the items of one tenant
+//! without changing the stored types. The tenant is applied in one of two ways:
+//!
+//! - [`Namespaced::prefix`] prepends a prefix to the [`ident`] of every descriptor, so every
+//!   tenant uses its own tables, directories or keys.
+//! - [`Namespaced::field`] adds a field containing the tenant to every type. The field is written
+//!   with every item, added to every query and becomes part of the primary key of types that
+//!   have one. All tenants share the same tables.
+//!
+//! ```
+//! use datastore::namespace::Namespaced;
+//! # use datastore::Store;
+//!
+//! # async fn run<SqliteStore: Store>() -> Result<(), SqliteStore::Error> {
+//! let store = Namespaced::prefix(SqliteStore::connect("sqlite://data.db").await?, "acme_");
+//!
+//! let store = Namespaced::field(SqliteStore::connect("sqlite://data.db").await?, "tenant", "acme");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Namespaced::connect_with`] reads the prefix from the `namespace_prefix` query parameter.
+//! [`Namespaced::connect`] parses the uri as [`ConnectOptions`] for the same purpose.
+//!
+//! [`ident`]: DataDescriptor::ident
+//! [`Namespaced::connect`]: Store::connect
+//! [`Namespaced::connect_with`]: Store::connect_with
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::options::ConnectOptions;
+use crate::{
+    Capabilities, DataDescriptor, DataQuery, Error, FieldAttributes, Reader, Store, StoreData,
+    TypeWriter, Write, WriteTypeSeed, Writer,
+};
+
+/// A [`Store`] isolating the items of a single tenant in the inner store `S`.
+#[derive(Debug)]
+pub struct Namespaced<S> {
+    store: S,
+    namespace: Namespace,
+}
+
+#[derive(Clone, Debug)]
+enum Namespace {
+    Prefix(String),
+    Field { key: &'static str, tenant: Arc<str> },
+}
+
+impl<S> Namespaced<S> {
+    /// Creates a new `Namespaced` store wrapping `store` that prepends `prefix` to the
+    /// [`ident`] of every descriptor.
+    ///
+    /// [`ident`]: DataDescriptor::ident
+    pub fn prefix<P>(store: S, prefix: P) -> Self
+    where
+        P: ToString,
+    {
+        Self {
+            store,
+            namespace: Namespace::Prefix(prefix.to_string()),
+        }
+    }
+
+    /// Creates a new `Namespaced` store wrapping `store` that adds the field `key` with the
+    /// value `tenant` to every type.
+    pub fn field<T>(store: S, key: &'static str, tenant: T) -> Self
+    where
+        T: ToString,
+    {
+        Self {
+            store,
+            namespace: Namespace::Field {
+                key,
+                tenant: tenant.to_string().into(),
+            },
+        }
+    }
+
+    /// Returns a reference to the inner store.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.store
+    }
+
+    /// Consumes the `Namespaced` store, returning the inner store.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+#[async_trait]
+impl<S> Store for Namespaced<S>
+where
+    S: Store,
+{
+    type DataStore = S::DataStore;
+    type Error = S::Error;
+
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        let options = ConnectOptions::parse(uri).map_err(S::Error::custom)?;
+        Self::connect_with(&options).await
+    }
+
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        let prefix = match options.get("namespace_prefix") {
+            Some(prefix) => prefix.to_owned(),
+            None => return Err(S::Error::custom("missing parameter namespace_prefix")),
+        };
+
+        let mut options = options.clone();
+        options.remove("namespace_prefix");
+
+        let store = S::connect_with(&options).await?;
+        Ok(Self::prefix(store, prefix))
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        match &self.namespace {
+            Namespace::Prefix(prefix) => self.store.create(Prefixed::new(prefix, descriptor)).await,
+            Namespace::Field { key, tenant } => {
+                let descriptor = TenantDescriptor::new(descriptor, key, tenant);
+                self.store.create(descriptor).await
+            }
+        }
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        match &self.namespace {
+            Namespace::Prefix(prefix) => {
+                let descriptor = Prefixed::new(prefix, descriptor);
+                self.store.delete(descriptor, query).await
+            }
+            Namespace::Field { key, tenant } => {
+                let descriptor = TenantDescriptor::new(descriptor, key, tenant);
+                let query = TenantQuery::new(Some(query), key, tenant);
+                self.store.delete(descriptor, query).await
+            }
+        }
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        match &self.namespace {
+            Namespace::Prefix(prefix) => {
+                let descriptor = Prefixed::new(prefix, descriptor);
+                self.store.get(descriptor, query).await
+            }
+            Namespace::Field { key, tenant } => {
+                let descriptor = TenantDescriptor::new(descriptor, key, tenant);
+                let query = TenantQuery::new(Some(query), key, tenant);
+                let items = self.store.get(descriptor, query).await?;
+                Ok(items.into_iter().map(|item| item.data).collect())
+            }
+        }
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        match &self.namespace {
+            Namespace::Prefix(prefix) => {
+                let descriptor = Prefixed::new(prefix, descriptor);
+                self.store.get_all(descriptor).await
+            }
+            Namespace::Field { key, tenant } => {
+                // Other tenants share the same table, so only the items matching the tenant
+                // field are returned.
+                let descriptor = TenantDescriptor::new(descriptor, key, tenant);
+                let query = TenantQuery::new(None::<NoQuery>, key, tenant);
+                let items = self.store.get(descriptor, query).await?;
+                Ok(items.into_iter().map(|item| item.data).collect())
+            }
+        }
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        match &self.namespace {
+            Namespace::Prefix(prefix) => {
+                let descriptor = Prefixed::new(prefix, descriptor);
+                self.store.get_one(descriptor, query).await
+            }
+            Namespace::Field { key, tenant } => {
+                let descriptor = TenantDescriptor::new(descriptor, key, tenant);
+                let query = TenantQuery::new(Some(query), key, tenant);
+                let item = self.store.get_one(descriptor, query).await?;
+                Ok(item.map(|item| item.data))
+            }
+        }
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        match &self.namespace {
+            Namespace::Prefix(prefix) => {
+                let descriptor = Prefixed::new(prefix, descriptor);
+                self.store.insert(descriptor, data).await
+            }
+            Namespace::Field { key, tenant } => {
+                let descriptor = TenantDescriptor::new(descriptor, key, tenant);
+                let data = Tenanted {
+                    data,
+                    key,
+                    tenant: tenant.clone(),
+                };
+                self.store.insert(descriptor, data).await
+            }
+        }
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.store.ping().await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.store.capabilities()
+    }
+}
+
+/// A descriptor with a prefixed [`ident`].
+///
+/// [`ident`]: DataDescriptor::ident
+struct Prefixed<D> {
+    inner: D,
+    ident: String,
+}
+
+impl<D> Prefixed<D> {
+    fn new<T, S>(prefix: &str, inner: D) -> Self
+    where
+        T: StoreData<S>,
+        S: Store,
+        D: DataDescriptor<T, S>,
+    {
+        Self {
+            ident: format!("{}{}", prefix, inner.ident()),
+            inner,
+        }
+    }
+}
+
+impl<T, S, D> DataDescriptor<T, S> for Prefixed<D>
+where
+    T: StoreData<S>,
+    S: Store,
+    D: DataDescriptor<T, S>,
+{
+    fn ident(&self) -> &str {
+        &self.ident
+    }
+
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        self.inner.write(writer)
+    }
+
+    fn read<R>(&self, reader: &mut R) -> Result<T, R::Error>
+    where
+        R: Reader<S>,
+    {
+        self.inner.read(reader)
+    }
+}
+
+/// An item of type `T` stored together with the tenant field.
+struct Tenanted<T> {
+    data: T,
+    key: &'static str,
+    tenant: Arc<str>,
+}
+
+impl<T, S> StoreData<S> for Tenanted<T>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    type Descriptor = TenantDescriptor<T::Descriptor>;
+    type Query = TenantQuery<T::Query>;
+
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        self.data.write(writer)?;
+        writer.write_field(self.key, &Tenant(&self.tenant))
+    }
+
+    // The tenant is only known to the `TenantDescriptor`, which reads the inner item directly.
+    // Rebuilding an item without it would silently drop the tenant field.
+    fn read<R>(_reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<S>,
+    {
+        Err(R::Error::custom(
+            "tenanted items can only be read through their descriptor",
+        ))
+    }
+}
+
+/// The value of the tenant field.
+struct Tenant<'a>(&'a str);
+
+impl<'a, S> Write<S> for Tenant<'a>
+where
+    S: Store,
+{
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        writer.write_str(self.0)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        writer.write_str()
+    }
+}
+
+/// The descriptor of a [`Tenanted`] type, wrapping the descriptor of the inner type.
+struct TenantDescriptor<D> {
+    inner: D,
+    key: &'static str,
+    tenant: Arc<str>,
+}
+
+impl<D> TenantDescriptor<D> {
+    fn new(inner: D, key: &'static str, tenant: &Arc<str>) -> Self {
+        Self {
+            inner,
+            key,
+            tenant: tenant.clone(),
+        }
+    }
+}
+
+impl<T, S, D> DataDescriptor<Tenanted<T>, S> for TenantDescriptor<D>
+where
+    T: StoreData<S>,
+    S: Store,
+    D: DataDescriptor<T, S>,
+{
+    fn ident(&self) -> &str {
+        self.inner.ident()
+    }
+
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        let mut fields = PrimaryKeys {
+            writer: &mut *writer,
+            primary_key: false,
+        };
+        self.inner.write(&mut fields)?;
+
+        // Items of different tenants may share the same primary key.
+        let attrs = FieldAttributes::new()
+            .primary_key(fields.primary_key)
+            .index(true);
+        writer.write_field_with::<Tenant<'static>>(self.key, attrs)
+    }
+
+    fn read<R>(&self, reader: &mut R) -> Result<Tenanted<T>, R::Error>
+    where
+        R: Reader<S>,
+    {
+        Ok(Tenanted {
+            data: self.inner.read(reader)?,
+            key: self.key,
+            tenant: self.tenant.clone(),
+        })
+    }
+}
+
+/// The query of a [`Tenanted`] type, matching the tenant field and the fields of the inner
+/// query.
+struct TenantQuery<Q> {
+    inner: Option<Q>,
+    key: &'static str,
+    tenant: Arc<str>,
+}
+
+impl<Q> TenantQuery<Q> {
+    fn new(inner: Option<Q>, key: &'static str, tenant: &Arc<str>) -> Self {
+        Self {
+            inner,
+            key,
+            tenant: tenant.clone(),
+        }
+    }
+}
+
+impl<T, S, Q> DataQuery<Tenanted<T>, S> for TenantQuery<Q>
+where
+    T: StoreData<S>,
+    S: Store,
+    Q: DataQuery<T, S>,
+{
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        if let Some(inner) = &self.inner {
+            inner.write(writer)?;
+        }
+
+        writer.write_field(self.key, &Tenant(&self.tenant))
+    }
+}
+
+/// A query without any fields.
+struct NoQuery;
+
+impl<T, S> DataQuery<T, S> for NoQuery
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    fn write<W>(&self, _writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        Ok(())
+    }
+}
+
+/// A [`TypeWriter`] recording whether any field is part of the primary key.
+struct PrimaryKeys<'a, W> {
+    writer: &'a mut W,
+    primary_key: bool,
+}
+
+impl<'a, S, W> TypeWriter<S> for PrimaryKeys<'a, W>
+where
+    S: Store,
+    W: TypeWriter<S>,
+{
+    type Error = W::Error;
+
+    fn write_bool(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_bool()
+    }
+
+    fn write_i8(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_i8()
+    }
+
+    fn write_i16(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_i16()
+    }
+
+    fn write_i32(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_i32()
+    }
+
+    fn write_i64(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_i64()
+    }
+
+    fn write_u8(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_u8()
+    }
+
+    fn write_u16(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_u16()
+    }
+
+    fn write_u32(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_u32()
+    }
+
+    fn write_u64(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_u64()
+    }
+
+    fn write_f32(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_f32()
+    }
+
+    fn write_f64(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_f64()
+    }
+
+    fn write_bytes(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_bytes()
+    }
+
+    fn write_str(&mut self) -> Result<(), Self::Error> {
+        self.writer.write_str()
+    }
+
+    fn write_field_seed<T>(
+        &mut self,
+        key: &str,
+        seed: T,
+        attrs: FieldAttributes,
+    ) -> Result<(), Self::Error>
+    where
+        T: WriteTypeSeed<S>,
+    {
+        self.primary_key |= attrs.is_primary_key();
+        self.writer.write_field_seed(key, seed, attrs)
+    }
+}
@@ -0,0 +1,668 @@
+//! Connection pooling for any [`Store`].
+//!
+//! A [`Pool`] keeps multiple connections to the same store and itself implements [`Store`]. Every
+//! operation acquires a connection from the pool, runs on it and returns the connection
+//! afterwards. The [`DataStore`] of a `Pool<S>` is the [`DataStore`] of `S`, so all [`StoreData`]
+//! types that can be used with `S` can also be used with the pool:
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use datastore::pool::Pool;
+//! # use datastore::Store;
+//!
+//! # async fn run<SqliteStore: Store>() -> Result<(), SqliteStore::Error> {
+//! let pool = Pool::<SqliteStore>::builder()
+//!     .min_size(2)
+//!     .max_size(16)
+//!     .idle_timeout(Some(Duration::from_secs(60)))
+//!     .connect("sqlite://data.db")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! When connecting using [`Store::connect_with`], the pool is configured from the query
+//! parameters `pool_min_size`, `pool_max_size`, `pool_idle_timeout` and `pool_health_check`. All
+//! other options are passed on to `S`.
+//!
+//! Connections whose operation failed or was cancelled are closed instead of being returned to
+//! the pool.
+//!
+//! Tasks waiting for a connection are served in the order they started waiting. The pool does not
+//! depend on an async runtime: idle connections are closed lazily when a connection is acquired.
+//! To limit the time spent waiting for a connection, wrap [`Pool::acquire`] in the timeout of
+//! your runtime.
+//!
+//! [`DataStore`]: Store::DataStore
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::options::{self, ConnectOptions};
+use crate::{Capabilities, DataDescriptor, DataQuery, Error, Store, StoreData};
+
+/// The query parameters read by [`Pool::connect_with`].
+const PARAMS: [&str; 4] = [
+    "pool_min_size",
+    "pool_max_size",
+    "pool_idle_timeout",
+    "pool_health_check",
+];
+
+/// A pool of connections to the store `S`.
+pub struct Pool<S> {
+    config: Config,
+    target: Target,
+    state: Mutex<State<S>>,
+}
+
+impl<S> Pool<S>
+where
+    S: Store,
+{
+    /// Returns a new [`PoolBuilder`] to configure a `Pool`.
+    #[inline]
+    pub fn builder() -> PoolBuilder<S> {
+        PoolBuilder::new()
+    }
+
+    /// Acquires a connection from the pool, waiting until one becomes available if `max_size`
+    /// connections are already in use.
+    ///
+    /// Idle connections are checked using [`Store::ping`] before they are returned if health
+    /// checks are enabled. A new connection is opened if no idle connection is available.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a new connection has to be opened and connecting fails.
+    pub async fn acquire(&self) -> Result<Pooled<'_, S>, S::Error> {
+        Permit {
+            state: &self.state,
+            id: None,
+        }
+        .await;
+
+        // From here on the permit is owned by `conn` and released when it is dropped.
+        let mut conn = Pooled {
+            pool: self,
+            store: None,
+            discard: false,
+        };
+
+        loop {
+            let idle = {
+                let mut state = self.lock();
+                let expired = self.reap(&mut state);
+                let idle = state.idle.pop_back();
+
+                drop(state);
+                drop(expired);
+                idle
+            };
+
+            match idle {
+                Some(idle) => {
+                    conn.store = Some(idle.store);
+                    // The connection is closed if `acquire` is cancelled during the health check.
+                    conn.discard = true;
+                    if !self.config.health_check || conn.ping().await.is_ok() {
+                        conn.discard = false;
+                        return Ok(conn);
+                    }
+
+                    conn.store = None;
+                    self.lock().open -= 1;
+                }
+                None => {
+                    conn.store = Some(self.open().await?);
+                    conn.discard = false;
+                    return Ok(conn);
+                }
+            }
+        }
+    }
+
+    /// Returns the number of open connections, including connections that are in use.
+    pub fn size(&self) -> usize {
+        self.lock().open
+    }
+
+    /// Returns the number of idle connections.
+    pub fn idle(&self) -> usize {
+        self.lock().idle.len()
+    }
+
+    /// Opens a new connection and counts it as open.
+    async fn open(&self) -> Result<S, S::Error> {
+        self.lock().open += 1;
+
+        let guard = Opening { pool: self };
+        let store = match &self.target {
+            Target::Uri(uri) => S::connect(uri).await?,
+            Target::Options(options) => S::connect_with(options).await?,
+        };
+
+        std::mem::forget(guard);
+        self.lock().capabilities = Some(store.capabilities());
+        Ok(store)
+    }
+
+    /// Removes all idle connections that exceeded the idle timeout while keeping at least
+    /// `min_size` connections open. The connections are returned to be dropped after the lock is
+    /// released.
+    fn reap(&self, state: &mut State<S>) -> Vec<Idle<S>> {
+        let mut expired = Vec::new();
+
+        if let Some(timeout) = self.config.idle_timeout {
+            while state.open > self.config.min_size {
+                match state.idle.front() {
+                    Some(idle) if idle.since.elapsed() >= timeout => {
+                        expired.extend(state.idle.pop_front());
+                        state.open -= 1;
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        expired
+    }
+}
+
+impl<S> Pool<S> {
+    fn lock(&self) -> MutexGuard<'_, State<S>> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<S> Debug for Pool<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+
+        f.debug_struct("Pool")
+            .field("config", &self.config)
+            .field("size", &state.open)
+            .field("idle", &state.idle.len())
+            .field("waiting", &state.waiters.len())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<S> Store for Pool<S>
+where
+    S: Store,
+{
+    type DataStore = S::DataStore;
+    type Error = S::Error;
+
+    /// Connects a pool with the default configuration to `uri`. All connections are opened using
+    /// [`Store::connect`] of `S`.
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        Self::builder().connect(uri).await
+    }
+
+    /// Connects a pool configured from the query parameters of `options`. All connections are
+    /// opened using [`Store::connect_with`] of `S` with the remaining options.
+    ///
+    /// | Parameter           | Value                                            |
+    /// | ------------------- | ------------------------------------------------ |
+    /// | `pool_min_size`     | See [`PoolBuilder::min_size`].                   |
+    /// | `pool_max_size`     | See [`PoolBuilder::max_size`].                   |
+    /// | `pool_idle_timeout` | A duration, see [`ConnectOptions::duration`].    |
+    /// | `pool_health_check` | `true` or `false`.                               |
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        let builder = PoolBuilder::from_options(options).map_err(S::Error::custom)?;
+
+        let mut options = options.clone();
+        for key in PARAMS {
+            options.remove(key);
+        }
+
+        builder.connect_with(&options).await
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let mut conn = self.acquire().await?;
+        conn.discard = true;
+        let res = conn.create(descriptor).await;
+        conn.discard = res.is_err();
+        res
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut conn = self.acquire().await?;
+        conn.discard = true;
+        let res = conn.delete(descriptor, query).await;
+        conn.discard = res.is_err();
+        res
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut conn = self.acquire().await?;
+        conn.discard = true;
+        let res = conn.get(descriptor, query).await;
+        conn.discard = res.is_err();
+        res
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let mut conn = self.acquire().await?;
+        conn.discard = true;
+        let res = conn.get_all(descriptor).await;
+        conn.discard = res.is_err();
+        res
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let mut conn = self.acquire().await?;
+        conn.discard = true;
+        let res = conn.get_one(descriptor, query).await;
+        conn.discard = res.is_err();
+        res
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let mut conn = self.acquire().await?;
+        conn.discard = true;
+        let res = conn.insert(descriptor, data).await;
+        conn.discard = res.is_err();
+        res
+    }
+
+    /// Acquires a connection and pings it.
+    async fn ping(&self) -> Result<(), Self::Error> {
+        let mut conn = self.acquire().await?;
+        conn.discard = true;
+        let res = conn.ping().await;
+        conn.discard = res.is_err();
+        res
+    }
+
+    /// Returns the capabilities of the most recently opened connection, or
+    /// [`Capabilities::WRITE`] if no connection was opened yet.
+    fn capabilities(&self) -> Capabilities {
+        self.lock().capabilities.unwrap_or(Capabilities::WRITE)
+    }
+}
+
+/// A builder for a [`Pool`].
+pub struct PoolBuilder<S> {
+    config: Config,
+    _marker: PhantomData<fn() -> S>,
+}
+
+impl<S> PoolBuilder<S>
+where
+    S: Store,
+{
+    /// Creates a new `PoolBuilder` with the default configuration: no minimum size, a maximum
+    /// size of 10 connections, an idle timeout of 10 minutes and health checks enabled.
+    pub fn new() -> Self {
+        Self {
+            config: Config {
+                min_size: 0,
+                max_size: 10,
+                idle_timeout: Some(Duration::from_secs(10 * 60)),
+                health_check: true,
+            },
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets the number of connections opened when the pool is created. Idle connections are not
+    /// closed because of the idle timeout while the pool has `min_size` or fewer connections. A
+    /// `min_size` greater than `max_size` is reduced to `max_size`.
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.config.min_size = min_size;
+        self
+    }
+
+    /// Sets the maximum number of connections that can be open at the same time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_size` is `0`.
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        assert!(max_size > 0, "max_size must be greater than 0");
+
+        self.config.max_size = max_size;
+        self
+    }
+
+    /// Sets the duration after which an idle connection is closed, or `None` to keep idle
+    /// connections open forever.
+    pub fn idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.config.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Sets whether idle connections are checked using [`Store::ping`] before they are acquired.
+    /// Connections failing the check are closed.
+    pub fn health_check(mut self, health_check: bool) -> Self {
+        self.config.health_check = health_check;
+        self
+    }
+
+    /// Creates a [`Pool`] opening all connections using [`Store::connect`] with `uri`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if opening any of the initial `min_size` connections fails.
+    pub async fn connect(self, uri: &str) -> Result<Pool<S>, S::Error> {
+        self.build(Target::Uri(uri.to_owned())).await
+    }
+
+    /// Creates a [`Pool`] opening all connections using [`Store::connect_with`] with `options`.
+    /// Unlike [`Pool::connect_with`] this does not read any query parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if opening any of the initial `min_size` connections fails.
+    pub async fn connect_with(self, options: &ConnectOptions) -> Result<Pool<S>, S::Error> {
+        self.build(Target::Options(options.clone())).await
+    }
+
+    async fn build(mut self, target: Target) -> Result<Pool<S>, S::Error> {
+        self.config.min_size = self.config.min_size.min(self.config.max_size);
+
+        let pool = Pool {
+            state: Mutex::new(State {
+                idle: VecDeque::new(),
+                open: 0,
+                permits: self.config.max_size,
+                waiters: VecDeque::new(),
+                next_id: 0,
+                capabilities: None,
+            }),
+            config: self.config,
+            target,
+        };
+
+        for _ in 0..pool.config.min_size {
+            let store = pool.open().await?;
+            pool.lock().idle.push_back(Idle {
+                store,
+                since: Instant::now(),
+            });
+        }
+
+        Ok(pool)
+    }
+
+    fn from_options(options: &ConnectOptions) -> Result<Self, options::Error> {
+        let mut builder = Self::new();
+
+        if let Some(min_size) = options.get_as("pool_min_size")? {
+            builder = builder.min_size(min_size);
+        }
+
+        match options.get_as("pool_max_size")? {
+            Some(0) => {
+                return Err(options::Error::InvalidParam {
+                    key: "pool_max_size".to_owned(),
+                })
+            }
+            Some(max_size) => builder = builder.max_size(max_size),
+            None => (),
+        }
+
+        if let Some(idle_timeout) = options.duration("pool_idle_timeout")? {
+            builder = builder.idle_timeout(Some(idle_timeout));
+        }
+
+        if let Some(health_check) = options.get_as("pool_health_check")? {
+            builder = builder.health_check(health_check);
+        }
+
+        Ok(builder)
+    }
+}
+
+impl<S> Default for PoolBuilder<S>
+where
+    S: Store,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Debug for PoolBuilder<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoolBuilder")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// A connection acquired from a [`Pool`]. The connection is returned to the pool when the
+/// `Pooled` is dropped.
+pub struct Pooled<'a, S> {
+    pool: &'a Pool<S>,
+    /// The connection. This is only `None` while the connection is being acquired.
+    store: Option<S>,
+    /// Whether the connection is closed instead of being returned to the pool. This is set while
+    /// an operation of the pool runs and after it failed, since a failed or cancelled operation
+    /// may leave the connection in an unknown state.
+    discard: bool,
+}
+
+impl<'a, S> Deref for Pooled<'a, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.store.as_ref().unwrap()
+    }
+}
+
+impl<'a, S> Drop for Pooled<'a, S> {
+    fn drop(&mut self) {
+        let mut state = self.pool.lock();
+
+        let store = self.store.take();
+        let discarded = match store {
+            Some(_) if self.discard => {
+                state.open -= 1;
+                store
+            }
+            Some(store) => {
+                state.idle.push_back(Idle {
+                    store,
+                    since: Instant::now(),
+                });
+                None
+            }
+            None => None,
+        };
+
+        state.release();
+
+        // Close the discarded connection after the lock is released.
+        drop(state);
+        drop(discarded);
+    }
+}
+
+impl<'a, S> Debug for Pooled<'a, S>
+where
+    S: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Pooled").field(&self.store).finish()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Config {
+    min_size: usize,
+    max_size: usize,
+    idle_timeout: Option<Duration>,
+    health_check: bool,
+}
+
+/// How new connections are opened.
+#[derive(Clone, Debug)]
+enum Target {
+    Uri(String),
+    Options(ConnectOptions),
+}
+
+struct State<S> {
+    /// The idle connections ordered from least to most recently used.
+    idle: VecDeque<Idle<S>>,
+    /// The number of open connections, including connections that are in use or being opened.
+    open: usize,
+    /// The number of connections that can be acquired without waiting.
+    permits: usize,
+    /// The tasks waiting for a connection in the order they started waiting.
+    waiters: VecDeque<Waiter>,
+    next_id: u64,
+    /// The capabilities of the most recently opened connection.
+    capabilities: Option<Capabilities>,
+}
+
+impl<S> State<S> {
+    /// Releases a permit, handing it directly to the first waiting task if there is one. Handing
+    /// over the permit prevents tasks that did not wait from acquiring it first.
+    fn release(&mut self) {
+        match self.waiters.iter_mut().find(|waiter| !waiter.granted) {
+            Some(waiter) => {
+                waiter.granted = true;
+                waiter.waker.wake_by_ref();
+            }
+            None => self.permits += 1,
+        }
+    }
+}
+
+struct Idle<S> {
+    store: S,
+    since: Instant,
+}
+
+struct Waiter {
+    id: u64,
+    waker: Waker,
+    /// Whether a permit was handed to the task.
+    granted: bool,
+}
+
+/// A future resolving once a permit to use a connection is acquired.
+struct Permit<'a, S> {
+    state: &'a Mutex<State<S>>,
+    /// The id of the waiter, or `None` if not waiting.
+    id: Option<u64>,
+}
+
+impl<'a, S> Future for Permit<'a, S> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut state = this.state.lock().unwrap_or_else(|err| err.into_inner());
+
+        let id = match this.id {
+            Some(id) => id,
+            None => {
+                if state.permits > 0 {
+                    state.permits -= 1;
+                    return Poll::Ready(());
+                }
+
+                let id = state.next_id;
+                state.next_id += 1;
+                state.waiters.push_back(Waiter {
+                    id,
+                    waker: cx.waker().clone(),
+                    granted: false,
+                });
+
+                this.id = Some(id);
+                return Poll::Pending;
+            }
+        };
+
+        let index = state
+            .waiters
+            .iter()
+            .position(|waiter| waiter.id == id)
+            .unwrap();
+
+        if state.waiters[index].granted {
+            state.waiters.remove(index);
+            this.id = None;
+            Poll::Ready(())
+        } else {
+            state.waiters[index].waker.clone_from(cx.waker());
+            Poll::Pending
+        }
+    }
+}
+
+impl<'a, S> Drop for Permit<'a, S> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+
+            if let Some(index) = state.waiters.iter().position(|waiter| waiter.id == id) {
+                // Pass on a permit that was handed to the task before it stopped waiting.
+                if state
+                    .waiters
+                    .remove(index)
+                    .is_some_and(|waiter| waiter.granted)
+                {
+                    state.release();
+                }
+            }
+        }
+    }
+}
+
+/// Uncounts a connection that is being opened if opening it fails or is cancelled.
+struct Opening<'a, S> {
+    pool: &'a Pool<S>,
+}
+
+impl<'a, S> Drop for Opening<'a, S> {
+    fn drop(&mut self) {
+        self.pool.lock().open -= 1;
+    }
+}
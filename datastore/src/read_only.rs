@@ -0,0 +1,212 @@
+//! A read-only view of any [`Store`].
+//!
+//! [`ReadOnly`] wraps a store and rejects [`insert`], [`delete`] and [`create`] with
+//! [`Error::PermissionDenied`] before they reach the inner store. All reads are passed through
+//! unchanged. This makes it safe to hand a store to code that must never write:
+//!
+//! ```
+//! use datastore::read_only::{Error, ReadOnly};
+//! # use datastore::Store;
+//!
+//! # async fn run<SqliteStore: Store>() -> Result<(), Error<SqliteStore::Error>> {
+//! let store = ReadOnly::<SqliteStore>::connect("sqlite://data.db").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Store::capabilities`] of a `ReadOnly` store never contain [`Capabilities::WRITE`] or
+//! [`Capabilities::UPSERT`].
+//!
+//! [`insert`]: Store::insert
+//! [`delete`]: Store::delete
+//! [`create`]: Store::create
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+use async_trait::async_trait;
+
+use crate::options::ConnectOptions;
+use crate::{Capabilities, DataDescriptor, DataQuery, Store, StoreData};
+
+/// A [`Store`] rejecting all writes to the inner store `S`.
+#[derive(Debug)]
+pub struct ReadOnly<S> {
+    store: S,
+}
+
+impl<S> ReadOnly<S> {
+    /// Creates a new `ReadOnly` store wrapping `store`.
+    #[inline]
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    /// Returns a reference to the inner store.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.store
+    }
+
+    /// Consumes the `ReadOnly` store, returning the inner store.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+#[async_trait]
+impl<S> Store for ReadOnly<S>
+where
+    S: Store,
+{
+    type DataStore = S::DataStore;
+    type Error = Error<S::Error>;
+
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        S::connect(uri).await.map(Self::new).map_err(Error::Store)
+    }
+
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        S::connect_with(options)
+            .await
+            .map(Self::new)
+            .map_err(Error::Store)
+    }
+
+    async fn create<T, D>(&self, _descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        Err(Error::PermissionDenied {
+            operation: "create",
+        })
+    }
+
+    async fn delete<T, D, Q>(&self, _descriptor: D, _query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        Err(Error::PermissionDenied {
+            operation: "delete",
+        })
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        self.store
+            .get(descriptor, query)
+            .await
+            .map_err(Error::Store)
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        self.store.get_all(descriptor).await.map_err(Error::Store)
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        self.store
+            .get_one(descriptor, query)
+            .await
+            .map_err(Error::Store)
+    }
+
+    async fn insert<T, D>(&self, _descriptor: D, _data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        Err(Error::PermissionDenied {
+            operation: "insert",
+        })
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.store.ping().await.map_err(Error::Store)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.store
+            .capabilities()
+            .difference(Capabilities::WRITE | Capabilities::UPSERT)
+    }
+}
+
+/// An error returned by a [`ReadOnly`] store.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// The write `operation` was rejected.
+    PermissionDenied { operation: &'static str },
+    /// The inner store returned an error.
+    Store(E),
+}
+
+impl<E> Error<E> {
+    /// Returns `true` if the error is a [`PermissionDenied`] error.
+    ///
+    /// [`PermissionDenied`]: Self::PermissionDenied
+    #[inline]
+    pub fn is_permission_denied(&self) -> bool {
+        matches!(self, Self::PermissionDenied { .. })
+    }
+}
+
+impl<E> Display for Error<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PermissionDenied { operation } => {
+                write!(f, "permission denied: {} on a read-only store", operation)
+            }
+            Self::Store(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E> error::Error for Error<E>
+where
+    E: error::Error,
+{
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::PermissionDenied { .. } => None,
+            Self::Store(err) => err.source(),
+        }
+    }
+}
+
+impl<E> crate::Error for Error<E>
+where
+    E: crate::Error,
+{
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Store(E::custom(msg))
+    }
+
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::PermissionDenied { .. } => false,
+            Self::Store(err) => err.is_transient(),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! A registry of stores by URI scheme.
+//!
+//! Drivers register their [`Store`] for a URI scheme using [`register`]. [`connect`] then
+//! connects to a URI using the store registered for its scheme and returns it as a type-erased
+//! [`DynStore`]:
+//!
+//! ```
+//! # use datastore::Store;
+//! # async fn run<SqliteStore>() -> Result<(), datastore::registry::Error>
+//! # where
+//! #     SqliteStore: Store + 'static,
+//! #     SqliteStore::Error: Send + Sync + 'static,
+//! # {
+//! datastore::registry::register::<SqliteStore>("sqlite");
+//!
+//! let store = datastore::connect("sqlite://:memory:").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The registry is global to the process. The complete URI, including the scheme, is passed to
+//! [`Store::connect`].
+//!
+//! The drivers in this repository provide a `register` function registering their store for its
+//! scheme: `datastore-sqlite` for `sqlite://`, `datastore-fs` for `file://`, `datastore-log` for
+//! `log://`, `datastore-redis` for `redis://` and `datastore-memory` for `memory://`.
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+use crate::dynamic::{self, DynStore};
+use crate::Store;
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A function connecting to a URI using a registered [`Store`].
+type Connector = for<'a> fn(&'a str) -> BoxFuture<'a, Result<Arc<dyn DynStore>, dynamic::Error>>;
+
+static REGISTRY: RwLock<BTreeMap<String, Connector>> = RwLock::new(BTreeMap::new());
+
+/// Registers the [`Store`] `S` for the URI `scheme`. Schemes are case-insensitive. A store that
+/// was previously registered for the same scheme is replaced.
+///
+/// # Panics
+///
+/// Panics if `scheme` is not a valid URI scheme, i.e. it does not start with an ASCII letter
+/// followed by only ASCII letters, digits, `+`, `-` and `.`.
+pub fn register<S>(scheme: &str)
+where
+    S: Store + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    assert!(is_valid_scheme(scheme), "invalid URI scheme {:?}", scheme);
+
+    REGISTRY
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .insert(scheme.to_ascii_lowercase(), connector::<S>);
+}
+
+/// Removes the store registered for the URI `scheme`. Returns `true` if a store was registered.
+pub fn unregister(scheme: &str) -> bool {
+    REGISTRY
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .remove(&scheme.to_ascii_lowercase())
+        .is_some()
+}
+
+/// Returns all registered schemes in alphabetical order.
+pub fn schemes() -> Vec<String> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// Connects to the store at `uri` using the [`Store`] registered for the scheme of `uri`.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if `uri` has no scheme, no store is registered for the scheme or the
+/// store fails to connect.
+pub async fn connect(uri: &str) -> Result<Arc<dyn DynStore>, Error> {
+    let scheme = match uri.split_once("://") {
+        Some((scheme, _)) if is_valid_scheme(scheme) => scheme.to_ascii_lowercase(),
+        _ => {
+            return Err(Error::MissingScheme {
+                uri: uri.to_owned(),
+            })
+        }
+    };
+
+    let connector = {
+        let registry = REGISTRY.read().unwrap_or_else(|err| err.into_inner());
+        match registry.get(&scheme) {
+            Some(connector) => *connector,
+            None => {
+                return Err(Error::UnknownScheme {
+                    scheme,
+                    known: registry.keys().cloned().collect(),
+                })
+            }
+        }
+    };
+
+    connector(uri)
+        .await
+        .map_err(|error| Error::Connect { scheme, error })
+}
+
+fn connector<S>(uri: &str) -> BoxFuture<'_, Result<Arc<dyn DynStore>, dynamic::Error>>
+where
+    S: Store + 'static,
+    S::Error: Send + Sync + 'static,
+{
+    Box::pin(async move {
+        let store = S::connect(uri).await.map_err(dynamic::Error::new)?;
+        Ok(Arc::new(store) as Arc<dyn DynStore>)
+    })
+}
+
+fn is_valid_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// An error returned by [`connect`].
+#[derive(Debug)]
+pub enum Error {
+    /// The URI does not start with a scheme followed by `://`.
+    MissingScheme { uri: String },
+    /// No store is registered for the scheme. `known` contains all registered schemes.
+    UnknownScheme { scheme: String, known: Vec<String> },
+    /// The store registered for the scheme failed to connect.
+    Connect {
+        scheme: String,
+        error: dynamic::Error,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingScheme { uri } => write!(f, "missing scheme in uri {:?}", uri),
+            Self::UnknownScheme { scheme, known } => {
+                write!(f, "no store registered for scheme {:?}", scheme)?;
+                match known.is_empty() {
+                    true => write!(f, ", no schemes are registered"),
+                    false => write!(f, ", registered schemes: {}", known.join(", ")),
+                }
+            }
+            Self::Connect { scheme, error } => {
+                write!(f, "failed to connect to {:?} store: {}", scheme, error)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Connect { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
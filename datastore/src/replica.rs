@@ -0,0 +1,386 @@
+//! Routing of reads to read replicas.
+//!
+//! [`Replicated`] wraps a primary store and any number of replicas of it. [`insert`],
+//! [`delete`] and [`create`] are always sent to the primary, while [`get`], [`get_one`] and
+//! [`get_all`] are distributed over the replicas in turn. Reads are sent to the primary if there
+//! are no replicas.
+//!
+//! Replicas usually lag behind the primary, so an item that was just inserted may not be
+//! returned by the next read. Every `Replicated` store acts as a session that can guarantee to
+//! read its own writes: with [`with_read_your_writes`], all reads are sent to the primary for
+//! the given window after a write of the same store. [`Replicated::session`] returns a
+//! [`Session`] sharing the stores of a `Replicated` store in an [`Arc`], but tracking its own
+//! writes, e.g. for every request of a server. A `Session` is a [`Store`] itself and can be
+//! wrapped like any other store.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use datastore::replica::Replicated;
+//! # use datastore::Store;
+//!
+//! # async fn run<RedisStore: Store>() -> Result<(), RedisStore::Error> {
+//! let primary = RedisStore::connect("redis://primary").await?;
+//! let replicas = vec![
+//!     RedisStore::connect("redis://replica-0").await?,
+//!     RedisStore::connect("redis://replica-1").await?,
+//! ];
+//!
+//! let store = Replicated::new(primary, replicas)
+//!     .with_read_your_writes(Some(Duration::from_secs(5)));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Replicated::connect_with`] connects to the primary with the given options and to every uri
+//! in the repeated `replica` query parameter. The `read_your_writes` parameter sets the window of
+//! [`with_read_your_writes`], e.g.
+//! `redis://primary?replica=redis%3A%2F%2Freplica-0&read_your_writes=5s`.
+//!
+//! [`insert`]: Store::insert
+//! [`delete`]: Store::delete
+//! [`create`]: Store::create
+//! [`get`]: Store::get
+//! [`get_one`]: Store::get_one
+//! [`get_all`]: Store::get_all
+//! [`with_read_your_writes`]: Replicated::with_read_your_writes
+//! [`Replicated::connect_with`]: Store::connect_with
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::options::ConnectOptions;
+use crate::{Capabilities, DataDescriptor, DataQuery, Error, Store, StoreData};
+
+/// A [`Store`] sending writes to a primary store and reads to its replicas.
+#[derive(Debug)]
+pub struct Replicated<S> {
+    primary: S,
+    replicas: Vec<S>,
+    next: AtomicUsize,
+    read_your_writes: Option<Duration>,
+    last_write: Mutex<Option<Instant>>,
+}
+
+impl<S> Replicated<S> {
+    /// Creates a new `Replicated` store writing to `primary` and reading from `replicas`.
+    pub fn new(primary: S, replicas: Vec<S>) -> Self {
+        Self {
+            primary,
+            replicas,
+            next: AtomicUsize::new(0),
+            read_your_writes: None,
+            last_write: Mutex::new(None),
+        }
+    }
+
+    /// Sets the window after a write in which all reads are sent to the primary. The default is
+    /// `None`, which always reads from the replicas.
+    ///
+    /// Only writes of this `Replicated` store pin its reads. Writes of other stores connected
+    /// to the same primary are not seen.
+    pub fn with_read_your_writes(mut self, window: Option<Duration>) -> Self {
+        self.read_your_writes = window;
+        self
+    }
+
+    /// Returns a reference to the primary store.
+    #[inline]
+    pub fn primary(&self) -> &S {
+        &self.primary
+    }
+
+    /// Returns the replica stores.
+    #[inline]
+    pub fn replicas(&self) -> &[S] {
+        &self.replicas
+    }
+
+    /// Consumes the `Replicated` store, returning the primary and the replica stores.
+    #[inline]
+    pub fn into_parts(self) -> (S, Vec<S>) {
+        (self.primary, self.replicas)
+    }
+
+    /// Returns a new [`Session`] reading from and writing to the stores of this `Replicated`
+    /// store. Reads of the session are only pinned to the primary by writes of the same session.
+    pub fn session(self: &Arc<Self>) -> Session<S> {
+        Session {
+            store: self.clone(),
+            last_write: Mutex::new(None),
+        }
+    }
+
+    /// Returns the store the next read of the session writing at `last_write` is sent to.
+    fn reader(&self, last_write: &Mutex<Option<Instant>>) -> &S {
+        if self.replicas.is_empty() || self.is_pinned(last_write) {
+            return &self.primary;
+        }
+
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        &self.replicas[index]
+    }
+
+    /// Returns `true` if reads are pinned to the primary by a recent write.
+    fn is_pinned(&self, last_write: &Mutex<Option<Instant>>) -> bool {
+        let window = match self.read_your_writes {
+            Some(window) => window,
+            None => return false,
+        };
+
+        match *last_write.lock().unwrap_or_else(|err| err.into_inner()) {
+            Some(last_write) => last_write.elapsed() < window,
+            None => false,
+        }
+    }
+
+    /// Records a write to the primary.
+    fn pin(&self, last_write: &Mutex<Option<Instant>>) {
+        if self.read_your_writes.is_some() {
+            *last_write.lock().unwrap_or_else(|err| err.into_inner()) = Some(Instant::now());
+        }
+    }
+
+    /// Runs the write `future`, pinning reads to the primary while it runs and for the window
+    /// after it completed. Failed writes pin reads too, since they may still have been applied.
+    async fn write<F>(&self, last_write: &Mutex<Option<Instant>>, future: F) -> F::Output
+    where
+        F: Future,
+    {
+        // Reads started while the write is in flight may already observe it on the primary.
+        self.pin(last_write);
+        let res = future.await;
+        self.pin(last_write);
+        res
+    }
+}
+
+#[async_trait]
+impl<S> Store for Replicated<S>
+where
+    S: Store,
+{
+    type DataStore = S::DataStore;
+    type Error = S::Error;
+
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        let options = ConnectOptions::parse(uri).map_err(S::Error::custom)?;
+        Self::connect_with(&options).await
+    }
+
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        let read_your_writes = options
+            .duration("read_your_writes")
+            .map_err(S::Error::custom)?;
+
+        let uris: Vec<_> = options
+            .params()
+            .filter(|(key, _)| *key == "replica")
+            .map(|(_, uri)| uri.to_owned())
+            .collect();
+
+        let mut options = options.clone();
+        options.remove("replica");
+        options.remove("read_your_writes");
+
+        let primary = S::connect_with(&options).await?;
+
+        let mut replicas = Vec::with_capacity(uris.len());
+        for uri in uris {
+            replicas.push(S::connect(&uri).await?);
+        }
+
+        Ok(Self::new(primary, replicas).with_read_your_writes(read_your_writes))
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        self.write(&self.last_write, self.primary.create(descriptor))
+            .await
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        self.write(&self.last_write, self.primary.delete(descriptor, query))
+            .await
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        self.reader(&self.last_write).get(descriptor, query).await
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        self.reader(&self.last_write).get_all(descriptor).await
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        self.reader(&self.last_write)
+            .get_one(descriptor, query)
+            .await
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        self.write(&self.last_write, self.primary.insert(descriptor, data))
+            .await
+    }
+
+    /// Pings the primary and all replicas.
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.primary.ping().await?;
+        for replica in &self.replicas {
+            replica.ping().await?;
+        }
+
+        Ok(())
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.primary.capabilities()
+    }
+}
+
+/// A session of a [`Replicated`] store, reading its own writes.
+///
+/// The session is created by [`Replicated::session`] and uses the stores and the read your writes
+/// window of the `Replicated` store. Reads are only pinned to the primary by writes of the same
+/// session. Connecting a `Session` directly connects a new `Replicated` store used only by the
+/// session.
+#[derive(Debug)]
+pub struct Session<S> {
+    store: Arc<Replicated<S>>,
+    last_write: Mutex<Option<Instant>>,
+}
+
+impl<S> Session<S> {
+    /// Returns the `Replicated` store of the session.
+    #[inline]
+    pub fn get_ref(&self) -> &Arc<Replicated<S>> {
+        &self.store
+    }
+}
+
+#[async_trait]
+impl<S> Store for Session<S>
+where
+    S: Store,
+{
+    type DataStore = S::DataStore;
+    type Error = S::Error;
+
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        Ok(Arc::new(Replicated::connect(uri).await?).session())
+    }
+
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        Ok(Arc::new(Replicated::connect_with(options).await?).session())
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        self.store
+            .write(&self.last_write, self.store.primary.create(descriptor))
+            .await
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        self.store
+            .write(
+                &self.last_write,
+                self.store.primary.delete(descriptor, query),
+            )
+            .await
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        self.store
+            .reader(&self.last_write)
+            .get(descriptor, query)
+            .await
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        self.store
+            .reader(&self.last_write)
+            .get_all(descriptor)
+            .await
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        self.store
+            .reader(&self.last_write)
+            .get_one(descriptor, query)
+            .await
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        self.store
+            .write(
+                &self.last_write,
+                self.store.primary.insert(descriptor, data),
+            )
+            .await
+    }
+
+    /// Pings the primary and all replicas.
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.store.ping().await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.store.capabilities()
+    }
+}
@@ -0,0 +1,432 @@
+//! Retries and timeouts for any [`Store`].
+//!
+//! [`Retrying`] wraps a store and retries operations failing with a transient error, as
+//! classified by [`Error::is_transient`]. Retries are delayed by an exponential backoff with
+//! jitter, and every attempt can be limited by a timeout:
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use datastore::retry::Retrying;
+//! # use datastore::Store;
+//!
+//! # async fn run<RedisStore: Store>() -> Result<(), RedisStore::Error> {
+//! let store = RedisStore::connect("redis://localhost").await?;
+//!
+//! let store = Retrying::new(store)
+//!     .with_max_attempts(5)
+//!     .with_backoff(Duration::from_millis(50), Duration::from_secs(2))
+//!     .with_timeout(Some(Duration::from_secs(1)));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! All operations except [`insert`] are idempotent and retried after transient errors and
+//! timeouts. An [`insert`] that failed may still have been applied and inserting the item again
+//! could duplicate it, so inserts are only attempted once unless [`with_idempotent_inserts`] is
+//! enabled. The timeout still applies to inserts.
+//!
+//! The timers use the [`tokio`] runtime. This module is only available with the `tokio`
+//! feature.
+//!
+//! [`insert`]: Store::insert
+//! [`with_idempotent_inserts`]: Retrying::with_idempotent_inserts
+//! [`tokio`]: https://docs.rs/tokio
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::binary::{self, BinaryReader};
+use crate::options::{self, ConnectOptions};
+use crate::shared::Shared;
+use crate::{Capabilities, DataDescriptor, DataQuery, Error, Store, StoreData};
+
+/// The query parameters read by [`Retrying::connect_with`].
+const PARAMS: [&str; 4] = [
+    "retry_max_attempts",
+    "retry_backoff",
+    "retry_max_backoff",
+    "retry_timeout",
+];
+
+/// A [`Store`] retrying operations of the inner store `S` that fail with a transient error.
+#[derive(Debug)]
+pub struct Retrying<S> {
+    store: S,
+    policy: Policy,
+    idempotent_inserts: bool,
+}
+
+impl<S> Retrying<S> {
+    /// Creates a new `Retrying` store wrapping `store`. Operations are attempted up to 3 times
+    /// with a backoff starting at 100ms, and attempts do not time out.
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            policy: Policy::default(),
+            idempotent_inserts: false,
+        }
+    }
+
+    /// Sets the maximum number of attempts of an operation, including the first one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_attempts` is `0`.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "max_attempts must be greater than 0");
+
+        self.policy.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry and the maximum delay between two attempts. The
+    /// delay is multiplied by the [`multiplier`] after every retry.
+    ///
+    /// [`multiplier`]: Self::with_multiplier
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.policy.backoff = initial;
+        self.policy.max_backoff = max;
+        self
+    }
+
+    /// Sets the factor the delay is multiplied by after every retry. The default is `2.0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `multiplier` is less than `1.0`.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        assert!(multiplier >= 1.0, "multiplier must be at least 1.0");
+
+        self.policy.multiplier = multiplier;
+        self
+    }
+
+    /// Sets the fraction of every delay that is randomized. A delay `d` becomes a random delay
+    /// between `d * (1.0 - jitter)` and `d`. The default is `0.5`, `0.0` disables the jitter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `jitter` is not between `0.0` and `1.0`.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&jitter),
+            "jitter must be between 0.0 and 1.0"
+        );
+
+        self.policy.jitter = jitter;
+        self
+    }
+
+    /// Sets the duration after which a single attempt is cancelled, or `None` to wait for
+    /// attempts forever. An attempt that timed out is retried like a transient error.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.policy.timeout = timeout;
+        self
+    }
+
+    /// Sets whether [`insert`] is retried like all other operations. Only enable this if
+    /// inserting the same item twice has no additional effect, e.g. because all inserted types
+    /// have a primary key.
+    ///
+    /// Items are copied in the [`binary`] encoding for retries, so inserted types do not need to
+    /// implement [`Clone`]. Items that cannot be encoded are not retried.
+    ///
+    /// [`insert`]: Store::insert
+    pub fn with_idempotent_inserts(mut self, idempotent_inserts: bool) -> Self {
+        self.idempotent_inserts = idempotent_inserts;
+        self
+    }
+
+    /// Returns a reference to the inner store.
+    #[inline]
+    pub fn get_ref(&self) -> &S {
+        &self.store
+    }
+
+    /// Consumes the `Retrying` store, returning the inner store.
+    #[inline]
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+#[async_trait]
+impl<S> Store for Retrying<S>
+where
+    S: Store,
+{
+    type DataStore = S::DataStore;
+    type Error = S::Error;
+
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        Policy::default()
+            .run(true, || S::connect(uri))
+            .await
+            .map(Self::new)
+    }
+
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        let policy = Policy::from_options(options).map_err(S::Error::custom)?;
+
+        let mut options = options.clone();
+        for key in PARAMS {
+            options.remove(key);
+        }
+
+        let store = policy.run(true, || S::connect_with(&options)).await?;
+
+        Ok(Self {
+            store,
+            policy,
+            idempotent_inserts: false,
+        })
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let ident = descriptor.ident().to_owned();
+        let descriptor = Mutex::new(descriptor);
+        let descriptor = Shared::new(&descriptor, &ident);
+
+        self.policy
+            .run(true, move || self.store.create::<T, _>(descriptor))
+            .await
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let ident = descriptor.ident().to_owned();
+        let (descriptor, query) = (Mutex::new(descriptor), Mutex::new(query));
+        let (descriptor, query) = (Shared::new(&descriptor, &ident), Shared::new(&query, ""));
+
+        self.policy
+            .run(true, move || {
+                self.store.delete::<T, _, _>(descriptor, query)
+            })
+            .await
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let ident = descriptor.ident().to_owned();
+        let (descriptor, query) = (Mutex::new(descriptor), Mutex::new(query));
+        let (descriptor, query) = (Shared::new(&descriptor, &ident), Shared::new(&query, ""));
+
+        self.policy
+            .run(true, move || self.store.get(descriptor, query))
+            .await
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let ident = descriptor.ident().to_owned();
+        let descriptor = Mutex::new(descriptor);
+        let descriptor = Shared::new(&descriptor, &ident);
+
+        self.policy
+            .run(true, move || self.store.get_all(descriptor))
+            .await
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let ident = descriptor.ident().to_owned();
+        let (descriptor, query) = (Mutex::new(descriptor), Mutex::new(query));
+        let (descriptor, query) = (Shared::new(&descriptor, &ident), Shared::new(&query, ""));
+
+        self.policy
+            .run(true, move || self.store.get_one(descriptor, query))
+            .await
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        // Every retry inserts a new copy decoded from `buf`.
+        let buf = match self.idempotent_inserts {
+            true => binary::to_vec::<T, Self::DataStore>(&data).ok(),
+            false => None,
+        };
+        let buf = &buf;
+
+        let ident = descriptor.ident().to_owned();
+        let descriptor = Mutex::new(descriptor);
+        let descriptor = Shared::new(&descriptor, &ident);
+
+        let mut data = Some(data);
+        self.policy
+            .run(buf.is_some(), move || {
+                let data = data.take();
+
+                async move {
+                    // Copies are read through the descriptor, like items read from a store.
+                    let data = match data {
+                        Some(data) => data,
+                        None => {
+                            let buf = buf.as_deref().unwrap_or_default();
+                            let mut reader = BinaryReader::new(buf).map_err(S::Error::custom)?;
+                            descriptor.read(&mut reader).map_err(S::Error::custom)?
+                        }
+                    };
+
+                    self.store.insert(descriptor, data).await
+                }
+            })
+            .await
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        self.policy.run(true, || self.store.ping()).await
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.store.capabilities()
+    }
+}
+
+/// The backoff and timeout configuration of a [`Retrying`] store.
+#[derive(Copy, Clone, Debug)]
+struct Policy {
+    max_attempts: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    timeout: Option<Duration>,
+}
+
+impl Policy {
+    fn from_options(options: &ConnectOptions) -> Result<Self, options::Error> {
+        let mut policy = Self::default();
+
+        match options.get_as("retry_max_attempts")? {
+            Some(0) => {
+                return Err(options::Error::InvalidParam {
+                    key: "retry_max_attempts".to_owned(),
+                })
+            }
+            Some(max_attempts) => policy.max_attempts = max_attempts,
+            None => (),
+        }
+
+        if let Some(backoff) = options.duration("retry_backoff")? {
+            policy.backoff = backoff;
+        }
+
+        if let Some(max_backoff) = options.duration("retry_max_backoff")? {
+            policy.max_backoff = max_backoff;
+        }
+
+        policy.timeout = options.duration("retry_timeout")?;
+
+        Ok(policy)
+    }
+
+    /// Runs the operation created by `f` until it succeeds, fails with an error that cannot be
+    /// retried or the maximum number of attempts is reached. Operations that are not
+    /// `idempotent` are only attempted once.
+    async fn run<T, E, F, Fut>(&self, idempotent: bool, mut f: F) -> Result<T, E>
+    where
+        E: Error,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut attempt = 1;
+
+        loop {
+            let delay = match self.attempt(f()).await {
+                Ok(value) => return Ok(value),
+                Err((err, transient)) => {
+                    if !idempotent || !transient || attempt >= self.max_attempts {
+                        return Err(err);
+                    }
+
+                    self.delay(attempt)
+                }
+            };
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Runs a single attempt of an operation. Returns the error and whether it is transient if
+    /// the attempt failed or timed out.
+    async fn attempt<T, E, Fut>(&self, future: Fut) -> Result<T, (E, bool)>
+    where
+        E: Error,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let res = match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, future).await {
+                Ok(res) => res,
+                Err(_) => {
+                    let msg = format!("operation timed out after {:?}", timeout);
+                    return Err((E::custom(msg), true));
+                }
+            },
+            None => future.await,
+        };
+
+        res.map_err(|err| {
+            let transient = err.is_transient();
+            (err, transient)
+        })
+    }
+
+    /// Returns the delay before the retry following the failed `attempt`, starting at `1`.
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.powi(attempt as i32 - 1);
+        let delay = Duration::try_from_secs_f64(self.backoff.as_secs_f64() * factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        delay.mul_f64(1.0 - self.jitter * random())
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.5,
+            timeout: None,
+        }
+    }
+}
+
+/// Returns a random number between `0.0` and `1.0`.
+fn random() -> f64 {
+    // Every `RandomState` uses different keys: they are seeded randomly once per thread and
+    // incremented for every new `RandomState`.
+    let hash = RandomState::new().build_hasher().finish();
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,148 @@
+use std::marker::PhantomData;
+
+use ::serde::de::{self, IntoDeserializer, Visitor};
+
+use super::Error;
+use crate::{Reader, Store};
+
+/// A [`Deserializer`] reading from a [`Reader`].
+///
+/// Since a [`Reader`] is not self-describing, only values that are read from a single primitive
+/// are supported. Compound types like structs, sequences and maps need the type information
+/// traced by [`Serde`].
+///
+/// [`Deserializer`]: de::Deserializer
+/// [`Serde`]: super::Serde
+pub struct Deserializer<'a, R, S>
+where
+    R: Reader<S>,
+    S: Store,
+{
+    reader: &'a mut R,
+    _marker: PhantomData<fn() -> S>,
+}
+
+impl<'a, R, S> Deserializer<'a, R, S>
+where
+    R: Reader<S>,
+    S: Store,
+{
+    /// Creates a new `Deserializer` reading from `reader`.
+    #[inline]
+    pub fn new(reader: &'a mut R) -> Self {
+        Self {
+            reader,
+            _marker: PhantomData,
+        }
+    }
+}
+
+macro_rules! deserialize_primitive {
+    ($($deserialize:ident => $visit:ident, $read:ident;)*) => {
+        $(
+            fn $deserialize<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.$visit(self.reader.$read().map_err(Error)?)
+            }
+        )*
+    };
+}
+
+macro_rules! deserialize_unsupported {
+    ($($deserialize:ident($($arg:ident: $ty:ty),*) => $what:expr;)*) => {
+        $(
+            fn $deserialize<V>(self, $($arg: $ty,)* _visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                $(let _ = $arg;)*
+                Err(Error::unsupported($what))
+            }
+        )*
+    };
+}
+
+impl<'a, 'de, R, S> de::Deserializer<'de> for Deserializer<'a, R, S>
+where
+    R: Reader<S>,
+    S: Store,
+{
+    type Error = Error<R::Error>;
+
+    deserialize_primitive! {
+        deserialize_bool => visit_bool, read_bool;
+        deserialize_i8 => visit_i8, read_i8;
+        deserialize_i16 => visit_i16, read_i16;
+        deserialize_i32 => visit_i32, read_i32;
+        deserialize_i64 => visit_i64, read_i64;
+        deserialize_u8 => visit_u8, read_u8;
+        deserialize_u16 => visit_u16, read_u16;
+        deserialize_u32 => visit_u32, read_u32;
+        deserialize_u64 => visit_u64, read_u64;
+        deserialize_f32 => visit_f32, read_f32;
+        deserialize_f64 => visit_f64, read_f64;
+        deserialize_str => visit_string, read_string;
+        deserialize_string => visit_string, read_string;
+        deserialize_identifier => visit_string, read_string;
+        deserialize_bytes => visit_byte_buf, read_byte_buf;
+        deserialize_byte_buf => visit_byte_buf, read_byte_buf;
+    }
+
+    deserialize_unsupported! {
+        deserialize_any() => "deserialize_any";
+        deserialize_ignored_any() => "deserialize_ignored_any";
+        deserialize_unit() => "unit";
+        deserialize_unit_struct(_name: &'static str) => "unit struct";
+        deserialize_seq() => "sequence";
+        deserialize_tuple(_len: usize) => "tuple";
+        deserialize_tuple_struct(_name: &'static str, _len: usize) => "tuple struct";
+        deserialize_map() => "map";
+        deserialize_struct(_name: &'static str, _fields: &'static [&'static str]) => "struct";
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let s = self.reader.read_string().map_err(Error)?;
+
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(de::Error::invalid_value(de::Unexpected::Str(&s), &"a char")),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let variant = self.reader.read_string().map_err(Error)?;
+        visitor.visit_enum(variant.into_deserializer())
+    }
+}
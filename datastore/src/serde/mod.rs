@@ -0,0 +1,286 @@
+//! Integration with [`serde`].
+//!
+//! This module requires the `serde` feature.
+//!
+//! [`Serde`] adapts any type implementing [`Serialize`] and [`DeserializeOwned`] into a field
+//! type implementing [`Write`] and [`Read`] for every [`Store`]. The value is passed through a
+//! [`Serializer`] on top of the [`Writer`] and a [`Deserializer`] on top of the [`Reader`].
+//!
+//! Fields can be routed through [`Serde`] using the `#[datastore(serde)]` attribute of the
+//! `StoreData` macro:
+//!
+//! ```
+//! # use datastore::StoreData;
+//! # use serde::{Deserialize, Serialize};
+//! #[derive(Clone, Serialize, Deserialize)]
+//! enum Status {
+//!     Active,
+//!     Disabled,
+//! }
+//!
+//! #[derive(StoreData)]
+//! struct User {
+//!     id: i64,
+//!     #[datastore(serde)]
+//!     status: Status,
+//! }
+//! ```
+//!
+//! [`Writer`] and [`Reader`] are not self-describing. The type of a field is traced from the
+//! values requested by the [`Deserialize`] implementation of `T`, which is then used to write the
+//! field type and to read the value:
+//!
+//! - `bool`, integers up to 64 bits, floats, `char`, strings and bytes are written as a single
+//!   primitive value. Newtype structs are written as their inner value.
+//! - Unit variants are written as the variant name. Variants with data are not supported.
+//! - Structs are written as nested fields named after the struct fields, tuples and tuple
+//!   structs as nested fields named after the element index (`0`, `1`, ...). Tuples with more
+//!   than 32 elements are not supported.
+//! - Sequences and maps have no fixed number of elements. They are packed with their elements
+//!   into a single bytes value in a self-describing format.
+//! - `None` and unit values write nothing. An optional value that is missing is read as `None`,
+//!   other errors are returned.
+//!
+//! Nested fields require a [`Writer`] and [`Reader`] with support for nested fields, e.g.
+//! [`Record`]. Types that cannot be traced, e.g. types relying on `deserialize_any`, return an
+//! error.
+//!
+//! # Serde formats
+//!
+//! In the other direction [`SerdeWriter`] forwards a [`StoreData`] type to any [`Serializer`]
+//! and [`SerdeReader`] reads it back from any self-describing [`Deserializer`]. Fields become the
+//! entries of a map. This allows dumping data into formats like JSON without a [`Store`]:
+//!
+//! ```
+//! # use datastore::{Store, StoreData};
+//! # fn roundtrip<T, S>(data: &T) -> T
+//! # where
+//! #     T: StoreData<S>,
+//! #     S: Store,
+//! # {
+//! let mut buf = Vec::new();
+//! datastore::serde::serialize(data, &mut serde_json::Serializer::new(&mut buf)).unwrap();
+//!
+//! let mut deserializer = serde_json::Deserializer::from_slice(&buf);
+//! datastore::serde::deserialize(&mut deserializer).unwrap()
+//! # }
+//! ```
+//!
+//! [`Serialize`]: ::serde::Serialize
+//! [`Deserialize`]: ::serde::Deserialize
+//! [`Record`]: crate::value::Record
+//! [`DeserializeOwned`]: ::serde::de::DeserializeOwned
+//! [`Store`]: crate::Store
+//! [`Write`]: crate::Write
+//! [`Read`]: crate::Read
+//! [`Writer`]: crate::Writer
+//! [`Reader`]: crate::Reader
+//! [`StoreData`]: crate::StoreData
+//! [`Serializer`]: ::serde::Serializer
+//! [`Deserializer`]: ::serde::Deserializer
+mod content;
+mod de;
+mod packed;
+mod reader;
+mod ser;
+mod shape;
+mod writer;
+
+pub use de::Deserializer;
+pub use reader::{deserialize, ReaderError, SerdeReader};
+pub use ser::{Compound, Packed, Serializer};
+pub use writer::{serialize, SerdeWriter, SerializeData};
+
+use std::error;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::{Deref, DerefMut};
+
+use ::serde::de::DeserializeOwned;
+use ::serde::Serialize;
+
+use self::content::ContentDeserializer;
+use crate::{Read, Reader, Store, TypeWriter, Write, Writer};
+
+/// A wrapper for a type implementing [`Serialize`] and [`DeserializeOwned`].
+///
+/// `Serde<T>` implements [`Write`] and [`Read`] for all [`Store`]s. The type of the field is
+/// derived from the value requested by the [`Deserialize`] implementation of `T`.
+///
+/// [`Deserialize`]: ::serde::Deserialize
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Serde<T>(pub T);
+
+impl<T> Serde<T> {
+    /// Converts a reference to `T` into a reference to `Serde<T>`.
+    #[inline]
+    pub fn from_ref(value: &T) -> &Self {
+        // SAFETY: `Serde<T>` is `#[repr(transparent)]` over `T`.
+        unsafe { &*(value as *const T as *const Self) }
+    }
+
+    /// Consumes the `Serde`, returning the wrapped value.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Serde<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Serde<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for Serde<T> {
+    #[inline]
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<S, T> Write<S> for Serde<T>
+where
+    S: Store,
+    T: Serialize + DeserializeOwned,
+{
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        self.0
+            .serialize(Serializer::new(writer))
+            .map_err(Error::into_inner)
+    }
+
+    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        let shape = shape::trace::<T>().map_err(<W::Error as crate::Error>::custom)?;
+        shape::write_type(&shape, writer)
+    }
+}
+
+impl<S, T> Read<S> for Serde<T>
+where
+    S: Store,
+    T: DeserializeOwned,
+{
+    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<S>,
+    {
+        let shape = shape::trace::<T>().map_err(<R::Error as crate::Error>::custom)?;
+        let content = shape::read(&shape, reader)?;
+
+        T::deserialize(ContentDeserializer::<Error<R::Error>>::new(content))
+            .map(Self)
+            .map_err(Error::into_inner)
+    }
+}
+
+/// An error returned by the [`Serializer`], [`Deserializer`] and [`SerdeWriter`].
+///
+/// `Error` wraps the error `E` of the underlying [`Writer`], [`Reader`] or [`Serializer`].
+///
+/// [`Serializer`]: ::serde::Serializer
+/// [`Writer`]: crate::Writer
+/// [`Reader`]: crate::Reader
+pub struct Error<E>(E);
+
+impl<E> Error<E> {
+    /// Creates a new `Error` from the error `E`.
+    #[inline]
+    pub fn new(error: E) -> Self {
+        Self(error)
+    }
+
+    /// Consumes the `Error`, returning the wrapped error.
+    #[inline]
+    pub fn into_inner(self) -> E {
+        self.0
+    }
+}
+
+impl<E> Error<E>
+where
+    E: crate::Error,
+{
+    fn unsupported(what: &str) -> Self {
+        Self(E::custom(format_args!("{} is not supported", what)))
+    }
+}
+
+impl<E> Debug for Error<E>
+where
+    E: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<E> Display for Error<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<E> error::Error for Error<E>
+where
+    E: error::Error,
+{
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl<E> ::serde::ser::Error for Error<E>
+where
+    E: crate::Error,
+{
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self(E::custom(msg))
+    }
+}
+
+impl<E> ::serde::de::Error for Error<E>
+where
+    E: crate::Error,
+{
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self(E::custom(msg))
+    }
+}
+
+impl<E> crate::Error for Error<E>
+where
+    E: ::serde::ser::Error,
+{
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self(E::custom(msg))
+    }
+}
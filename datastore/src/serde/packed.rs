@@ -0,0 +1,524 @@
+use std::convert::TryFrom;
+use std::marker::PhantomData;
+
+use ::serde::ser::{self, Impossible, Serialize};
+
+use super::content::Content;
+use super::Error;
+
+/// The maximum nesting depth accepted by [`unpack`].
+const MAX_DEPTH: usize = 128;
+
+const TAG_NONE: u8 = 0;
+const TAG_SOME: u8 = 1;
+const TAG_UNIT: u8 = 2;
+const TAG_BOOL: u8 = 3;
+const TAG_I8: u8 = 4;
+const TAG_I16: u8 = 5;
+const TAG_I32: u8 = 6;
+const TAG_I64: u8 = 7;
+const TAG_U8: u8 = 8;
+const TAG_U16: u8 = 9;
+const TAG_U32: u8 = 10;
+const TAG_U64: u8 = 11;
+const TAG_F32: u8 = 12;
+const TAG_F64: u8 = 13;
+const TAG_BYTES: u8 = 14;
+const TAG_STRING: u8 = 15;
+const TAG_SEQ: u8 = 16;
+const TAG_MAP: u8 = 17;
+
+/// A [`Serializer`] packing a value into a self-describing buffer.
+///
+/// Every value starts with a tag byte followed by its little-endian payload. Bytes, strings,
+/// sequences and maps are prefixed with their length as a `u64`. Tuples are packed as sequences,
+/// structs as maps with string keys.
+///
+/// [`Serializer`]: ser::Serializer
+pub(super) struct Packer<'a, E> {
+    buf: &'a mut Vec<u8>,
+    _marker: PhantomData<fn() -> E>,
+}
+
+impl<'a, E> Packer<'a, E> {
+    #[inline]
+    pub(super) fn new(buf: &'a mut Vec<u8>) -> Self {
+        Self {
+            buf,
+            _marker: PhantomData,
+        }
+    }
+
+    fn pack(self, tag: u8, payload: &[u8]) -> Result<(), Error<E>> {
+        self.buf.push(tag);
+        self.buf.extend_from_slice(payload);
+        Ok(())
+    }
+
+    fn pack_len(self, tag: u8, bytes: &[u8]) -> Result<(), Error<E>> {
+        self.buf.push(tag);
+        self.buf
+            .extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        self.buf.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+macro_rules! pack_primitive {
+    ($($serialize:ident($ty:ty) => $tag:expr;)*) => {
+        $(
+            fn $serialize(self, v: $ty) -> Result<Self::Ok, Self::Error> {
+                self.pack($tag, &v.to_le_bytes())
+            }
+        )*
+    };
+}
+
+impl<'a, E> ser::Serializer for Packer<'a, E>
+where
+    E: crate::Error,
+{
+    type Ok = ();
+    type Error = Error<E>;
+
+    type SerializeSeq = PackedCompound<&'a mut Vec<u8>, E>;
+    type SerializeTuple = PackedCompound<&'a mut Vec<u8>, E>;
+    type SerializeTupleStruct = PackedCompound<&'a mut Vec<u8>, E>;
+    type SerializeTupleVariant = Impossible<(), Self::Error>;
+    type SerializeMap = PackedCompound<&'a mut Vec<u8>, E>;
+    type SerializeStruct = PackedCompound<&'a mut Vec<u8>, E>;
+    type SerializeStructVariant = Impossible<(), Self::Error>;
+
+    pack_primitive! {
+        serialize_i8(i8) => TAG_I8;
+        serialize_i16(i16) => TAG_I16;
+        serialize_i32(i32) => TAG_I32;
+        serialize_i64(i64) => TAG_I64;
+        serialize_u8(u8) => TAG_U8;
+        serialize_u16(u16) => TAG_U16;
+        serialize_u32(u32) => TAG_U32;
+        serialize_u64(u64) => TAG_U64;
+        serialize_f32(f32) => TAG_F32;
+        serialize_f64(f64) => TAG_F64;
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.pack(TAG_BOOL, &[u8::from(v)])
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.pack_len(TAG_STRING, v.encode_utf8(&mut [0; 4]).as_bytes())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.pack_len(TAG_STRING, v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.pack_len(TAG_BYTES, v)
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.pack(TAG_NONE, &[])
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.buf.push(TAG_SOME);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.pack(TAG_UNIT, &[])
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Error::unsupported("newtype variant"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(PackedCompound::new(self.buf, TAG_SEQ))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Ok(PackedCompound::new(self.buf, TAG_SEQ))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Ok(PackedCompound::new(self.buf, TAG_SEQ))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(Error::unsupported("tuple variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(PackedCompound::new(self.buf, TAG_MAP))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(PackedCompound::new(self.buf, TAG_MAP))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(Error::unsupported("struct variant"))
+    }
+}
+
+/// Packs the elements of a sequence or the entries of a map into the buffer `B`.
+///
+/// The length is only known after the last element, so a placeholder is written first and
+/// replaced by [`finish`](Self::finish).
+pub(super) struct PackedCompound<B, E> {
+    buf: B,
+    pos: usize,
+    len: u64,
+    _marker: PhantomData<fn() -> E>,
+}
+
+impl<B, E> PackedCompound<B, E>
+where
+    B: AsMut<Vec<u8>>,
+{
+    fn new(mut buf: B, tag: u8) -> Self {
+        let vec = buf.as_mut();
+        vec.push(tag);
+        let pos = vec.len();
+        vec.extend_from_slice(&0u64.to_le_bytes());
+
+        Self {
+            buf,
+            pos,
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    fn pack<T>(&mut self, value: &T) -> Result<(), Error<E>>
+    where
+        T: ?Sized + Serialize,
+        E: crate::Error,
+    {
+        value.serialize(Packer::new(self.buf.as_mut()))
+    }
+
+    /// Writes the length and returns the buffer.
+    pub(super) fn finish(mut self) -> B {
+        let pos = self.pos;
+        self.buf.as_mut()[pos..pos + 8].copy_from_slice(&self.len.to_le_bytes());
+        self.buf
+    }
+}
+
+impl<E> PackedCompound<Vec<u8>, E> {
+    /// Creates a new `PackedCompound` packing a sequence into its own buffer.
+    pub(super) fn seq() -> Self {
+        Self::new(Vec::new(), TAG_SEQ)
+    }
+
+    /// Creates a new `PackedCompound` packing a map into its own buffer.
+    pub(super) fn map() -> Self {
+        Self::new(Vec::new(), TAG_MAP)
+    }
+}
+
+impl<B, E> ser::SerializeSeq for PackedCompound<B, E>
+where
+    B: AsMut<Vec<u8>>,
+    E: crate::Error,
+{
+    type Ok = ();
+    type Error = Error<E>;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.pack(value)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish();
+        Ok(())
+    }
+}
+
+impl<B, E> ser::SerializeTuple for PackedCompound<B, E>
+where
+    B: AsMut<Vec<u8>>,
+    E: crate::Error,
+{
+    type Ok = ();
+    type Error = Error<E>;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<B, E> ser::SerializeTupleStruct for PackedCompound<B, E>
+where
+    B: AsMut<Vec<u8>>,
+    E: crate::Error,
+{
+    type Ok = ();
+    type Error = Error<E>;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<B, E> ser::SerializeMap for PackedCompound<B, E>
+where
+    B: AsMut<Vec<u8>>,
+    E: crate::Error,
+{
+    type Ok = ();
+    type Error = Error<E>;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.pack(key)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.pack(value)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.finish();
+        Ok(())
+    }
+}
+
+impl<B, E> ser::SerializeStruct for PackedCompound<B, E>
+where
+    B: AsMut<Vec<u8>>,
+    E: crate::Error,
+{
+    type Ok = ();
+    type Error = Error<E>;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeMap::serialize_entry(self, key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Unpacks a value packed by a [`Packer`] from `buf`.
+pub(super) fn unpack<E>(buf: &[u8]) -> Result<Content, E>
+where
+    E: crate::Error,
+{
+    let mut unpacker = Unpacker { buf, depth: 0 };
+    let content = unpacker.read_content()?;
+
+    if !unpacker.buf.is_empty() {
+        return Err(E::custom("trailing bytes after a packed value"));
+    }
+
+    Ok(content)
+}
+
+struct Unpacker<'a> {
+    buf: &'a [u8],
+    depth: usize,
+}
+
+impl<'a> Unpacker<'a> {
+    fn read_bytes<E>(&mut self, len: u64) -> Result<&'a [u8], E>
+    where
+        E: crate::Error,
+    {
+        let len = match usize::try_from(len) {
+            Ok(len) if len <= self.buf.len() => len,
+            _ => return Err(E::custom("unexpected end of a packed value")),
+        };
+
+        let (bytes, buf) = self.buf.split_at(len);
+        self.buf = buf;
+        Ok(bytes)
+    }
+
+    fn read_array<E, const N: usize>(&mut self) -> Result<[u8; N], E>
+    where
+        E: crate::Error,
+    {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_bytes(N as u64)?);
+        Ok(array)
+    }
+
+    fn read_len<E>(&mut self) -> Result<u64, E>
+    where
+        E: crate::Error,
+    {
+        self.read_array().map(u64::from_le_bytes)
+    }
+
+    fn read_content<E>(&mut self) -> Result<Content, E>
+    where
+        E: crate::Error,
+    {
+        if self.depth == MAX_DEPTH {
+            return Err(E::custom("depth limit exceeded"));
+        }
+
+        self.depth += 1;
+        let content = self.read_content_inner();
+        self.depth -= 1;
+        content
+    }
+
+    fn read_content_inner<E>(&mut self) -> Result<Content, E>
+    where
+        E: crate::Error,
+    {
+        let content = match self.read_array::<E, 1>()?[0] {
+            TAG_NONE => Content::None,
+            TAG_SOME => Content::Some(Box::new(self.read_content()?)),
+            TAG_UNIT => Content::Unit,
+            TAG_BOOL => match self.read_array::<E, 1>()?[0] {
+                0 => Content::Bool(false),
+                1 => Content::Bool(true),
+                _ => return Err(E::custom("invalid packed bool")),
+            },
+            TAG_I8 => Content::I8(i8::from_le_bytes(self.read_array()?)),
+            TAG_I16 => Content::I16(i16::from_le_bytes(self.read_array()?)),
+            TAG_I32 => Content::I32(i32::from_le_bytes(self.read_array()?)),
+            TAG_I64 => Content::I64(i64::from_le_bytes(self.read_array()?)),
+            TAG_U8 => Content::U8(u8::from_le_bytes(self.read_array()?)),
+            TAG_U16 => Content::U16(u16::from_le_bytes(self.read_array()?)),
+            TAG_U32 => Content::U32(u32::from_le_bytes(self.read_array()?)),
+            TAG_U64 => Content::U64(u64::from_le_bytes(self.read_array()?)),
+            TAG_F32 => Content::F32(f32::from_le_bytes(self.read_array()?)),
+            TAG_F64 => Content::F64(f64::from_le_bytes(self.read_array()?)),
+            TAG_BYTES => {
+                let len = self.read_len()?;
+                Content::Bytes(self.read_bytes(len)?.to_vec())
+            }
+            TAG_STRING => {
+                let len = self.read_len()?;
+                match String::from_utf8(self.read_bytes(len)?.to_vec()) {
+                    Ok(string) => Content::String(string),
+                    Err(_) => return Err(E::custom("invalid utf-8 in a packed string")),
+                }
+            }
+            TAG_SEQ => {
+                let len = self.read_len()?;
+
+                // Every element takes at least a byte, the length is not trusted further.
+                let mut seq = Vec::with_capacity(self.capacity(len));
+                for _ in 0..len {
+                    seq.push(self.read_content()?);
+                }
+
+                Content::Seq(seq)
+            }
+            TAG_MAP => {
+                let len = self.read_len()?;
+
+                let mut map = Vec::with_capacity(self.capacity(len));
+                for _ in 0..len {
+                    let key = self.read_content()?;
+                    let value = self.read_content()?;
+                    map.push((key, value));
+                }
+
+                Content::Map(map)
+            }
+            tag => return Err(E::custom(format_args!("invalid packed tag {}", tag))),
+        };
+
+        Ok(content)
+    }
+
+    fn capacity(&self, len: u64) -> usize {
+        usize::try_from(len).map_or(self.buf.len(), |len| len.min(self.buf.len()))
+    }
+}
@@ -0,0 +1,359 @@
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+
+use ::serde::de::value;
+use ::serde::de::{self, Deserialize, MapAccess, SeqAccess, Visitor};
+
+use crate::{ReadSeed, Reader, Store, StoreData};
+
+/// A [`Reader`] reading values from a [`Deserializer`].
+///
+/// The input of the [`Deserializer`] is buffered, fields are looked up by key regardless of their
+/// order. The [`Deserializer`] must be self-describing.
+///
+/// [`Deserializer`]: de::Deserializer
+#[derive(Clone, Debug)]
+pub struct SerdeReader {
+    content: Content,
+}
+
+impl SerdeReader {
+    /// Creates a new `SerdeReader` from the [`Deserializer`] `deserializer`.
+    ///
+    /// [`Deserializer`]: de::Deserializer
+    pub fn from_deserializer<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        Content::deserialize(deserializer).map(|content| Self { content })
+    }
+
+    fn take(&mut self) -> Content {
+        match mem::replace(&mut self.content, Content::None) {
+            Content::Some(content) => *content,
+            content => content,
+        }
+    }
+
+    fn read_int<T>(&mut self, expected: &'static str) -> Result<T, ReaderError>
+    where
+        T: TryFrom<u64> + TryFrom<i64>,
+    {
+        let content = self.take();
+        let value = match content {
+            Content::U64(v) => T::try_from(v).ok(),
+            Content::I64(v) => T::try_from(v).ok(),
+            _ => return Err(content.invalid_type(expected)),
+        };
+
+        value.ok_or_else(|| de::Error::invalid_value(content.unexpected(), &expected))
+    }
+
+    fn read_float(&mut self) -> Result<f64, ReaderError> {
+        match self.take() {
+            Content::F64(v) => Ok(v),
+            Content::U64(v) => Ok(v as f64),
+            Content::I64(v) => Ok(v as f64),
+            content => Err(content.invalid_type("a float")),
+        }
+    }
+}
+
+impl<S> Reader<S> for SerdeReader
+where
+    S: Store,
+{
+    type Error = ReaderError;
+
+    fn read_bool(&mut self) -> Result<bool, Self::Error> {
+        match self.take() {
+            Content::Bool(v) => Ok(v),
+            content => Err(content.invalid_type("a bool")),
+        }
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Self::Error> {
+        self.read_int("an i8")
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        self.read_int("an i16")
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        self.read_int("an i32")
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Self::Error> {
+        self.read_int("an i64")
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        self.read_int("an u8")
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        self.read_int("an u16")
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        self.read_int("an u32")
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Self::Error> {
+        self.read_int("an u64")
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Self::Error> {
+        self.read_float().map(|v| v as f32)
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Self::Error> {
+        self.read_float()
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Self::Error> {
+        match self.take() {
+            Content::Bytes(v) => Ok(v),
+            // Formats without a native byte type serialize bytes as a sequence of integers.
+            Content::Seq(seq) => seq
+                .into_iter()
+                .map(|content| match content {
+                    Content::U64(v) => u8::try_from(v).ok(),
+                    _ => None,
+                })
+                .collect::<Option<Vec<u8>>>()
+                .ok_or_else(|| de::Error::invalid_type(de::Unexpected::Seq, &"a byte buffer")),
+            content => Err(content.invalid_type("a byte buffer")),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, Self::Error> {
+        match self.take() {
+            Content::String(v) => Ok(v),
+            content => Err(content.invalid_type("a string")),
+        }
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>,
+    {
+        let map = match &mut self.content {
+            Content::Map(map) => map,
+            Content::Some(content) => match &mut **content {
+                Content::Map(map) => map,
+                content => return Err(content.invalid_type("a map")),
+            },
+            content => return Err(content.invalid_type("a map")),
+        };
+
+        // `None` values are serialized as null entries, which are read like missing fields.
+        let index = map
+            .iter()
+            .position(|(k, content)| k == key && !matches!(content, Content::None))
+            .ok_or_else(|| ReaderError {
+                inner: de::Error::custom(format_args!("missing field `{}`", key)),
+                missing_field: true,
+            })?;
+
+        let (_, content) = map.swap_remove(index);
+        seed.read(&mut SerdeReader { content })
+    }
+}
+
+/// Deserializes a new [`StoreData`] from the [`Deserializer`] `deserializer`.
+///
+/// [`Deserializer`]: de::Deserializer
+pub fn deserialize<'de, T, S, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: StoreData<S>,
+    S: Store,
+    D: de::Deserializer<'de>,
+{
+    let mut reader = SerdeReader::from_deserializer(deserializer)?;
+    T::read(&mut reader).map_err(de::Error::custom)
+}
+
+/// An error returned by the [`SerdeReader`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReaderError {
+    inner: value::Error,
+    missing_field: bool,
+}
+
+impl Display for ReaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+impl error::Error for ReaderError {}
+
+impl de::Error for ReaderError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self {
+            inner: de::Error::custom(msg),
+            missing_field: false,
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self {
+            inner: de::Error::missing_field(field),
+            missing_field: true,
+        }
+    }
+}
+
+impl crate::Error for ReaderError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        de::Error::custom(msg)
+    }
+
+    #[inline]
+    fn is_missing_field(&self) -> bool {
+        self.missing_field
+    }
+}
+
+/// A buffered value of a self-describing format.
+#[derive(Clone, Debug)]
+enum Content {
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    None,
+    Some(Box<Content>),
+    Seq(Vec<Content>),
+    Map(Vec<(String, Content)>),
+}
+
+impl Content {
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match self {
+            Self::Bool(v) => de::Unexpected::Bool(*v),
+            Self::U64(v) => de::Unexpected::Unsigned(*v),
+            Self::I64(v) => de::Unexpected::Signed(*v),
+            Self::F64(v) => de::Unexpected::Float(*v),
+            Self::String(v) => de::Unexpected::Str(v),
+            Self::Bytes(v) => de::Unexpected::Bytes(v),
+            Self::None => de::Unexpected::Option,
+            Self::Some(_) => de::Unexpected::Option,
+            Self::Seq(_) => de::Unexpected::Seq,
+            Self::Map(_) => de::Unexpected::Map,
+        }
+    }
+
+    fn invalid_type(&self, expected: &'static str) -> ReaderError {
+        de::Error::invalid_type(self.unexpected(), &expected)
+    }
+}
+
+impl<'de> Deserialize<'de> for Content {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ContentVisitor)
+    }
+}
+
+struct ContentVisitor;
+
+impl<'de> Visitor<'de> for ContentVisitor {
+    type Value = Content;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(Content::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Content::I64(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Content::U64(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Content::F64(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Content::String(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+        Ok(Content::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(Content::Bytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(Content::Bytes(v))
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(Content::None)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(Content::None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        Content::deserialize(deserializer).map(|content| Content::Some(Box::new(content)))
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        Content::deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(content) = seq.next_element()? {
+            vec.push(content);
+        }
+
+        Ok(Content::Seq(vec))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut vec = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(entry) = map.next_entry()? {
+            vec.push(entry);
+        }
+
+        Ok(Content::Map(vec))
+    }
+}
@@ -0,0 +1,426 @@
+use std::marker::PhantomData;
+
+use ::serde::ser::{self, Impossible, Serialize};
+
+use super::packed::PackedCompound;
+use super::shape::INDEX;
+use super::Error;
+use crate::{Store, TypeWriter, Write, Writer};
+
+/// A [`Serializer`] writing into a [`Writer`].
+///
+/// See the [module documentation] for the supported types.
+///
+/// [`Serializer`]: ser::Serializer
+/// [module documentation]: super
+pub struct Serializer<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    writer: &'a mut W,
+    _marker: PhantomData<fn() -> S>,
+}
+
+impl<'a, W, S> Serializer<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    /// Creates a new `Serializer` writing into `writer`.
+    #[inline]
+    pub fn new(writer: &'a mut W) -> Self {
+        Self {
+            writer,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, W, S> ser::Serializer for Serializer<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    type Ok = ();
+    type Error = Error<W::Error>;
+
+    type SerializeSeq = Packed<'a, W, S>;
+    type SerializeTuple = Compound<'a, W, S>;
+    type SerializeTupleStruct = Compound<'a, W, S>;
+    type SerializeTupleVariant = Impossible<(), Self::Error>;
+    type SerializeMap = Packed<'a, W, S>;
+    type SerializeStruct = Compound<'a, W, S>;
+    type SerializeStructVariant = Impossible<(), Self::Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_bool(v).map_err(Error)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_i8(v).map_err(Error)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_i16(v).map_err(Error)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_i32(v).map_err(Error)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_i64(v).map_err(Error)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_u8(v).map_err(Error)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_u16(v).map_err(Error)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_u32(v).map_err(Error)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_u64(v).map_err(Error)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_f32(v).map_err(Error)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_f64(v).map_err(Error)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.writer
+            .write_str(v.encode_utf8(&mut [0; 4]))
+            .map_err(Error)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_str(v).map_err(Error)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_bytes(v).map_err(Error)
+    }
+
+    /// `None` writes nothing. A missing optional value is read back as `None`.
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.writer.write_str(variant).map_err(Error)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(Error::unsupported("newtype variant"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(Packed::new(self.writer, PackedCompound::seq()))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Ok(Compound::new(self.writer))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Ok(Compound::new(self.writer))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(Error::unsupported("tuple variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(Packed::new(self.writer, PackedCompound::map()))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(Compound::new(self.writer))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(Error::unsupported("struct variant"))
+    }
+}
+
+/// Serializes structs and tuples into nested fields of a [`Writer`].
+///
+/// Struct fields are written under their names and tuple elements under their index.
+pub struct Compound<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    writer: &'a mut W,
+    len: usize,
+    _marker: PhantomData<fn() -> S>,
+}
+
+impl<'a, W, S> Compound<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    fn new(writer: &'a mut W) -> Self {
+        Self {
+            writer,
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    fn write_element<T>(&mut self, value: &T) -> Result<(), Error<W::Error>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = match INDEX.get(self.len) {
+            Some(key) => key,
+            None => return Err(Error::unsupported("a tuple with more than 32 elements")),
+        };
+
+        self.writer.write_field(key, &Field(value)).map_err(Error)?;
+        self.len += 1;
+        Ok(())
+    }
+}
+
+impl<'a, W, S> ser::SerializeTuple for Compound<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    type Ok = ();
+    type Error = Error<W::Error>;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.write_element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W, S> ser::SerializeTupleStruct for Compound<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    type Ok = ();
+    type Error = Error<W::Error>;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.write_element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a, W, S> ser::SerializeStruct for Compound<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    type Ok = ();
+    type Error = Error<W::Error>;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.writer.write_field(key, &Field(value)).map_err(Error)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(())
+    }
+}
+
+/// A nested value written through a [`Serializer`].
+///
+/// The type of a nested value is written by [`Serde`], therefore `write_type` writes nothing.
+///
+/// [`Serde`]: super::Serde
+struct Field<'a, T>(&'a T)
+where
+    T: ?Sized;
+
+impl<'a, S, T> Write<S> for Field<'a, T>
+where
+    S: Store,
+    T: ?Sized + Serialize,
+{
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        self.0
+            .serialize(Serializer::new(writer))
+            .map_err(Error::into_inner)
+    }
+
+    fn write_type<W>(_writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        Ok(())
+    }
+}
+
+/// Serializes sequences and maps into a single bytes value of a [`Writer`].
+///
+/// Sequences and maps have no fixed number of elements, so they are packed instead of writing a
+/// field per element.
+pub struct Packed<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    writer: &'a mut W,
+    compound: PackedCompound<Vec<u8>, W::Error>,
+    _marker: PhantomData<fn() -> S>,
+}
+
+impl<'a, W, S> Packed<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    fn new(writer: &'a mut W, compound: PackedCompound<Vec<u8>, W::Error>) -> Self {
+        Self {
+            writer,
+            compound,
+            _marker: PhantomData,
+        }
+    }
+
+    fn write(self) -> Result<(), Error<W::Error>> {
+        let buf = self.compound.finish();
+        self.writer.write_bytes(&buf).map_err(Error)
+    }
+}
+
+impl<'a, W, S> ser::SerializeSeq for Packed<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    type Ok = ();
+    type Error = Error<W::Error>;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.compound.serialize_element(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.write()
+    }
+}
+
+impl<'a, W, S> ser::SerializeMap for Packed<'a, W, S>
+where
+    W: Writer<S>,
+    S: Store,
+{
+    type Ok = ();
+    type Error = Error<W::Error>;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.compound.serialize_key(key)
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.compound.serialize_value(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.write()
+    }
+}
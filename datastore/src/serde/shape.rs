@@ -0,0 +1,488 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::iter;
+use std::rc::Rc;
+
+use ::serde::de::value::{MapDeserializer, SeqDeserializer};
+use ::serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+
+use super::content::Content;
+use super::packed;
+use crate::{Error, FieldAttributes, ReadSeed, Reader, Store, TypeWriter, WriteTypeSeed};
+
+/// The structure of a type implementing [`Deserialize`], traced by requesting a value of every
+/// type from its [`Deserialize`] implementation.
+///
+/// [`Deserialize`]: ::serde::Deserialize
+#[derive(Debug)]
+pub(super) enum Shape {
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Bytes,
+    Str,
+    Unit,
+    Option(Rc<Shape>),
+    Struct(Vec<(&'static str, Rc<Shape>)>),
+    Tuple(Vec<Rc<Shape>>),
+    /// A sequence or map, packed into a single bytes value.
+    Packed,
+}
+
+/// Traces the [`Shape`] of `T`.
+pub(super) fn trace<T>() -> Result<Rc<Shape>, TraceError>
+where
+    T: DeserializeOwned,
+{
+    let mut shape = None;
+    T::deserialize(Tracer { shape: &mut shape })?;
+    take(shape)
+}
+
+/// Writes the type of `shape` into the [`TypeWriter`] `writer`.
+///
+/// Nested values are written as fields, sequences and maps as bytes.
+pub(super) fn write_type<S, W>(shape: &Shape, writer: &mut W) -> Result<(), W::Error>
+where
+    S: Store,
+    W: TypeWriter<S>,
+{
+    match shape {
+        Shape::Bool => writer.write_bool(),
+        Shape::I8 => writer.write_i8(),
+        Shape::I16 => writer.write_i16(),
+        Shape::I32 => writer.write_i32(),
+        Shape::I64 => writer.write_i64(),
+        Shape::U8 => writer.write_u8(),
+        Shape::U16 => writer.write_u16(),
+        Shape::U32 => writer.write_u32(),
+        Shape::U64 => writer.write_u64(),
+        Shape::F32 => writer.write_f32(),
+        Shape::F64 => writer.write_f64(),
+        Shape::Bytes | Shape::Packed => writer.write_bytes(),
+        Shape::Str => writer.write_str(),
+        Shape::Option(shape) => write_type(shape, writer),
+        Shape::Struct(fields) => {
+            for (key, shape) in fields {
+                write_field_type(key, shape, writer)?;
+            }
+
+            Ok(())
+        }
+        Shape::Tuple(elements) => {
+            for (key, shape) in INDEX.iter().zip(elements) {
+                write_field_type(key, shape, writer)?;
+            }
+
+            Ok(())
+        }
+        Shape::Unit => Err(W::Error::custom("unit has no type")),
+    }
+}
+
+fn write_field_type<S, W>(key: &str, shape: &Rc<Shape>, writer: &mut W) -> Result<(), W::Error>
+where
+    S: Store,
+    W: TypeWriter<S>,
+{
+    let attrs = FieldAttributes::new().nullable(matches!(**shape, Shape::Option(_)));
+    writer.write_field_seed(key, Nested(shape), attrs)
+}
+
+/// Reads a value of the `shape` from the [`Reader`] `reader`.
+pub(super) fn read<S, R>(shape: &Shape, reader: &mut R) -> Result<Content, R::Error>
+where
+    S: Store,
+    R: Reader<S>,
+{
+    let content = match shape {
+        Shape::Bool => Content::Bool(reader.read_bool()?),
+        Shape::I8 => Content::I8(reader.read_i8()?),
+        Shape::I16 => Content::I16(reader.read_i16()?),
+        Shape::I32 => Content::I32(reader.read_i32()?),
+        Shape::I64 => Content::I64(reader.read_i64()?),
+        Shape::U8 => Content::U8(reader.read_u8()?),
+        Shape::U16 => Content::U16(reader.read_u16()?),
+        Shape::U32 => Content::U32(reader.read_u32()?),
+        Shape::U64 => Content::U64(reader.read_u64()?),
+        Shape::F32 => Content::F32(reader.read_f32()?),
+        Shape::F64 => Content::F64(reader.read_f64()?),
+        Shape::Bytes => Content::Bytes(reader.read_byte_buf()?),
+        Shape::Str => Content::String(reader.read_string()?),
+        Shape::Unit => Content::Unit,
+        Shape::Option(shape) => Content::Some(Box::new(read(shape, reader)?)),
+        Shape::Struct(fields) => {
+            let mut output = Vec::with_capacity(fields.len());
+            for (key, shape) in fields {
+                output.push((*key, read_field(key, shape, reader)?));
+            }
+
+            Content::Struct(output)
+        }
+        Shape::Tuple(elements) => {
+            let mut output = Vec::with_capacity(elements.len());
+            for (key, shape) in INDEX.iter().zip(elements) {
+                output.push(read_field(key, shape, reader)?);
+            }
+
+            Content::Seq(output)
+        }
+        Shape::Packed => packed::unpack(&reader.read_byte_buf()?)?,
+    };
+
+    Ok(content)
+}
+
+fn read_field<S, R>(key: &str, shape: &Rc<Shape>, reader: &mut R) -> Result<Content, R::Error>
+where
+    S: Store,
+    R: Reader<S>,
+{
+    match reader.read_field_seed(key, Nested(shape)) {
+        Ok(content) => Ok(content),
+        Err(err) if err.is_missing_field() => match **shape {
+            Shape::Option(_) => Ok(Content::None),
+            // The value containing the field exists, so a missing required field is not a
+            // missing optional value further up.
+            _ => Err(R::Error::custom(err)),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// The keys of the elements of a tuple. Larger tuples are not supported, matching the largest
+/// arrays supported by `serde`.
+pub(super) const INDEX: [&str; 32] = [
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16",
+    "17", "18", "19", "20", "21", "22", "23", "24", "25", "26", "27", "28", "29", "30", "31",
+];
+
+/// The seed of a nested field with the [`Shape`] only known at runtime.
+struct Nested<'a>(&'a Shape);
+
+impl<'a, S> WriteTypeSeed<S> for Nested<'a>
+where
+    S: Store,
+{
+    fn write_type<W>(self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        write_type(self.0, writer)
+    }
+}
+
+impl<'a, S> ReadSeed<S> for Nested<'a>
+where
+    S: Store,
+{
+    type Value = Content;
+
+    fn read<R>(self, reader: &mut R) -> Result<Self::Value, R::Error>
+    where
+        R: Reader<S>,
+    {
+        read(self.0, reader)
+    }
+}
+
+/// An error returned when the [`Shape`] of a type cannot be traced.
+#[derive(Debug)]
+pub(super) struct TraceError(String);
+
+impl Display for TraceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot trace serde type: {}", self.0)
+    }
+}
+
+impl error::Error for TraceError {}
+
+impl de::Error for TraceError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self(msg.to_string())
+    }
+}
+
+fn take(shape: Option<Rc<Shape>>) -> Result<Rc<Shape>, TraceError> {
+    shape.ok_or_else(|| TraceError("no value was requested".to_owned()))
+}
+
+/// A [`Deserializer`] recording the requested types into a [`Shape`].
+///
+/// Every requested value is answered with a placeholder, so the [`Deserialize`] implementation
+/// continues and requests all nested values.
+///
+/// [`Deserializer`]: de::Deserializer
+/// [`Deserialize`]: ::serde::Deserialize
+struct Tracer<'a> {
+    shape: &'a mut Option<Rc<Shape>>,
+}
+
+impl<'a> Tracer<'a> {
+    fn record(self, shape: Shape) {
+        *self.shape = Some(Rc::new(shape));
+    }
+}
+
+macro_rules! trace_primitive {
+    ($($deserialize:ident => $shape:ident, $visit:ident($value:expr);)*) => {
+        $(
+            fn $deserialize<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.record(Shape::$shape);
+                visitor.$visit($value)
+            }
+        )*
+    };
+}
+
+impl<'a, 'de> de::Deserializer<'de> for Tracer<'a> {
+    type Error = TraceError;
+
+    trace_primitive! {
+        deserialize_bool => Bool, visit_bool(false);
+        deserialize_i8 => I8, visit_i8(0);
+        deserialize_i16 => I16, visit_i16(0);
+        deserialize_i32 => I32, visit_i32(0);
+        deserialize_i64 => I64, visit_i64(0);
+        deserialize_u8 => U8, visit_u8(0);
+        deserialize_u16 => U16, visit_u16(0);
+        deserialize_u32 => U32, visit_u32(0);
+        deserialize_u64 => U64, visit_u64(0);
+        deserialize_f32 => F32, visit_f32(0.0);
+        deserialize_f64 => F64, visit_f64(0.0);
+        deserialize_char => Str, visit_char('\0');
+        deserialize_str => Str, visit_str("");
+        deserialize_string => Str, visit_str("");
+        deserialize_identifier => Str, visit_str("");
+        deserialize_bytes => Bytes, visit_bytes(&[]);
+        deserialize_byte_buf => Bytes, visit_bytes(&[]);
+    }
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::custom("deserialize_any is not supported"))
+    }
+
+    fn deserialize_ignored_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::custom(
+            "deserialize_ignored_any is not supported",
+        ))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut shape = None;
+        let value = visitor.visit_some(Tracer { shape: &mut shape })?;
+        self.record(Shape::Option(take(shape)?));
+        Ok(value)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.record(Shape::Unit);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Sequences are packed and read without their element shape, so they are traced empty.
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let value = visitor.visit_seq(SeqDeserializer::new(iter::empty::<()>()))?;
+        self.record(Shape::Packed);
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if len > INDEX.len() {
+            return Err(de::Error::custom(format_args!(
+                "tuples with more than {} elements are not supported",
+                INDEX.len()
+            )));
+        }
+
+        let mut seq = TraceSeq {
+            remaining: len,
+            shapes: Vec::with_capacity(len),
+        };
+        let value = visitor.visit_seq(&mut seq)?;
+
+        if seq.shapes.len() != len {
+            return Err(de::Error::invalid_length(
+                seq.shapes.len(),
+                &"all tuple elements",
+            ));
+        }
+
+        self.record(Shape::Tuple(seq.shapes));
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    /// Maps are packed like sequences and traced empty.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let value = visitor.visit_map(MapDeserializer::new(iter::empty::<((), ())>()))?;
+        self.record(Shape::Packed);
+        Ok(value)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut map = TraceStruct {
+            fields,
+            shapes: Vec::with_capacity(fields.len()),
+        };
+        let value = visitor.visit_map(&mut map)?;
+
+        self.record(Shape::Struct(map.shapes));
+        Ok(value)
+    }
+
+    /// Only enums with unit variants are supported. They are written as the variant name.
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let variant = match variants.first() {
+            Some(variant) => *variant,
+            None => return Err(de::Error::custom("enum without variants")),
+        };
+
+        self.record(Shape::Str);
+        visitor.visit_enum(variant.into_deserializer())
+    }
+}
+
+struct TraceSeq {
+    remaining: usize,
+    shapes: Vec<Rc<Shape>>,
+}
+
+impl<'de> de::SeqAccess<'de> for TraceSeq {
+    type Error = TraceError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+
+        let mut shape = None;
+        let value = seed.deserialize(Tracer { shape: &mut shape })?;
+        self.shapes.push(take(shape)?);
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+struct TraceStruct {
+    fields: &'static [&'static str],
+    shapes: Vec<(&'static str, Rc<Shape>)>,
+}
+
+impl<'de> de::MapAccess<'de> for TraceStruct {
+    type Error = TraceError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.fields.get(self.shapes.len()) {
+            Some(field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let key = self.fields[self.shapes.len()];
+
+        let mut shape = None;
+        let value = seed.deserialize(Tracer { shape: &mut shape })?;
+        self.shapes.push((key, take(shape)?));
+        Ok(value)
+    }
+}
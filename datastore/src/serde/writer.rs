@@ -0,0 +1,252 @@
+use std::marker::PhantomData;
+use std::mem;
+
+use ::serde::ser::{self, SerializeMap};
+
+use super::Error;
+use crate::{Store, StoreData, Write, Writer};
+
+/// A [`Writer`] forwarding all values to a [`Serializer`].
+///
+/// Fields written using [`write_field`] become entries of a map, all other values are forwarded
+/// directly. A value that does not write anything is serialized as `none`.
+///
+/// [`Serializer`]: ser::Serializer
+/// [`write_field`]: Writer::write_field
+pub struct SerdeWriter<W>
+where
+    W: ser::Serializer,
+{
+    state: State<W>,
+}
+
+enum State<W>
+where
+    W: ser::Serializer,
+{
+    Empty(W),
+    Map(W::SerializeMap),
+    Done(W::Ok),
+    Poisoned,
+}
+
+impl<W> SerdeWriter<W>
+where
+    W: ser::Serializer,
+{
+    /// Creates a new `SerdeWriter` forwarding to the [`Serializer`] `serializer`.
+    ///
+    /// [`Serializer`]: ser::Serializer
+    #[inline]
+    pub fn new(serializer: W) -> Self {
+        Self {
+            state: State::Empty(serializer),
+        }
+    }
+
+    /// Completes the serialization, returning the output of the [`Serializer`].
+    ///
+    /// If nothing was written an empty map is serialized.
+    ///
+    /// [`Serializer`]: ser::Serializer
+    pub fn finish(self) -> Result<W::Ok, W::Error> {
+        match self.state {
+            State::Empty(serializer) => serializer.serialize_map(Some(0))?.end(),
+            State::Map(map) => map.end(),
+            State::Done(ok) => Ok(ok),
+            State::Poisoned => Err(ser::Error::custom("writer poisoned by a previous error")),
+        }
+    }
+
+    /// Completes the serialization of a field value.
+    fn finish_value(self) -> Result<W::Ok, W::Error> {
+        match self.state {
+            State::Empty(serializer) => serializer.serialize_none(),
+            _ => self.finish(),
+        }
+    }
+
+    fn serialize<F>(&mut self, f: F) -> Result<(), Error<W::Error>>
+    where
+        F: FnOnce(W) -> Result<W::Ok, W::Error>,
+    {
+        match mem::replace(&mut self.state, State::Poisoned) {
+            State::Empty(serializer) => {
+                self.state = State::Done(f(serializer).map_err(Error)?);
+                Ok(())
+            }
+            _ => Err(Error(ser::Error::custom("multiple values written"))),
+        }
+    }
+}
+
+impl<S, W> Writer<S> for SerdeWriter<W>
+where
+    S: Store,
+    W: ser::Serializer,
+{
+    type Error = Error<W::Error>;
+
+    fn write_bool(&mut self, v: bool) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_bool(v))
+    }
+
+    fn write_i8(&mut self, v: i8) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_i8(v))
+    }
+
+    fn write_i16(&mut self, v: i16) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_i16(v))
+    }
+
+    fn write_i32(&mut self, v: i32) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_i32(v))
+    }
+
+    fn write_i64(&mut self, v: i64) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_i64(v))
+    }
+
+    fn write_u8(&mut self, v: u8) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_u8(v))
+    }
+
+    fn write_u16(&mut self, v: u16) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_u16(v))
+    }
+
+    fn write_u32(&mut self, v: u32) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_u32(v))
+    }
+
+    fn write_u64(&mut self, v: u64) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_u64(v))
+    }
+
+    fn write_f32(&mut self, v: f32) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_f32(v))
+    }
+
+    fn write_f64(&mut self, v: f64) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_f64(v))
+    }
+
+    fn write_bytes(&mut self, v: &[u8]) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_bytes(v))
+    }
+
+    fn write_str(&mut self, v: &str) -> Result<(), Self::Error> {
+        self.serialize(|serializer| serializer.serialize_str(v))
+    }
+
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<S>,
+    {
+        let mut map = match mem::replace(&mut self.state, State::Poisoned) {
+            State::Empty(serializer) => serializer.serialize_map(None).map_err(Error)?,
+            State::Map(map) => map,
+            _ => return Err(Error(ser::Error::custom("field written after value"))),
+        };
+
+        map.serialize_entry(
+            key,
+            &Field {
+                value,
+                _marker: PhantomData,
+            },
+        )
+        .map_err(Error)?;
+
+        self.state = State::Map(map);
+        Ok(())
+    }
+}
+
+/// A field value serialized using a nested [`SerdeWriter`].
+struct Field<'a, T, S>
+where
+    T: ?Sized,
+{
+    value: &'a T,
+    _marker: PhantomData<fn() -> S>,
+}
+
+impl<'a, T, S> ser::Serialize for Field<'a, T, S>
+where
+    T: ?Sized + Write<S>,
+    S: Store,
+{
+    fn serialize<W>(&self, serializer: W) -> Result<W::Ok, W::Error>
+    where
+        W: ser::Serializer,
+    {
+        let mut writer = SerdeWriter::new(serializer);
+        self.value.write(&mut writer).map_err(Error::into_inner)?;
+        writer.finish_value()
+    }
+}
+
+/// A wrapper implementing [`Serialize`] for a [`StoreData`] type.
+///
+/// The data is written into a [`SerdeWriter`].
+///
+/// ```
+/// # use datastore::{Store, StoreData};
+/// # use datastore::serde::SerializeData;
+/// # fn to_json<T, S>(data: &T) -> String
+/// # where
+/// #     T: StoreData<S>,
+/// #     S: Store,
+/// # {
+/// serde_json::to_string(&SerializeData::<T, S>::new(data)).unwrap()
+/// # }
+/// ```
+///
+/// [`Serialize`]: ser::Serialize
+pub struct SerializeData<'a, T, S> {
+    data: &'a T,
+    _marker: PhantomData<fn() -> S>,
+}
+
+impl<'a, T, S> SerializeData<'a, T, S>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    /// Creates a new `SerializeData` for the [`StoreData`] `data`.
+    #[inline]
+    pub fn new(data: &'a T) -> Self {
+        Self {
+            data,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, S> ser::Serialize for SerializeData<'a, T, S>
+where
+    T: StoreData<S>,
+    S: Store,
+{
+    fn serialize<W>(&self, serializer: W) -> Result<W::Ok, W::Error>
+    where
+        W: ser::Serializer,
+    {
+        serialize(self.data, serializer)
+    }
+}
+
+/// Serializes the [`StoreData`] `data` into the [`Serializer`] `serializer`.
+///
+/// [`Serializer`]: ser::Serializer
+pub fn serialize<T, S, W>(data: &T, serializer: W) -> Result<W::Ok, W::Error>
+where
+    T: StoreData<S>,
+    S: Store,
+    W: ser::Serializer,
+{
+    let mut writer = SerdeWriter::new(serializer);
+    data.write(&mut writer).map_err(Error::into_inner)?;
+    writer.finish()
+}
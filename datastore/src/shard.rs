@@ -0,0 +1,487 @@
+//! Partitioning of items across multiple stores.
+//!
+//! [`Sharded`] distributes the items of every type over a fixed set of inner stores, the
+//! shards. Every type has a shard key field, which is read from an item when it is inserted and
+//! passed to a [`Router`] to select the shard storing the item. The default router is
+//! [`ModuloHash`].
+//!
+//! [`get`], [`get_one`] and [`delete`] only reach a single shard if the query contains the shard
+//! key. All other reads and deletes are sent to all shards concurrently and the results are
+//! merged in shard order. [`create`] creates the type on all shards.
+//!
+//! ```
+//! use datastore::shard::{JumpHash, Sharded};
+//! # use datastore::Store;
+//!
+//! # async fn run<SqliteStore: Store>() -> Result<(), SqliteStore::Error> {
+//! let shards = vec![
+//!     SqliteStore::connect("sqlite://events-0.db").await?,
+//!     SqliteStore::connect("sqlite://events-1.db").await?,
+//! ];
+//!
+//! let store = Sharded::new(shards, "id")
+//!     .with_type_key("Event", "stream_id")
+//!     .with_router(JumpHash);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Sharded::connect_with`] connects to every uri in the repeated `shard` query parameter and
+//! reads the shard key from the `shard_key` parameter, e.g.
+//! `sharded://?shard_key=id&shard=sqlite%3A%2F%2Fa.db&shard=sqlite%3A%2F%2Fb.db`.
+//!
+//! [`get`]: Store::get
+//! [`get_one`]: Store::get_one
+//! [`delete`]: Store::delete
+//! [`create`]: Store::create
+//! [`Sharded::connect_with`]: Store::connect_with
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::Poll;
+
+use async_trait::async_trait;
+
+use crate::options::ConnectOptions;
+use crate::shared::Shared;
+use crate::value::{Record, Value};
+use crate::{Capabilities, DataDescriptor, DataQuery, Error, Store, StoreData};
+
+/// A [`Store`] distributing items over multiple inner stores of type `S`.
+pub struct Sharded<S> {
+    shards: Vec<S>,
+    key: String,
+    type_keys: HashMap<String, String>,
+    router: Box<dyn Router>,
+}
+
+impl<S> Sharded<S> {
+    /// Creates a new `Sharded` store distributing items over `shards` by the field `key`, using
+    /// the [`ModuloHash`] router.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty.
+    pub fn new<K>(shards: Vec<S>, key: K) -> Self
+    where
+        K: ToString,
+    {
+        assert!(!shards.is_empty(), "shards must not be empty");
+
+        Self {
+            shards,
+            key: key.to_string(),
+            type_keys: HashMap::new(),
+            router: Box::new(ModuloHash),
+        }
+    }
+
+    /// Sets the shard key field of the type with the [`ident`], overriding the key passed to
+    /// [`new`].
+    ///
+    /// [`ident`]: DataDescriptor::ident
+    /// [`new`]: Self::new
+    pub fn with_type_key<I, K>(mut self, ident: I, key: K) -> Self
+    where
+        I: ToString,
+        K: ToString,
+    {
+        self.type_keys.insert(ident.to_string(), key.to_string());
+        self
+    }
+
+    /// Sets the [`Router`] selecting the shard of an item.
+    ///
+    /// Changing the router of an existing store moves the shard of existing items. Those items
+    /// are still returned by reads sent to all shards, but no longer by reads that only reach a
+    /// single shard.
+    pub fn with_router<R>(mut self, router: R) -> Self
+    where
+        R: Router + 'static,
+    {
+        self.router = Box::new(router);
+        self
+    }
+
+    /// Returns the inner stores.
+    #[inline]
+    pub fn shards(&self) -> &[S] {
+        &self.shards
+    }
+
+    /// Consumes the `Sharded` store, returning the inner stores.
+    #[inline]
+    pub fn into_shards(self) -> Vec<S> {
+        self.shards
+    }
+
+    /// Returns the shard key field of the type with the `ident`.
+    fn key(&self, ident: &str) -> &str {
+        self.type_keys.get(ident).unwrap_or(&self.key)
+    }
+}
+
+impl<S> Sharded<S>
+where
+    S: Store,
+{
+    /// Returns the shard of items with the shard key `key`.
+    fn route(&self, key: &Value) -> Result<&S, S::Error> {
+        let index = self.router.route(key, self.shards.len());
+
+        self.shards.get(index).ok_or_else(|| {
+            S::Error::custom(format!(
+                "router selected shard {} of {}",
+                index,
+                self.shards.len()
+            ))
+        })
+    }
+
+    /// Returns the shard a query must be sent to, or `None` if the query does not contain the
+    /// shard key and must be sent to all shards.
+    fn route_query<T, Q>(&self, ident: &str, query: &Q) -> Result<Option<&S>, S::Error>
+    where
+        T: StoreData<S::DataStore>,
+        Q: DataQuery<T, S::DataStore>,
+    {
+        let mut record = Record::new();
+        query.write(&mut record).map_err(S::Error::custom)?;
+
+        match record.get(self.key(ident)) {
+            Some(key) => self.route(key).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<S> Debug for Sharded<S>
+where
+    S: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sharded")
+            .field("shards", &self.shards)
+            .field("key", &self.key)
+            .field("type_keys", &self.type_keys)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<S> Store for Sharded<S>
+where
+    S: Store,
+{
+    type DataStore = S::DataStore;
+    type Error = S::Error;
+
+    async fn connect(uri: &str) -> Result<Self, Self::Error> {
+        let options = ConnectOptions::parse(uri).map_err(S::Error::custom)?;
+        Self::connect_with(&options).await
+    }
+
+    async fn connect_with(options: &ConnectOptions) -> Result<Self, Self::Error> {
+        let key = match options.get("shard_key") {
+            Some(key) => key.to_owned(),
+            None => return Err(S::Error::custom("missing parameter shard_key")),
+        };
+
+        let uris: Vec<_> = options
+            .params()
+            .filter(|(key, _)| *key == "shard")
+            .map(|(_, uri)| uri.to_owned())
+            .collect();
+        if uris.is_empty() {
+            return Err(S::Error::custom("missing parameter shard"));
+        }
+
+        let mut shards = Vec::with_capacity(uris.len());
+        for uri in uris {
+            shards.push(S::connect(&uri).await?);
+        }
+
+        Ok(Self::new(shards, key))
+    }
+
+    async fn create<T, D>(&self, descriptor: D) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let ident = descriptor.ident().to_owned();
+        let descriptor = Mutex::new(descriptor);
+        let descriptor = Shared::new(&descriptor, &ident);
+
+        let futures = self
+            .shards
+            .iter()
+            .map(|shard| shard.create::<T, _>(descriptor));
+        try_join_all(futures).await?;
+        Ok(())
+    }
+
+    async fn delete<T, D, Q>(&self, descriptor: D, query: Q) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let shard = self.route_query(descriptor.ident(), &query)?;
+        if let Some(shard) = shard {
+            return shard.delete(descriptor, query).await;
+        }
+
+        let ident = descriptor.ident().to_owned();
+        let (descriptor, query) = (Mutex::new(descriptor), Mutex::new(query));
+        let (descriptor, query) = (Shared::new(&descriptor, &ident), Shared::new(&query, ""));
+
+        let futures = self
+            .shards
+            .iter()
+            .map(|shard| shard.delete::<T, _, _>(descriptor, query));
+        try_join_all(futures).await?;
+        Ok(())
+    }
+
+    async fn get<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let shard = self.route_query(descriptor.ident(), &query)?;
+        if let Some(shard) = shard {
+            return shard.get(descriptor, query).await;
+        }
+
+        let ident = descriptor.ident().to_owned();
+        let (descriptor, query) = (Mutex::new(descriptor), Mutex::new(query));
+        let (descriptor, query) = (Shared::new(&descriptor, &ident), Shared::new(&query, ""));
+
+        let futures = self.shards.iter().map(|shard| shard.get(descriptor, query));
+        Ok(try_join_all(futures).await?.into_iter().flatten().collect())
+    }
+
+    async fn get_all<T, D>(&self, descriptor: D) -> Result<Vec<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send + Sync,
+    {
+        let ident = descriptor.ident().to_owned();
+        let descriptor = Mutex::new(descriptor);
+        let descriptor = Shared::new(&descriptor, &ident);
+
+        let futures = self.shards.iter().map(|shard| shard.get_all(descriptor));
+        Ok(try_join_all(futures).await?.into_iter().flatten().collect())
+    }
+
+    async fn get_one<T, D, Q>(&self, descriptor: D, query: Q) -> Result<Option<T>, Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+        Q: DataQuery<T, Self::DataStore> + Send,
+    {
+        let shard = self.route_query(descriptor.ident(), &query)?;
+        if let Some(shard) = shard {
+            return shard.get_one(descriptor, query).await;
+        }
+
+        let ident = descriptor.ident().to_owned();
+        let (descriptor, query) = (Mutex::new(descriptor), Mutex::new(query));
+        let (descriptor, query) = (Shared::new(&descriptor, &ident), Shared::new(&query, ""));
+
+        let futures = self
+            .shards
+            .iter()
+            .map(|shard| shard.get_one(descriptor, query));
+        Ok(try_join_all(futures).await?.into_iter().flatten().next())
+    }
+
+    async fn insert<T, D>(&self, descriptor: D, data: T) -> Result<(), Self::Error>
+    where
+        T: StoreData<Self::DataStore> + Send + Sync + 'static,
+        D: DataDescriptor<T, Self::DataStore> + Send,
+    {
+        let mut record = Record::new();
+        data.write(&mut record).map_err(S::Error::custom)?;
+
+        let key = self.key(descriptor.ident());
+        let shard = match record.get(key) {
+            Some(value) => self.route(value)?,
+            None => {
+                return Err(S::Error::custom(format!(
+                    "missing shard key field {:?}",
+                    key
+                )))
+            }
+        };
+
+        shard.insert(descriptor, data).await
+    }
+
+    async fn ping(&self) -> Result<(), Self::Error> {
+        try_join_all(self.shards.iter().map(|shard| shard.ping())).await?;
+        Ok(())
+    }
+
+    /// Returns the capabilities supported by all shards. Operations spanning multiple shards are
+    /// neither atomic nor ordered, so [`Capabilities::TRANSACTIONS`] and
+    /// [`Capabilities::ORDERING`] are never returned.
+    fn capabilities(&self) -> Capabilities {
+        self.shards
+            .iter()
+            .fold(Capabilities::all(), |capabilities, shard| {
+                capabilities.intersection(shard.capabilities())
+            })
+            .difference(Capabilities::TRANSACTIONS | Capabilities::ORDERING)
+    }
+}
+
+/// A strategy selecting the shard of an item.
+pub trait Router: Send + Sync {
+    /// Returns the index of the shard for items with the shard key `key`. The index must be less
+    /// than `shards`.
+    fn route(&self, key: &Value, shards: usize) -> usize;
+}
+
+impl<F> Router for F
+where
+    F: Fn(&Value, usize) -> usize + Send + Sync,
+{
+    #[inline]
+    fn route(&self, key: &Value, shards: usize) -> usize {
+        self(key, shards)
+    }
+}
+
+/// A [`Router`] selecting the shard by the [`hash`] of the key modulo the number of shards.
+///
+/// Changing the number of shards moves most items to a different shard.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ModuloHash;
+
+impl Router for ModuloHash {
+    fn route(&self, key: &Value, shards: usize) -> usize {
+        (hash(key) % shards as u64) as usize
+    }
+}
+
+/// A [`Router`] using jump consistent hashing on the [`hash`] of the key.
+///
+/// Adding a shard only moves the items that are assigned to the new shard.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct JumpHash;
+
+impl Router for JumpHash {
+    fn route(&self, key: &Value, shards: usize) -> usize {
+        // "A Fast, Minimal Memory, Consistent Hash Algorithm" by Lamping and Veach
+        let mut key = hash(key);
+        let (mut bucket, mut jump) = (0, 0);
+
+        while jump < shards as u64 {
+            bucket = jump;
+            key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+            jump = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as u64;
+        }
+
+        bucket as usize
+    }
+}
+
+/// Returns a stable hash of the `value`.
+///
+/// The hash does not change between program runs or platforms. Integers of different types hash
+/// equally if they have the same value.
+pub fn hash(value: &Value) -> u64 {
+    let mut hasher = Fnv(0xcbf2_9ce4_8422_2325);
+    hasher.value(value);
+    hasher.0
+}
+
+/// A 64-bit FNV-1a hasher.
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0 ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Bool(v) => self.write(&[0, u8::from(*v)]),
+            Value::I8(v) => self.int(i128::from(*v)),
+            Value::I16(v) => self.int(i128::from(*v)),
+            Value::I32(v) => self.int(i128::from(*v)),
+            Value::I64(v) => self.int(i128::from(*v)),
+            Value::U8(v) => self.int(i128::from(*v)),
+            Value::U16(v) => self.int(i128::from(*v)),
+            Value::U32(v) => self.int(i128::from(*v)),
+            Value::U64(v) => self.int(i128::from(*v)),
+            Value::F32(v) => self.float(f64::from(*v)),
+            Value::F64(v) => self.float(*v),
+            Value::Bytes(v) => self.bytes(4, v),
+            Value::String(v) => self.bytes(5, v.as_bytes()),
+            Value::Record(record) => {
+                self.write(&[6]);
+                for (key, value) in record.iter() {
+                    self.bytes(5, key.as_bytes());
+                    self.value(value);
+                }
+            }
+        }
+    }
+
+    fn int(&mut self, v: i128) {
+        self.write(&[1]);
+        self.write(&v.to_le_bytes());
+    }
+
+    fn float(&mut self, v: f64) {
+        self.write(&[2]);
+        self.write(&v.to_bits().to_le_bytes());
+    }
+
+    fn bytes(&mut self, tag: u8, v: &[u8]) {
+        self.write(&[tag]);
+        self.write(&(v.len() as u64).to_le_bytes());
+        self.write(v);
+    }
+}
+
+/// Polls all `futures` concurrently, returning their outputs in order or the first error.
+async fn try_join_all<I, F, T, E>(futures: I) -> Result<Vec<T>, E>
+where
+    I: IntoIterator<Item = F>,
+    F: Future<Output = Result<T, E>> + Unpin,
+{
+    let mut futures: Vec<_> = futures.into_iter().map(Some).collect();
+    let mut outputs: Vec<_> = futures.iter().map(|_| None).collect();
+
+    future::poll_fn(|cx| {
+        let mut pending = false;
+
+        for (slot, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if let Some(future) = slot {
+                match Pin::new(future).poll(cx) {
+                    Poll::Ready(Ok(value)) => {
+                        *output = Some(value);
+                        *slot = None;
+                    }
+                    Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                    Poll::Pending => pending = true,
+                }
+            }
+        }
+
+        match pending {
+            true => Poll::Pending,
+            false => Poll::Ready(Ok(outputs.iter_mut().flat_map(Option::take).collect())),
+        }
+    })
+    .await
+}
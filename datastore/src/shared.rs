@@ -0,0 +1,70 @@
+//! Descriptors and queries shared between multiple store calls.
+use std::sync::{Mutex, MutexGuard};
+
+use crate::{DataDescriptor, DataQuery, Reader, Store, StoreData, TypeWriter, Writer};
+
+/// A descriptor or query shared by multiple calls to inner stores.
+///
+/// Stores take descriptors and queries by value, but they are neither [`Clone`] nor [`Sync`].
+/// The `Mutex` allows every call to use the same value.
+pub(crate) struct Shared<'a, D> {
+    inner: &'a Mutex<D>,
+    ident: &'a str,
+}
+
+impl<'a, D> Shared<'a, D> {
+    pub(crate) fn new(inner: &'a Mutex<D>, ident: &'a str) -> Self {
+        Self { inner, ident }
+    }
+
+    fn lock(&self) -> MutexGuard<'a, D> {
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<'a, D> Clone for Shared<'a, D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, D> Copy for Shared<'a, D> {}
+
+impl<'a, T, S, D> DataDescriptor<T, S> for Shared<'a, D>
+where
+    T: StoreData<S>,
+    S: Store,
+    D: DataDescriptor<T, S>,
+{
+    fn ident(&self) -> &str {
+        self.ident
+    }
+
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        self.lock().write(writer)
+    }
+
+    fn read<R>(&self, reader: &mut R) -> Result<T, R::Error>
+    where
+        R: Reader<S>,
+    {
+        self.lock().read(reader)
+    }
+}
+
+impl<'a, T, S, Q> DataQuery<T, S> for Shared<'a, Q>
+where
+    T: StoreData<S>,
+    S: Store,
+    Q: DataQuery<T, S>,
+{
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        self.lock().write(writer)
+    }
+}
@@ -0,0 +1,480 @@
+//! SQL DDL generation from [`DataDescriptor`]s.
+//!
+//! A [`Table`] is created from the [`TypeWriter`] pass of a [`DataDescriptor`] and renders the
+//! `CREATE TABLE` and `CREATE INDEX` statements for a SQL [`Dialect`]. No database connection is
+//! required.
+//!
+//! ```
+//! # use datastore::{DataDescriptor, Store, StoreData, Write};
+//! use datastore::sql::{Dialect, Table};
+//!
+//! # fn ddl<S, T>(descriptor: &T::Descriptor) -> Result<(), datastore::sql::Error>
+//! # where
+//! #     S: Store,
+//! #     T: StoreData<S>,
+//! # {
+//! let table = Table::new::<T, S, _>(descriptor)?;
+//!
+//! for stmt in table.to_ddl(Dialect::Postgres) {
+//!     println!("{};", stmt);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+use crate::{DataDescriptor, FieldAttributes, Store, StoreData, TypeWriter, WriteTypeSeed};
+
+/// A SQL dialect.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    Sqlite,
+    Postgres,
+    MySql,
+}
+
+impl Dialect {
+    /// Quotes the identifier `ident` for this `Dialect`.
+    ///
+    /// ```
+    /// # use datastore::sql::Dialect;
+    /// assert_eq!(Dialect::Postgres.quote_ident("my\"table"), "\"my\"\"table\"");
+    /// assert_eq!(Dialect::MySql.quote_ident("my_table"), "`my_table`");
+    /// ```
+    pub fn quote_ident(&self, ident: &str) -> String {
+        let quote = match self {
+            Self::Sqlite | Self::Postgres => '"',
+            Self::MySql => '`',
+        };
+
+        let mut buf = String::with_capacity(ident.len() + 2);
+        buf.push(quote);
+        for c in ident.chars() {
+            if c == quote {
+                buf.push(quote);
+            }
+
+            buf.push(c);
+        }
+        buf.push(quote);
+        buf
+    }
+
+    /// Returns the SQL type used for a column of the type `ty` with the [`FieldAttributes`]
+    /// `attrs`.
+    pub fn column_type(&self, ty: ColumnType, attrs: FieldAttributes) -> &'static str {
+        match self {
+            Self::Sqlite => match ty {
+                ColumnType::Bool
+                | ColumnType::I8
+                | ColumnType::I16
+                | ColumnType::I32
+                | ColumnType::I64
+                | ColumnType::U8
+                | ColumnType::U16
+                | ColumnType::U32
+                | ColumnType::U64 => "INTEGER",
+                ColumnType::F32 | ColumnType::F64 => "REAL",
+                ColumnType::Bytes => "BLOB",
+                ColumnType::Str => "TEXT",
+            },
+            Self::Postgres => match ty {
+                ColumnType::Bool => "BOOLEAN",
+                ColumnType::I8 | ColumnType::I16 | ColumnType::U8 => "SMALLINT",
+                ColumnType::I32 | ColumnType::U16 => "INTEGER",
+                ColumnType::I64 | ColumnType::U32 => "BIGINT",
+                ColumnType::U64 => "NUMERIC(20)",
+                ColumnType::F32 => "REAL",
+                ColumnType::F64 => "DOUBLE PRECISION",
+                ColumnType::Bytes => "BYTEA",
+                ColumnType::Str => "TEXT",
+            },
+            Self::MySql => match ty {
+                ColumnType::Bool => "BOOLEAN",
+                ColumnType::I8 => "TINYINT",
+                ColumnType::I16 => "SMALLINT",
+                ColumnType::I32 => "INT",
+                ColumnType::I64 => "BIGINT",
+                ColumnType::U8 => "TINYINT UNSIGNED",
+                ColumnType::U16 => "SMALLINT UNSIGNED",
+                ColumnType::U32 => "INT UNSIGNED",
+                ColumnType::U64 => "BIGINT UNSIGNED",
+                ColumnType::F32 => "FLOAT",
+                ColumnType::F64 => "DOUBLE",
+                // MySQL cannot index TEXT and BLOB columns without a prefix length.
+                ColumnType::Bytes if attrs.is_primary_key() || attrs.is_index() => "VARBINARY(255)",
+                ColumnType::Bytes => "BLOB",
+                ColumnType::Str if attrs.is_primary_key() || attrs.is_index() => "VARCHAR(255)",
+                ColumnType::Str => "TEXT",
+            },
+        }
+    }
+}
+
+/// The type of a [`Column`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ColumnType {
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Bytes,
+    Str,
+}
+
+/// A column of a [`Table`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Column {
+    name: String,
+    ty: ColumnType,
+    attrs: FieldAttributes,
+}
+
+impl Column {
+    /// Returns the name of the `Column`.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type of the `Column`.
+    #[inline]
+    pub fn ty(&self) -> ColumnType {
+        self.ty
+    }
+
+    /// Returns the [`FieldAttributes`] of the `Column`.
+    #[inline]
+    pub fn attributes(&self) -> FieldAttributes {
+        self.attrs
+    }
+}
+
+/// A table described by a [`DataDescriptor`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Table {
+    name: String,
+    columns: Vec<Column>,
+}
+
+impl Table {
+    /// Creates a new `Table` from the [`DataDescriptor`] `descriptor`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if a field of the descriptor cannot be represented as a single column.
+    pub fn new<T, S, D>(descriptor: &D) -> Result<Self, Error>
+    where
+        T: StoreData<S>,
+        S: Store,
+        D: DataDescriptor<T, S>,
+    {
+        let mut writer = TableWriter::new();
+        descriptor.write(&mut writer)?;
+
+        Ok(Self {
+            name: descriptor.ident().to_owned(),
+            columns: writer.columns,
+        })
+    }
+
+    /// Returns the name of the `Table`.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the [`Column`]s of the `Table` in field order.
+    #[inline]
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Returns an iterator over all [`Column`]s that are part of the primary key.
+    pub fn primary_key(&self) -> impl Iterator<Item = &Column> + '_ {
+        self.columns
+            .iter()
+            .filter(|column| column.attrs.is_primary_key())
+    }
+
+    /// Returns the `CREATE TABLE` statement for the `Table`.
+    ///
+    /// For [`Dialect::MySql`] the indexes are part of the `CREATE TABLE` statement, for all other
+    /// dialects they are returned by [`create_indexes`].
+    ///
+    /// [`create_indexes`]: Self::create_indexes
+    pub fn create_table(&self, dialect: Dialect) -> String {
+        let mut defs = Vec::with_capacity(self.columns.len() + 1);
+
+        for column in &self.columns {
+            let mut def = format!(
+                "{} {}",
+                dialect.quote_ident(&column.name),
+                dialect.column_type(column.ty, column.attrs)
+            );
+
+            if !column.attrs.is_nullable() {
+                def.push_str(" NOT NULL");
+            }
+
+            defs.push(def);
+        }
+
+        let primary_key: Vec<String> = self
+            .primary_key()
+            .map(|column| dialect.quote_ident(&column.name))
+            .collect();
+
+        if !primary_key.is_empty() {
+            defs.push(format!("PRIMARY KEY ({})", primary_key.join(", ")));
+        }
+
+        if dialect == Dialect::MySql {
+            for column in self.indexed() {
+                defs.push(format!(
+                    "INDEX {} ({})",
+                    dialect.quote_ident(&self.index_name(column)),
+                    dialect.quote_ident(&column.name)
+                ));
+            }
+        }
+
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (\n    {}\n)",
+            dialect.quote_ident(&self.name),
+            defs.join(",\n    ")
+        )
+    }
+
+    /// Returns the `CREATE INDEX` statements for all indexed columns of the `Table`.
+    ///
+    /// Always returns an empty [`Vec`] for [`Dialect::MySql`] since the indexes are already part
+    /// of [`create_table`].
+    ///
+    /// [`create_table`]: Self::create_table
+    pub fn create_indexes(&self, dialect: Dialect) -> Vec<String> {
+        if dialect == Dialect::MySql {
+            return Vec::new();
+        }
+
+        self.indexed()
+            .map(|column| {
+                format!(
+                    "CREATE INDEX IF NOT EXISTS {} ON {} ({})",
+                    dialect.quote_ident(&self.index_name(column)),
+                    dialect.quote_ident(&self.name),
+                    dialect.quote_ident(&column.name)
+                )
+            })
+            .collect()
+    }
+
+    /// Returns all statements required to create the `Table` including its indexes.
+    pub fn to_ddl(&self, dialect: Dialect) -> Vec<String> {
+        let mut stmts = vec![self.create_table(dialect)];
+        stmts.extend(self.create_indexes(dialect));
+        stmts
+    }
+
+    fn indexed(&self) -> impl Iterator<Item = &Column> + '_ {
+        self.columns.iter().filter(|column| column.attrs.is_index())
+    }
+
+    fn index_name(&self, column: &Column) -> String {
+        format!("{}_{}_idx", self.name, column.name)
+    }
+}
+
+/// Returns all statements required to create the table described by `descriptor` in the
+/// [`Dialect`] `dialect`.
+///
+/// This is a shorthand for [`Table::new`] followed by [`Table::to_ddl`].
+pub fn create_table<T, S, D>(descriptor: &D, dialect: Dialect) -> Result<Vec<String>, Error>
+where
+    T: StoreData<S>,
+    S: Store,
+    D: DataDescriptor<T, S>,
+{
+    Table::new(descriptor).map(|table| table.to_ddl(dialect))
+}
+
+/// An error that can occur when creating a [`Table`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A type was written outside of a field.
+    UnexpectedType,
+    /// A field did not write any type.
+    MissingType { key: String },
+    /// A field wrote more than a single type.
+    MultipleTypes { key: String },
+    /// A field was written inside of another field.
+    NestedField { key: String },
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedType => write!(f, "type written outside of a field"),
+            Self::MissingType { key } => write!(f, "field {:?} has no type", key),
+            Self::MultipleTypes { key } => write!(f, "field {:?} has multiple types", key),
+            Self::NestedField { key } => write!(f, "nested field {:?} is not supported", key),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl crate::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+}
+
+/// The [`TypeWriter`] collecting the [`Column`]s of a [`Table`].
+#[derive(Debug)]
+struct TableWriter {
+    columns: Vec<Column>,
+    field: Option<Field>,
+}
+
+#[derive(Debug)]
+struct Field {
+    key: String,
+    ty: Option<ColumnType>,
+}
+
+impl TableWriter {
+    fn new() -> Self {
+        Self {
+            columns: Vec::new(),
+            field: None,
+        }
+    }
+
+    fn write_type(&mut self, ty: ColumnType) -> Result<(), Error> {
+        match &mut self.field {
+            Some(field) => match field.ty {
+                Some(_) => Err(Error::MultipleTypes {
+                    key: field.key.clone(),
+                }),
+                None => {
+                    field.ty = Some(ty);
+                    Ok(())
+                }
+            },
+            None => Err(Error::UnexpectedType),
+        }
+    }
+}
+
+impl<S> TypeWriter<S> for TableWriter
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::Bool)
+    }
+
+    fn write_i8(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::I8)
+    }
+
+    fn write_i16(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::I16)
+    }
+
+    fn write_i32(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::I32)
+    }
+
+    fn write_i64(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::I64)
+    }
+
+    fn write_u8(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::U8)
+    }
+
+    fn write_u16(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::U16)
+    }
+
+    fn write_u32(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::U32)
+    }
+
+    fn write_u64(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::U64)
+    }
+
+    fn write_f32(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::F32)
+    }
+
+    fn write_f64(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::F64)
+    }
+
+    fn write_bytes(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::Bytes)
+    }
+
+    fn write_str(&mut self) -> Result<(), Self::Error> {
+        self.write_type(ColumnType::Str)
+    }
+
+    fn write_field_seed<T>(
+        &mut self,
+        key: &str,
+        seed: T,
+        attrs: FieldAttributes,
+    ) -> Result<(), Self::Error>
+    where
+        T: WriteTypeSeed<S>,
+    {
+        if self.field.is_some() {
+            return Err(Error::NestedField {
+                key: key.to_owned(),
+            });
+        }
+
+        self.field = Some(Field {
+            key: key.to_owned(),
+            ty: None,
+        });
+
+        seed.write_type(self)?;
+
+        // `self.field` is always `Some` after a successful `write_type`.
+        let field = self.field.take().unwrap();
+        match field.ty {
+            Some(ty) => {
+                self.columns.push(Column {
+                    name: field.key,
+                    ty,
+                    attrs,
+                });
+                Ok(())
+            }
+            None => Err(Error::MissingType {
+                key: key.to_owned(),
+            }),
+        }
+    }
+}
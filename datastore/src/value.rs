@@ -0,0 +1,1162 @@
+//! Dynamic values for schema-less access.
+//!
+//! A [`Record`] is an ordered map of field names to [`Value`]s. It implements [`Writer`] to
+//! capture any [`StoreData`] type and [`Reader`] to replay it into any [`StoreData`] type:
+//!
+//! ```
+//! # use datastore::{Store, StoreData};
+//! use datastore::value::Record;
+//!
+//! # fn capture<T, S>(data: &T) -> T
+//! # where
+//! #     T: StoreData<S>,
+//! #     S: Store,
+//! # {
+//! let record = Record::from_data::<T, S>(data).unwrap();
+//! for (key, value) in record.iter() {
+//!     println!("{}: {:?}", key, value);
+//! }
+//!
+//! record.to_data::<T, S>().unwrap()
+//! # }
+//! ```
+//!
+//! `Record` itself is a [`StoreData`] type for all stores. Since its fields are only known at
+//! runtime it must be described by a [`RecordDescriptor`] built at runtime. Stores read items
+//! through [`DataDescriptor::read`] which allows reading arbitrary collections as `Vec<Record>`:
+//!
+//! ```
+//! # use datastore::Store;
+//! use datastore::value::{Record, RecordDescriptor, Type};
+//!
+//! # async fn get_all<S>(store: &S) -> Result<Vec<Record>, S::Error>
+//! # where
+//! #     S: Store,
+//! # {
+//! let descriptor = RecordDescriptor::new("people")
+//!     .field("id", Type::I64)
+//!     .field("name", Type::String);
+//!
+//! store.get_all(descriptor).await
+//! # }
+//! ```
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+use crate::sql::{self, ColumnType, Table};
+use crate::{
+    DataDescriptor, DataQuery, FieldAttributes, ReadSeed, Reader, Store, StoreData, TypeWriter,
+    Write, Writer,
+};
+
+/// A dynamically typed value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    /// A value containing nested fields.
+    Record(Record),
+}
+
+impl Value {
+    /// Returns the [`Type`] of the `Value`. Returns `None` for [`Value::Record`].
+    pub fn ty(&self) -> Option<Type> {
+        match self {
+            Self::Bool(_) => Some(Type::Bool),
+            Self::I8(_) => Some(Type::I8),
+            Self::I16(_) => Some(Type::I16),
+            Self::I32(_) => Some(Type::I32),
+            Self::I64(_) => Some(Type::I64),
+            Self::U8(_) => Some(Type::U8),
+            Self::U16(_) => Some(Type::U16),
+            Self::U32(_) => Some(Type::U32),
+            Self::U64(_) => Some(Type::U64),
+            Self::F32(_) => Some(Type::F32),
+            Self::F64(_) => Some(Type::F64),
+            Self::Bytes(_) => Some(Type::Bytes),
+            Self::String(_) => Some(Type::String),
+            Self::Record(_) => None,
+        }
+    }
+}
+
+macro_rules! value_from {
+    ($($ty:ty => $variant:ident,)*) => {
+        $(
+            impl From<$ty> for Value {
+                #[inline]
+                fn from(v: $ty) -> Self {
+                    Self::$variant(v)
+                }
+            }
+        )*
+    };
+}
+
+value_from! {
+    bool => Bool,
+    i8 => I8,
+    i16 => I16,
+    i32 => I32,
+    i64 => I64,
+    u8 => U8,
+    u16 => U16,
+    u32 => U32,
+    u64 => U64,
+    f32 => F32,
+    f64 => F64,
+    Vec<u8> => Bytes,
+    String => String,
+    Record => Record,
+}
+
+impl From<&str> for Value {
+    #[inline]
+    fn from(v: &str) -> Self {
+        Self::String(v.to_owned())
+    }
+}
+
+impl From<&[u8]> for Value {
+    #[inline]
+    fn from(v: &[u8]) -> Self {
+        Self::Bytes(v.to_vec())
+    }
+}
+
+/// The type of a [`Value`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Type {
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Bytes,
+    String,
+}
+
+/// An ordered map of field names to [`Value`]s.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    fields: Vec<(Cow<'static, str>, Value)>,
+}
+
+impl Record {
+    /// Creates a new, empty `Record`.
+    #[inline]
+    pub fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    /// Creates a new, empty `Record` with space for at least `capacity` fields.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            fields: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Captures the [`StoreData`] `data` into a new `Record`.
+    pub fn from_data<T, S>(data: &T) -> Result<Self, Error>
+    where
+        T: StoreData<S>,
+        S: Store,
+    {
+        let mut record = Self::new();
+        data.write(&mut record)?;
+        Ok(record)
+    }
+
+    /// Replays the `Record` into a new [`StoreData`] `T`.
+    pub fn to_data<T, S>(&self) -> Result<T, Error>
+    where
+        T: StoreData<S>,
+        S: Store,
+    {
+        T::read(&mut RecordReader { record: self })
+    }
+
+    /// Returns the number of fields in the `Record`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns `true` if the `Record` contains no fields.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns a reference to the value of the field `key`.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns a mutable reference to the value of the field `key`.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.fields
+            .iter_mut()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Inserts the field `key` with the `value` into the `Record`.
+    ///
+    /// If the field already exists its value is replaced in place and the old value is
+    /// returned. Otherwise the field is appended.
+    pub fn insert<K>(&mut self, key: K, value: Value) -> Option<Value>
+    where
+        K: Into<Cow<'static, str>>,
+    {
+        let key = key.into();
+        match self.get_mut(&key) {
+            Some(v) => Some(std::mem::replace(v, value)),
+            None => {
+                self.fields.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes the field `key` from the `Record`, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let index = self.fields.iter().position(|(k, _)| *k == key)?;
+        Some(self.fields.remove(index).1)
+    }
+
+    /// Returns an iterator over all fields in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> + '_ {
+        self.fields.iter().map(|(key, value)| (&**key, value))
+    }
+
+    /// Returns an iterator over all field names in order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> + '_ {
+        self.fields.iter().map(|(key, _)| &**key)
+    }
+}
+
+impl<K> FromIterator<(K, Value)> for Record
+where
+    K: Into<Cow<'static, str>>,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, Value)>,
+    {
+        let mut record = Self::new();
+        for (key, value) in iter {
+            record.insert(key, value);
+        }
+        record
+    }
+}
+
+impl IntoIterator for Record {
+    type Item = (Cow<'static, str>, Value);
+    type IntoIter = std::vec::IntoIter<(Cow<'static, str>, Value)>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.fields.into_iter()
+    }
+}
+
+/// An error that can occur when capturing or replaying a [`Record`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The field `key` does not exist.
+    MissingField { key: String },
+    /// A value has a different type than expected.
+    InvalidType { expected: &'static str },
+    /// A value was written outside of a field.
+    UnexpectedValue,
+    /// More than a single value was written into a field.
+    MultipleValues,
+    /// A custom error.
+    Custom(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingField { key } => write!(f, "missing field {:?}", key),
+            Self::InvalidType { expected } => write!(f, "invalid type, expected {}", expected),
+            Self::UnexpectedValue => write!(f, "value written outside of a field"),
+            Self::MultipleValues => write!(f, "multiple values written into a field"),
+            Self::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl crate::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+
+    #[inline]
+    fn is_missing_field(&self) -> bool {
+        matches!(self, Self::MissingField { .. })
+    }
+}
+
+impl<S> Writer<S> for Record
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self, _v: bool) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_i8(&mut self, _v: i8) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_i16(&mut self, _v: i16) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_i32(&mut self, _v: i32) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_i64(&mut self, _v: i64) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_u8(&mut self, _v: u8) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_u16(&mut self, _v: u16) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_u32(&mut self, _v: u32) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_u64(&mut self, _v: u64) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_f32(&mut self, _v: f32) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_f64(&mut self, _v: f64) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_bytes(&mut self, _v: &[u8]) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_str(&mut self, _v: &str) -> Result<(), Self::Error> {
+        Err(Error::UnexpectedValue)
+    }
+
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<S>,
+    {
+        let mut writer = ValueWriter { value: None };
+        value.write(&mut writer)?;
+
+        // Values that write nothing are not captured.
+        if let Some(value) = writer.value {
+            self.insert(key.to_owned(), value);
+        }
+
+        Ok(())
+    }
+}
+
+/// The [`Writer`] capturing a single field value.
+struct ValueWriter {
+    value: Option<Value>,
+}
+
+impl ValueWriter {
+    fn write_value(&mut self, value: Value) -> Result<(), Error> {
+        match self.value {
+            Some(_) => Err(Error::MultipleValues),
+            None => {
+                self.value = Some(value);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<S> Writer<S> for ValueWriter
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn write_bool(&mut self, v: bool) -> Result<(), Self::Error> {
+        self.write_value(Value::Bool(v))
+    }
+
+    fn write_i8(&mut self, v: i8) -> Result<(), Self::Error> {
+        self.write_value(Value::I8(v))
+    }
+
+    fn write_i16(&mut self, v: i16) -> Result<(), Self::Error> {
+        self.write_value(Value::I16(v))
+    }
+
+    fn write_i32(&mut self, v: i32) -> Result<(), Self::Error> {
+        self.write_value(Value::I32(v))
+    }
+
+    fn write_i64(&mut self, v: i64) -> Result<(), Self::Error> {
+        self.write_value(Value::I64(v))
+    }
+
+    fn write_u8(&mut self, v: u8) -> Result<(), Self::Error> {
+        self.write_value(Value::U8(v))
+    }
+
+    fn write_u16(&mut self, v: u16) -> Result<(), Self::Error> {
+        self.write_value(Value::U16(v))
+    }
+
+    fn write_u32(&mut self, v: u32) -> Result<(), Self::Error> {
+        self.write_value(Value::U32(v))
+    }
+
+    fn write_u64(&mut self, v: u64) -> Result<(), Self::Error> {
+        self.write_value(Value::U64(v))
+    }
+
+    fn write_f32(&mut self, v: f32) -> Result<(), Self::Error> {
+        self.write_value(Value::F32(v))
+    }
+
+    fn write_f64(&mut self, v: f64) -> Result<(), Self::Error> {
+        self.write_value(Value::F64(v))
+    }
+
+    fn write_bytes(&mut self, v: &[u8]) -> Result<(), Self::Error> {
+        self.write_value(Value::Bytes(v.to_vec()))
+    }
+
+    fn write_str(&mut self, v: &str) -> Result<(), Self::Error> {
+        self.write_value(Value::String(v.to_owned()))
+    }
+
+    fn write_field<T>(&mut self, key: &str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Write<S>,
+    {
+        let record = match &mut self.value {
+            Some(Value::Record(record)) => record,
+            Some(_) => return Err(Error::MultipleValues),
+            None => {
+                self.value = Some(Value::Record(Record::new()));
+                match &mut self.value {
+                    Some(Value::Record(record)) => record,
+                    _ => unreachable!(),
+                }
+            }
+        };
+
+        Writer::<S>::write_field(record, key, value)
+    }
+}
+
+/// The [`Reader`] replaying a single field value.
+///
+/// Integers are converted between all integer types as long as the value is in range.
+struct ValueReader<'a> {
+    value: &'a Value,
+}
+
+impl<'a> ValueReader<'a> {
+    fn read_int<T>(&self, expected: &'static str) -> Result<T, Error>
+    where
+        T: TryFrom<i64> + TryFrom<u64>,
+    {
+        let value = match *self.value {
+            Value::I8(v) => T::try_from(i64::from(v)).ok(),
+            Value::I16(v) => T::try_from(i64::from(v)).ok(),
+            Value::I32(v) => T::try_from(i64::from(v)).ok(),
+            Value::I64(v) => T::try_from(v).ok(),
+            Value::U8(v) => T::try_from(u64::from(v)).ok(),
+            Value::U16(v) => T::try_from(u64::from(v)).ok(),
+            Value::U32(v) => T::try_from(u64::from(v)).ok(),
+            Value::U64(v) => T::try_from(v).ok(),
+            _ => None,
+        };
+
+        value.ok_or(Error::InvalidType { expected })
+    }
+
+    fn read_float(&self) -> Result<f64, Error> {
+        match *self.value {
+            Value::F32(v) => Ok(f64::from(v)),
+            Value::F64(v) => Ok(v),
+            _ => Err(Error::InvalidType {
+                expected: "a float",
+            }),
+        }
+    }
+}
+
+impl<'a, S> Reader<S> for ValueReader<'a>
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn read_bool(&mut self) -> Result<bool, Self::Error> {
+        match *self.value {
+            Value::Bool(v) => Ok(v),
+            _ => Err(Error::InvalidType { expected: "a bool" }),
+        }
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Self::Error> {
+        self.read_int("an i8")
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        self.read_int("an i16")
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        self.read_int("an i32")
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Self::Error> {
+        self.read_int("an i64")
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        self.read_int("an u8")
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        self.read_int("an u16")
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        self.read_int("an u32")
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Self::Error> {
+        self.read_int("an u64")
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Self::Error> {
+        self.read_float().map(|v| v as f32)
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Self::Error> {
+        self.read_float()
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Self::Error> {
+        match self.value {
+            Value::Bytes(v) => Ok(v.clone()),
+            _ => Err(Error::InvalidType {
+                expected: "a byte buffer",
+            }),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, Self::Error> {
+        match self.value {
+            Value::String(v) => Ok(v.clone()),
+            _ => Err(Error::InvalidType {
+                expected: "a string",
+            }),
+        }
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>,
+    {
+        match self.value {
+            Value::Record(record) => {
+                Reader::<S>::read_field_seed(&mut RecordReader { record }, key, seed)
+            }
+            _ => Err(Error::InvalidType {
+                expected: "a record",
+            }),
+        }
+    }
+}
+
+/// The [`Reader`] replaying the fields of a [`Record`].
+struct RecordReader<'a> {
+    record: &'a Record,
+}
+
+impl<'a> RecordReader<'a> {
+    fn invalid_type<T>(&self) -> Result<T, Error> {
+        Err(Error::InvalidType {
+            expected: "a field",
+        })
+    }
+}
+
+impl<'a, S> Reader<S> for RecordReader<'a>
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn read_bool(&mut self) -> Result<bool, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_string(&mut self) -> Result<String, Self::Error> {
+        self.invalid_type()
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>,
+    {
+        match self.record.get(key) {
+            Some(value) => seed.read(&mut ValueReader { value }),
+            None => Err(Error::MissingField {
+                key: key.to_owned(),
+            }),
+        }
+    }
+}
+
+impl<S> Reader<S> for Record
+where
+    S: Store,
+{
+    type Error = Error;
+
+    fn read_bool(&mut self) -> Result<bool, Self::Error> {
+        Reader::<S>::read_bool(&mut RecordReader { record: self })
+    }
+
+    fn read_i8(&mut self) -> Result<i8, Self::Error> {
+        Reader::<S>::read_i8(&mut RecordReader { record: self })
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        Reader::<S>::read_i16(&mut RecordReader { record: self })
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        Reader::<S>::read_i32(&mut RecordReader { record: self })
+    }
+
+    fn read_i64(&mut self) -> Result<i64, Self::Error> {
+        Reader::<S>::read_i64(&mut RecordReader { record: self })
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        Reader::<S>::read_u8(&mut RecordReader { record: self })
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        Reader::<S>::read_u16(&mut RecordReader { record: self })
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        Reader::<S>::read_u32(&mut RecordReader { record: self })
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Self::Error> {
+        Reader::<S>::read_u64(&mut RecordReader { record: self })
+    }
+
+    fn read_f32(&mut self) -> Result<f32, Self::Error> {
+        Reader::<S>::read_f32(&mut RecordReader { record: self })
+    }
+
+    fn read_f64(&mut self) -> Result<f64, Self::Error> {
+        Reader::<S>::read_f64(&mut RecordReader { record: self })
+    }
+
+    fn read_byte_buf(&mut self) -> Result<Vec<u8>, Self::Error> {
+        Reader::<S>::read_byte_buf(&mut RecordReader { record: self })
+    }
+
+    fn read_string(&mut self) -> Result<String, Self::Error> {
+        Reader::<S>::read_string(&mut RecordReader { record: self })
+    }
+
+    fn read_field_seed<T>(&mut self, key: &str, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: ReadSeed<S>,
+    {
+        Reader::<S>::read_field_seed(&mut RecordReader { record: self }, key, seed)
+    }
+}
+
+/// The type of a `Value` is only known at runtime, therefore `write_type` writes nothing.
+impl<S> Write<S> for Value
+where
+    S: Store,
+{
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        match self {
+            Self::Bool(v) => writer.write_bool(*v),
+            Self::I8(v) => writer.write_i8(*v),
+            Self::I16(v) => writer.write_i16(*v),
+            Self::I32(v) => writer.write_i32(*v),
+            Self::I64(v) => writer.write_i64(*v),
+            Self::U8(v) => writer.write_u8(*v),
+            Self::U16(v) => writer.write_u16(*v),
+            Self::U32(v) => writer.write_u32(*v),
+            Self::U64(v) => writer.write_u64(*v),
+            Self::F32(v) => writer.write_f32(*v),
+            Self::F64(v) => writer.write_f64(*v),
+            Self::Bytes(v) => writer.write_bytes(v),
+            Self::String(v) => writer.write_str(v),
+            Self::Record(record) => Write::<S>::write(record, writer),
+        }
+    }
+
+    fn write_type<W>(_writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        Ok(())
+    }
+}
+
+/// The fields of a `Record` are only known at runtime, therefore `write_type` writes nothing.
+impl<S> Write<S> for Record
+where
+    S: Store,
+{
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        for (key, value) in self.iter() {
+            writer.write_field(key, value)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_type<W>(_writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        Ok(())
+    }
+}
+
+impl<S> StoreData<S> for Record
+where
+    S: Store,
+{
+    type Descriptor = RecordDescriptor;
+    type Query = RecordQuery;
+
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        Write::<S>::write(self, writer)
+    }
+
+    /// A `Record` can only be read through a [`RecordDescriptor`]. Calling `read` directly always
+    /// returns an error.
+    fn read<R>(_reader: &mut R) -> Result<Self, R::Error>
+    where
+        R: Reader<S>,
+    {
+        Err(crate::Error::custom(
+            "a Record can only be read through a RecordDescriptor",
+        ))
+    }
+}
+
+/// A field of a [`RecordDescriptor`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FieldDescriptor {
+    name: Cow<'static, str>,
+    ty: Type,
+    attrs: FieldAttributes,
+}
+
+impl FieldDescriptor {
+    /// Returns the name of the field.
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the [`Type`] of the field.
+    #[inline]
+    pub fn ty(&self) -> Type {
+        self.ty
+    }
+
+    /// Returns the [`FieldAttributes`] of the field.
+    #[inline]
+    pub fn attributes(&self) -> FieldAttributes {
+        self.attrs
+    }
+}
+
+/// A [`DataDescriptor`] for a [`Record`] built at runtime.
+///
+/// Fields marked as [`nullable`] are left out of the read [`Record`] if they are missing, e.g.
+/// because the value is `NULL`. Any other error reading a field is returned.
+///
+/// [`nullable`]: FieldAttributes::nullable
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RecordDescriptor {
+    ident: String,
+    fields: Vec<FieldDescriptor>,
+}
+
+impl RecordDescriptor {
+    /// Creates a new `RecordDescriptor` with the given `ident` and no fields.
+    pub fn new<T>(ident: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self {
+            ident: ident.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a field with the given `name` and [`Type`].
+    pub fn field<N>(self, name: N, ty: Type) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        self.field_with(name, ty, FieldAttributes::new())
+    }
+
+    /// Adds a field with the given `name`, [`Type`] and [`FieldAttributes`].
+    pub fn field_with<N>(mut self, name: N, ty: Type, attrs: FieldAttributes) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+    {
+        self.fields.push(FieldDescriptor {
+            name: name.into(),
+            ty,
+            attrs,
+        });
+        self
+    }
+
+    /// Creates a new `RecordDescriptor` with the same ident and fields as the [`DataDescriptor`]
+    /// `descriptor`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`sql::Error`] if a field of the descriptor is not a single value.
+    pub fn from_descriptor<T, S, D>(descriptor: &D) -> Result<Self, sql::Error>
+    where
+        T: StoreData<S>,
+        S: Store,
+        D: DataDescriptor<T, S>,
+    {
+        let table = Table::new(descriptor)?;
+
+        let mut output = Self::new(table.name());
+        for column in table.columns() {
+            let ty = match column.ty() {
+                ColumnType::Bool => Type::Bool,
+                ColumnType::I8 => Type::I8,
+                ColumnType::I16 => Type::I16,
+                ColumnType::I32 => Type::I32,
+                ColumnType::I64 => Type::I64,
+                ColumnType::U8 => Type::U8,
+                ColumnType::U16 => Type::U16,
+                ColumnType::U32 => Type::U32,
+                ColumnType::U64 => Type::U64,
+                ColumnType::F32 => Type::F32,
+                ColumnType::F64 => Type::F64,
+                ColumnType::Bytes => Type::Bytes,
+                ColumnType::Str => Type::String,
+            };
+
+            output = output.field_with(column.name().to_owned(), ty, column.attributes());
+        }
+
+        Ok(output)
+    }
+
+    /// Returns the fields of the descriptor in order.
+    #[inline]
+    pub fn fields(&self) -> &[FieldDescriptor] {
+        &self.fields
+    }
+}
+
+impl<S> DataDescriptor<Record, S> for RecordDescriptor
+where
+    S: Store,
+{
+    fn ident(&self) -> &str {
+        &self.ident
+    }
+
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: TypeWriter<S>,
+    {
+        for field in &self.fields {
+            let (name, attrs) = (&*field.name, field.attrs);
+
+            match field.ty {
+                Type::Bool => writer.write_field_with::<types::Bool>(name, attrs),
+                Type::I8 => writer.write_field_with::<types::I8>(name, attrs),
+                Type::I16 => writer.write_field_with::<types::I16>(name, attrs),
+                Type::I32 => writer.write_field_with::<types::I32>(name, attrs),
+                Type::I64 => writer.write_field_with::<types::I64>(name, attrs),
+                Type::U8 => writer.write_field_with::<types::U8>(name, attrs),
+                Type::U16 => writer.write_field_with::<types::U16>(name, attrs),
+                Type::U32 => writer.write_field_with::<types::U32>(name, attrs),
+                Type::U64 => writer.write_field_with::<types::U64>(name, attrs),
+                Type::F32 => writer.write_field_with::<types::F32>(name, attrs),
+                Type::F64 => writer.write_field_with::<types::F64>(name, attrs),
+                Type::Bytes => writer.write_field_with::<types::Bytes>(name, attrs),
+                Type::String => writer.write_field_with::<types::String>(name, attrs),
+            }?;
+        }
+
+        Ok(())
+    }
+
+    fn read<R>(&self, reader: &mut R) -> Result<Record, R::Error>
+    where
+        R: Reader<S>,
+    {
+        let mut record = Record::with_capacity(self.fields.len());
+
+        for field in &self.fields {
+            let name = &*field.name;
+
+            let value = (|| -> Result<Value, R::Error> {
+                Ok(match field.ty {
+                    Type::Bool => Value::Bool(reader.read_field::<types::Bool>(name)?.0),
+                    Type::I8 => Value::I8(reader.read_field::<types::I8>(name)?.0),
+                    Type::I16 => Value::I16(reader.read_field::<types::I16>(name)?.0),
+                    Type::I32 => Value::I32(reader.read_field::<types::I32>(name)?.0),
+                    Type::I64 => Value::I64(reader.read_field::<types::I64>(name)?.0),
+                    Type::U8 => Value::U8(reader.read_field::<types::U8>(name)?.0),
+                    Type::U16 => Value::U16(reader.read_field::<types::U16>(name)?.0),
+                    Type::U32 => Value::U32(reader.read_field::<types::U32>(name)?.0),
+                    Type::U64 => Value::U64(reader.read_field::<types::U64>(name)?.0),
+                    Type::F32 => Value::F32(reader.read_field::<types::F32>(name)?.0),
+                    Type::F64 => Value::F64(reader.read_field::<types::F64>(name)?.0),
+                    Type::Bytes => Value::Bytes(reader.read_field::<types::Bytes>(name)?.0),
+                    Type::String => Value::String(reader.read_field::<types::String>(name)?.0),
+                })
+            })();
+
+            match value {
+                Ok(value) => {
+                    record.insert(field.name.clone(), value);
+                }
+                // Missing values, e.g. `NULL` columns, of nullable fields are left out of the
+                // record.
+                Err(err) if field.attrs.is_nullable() && crate::Error::is_missing_field(&err) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(record)
+    }
+}
+
+/// A [`DataQuery`] for a [`Record`] matching all items with equal field values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordQuery {
+    record: Record,
+}
+
+impl RecordQuery {
+    /// Creates a new `RecordQuery` matching all items.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only matches items where the field `name` is equal to `value`.
+    pub fn field<N, T>(mut self, name: N, value: T) -> Self
+    where
+        N: Into<Cow<'static, str>>,
+        T: Into<Value>,
+    {
+        self.record.insert(name, value.into());
+        self
+    }
+
+    /// Returns the fields of the query as a [`Record`].
+    #[inline]
+    pub fn as_record(&self) -> &Record {
+        &self.record
+    }
+}
+
+impl From<Record> for RecordQuery {
+    #[inline]
+    fn from(record: Record) -> Self {
+        Self { record }
+    }
+}
+
+impl<S> DataQuery<Record, S> for RecordQuery
+where
+    S: Store,
+{
+    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+    where
+        W: Writer<S>,
+    {
+        Write::<S>::write(&self.record, writer)
+    }
+}
+
+/// Field types with a static type used by [`RecordDescriptor`]. Unlike the primitive types these
+/// implement [`Write`] and [`Read`] for all stores.
+mod types {
+    use crate::{Read, Reader, Store, TypeWriter, Write, Writer};
+
+    macro_rules! types {
+        ($($name:ident($ty:ty) => $write:ident($($ref:tt)?), $read:ident;)*) => {
+            $(
+                pub struct $name(pub $ty);
+
+                impl<S> Write<S> for $name
+                where
+                    S: Store,
+                {
+                    fn write<W>(&self, writer: &mut W) -> Result<(), W::Error>
+                    where
+                        W: Writer<S>,
+                    {
+                        writer.$write($($ref)? self.0)
+                    }
+
+                    fn write_type<W>(writer: &mut W) -> Result<(), W::Error>
+                    where
+                        W: TypeWriter<S>,
+                    {
+                        writer.$write()
+                    }
+                }
+
+                impl<S> Read<S> for $name
+                where
+                    S: Store,
+                {
+                    fn read<R>(reader: &mut R) -> Result<Self, R::Error>
+                    where
+                        R: Reader<S>,
+                    {
+                        reader.$read().map(Self)
+                    }
+                }
+            )*
+        };
+    }
+
+    types! {
+        Bool(bool) => write_bool(), read_bool;
+        I8(i8) => write_i8(), read_i8;
+        I16(i16) => write_i16(), read_i16;
+        I32(i32) => write_i32(), read_i32;
+        I64(i64) => write_i64(), read_i64;
+        U8(u8) => write_u8(), read_u8;
+        U16(u16) => write_u16(), read_u16;
+        U32(u32) => write_u32(), read_u32;
+        U64(u64) => write_u64(), read_u64;
+        F32(f32) => write_f32(), read_f32;
+        F64(f64) => write_f64(), read_f64;
+        Bytes(Vec<u8>) => write_bytes(&), read_byte_buf;
+        String(std::string::String) => write_str(&), read_string;
+    }
+}
@@ -1,9 +1,10 @@
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
 use syn::{
-    parenthesized, parse_macro_input, Data, DeriveInput, Expr, Fields, Ident, Lit, Result, Token,
-    Type,
+    parenthesized, parse_macro_input, parse_quote, Data, DeriveInput, Error, Expr, Fields,
+    GenericArgument, Ident, Lit, PathArguments, Result, Token, Type,
 };
 
 pub fn expand_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -15,20 +16,40 @@ pub fn expand_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         if let Some(ident) = attr.path.get_ident() {
             if ident == "datastore" {
                 let tokens = attr.tokens.into();
-                attrs.push(parse_macro_input!(tokens as Attr));
+                attrs.extend(parse_macro_input!(tokens as Attrs));
             }
         }
     }
 
+    if let Some(attr) = attrs.0.iter().find(|attr| !attr.is_container_attr()) {
+        return attr.unexpected("container").into();
+    }
+
     let mut types = Vec::new();
     let mut idents = Vec::new();
+    let mut field_attrs = Vec::new();
 
     match input.data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
                 for field in fields.named.iter() {
+                    let mut attrs = Attrs::new();
+                    for attr in field.attrs.iter() {
+                        if let Some(ident) = attr.path.get_ident() {
+                            if ident == "datastore" {
+                                let tokens = attr.tokens.clone().into();
+                                attrs.extend(parse_macro_input!(tokens as Attrs));
+                            }
+                        }
+                    }
+
+                    if let Some(attr) = attrs.0.iter().find(|attr| attr.is_container_attr()) {
+                        return attr.unexpected("field").into();
+                    }
+
                     types.push(field.ty.clone());
                     idents.push(field.ident.clone().unwrap());
+                    field_attrs.push(attrs);
                 }
             }
             _ => unimplemented!(),
@@ -36,9 +57,10 @@ pub fn expand_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         _ => unimplemented!(),
     }
 
-    let storedata = expand_storedata_impl(&input.ident, &idents, &types);
-    let descriptor = expand_datadescriptor_impl(&input.ident, &idents, &types, attrs.name());
-    let query = expand_dataquery_impl(&input.ident, &idents, &types);
+    let storedata = expand_storedata_impl(&input.ident, &idents, &types, &field_attrs);
+    let descriptor =
+        expand_datadescriptor_impl(&input.ident, &idents, &types, &field_attrs, attrs.name());
+    let query = expand_dataquery_impl(&input.ident, &idents, &types, &field_attrs);
 
     let expanded = quote! {
         #storedata
@@ -49,24 +71,57 @@ pub fn expand_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     proc_macro::TokenStream::from(expanded)
 }
 
-fn expand_storedata_impl(ident: &Ident, idents: &[Ident], types: &[Type]) -> TokenStream {
-    let trait_bounds = expand_trait_bounds(types);
+fn expand_storedata_impl(
+    ident: &Ident,
+    idents: &[Ident],
+    types: &[Type],
+    field_attrs: &[Attrs],
+) -> TokenStream {
+    let trait_bounds = expand_trait_bounds(types, field_attrs);
 
-    let write_impl = idents.iter().map(|ident| {
+    let write_impl = idents.iter().zip(field_attrs).map(|(ident, attrs)| {
         let name = ident.to_string();
+        let value = expand_field_value(quote! { &self.#ident }, attrs);
 
         quote! {
-            writer.write_field(#name, &self.#ident)?;
+            writer.write_field(#name, #value)?;
         }
     });
 
-    let read_impl = idents.iter().map(|ident| {
-        let name = ident.to_string();
+    let read_impl = idents
+        .iter()
+        .zip(types)
+        .zip(field_attrs)
+        .map(|((ident, ty), attrs)| {
+            let name = ident.to_string();
+
+            if attrs.serde() && is_option(ty) {
+                let ty = field_type(ty, attrs);
+
+                // `None` writes nothing, so only a missing optional field is read as `None`.
+                quote! {
+                    let #ident = match reader.read_field::<#ty>(#name) {
+                        ::std::result::Result::Ok(v) => v.0,
+                        ::std::result::Result::Err(err)
+                            if ::datastore::Error::is_missing_field(&err) =>
+                        {
+                            ::std::option::Option::None
+                        }
+                        ::std::result::Result::Err(err) => return ::std::result::Result::Err(err),
+                    };
+                }
+            } else if attrs.serde() {
+                let ty = field_type(ty, attrs);
 
-        quote! {
-            let #ident = reader.read_field(#name)?;
-        }
-    });
+                quote! {
+                    let #ident = reader.read_field::<#ty>(#name)?.0;
+                }
+            } else {
+                quote! {
+                    let #ident = reader.read_field(#name)?;
+                }
+            }
+        });
 
     let descriptor_ident = Ident::new(&format!("{}Descriptor", ident), Span::call_site());
     let query_ident = Ident::new(&format!("{}Query", ident), Span::call_site());
@@ -107,20 +162,41 @@ fn expand_datadescriptor_impl(
     ident: &Ident,
     idents: &[Ident],
     types: &[Type],
+    field_attrs: &[Attrs],
     name: Option<String>,
 ) -> TokenStream {
-    let trait_bounds = expand_trait_bounds(types);
+    let trait_bounds = expand_trait_bounds(types, field_attrs);
 
     let datadescriptor_ident = Ident::new(&format!("{}Descriptor", ident), Span::call_site());
 
-    let write_impl = idents.iter().zip(types).map(|(ident, ty)| {
-        let name = ident.to_string();
-        let ty = ty.clone();
-
-        quote! {
-            writer.write_field::<#ty>(#name)?;
-        }
-    });
+    let write_impl = idents
+        .iter()
+        .zip(types)
+        .zip(field_attrs)
+        .map(|((ident, ty), attrs)| {
+            let name = ident.to_string();
+            let nullable = is_option(ty);
+            let ty = field_type(ty, attrs);
+
+            let primary_key = attrs.primary_key();
+            let index = attrs.index();
+
+            if primary_key || index || nullable {
+                quote! {
+                    writer.write_field_with::<#ty>(
+                        #name,
+                        ::datastore::FieldAttributes::new()
+                            .primary_key(#primary_key)
+                            .index(#index)
+                            .nullable(#nullable),
+                    )?;
+                }
+            } else {
+                quote! {
+                    writer.write_field::<#ty>(#name)?;
+                }
+            }
+        });
 
     let name = match name {
         Some(name) => name,
@@ -152,8 +228,13 @@ fn expand_datadescriptor_impl(
     }
 }
 
-fn expand_dataquery_impl(ident: &Ident, idents: &[Ident], types: &[Type]) -> TokenStream {
-    let trait_bounds = expand_trait_bounds(types);
+fn expand_dataquery_impl(
+    ident: &Ident,
+    idents: &[Ident],
+    types: &[Type],
+    field_attrs: &[Attrs],
+) -> TokenStream {
+    let trait_bounds = expand_trait_bounds(types, field_attrs);
 
     let dataquery_ident = Ident::new(&format!("{}Query", ident), Span::call_site());
 
@@ -172,12 +253,13 @@ fn expand_dataquery_impl(ident: &Ident, idents: &[Ident], types: &[Type]) -> Tok
         }
     });
 
-    let write_impl = idents.iter().map(|ident| {
+    let write_impl = idents.iter().zip(field_attrs).map(|(ident, attrs)| {
         let name = ident.to_string();
+        let value = expand_field_value(quote! { value }, attrs);
 
         quote! {
             if let Some(value) = self.#ident.as_ref() {
-                writer.write_field(#name, value)?;
+                writer.write_field(#name, #value)?;
             }
         }
     });
@@ -209,11 +291,12 @@ fn expand_dataquery_impl(ident: &Ident, idents: &[Ident], types: &[Type]) -> Tok
     }
 }
 
-fn expand_trait_bounds(types: &[Type]) -> TokenStream {
+fn expand_trait_bounds(types: &[Type], field_attrs: &[Attrs]) -> TokenStream {
     let mut bounds = Vec::new();
-    for ty in types {
-        if !bounds.contains(ty) {
-            bounds.push(ty.clone());
+    for (ty, attrs) in types.iter().zip(field_attrs) {
+        let ty = field_type(ty, attrs);
+        if !bounds.contains(&ty) {
+            bounds.push(ty);
         }
     }
 
@@ -224,22 +307,84 @@ fn expand_trait_bounds(types: &[Type]) -> TokenStream {
     }
 }
 
+/// Returns the type that is written into the store for a field with the type `ty`.
+fn field_type(ty: &Type, attrs: &Attrs) -> Type {
+    if attrs.serde() {
+        parse_quote! { ::datastore::serde::Serde<#ty> }
+    } else {
+        ty.clone()
+    }
+}
+
+/// Returns the reference that is written into the store for the field reference `value`.
+fn expand_field_value(value: TokenStream, attrs: &Attrs) -> TokenStream {
+    if attrs.serde() {
+        quote! { ::datastore::serde::Serde::from_ref(#value) }
+    } else {
+        value
+    }
+}
+
+/// Returns `true` if the type `ty` is an `Option`.
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => match path.path.segments.last() {
+            Some(segment) => {
+                segment.ident == "Option"
+                    && match &segment.arguments {
+                        PathArguments::AngleBracketed(args) => {
+                            args.args.len() == 1
+                                && matches!(args.args.first(), Some(GenericArgument::Type(_)))
+                        }
+                        _ => false,
+                    }
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Attr {
     Name(String),
+    PrimaryKey(Span),
+    Index(Span),
+    Serde(Span),
+}
+
+impl Attr {
+    /// Returns `true` if the attribute can be used on the container.
+    fn is_container_attr(&self) -> bool {
+        matches!(self, Self::Name(_))
+    }
+
+    /// Returns a compile error for an attribute that is not allowed at the `position`.
+    fn unexpected(&self, position: &str) -> TokenStream {
+        let (name, span) = match self {
+            Self::Name(_) => ("name", Span::call_site()),
+            Self::PrimaryKey(span) => ("primary_key", *span),
+            Self::Index(span) => ("index", *span),
+            Self::Serde(span) => ("serde", *span),
+        };
+
+        Error::new(
+            span,
+            format!("the {} attribute is not allowed on a {}", name, position),
+        )
+        .to_compile_error()
+    }
 }
 
 impl Parse for Attr {
     fn parse(input: ParseStream) -> Result<Self> {
-        let content;
-        parenthesized!(content in input);
-
-        let key = content.parse::<Ident>()?;
-        content.parse::<Token![=]>()?;
-        let val = content.parse::<Expr>()?;
+        let key = input.parse::<Ident>()?;
 
         match key {
             arg if arg == "name" => {
+                input.parse::<Token![=]>()?;
+                let val = input.parse::<Expr>()?;
+
                 // Only accept a LitStr.
                 match val {
                     Expr::Lit(lit) => match lit.lit {
@@ -249,6 +394,9 @@ impl Parse for Attr {
                     _ => Err(input.error("the name attribute only accepts a string literal")),
                 }
             }
+            arg if arg == "primary_key" => Ok(Self::PrimaryKey(arg.span())),
+            arg if arg == "index" => Ok(Self::Index(arg.span())),
+            arg if arg == "serde" => Ok(Self::Serde(arg.span())),
             _ => Err(input.error(format!("unknwon attribute {}", key))),
         }
     }
@@ -262,17 +410,38 @@ impl Attrs {
         Self(Vec::new())
     }
 
-    fn push(&mut self, attr: Attr) {
-        self.0.push(attr);
+    fn extend(&mut self, attrs: Attrs) {
+        self.0.extend(attrs.0);
     }
 
     fn name(&self) -> Option<String> {
+        self.0.iter().find_map(|attr| match attr {
+            Attr::Name(name) => Some(name.clone()),
+            _ => None,
+        })
+    }
+
+    fn primary_key(&self) -> bool {
         self.0
             .iter()
-            .find(|attr| matches!(attr, Attr::Name(_)))
-            .map(|attr| match attr {
-                Attr::Name(name) => name,
-            })
-            .cloned()
+            .any(|attr| matches!(attr, Attr::PrimaryKey(_)))
+    }
+
+    fn index(&self) -> bool {
+        self.0.iter().any(|attr| matches!(attr, Attr::Index(_)))
+    }
+
+    fn serde(&self) -> bool {
+        self.0.iter().any(|attr| matches!(attr, Attr::Serde(_)))
+    }
+}
+
+impl Parse for Attrs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        parenthesized!(content in input);
+
+        let attrs = Punctuated::<Attr, Token![,]>::parse_terminated(&content)?;
+        Ok(Self(attrs.into_iter().collect()))
     }
 }